@@ -1,7 +1,76 @@
 use convert_case::{Case, Casing};
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ImplItem, Item, ItemImpl, ItemMod};
+use syn::{parse_macro_input, ImplItem, Item, ItemImpl, ItemMod, Path};
+
+/// The roles an endpoint can require, from least to most privileged.
+/// `Reader`, `Commenter` and `Editor` are the roles of an access to a project.
+const ROLES: [&str; 6] = [
+    "Public",
+    "Authenticated",
+    "Reader",
+    "Commenter",
+    "Editor",
+    "Owner",
+];
+
+/// The role of endpoints without a `#[requires(...)]` attribute.
+const DEFAULT_ROLE: &str = "Authenticated";
+
+/// Reads and removes the `#[requires(...)]` attribute of a function, returning the role it names.
+///
+/// Both `#[requires(Role::Editor)]` and `#[requires(Editor)]` are accepted.
+fn take_required_role(function: &mut syn::ImplItemFn) -> String {
+    let mut role = DEFAULT_ROLE.to_string();
+
+    function.attrs.retain(|attr| {
+        if !attr.path().is_ident("requires") {
+            return true;
+        }
+        let path: Path = attr
+            .parse_args()
+            .expect("Expected a role in the requires attribute, e.g. #[requires(Role::Editor)]");
+        role = path
+            .segments
+            .last()
+            .expect("Expected a role in the requires attribute")
+            .ident
+            .to_string();
+        false
+    });
+
+    if !ROLES.contains(&role.as_str()) {
+        panic!(
+            "Unknown role '{}' required by '{}', expected one of {:?}",
+            role, function.sig.ident, ROLES
+        );
+    }
+
+    role
+}
+
+/// Returns the message type `T` of the `Request<T>` argument of an endpoint, if it has one.
+fn request_type(function: &syn::ImplItemFn) -> Option<syn::Type> {
+    function.sig.inputs.iter().find_map(|input| {
+        let syn::FnArg::Typed(argument) = input else {
+            return None;
+        };
+        let syn::Type::Path(type_path) = argument.ty.as_ref() else {
+            return None;
+        };
+        let segment = type_path.path.segments.last()?;
+        let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+            return None;
+        };
+        if segment.ident != "Request" {
+            return None;
+        }
+        arguments.args.iter().find_map(|argument| match argument {
+            syn::GenericArgument::Type(message) => Some(message.clone()),
+            _ => None,
+        })
+    })
+}
 
 #[proc_macro_attribute]
 pub fn endpoints(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -50,6 +119,25 @@ pub fn endpoints(_attr: TokenStream, item: TokenStream) -> TokenStream {
         .collect();
 
     let mut endpoints: Vec<String> = Vec::new();
+    let mut roles: Vec<String> = Vec::new();
+    let mut checked_endpoints: Vec<String> = Vec::new();
+    let mut request_types: Vec<syn::Type> = Vec::new();
+
+    // Reads the role each function requires, removing the `requires` attributes as they are not real attributes.
+    if let Some((_, items)) = item_mod.content.as_mut() {
+        for item in items.iter_mut() {
+            if let Item::Impl(item_impl) = item {
+                if item_impl.trait_.is_none() {
+                    continue;
+                }
+                for impl_item in item_impl.items.iter_mut() {
+                    if let ImplItem::Fn(function) = impl_item {
+                        roles.push(take_required_role(function));
+                    }
+                }
+            }
+        }
+    }
 
     let items_impl: Vec<Item> = item_mod.clone().content.unwrap().1;
 
@@ -71,28 +159,65 @@ pub fn endpoints(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     // Iterate over all functions in an implementation and adds the name of trait and function in Pascal case.
     for i in 0..items_impl.len() {
-        let existing_functions: Vec<String> = items_impl[i]
+        let existing_functions: Vec<(String, Option<syn::Type>)> = items_impl[i]
             .items
             .iter()
             .filter_map(|item| match item {
-                syn::ImplItem::Fn(function) => {
-                    Some(function.sig.ident.to_string().to_case(Case::Pascal))
-                }
+                syn::ImplItem::Fn(function) => Some((
+                    function.sig.ident.to_string().to_case(Case::Pascal),
+                    request_type(function),
+                )),
                 _ => None,
             })
             .collect();
 
-        for function in existing_functions {
-            endpoints.push(format!("{}/{}", names[i], function));
+        for (function, request) in existing_functions {
+            let endpoint = format!("{}/{}", names[i], function);
+            if let Some(request) = request {
+                checked_endpoints.push(endpoint.clone());
+                request_types.push(request);
+            }
+            endpoints.push(endpoint);
         }
     }
 
+    // The endpoints and the role they require are added to the module as a constant.
+    let endpoint_roles: TokenStream = quote! {
+        /// Every endpoint together with the minimum role it requires, as read from the `#[requires(...)]` attributes.
+        pub(crate) const ENDPOINT_ROLES: &[(&str, &str)] = &[#((#endpoints, #roles)),*];
+    }
+    .into();
+    let endpoint_roles = parse_macro_input!(endpoint_roles as Item);
+
+    // Every endpoint is paired with a check for fields its request message does not declare.
+    let endpoint_unknown_field_checks: TokenStream = quote! {
+        /// Every endpoint together with a function telling whether an encoded request to it carries fields
+        /// that its message does not declare.
+        pub(crate) const ENDPOINT_UNKNOWN_FIELD_CHECKS: &[(&str, fn(&[u8]) -> bool)] =
+            &[#((#checked_endpoints, crate::api::unknown_fields::has_unknown_fields::<#request_types>)),*];
+    }
+    .into();
+    let endpoint_unknown_field_checks = parse_macro_input!(endpoint_unknown_field_checks as Item);
+
     // The endpoints function is constructed.
+    let role_order = ROLES.iter();
     let new_function: TokenStream = quote! {
         async fn endpoints(&self, request: tonic::Request<()>) -> std::result::Result<tonic::Response<EndpointsResponse>, tonic::Status> {
-            let names = vec![#(#endpoints.to_string()),*];
+            let names = ENDPOINT_ROLES.iter().map(|(endpoint, _)| endpoint.to_string()).collect();
+            let endpoints_by_role = [#(#role_order),*]
+                .iter()
+                .map(|role| RoleEndpoints {
+                    role: role.to_string(),
+                    endpoints: ENDPOINT_ROLES
+                        .iter()
+                        .filter(|(_, required)| required == role)
+                        .map(|(endpoint, _)| endpoint.to_string())
+                        .collect(),
+                })
+                .collect();
             Ok(Response::new(EndpointsResponse {
-                endpoints: names
+                endpoints: names,
+                endpoints_by_role,
             }))
         }
     }
@@ -126,6 +251,11 @@ pub fn endpoints(_attr: TokenStream, item: TokenStream) -> TokenStream {
             .push(parse_macro_input!(new_function as ImplItem));
     }
 
+    if let Some((_, items)) = item_mod.content.as_mut() {
+        items.push(endpoint_roles);
+        items.push(endpoint_unknown_field_checks);
+    }
+
     // Construct the tokens for the whole module.
     let output = quote! {#item_mod};
 
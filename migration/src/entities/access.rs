@@ -8,20 +8,20 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub role: String,
-    pub model_id: i32,
+    pub project_id: i32,
     pub user_id: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
-        belongs_to = "super::model::Entity",
-        from = "Column::ModelId",
-        to = "super::model::Column::Id",
+        belongs_to = "super::project::Entity",
+        from = "Column::ProjectId",
+        to = "super::project::Column::Id",
         on_update = "NoAction",
         on_delete = "Cascade"
     )]
-    Model,
+    Project,
     #[sea_orm(
         belongs_to = "super::role::Entity",
         from = "Column::Role",
@@ -40,9 +40,9 @@ pub enum Relation {
     User,
 }
 
-impl Related<super::model::Entity> for Entity {
+impl Related<super::project::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Model.def()
+        Relation::Project.def()
     }
 }
 
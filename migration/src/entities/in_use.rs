@@ -6,7 +6,7 @@ use sea_orm::entity::prelude::*;
 #[sea_orm(table_name = "in_use")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
-    pub model_id: i32,
+    pub project_id: i32,
     pub session_id: i32,
     pub latest_activity: DateTime,
 }
@@ -14,13 +14,13 @@ pub struct Model {
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
-        belongs_to = "super::model::Entity",
-        from = "Column::ModelId",
-        to = "super::model::Column::Id",
+        belongs_to = "super::project::Entity",
+        from = "Column::ProjectId",
+        to = "super::project::Column::Id",
         on_update = "NoAction",
         on_delete = "Cascade"
     )]
-    Model,
+    Project,
     #[sea_orm(
         belongs_to = "super::session::Entity",
         from = "Column::SessionId",
@@ -31,9 +31,9 @@ pub enum Relation {
     Session,
 }
 
-impl Related<super::model::Entity> for Entity {
+impl Related<super::project::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Model.def()
+        Relation::Project.def()
     }
 }
 
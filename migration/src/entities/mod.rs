@@ -4,7 +4,7 @@ pub mod prelude;
 
 pub mod access;
 pub mod in_use;
-pub mod model;
+pub mod project;
 pub mod query;
 pub mod role;
 pub mod session;
@@ -3,7 +3,7 @@
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
-#[sea_orm(table_name = "model")]
+#[sea_orm(table_name = "project")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
@@ -10,24 +10,24 @@ pub struct Model {
     pub string: String,
     pub result: Option<Json>,
     pub outdated: bool,
-    pub model_id: i32,
+    pub project_id: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
-        belongs_to = "super::model::Entity",
-        from = "Column::ModelId",
-        to = "super::model::Column::Id",
+        belongs_to = "super::project::Entity",
+        from = "Column::ProjectId",
+        to = "super::project::Column::Id",
         on_update = "NoAction",
         on_delete = "Cascade"
     )]
-    Model,
+    Project,
 }
 
-impl Related<super::model::Entity> for Entity {
+impl Related<super::project::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Model.def()
+        Relation::Project.def()
     }
 }
 
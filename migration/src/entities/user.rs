@@ -18,8 +18,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::access::Entity")]
     Access,
-    #[sea_orm(has_many = "super::model::Entity")]
-    Model,
+    #[sea_orm(has_many = "super::project::Entity")]
+    Project,
     #[sea_orm(has_many = "super::session::Entity")]
     Session,
 }
@@ -30,9 +30,9 @@ impl Related<super::access::Entity> for Entity {
     }
 }
 
-impl Related<super::model::Entity> for Entity {
+impl Related<super::project::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Model.def()
+        Relation::Project.def()
     }
 }
 
@@ -7,6 +7,27 @@ mod m20231012_094303_create_in_use_table;
 mod m20231012_094422_create_session_table;
 mod m20231012_094533_create_access_table;
 mod m20231111_205633_create_role_table;
+mod m20261015_090000_create_project_event_table;
+mod m20261015_100000_add_last_run_ms_to_query_table;
+mod m20261015_110000_add_result_computed_at_to_query_table;
+mod m20261015_120000_add_components_snapshot_to_project_event_table;
+mod m20261015_130000_add_timestamps_to_access_and_query_tables;
+mod m20261015_140000_create_query_result_table;
+mod m20261015_150000_limit_user_column_lengths;
+mod m20261015_160000_create_component_table;
+mod m20261015_170000_add_display_name_to_user_table;
+mod m20261015_180000_add_title_to_query_table;
+mod m20261015_190000_add_settings_to_query_table;
+mod m20261015_200000_add_created_at_to_session_table;
+
+/// The maximum length of a username, which the user table's username column is sized to.
+pub const USERNAME_MAX_LENGTH: u32 = 32;
+/// The maximum length of an email address, which the user table's email column is sized to.
+pub const EMAIL_MAX_LENGTH: u32 = 254;
+/// The maximum length of a display name, which the user table's display_name column is sized to.
+pub const DISPLAY_NAME_MAX_LENGTH: u32 = 64;
+/// The maximum length of a query title, which the query table's title column is sized to.
+pub const QUERY_TITLE_MAX_LENGTH: u32 = 128;
 
 pub struct Migrator;
 
@@ -21,6 +42,18 @@ impl MigratorTrait for Migrator {
             Box::new(m20231012_094303_create_in_use_table::Migration),
             Box::new(m20231111_205633_create_role_table::Migration),
             Box::new(m20231012_094533_create_access_table::Migration),
+            Box::new(m20261015_090000_create_project_event_table::Migration),
+            Box::new(m20261015_100000_add_last_run_ms_to_query_table::Migration),
+            Box::new(m20261015_110000_add_result_computed_at_to_query_table::Migration),
+            Box::new(m20261015_120000_add_components_snapshot_to_project_event_table::Migration),
+            Box::new(m20261015_130000_add_timestamps_to_access_and_query_tables::Migration),
+            Box::new(m20261015_140000_create_query_result_table::Migration),
+            Box::new(m20261015_150000_limit_user_column_lengths::Migration),
+            Box::new(m20261015_160000_create_component_table::Migration),
+            Box::new(m20261015_170000_add_display_name_to_user_table::Migration),
+            Box::new(m20261015_180000_add_title_to_query_table::Migration),
+            Box::new(m20261015_190000_add_settings_to_query_table::Migration),
+            Box::new(m20261015_200000_add_created_at_to_session_table::Migration),
         ]
     }
 }
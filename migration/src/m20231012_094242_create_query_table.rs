@@ -48,7 +48,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Query {
+pub enum Query {
     Table,
     Id,
     String,
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20231012_094213_create_user_table::User;
+use super::m20231012_094228_create_project_table::Project;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProjectEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProjectEvent::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ProjectEvent::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(ProjectEvent::UserId).integer().not_null())
+                    .col(ColumnDef::new(ProjectEvent::EventType).string().not_null())
+                    .col(
+                        ColumnDef::new(ProjectEvent::Description)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProjectEvent::CreatedAt)
+                            .timestamp()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ProjectEvent::Table, ProjectEvent::ProjectId)
+                            .to(Project::Table, Project::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ProjectEvent::Table, ProjectEvent::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProjectEvent::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProjectEvent {
+    Table,
+    Id,
+    ProjectId,
+    UserId,
+    EventType,
+    Description,
+    CreatedAt,
+}
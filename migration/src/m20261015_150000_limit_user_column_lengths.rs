@@ -0,0 +1,57 @@
+use crate::{EMAIL_MAX_LENGTH, USERNAME_MAX_LENGTH};
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite can neither alter a column nor enforce its length, so the limits only apply elsewhere
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return Ok(());
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .modify_column(
+                        ColumnDef::new(User::Username)
+                            .string_len(USERNAME_MAX_LENGTH)
+                            .not_null(),
+                    )
+                    .modify_column(
+                        ColumnDef::new(User::Email)
+                            .string_len(EMAIL_MAX_LENGTH)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return Ok(());
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .modify_column(ColumnDef::new(User::Username).string().not_null())
+                    .modify_column(ColumnDef::new(User::Email).string().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Email,
+    Username,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20231012_094228_create_project_table::Project;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Component::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Component::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Component::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(Component::Position).integer().not_null())
+                    .col(ColumnDef::new(Component::Name).string().null())
+                    .col(ColumnDef::new(Component::Component).json().not_null())
+                    .index(
+                        Index::create()
+                            .col(Component::ProjectId)
+                            .col(Component::Position)
+                            .unique(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Component::Table, Component::ProjectId)
+                            .to(Project::Table, Project::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Component::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Component {
+    Table,
+    Id,
+    ProjectId,
+    Position,
+    Name,
+    Component,
+}
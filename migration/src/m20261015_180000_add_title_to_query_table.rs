@@ -0,0 +1,40 @@
+use crate::QUERY_TITLE_MAX_LENGTH;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Query::Table)
+                    .add_column(
+                        ColumnDef::new(Query::Title)
+                            .string_len(QUERY_TITLE_MAX_LENGTH)
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Query::Table)
+                    .drop_column(Query::Title)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Query {
+    Table,
+    Title,
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Session::Table)
+                    .add_column(ColumnDef::new(Session::CreatedAt).timestamp().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The creation time of existing sessions is unknown, so they are taken to be created
+        // when they were last refreshed.
+        let backfill = Query::update()
+            .table(Session::Table)
+            .value(Session::CreatedAt, Expr::col(Session::UpdatedAt))
+            .and_where(Expr::col(Session::CreatedAt).is_null())
+            .to_owned();
+        manager.exec_stmt(backfill).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Session::Table)
+                    .drop_column(Session::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Session {
+    Table,
+    CreatedAt,
+    UpdatedAt,
+}
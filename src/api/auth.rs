@@ -4,14 +4,80 @@ use jsonwebtoken::{
 };
 
 use serde::{Deserialize, Serialize};
-use std::{env, fmt::Display, str::FromStr};
-use tonic::{
-    metadata::{self, errors::ToStrError},
-    Request, Status,
-};
+use std::{env, fmt::Display};
+use tonic::{metadata::errors::ToStrError, Request, Status};
+
+/// Parses the value of `ALLOWED_ORIGINS`, a comma separated list of the origins that browser clients
+/// may authenticate from, returning them in lowercase and without a trailing slash.
+/// # Errors
+/// Returns a description of the problem if an entry is not an `http` or `https` origin, e.g. if it has a path.
+pub fn parse_allowed_origins(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_lowercase())
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            let host = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"));
+            match host {
+                Some(host) if !host.is_empty() && !host.contains(['/', '?', '#', '@']) => {
+                    Ok(origin)
+                }
+                _ => Err(format!(
+                    "'{}' is not an origin, expected e.g. 'https://ecdar.example.com'",
+                    origin
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Checks the `origin` metadata of a request against `allowed_origins`.
+///
+/// Requests without an origin, i.e. requests not sent by a browser, are always allowed,
+/// as are all requests if `allowed_origins` is empty.
+/// # Errors
+/// Returns a `PermissionDenied` status if the request comes from an origin that is not allowed.
+pub fn check_origin<T>(req: &Request<T>, allowed_origins: &[String]) -> Result<(), Status> {
+    if allowed_origins.is_empty() {
+        return Ok(());
+    }
+
+    let Some(origin) = req.metadata().get("origin") else {
+        return Ok(());
+    };
+    let origin = origin
+        .to_str()
+        .map(|origin| origin.trim_end_matches('/').to_lowercase())
+        .map_err(|_| Status::permission_denied("Origin is not allowed"))?;
+
+    if allowed_origins.contains(&origin) {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(format!(
+            "Origin '{}' is not allowed",
+            origin
+        )))
+    }
+}
+
+/// The id of the user a request is sent by, as verified from its access token.
+///
+/// It is stored in the request extensions by [`validation_interceptor`], which clients cannot write to,
+/// so a client cannot make a request on behalf of another user by sending a `uid` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifiedUid(pub i32);
+
+/// This method is used to validate the access token (not refresh),
+/// and the origin of the request if any `allowed_origins` are configured.
+/// The uid of the token is stored as the request's [`VerifiedUid`], and any `uid` header sent by the client is dropped.
+pub fn validation_interceptor(
+    mut req: Request<()>,
+    allowed_origins: &[String],
+) -> Result<Request<()>, Status> {
+    check_origin(&req, allowed_origins)?;
 
-/// This method is used to validate the access token (not refresh).
-pub fn validation_interceptor(mut req: Request<()>) -> Result<Request<()>, Status> {
     let token = match req.token_string().map_err(|err| {
         Status::internal(format!(
             "could not stringify user id in request metadata, internal error {}",
@@ -22,13 +88,14 @@ pub fn validation_interceptor(mut req: Request<()>) -> Result<Request<()>, Statu
         None => return Err(Status::unauthenticated("Token not found")),
     };
 
+    req.metadata_mut().remove("uid");
+
     match token.validate() {
         Ok(token_data) => {
-            req.metadata_mut().insert(
-                "uid",
-                metadata::MetadataValue::from_str(&token_data.claims.sub)
-                    .map_err(|err| Status::internal(err.to_string()))?,
-            );
+            let uid = token_data.claims.sub.parse().map_err(|_| {
+                Status::unauthenticated("The access token does not identify a user")
+            })?;
+            req.extensions_mut().insert(VerifiedUid(uid));
             Ok(req)
         }
         Err(err) => Err(err.into()),
@@ -241,6 +308,60 @@ impl Display for Token {
     }
 }
 
+/// The claims of a token confirming that a user wants to delete a project.
+///
+/// Confirmation tokens are signed with the access token secret, but their claims differ from [`Claims`],
+/// so an access token is never accepted as a confirmation token or vice versa.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteConfirmationClaims {
+    pub uid: i32,
+    pub project_id: i32,
+    exp: usize,
+}
+
+impl DeleteConfirmationClaims {
+    /// Creates a signed confirmation token for deleting `project_id` as `uid`, valid for `duration`.
+    ///
+    /// Returns the token together with the unix timestamp at which it expires.
+    pub fn new_token(
+        uid: i32,
+        project_id: i32,
+        duration: Duration,
+    ) -> Result<(String, i64), TokenError> {
+        let expiration = Utc::now()
+            .checked_add_signed(duration)
+            .ok_or(TokenError::InvalidSignature)?
+            .timestamp();
+
+        let claims = DeleteConfirmationClaims {
+            uid,
+            project_id,
+            exp: expiration as usize,
+        };
+
+        let token = encode(
+            &Header::new(Algorithm::HS512),
+            &claims,
+            &EncodingKey::from_secret(TokenType::AccessToken.secret().as_bytes()),
+        )?;
+
+        Ok((token, expiration))
+    }
+
+    /// Validates a confirmation token and returns its claims.
+    pub fn validate(token: &str) -> Result<DeleteConfirmationClaims, TokenError> {
+        let mut validation = Validation::new(Algorithm::HS512);
+        validation.validate_exp = true;
+
+        Ok(decode::<DeleteConfirmationClaims>(
+            token,
+            &DecodingKey::from_secret(TokenType::AccessToken.secret().as_bytes()),
+            &validation,
+        )?
+        .claims)
+    }
+}
+
 /// Token errors that can be returned by authentication.
 #[derive(Debug, PartialEq, thiserror::Error)]
 pub enum TokenError {
@@ -307,15 +428,13 @@ impl<T> RequestExt for Request<T> {
             None => Ok(None),
         }
     }
-    /// Returns the uid from the request metadata.
+    /// Returns the uid verified from the request's access token by [`validation_interceptor`].
+    /// A `uid` header sent by the client is never read.
     fn uid(&self) -> Result<Option<i32>, ToStrError> {
-        match self.metadata().get("uid") {
-            Some(val) => match val.to_str()?.parse::<i32>() {
-                Ok(val) => Ok(Some(val)),
-                Err(_err) => Ok(None),
-            },
-            None => Ok(None),
-        }
+        Ok(self
+            .extensions()
+            .get::<VerifiedUid>()
+            .map(|VerifiedUid(uid)| *uid))
     }
 }
 
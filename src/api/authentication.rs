@@ -0,0 +1,108 @@
+//! Authentication required on every endpoint, for internal-only deployments.
+//!
+//! When enabled, the [`RequireAuthLayer`] rejects every request to an endpoint that is not marked
+//! `#[requires(Public)]` with [`tonic::Code::Unauthenticated`], unless it carries a valid access token.
+//! This happens before the request reaches any controller.
+use crate::api::auth::{Token, TokenType};
+use crate::api::ecdar_api::ENDPOINT_ROLES;
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Returns the name of the endpoint with the given gRPC path as listed in [`ENDPOINT_ROLES`],
+/// e.g. `EcdarApiAuth/GetAuthToken` for `/ecdar_proto_buf.EcdarApiAuth/GetAuthToken`.
+pub fn endpoint_name(path: &str) -> String {
+    let (service, method) = path
+        .trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or_default();
+    let service = service.rsplit('.').next().unwrap_or_default();
+    format!("{}/{}", service, method)
+}
+
+/// Returns true if the endpoint with the given gRPC path, e.g. `/ecdar_proto_buf.EcdarApiAuth/GetAuthToken`,
+/// is marked `#[requires(Public)]`. Unknown endpoints are not public.
+pub fn is_public_endpoint(path: &str) -> bool {
+    let endpoint = endpoint_name(path);
+
+    ENDPOINT_ROLES
+        .iter()
+        .any(|(name, role)| *name == endpoint && *role == "Public")
+}
+
+/// Returns true if the request carries an access token that is valid and not expired.
+fn has_valid_access_token<B>(request: &http::Request<B>) -> bool {
+    request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim_start_matches("Bearer "))
+        .is_some_and(|token| {
+            Token::from_str(TokenType::AccessToken, token)
+                .validate()
+                .is_ok()
+        })
+}
+
+/// A [`Layer`] that, when enabled, rejects unauthenticated requests to every endpoint that is not public.
+#[derive(Clone, Debug, Default)]
+pub struct RequireAuthLayer {
+    enabled: bool,
+}
+
+impl RequireAuthLayer {
+    pub fn new(enabled: bool) -> Self {
+        RequireAuthLayer { enabled }
+    }
+}
+
+impl<S> Layer<S> for RequireAuthLayer {
+    type Service = RequireAuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireAuthService {
+            inner,
+            enabled: self.enabled,
+        }
+    }
+}
+
+/// The [`Service`] created by [`RequireAuthLayer`].
+#[derive(Clone, Debug)]
+pub struct RequireAuthService<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S, B> Service<http::Request<B>> for RequireAuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if self.enabled
+            && !is_public_endpoint(request.uri().path())
+            && !has_valid_access_token(&request)
+        {
+            let response = Status::unauthenticated("A valid access token is required").to_http();
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/api/authentication.rs"]
+mod tests;
@@ -12,22 +12,47 @@ impl ConcreteEcdarApi {
     }
 }
 
+pub(crate) use routes::ENDPOINT_ROLES;
+
+/// Logs a call to one of the deprecated `*_model` endpoints, which forward to their `*_project` counterparts
+/// until clients have moved over.
+fn warn_deprecated(endpoint: &str, replacement: &str) {
+    log::warn!(
+        "the {} endpoint is deprecated, use {} instead",
+        endpoint,
+        replacement
+    );
+}
+
 /// A module that contains all implementations for protobuffer endpoints.
 ///
 /// The module uses the attribute macro `endpoints` to automatically implement the `endpoints` function as specified by the protobuffers.
 /// Therefore, if new endpoints or services are added and implemented by the api server, then the macro will automatically add it to the list.
+/// Each endpoint is annotated with the minimum role it requires, e.g. `#[requires(Role::Editor)]`, which the macro
+/// groups the endpoints by. Endpoints without the attribute require the caller to be authenticated.
 /// The macro can be found in the `ecdar_api_macros` crate.
 #[ecdar_api_macros::endpoints]
 mod routes {
     use super::super::server::protobuf::{
         ecdar_api_auth_server::EcdarApiAuth, ecdar_api_server::EcdarApi,
-        ecdar_backend_server::EcdarBackend, CreateAccessRequest, CreateProjectRequest,
-        CreateProjectResponse, CreateQueryRequest, CreateUserRequest, DeleteAccessRequest,
-        DeleteProjectRequest, DeleteQueryRequest, EndpointsResponse, GetAuthTokenRequest,
-        GetAuthTokenResponse, GetProjectRequest, GetProjectResponse, GetUsersRequest,
-        GetUsersResponse, ListAccessInfoRequest, ListAccessInfoResponse, ListProjectsInfoResponse,
-        QueryRequest, QueryResponse, SendQueryRequest, SendQueryResponse, SimulationStartRequest,
-        SimulationStepRequest, SimulationStepResponse, UpdateAccessRequest, UpdateProjectRequest,
+        ecdar_backend_server::EcdarBackend, CheckConsistencyRequest, CheckConsistencyResponse,
+        CreateAccessRequest, CreateProjectRequest, CreateProjectResponse,
+        CreateProjectWithQueriesRequest, CreateProjectWithQueriesResponse, CreateQueryRequest,
+        CreateUserRequest, DeleteAccessRequest, DeleteProjectRequest, DeleteQueryRequest,
+        DiffProjectRequest, DiffProjectResponse, EndpointsResponse, GetAccessesRequest,
+        GetAccessesResponse, GetAuthTokenRequest, GetAuthTokenResponse, GetCurrentUserResponse,
+        GetDashboardResponse, GetLockStatusRequest, GetLockStatusResponse,
+        GetOutdatedQueryCountRequest, GetOutdatedQueryCountResponse, GetProjectHistoryRequest,
+        GetProjectHistoryResponse, GetProjectRequest, GetProjectResponse, GetQueryHistoryRequest,
+        GetQueryHistoryResponse, GetServerInfoResponse, GetUsersRequest, GetUsersResponse,
+        HeartbeatRequest, ListAccessInfoRequest, ListAccessInfoResponse, ListMyLocksResponse,
+        ListMyRolesRequest, ListMyRolesResponse, ListProjectsInfoRequest, ListProjectsInfoResponse,
+        ListProjectsRequest, ListProjectsResponse, ListSessionsResponse, ProxyReveaalRequest,
+        ProxyReveaalResponse, QueryRequest, QueryResponse, RemoveCollaboratorRequest,
+        RequestDeleteRequest, RequestDeleteResponse, ResolveUserRequest, ResolveUserResponse,
+        RoleEndpoints, SendOutdatedQueriesRequest, SendOutdatedQueriesResponse, SendQueryRequest,
+        SendQueryResponse, SimulationStartRequest, SimulationStepRequest, SimulationStepResponse,
+        UpdateAccessRequest, UpdateAccessesRequest, UpdateAccessesResponse, UpdateProjectRequest,
         UpdateQueryRequest, UpdateUserRequest, UserTokenResponse,
     };
 
@@ -36,6 +61,7 @@ mod routes {
     /// Implementation of all the endpoits that the Ecdar API service expose through protobuffers.
     #[tonic::async_trait]
     impl EcdarApi for super::ConcreteEcdarApi {
+        #[requires(Role::Reader)]
         async fn get_project(
             &self,
             request: Request<GetProjectRequest>,
@@ -46,6 +72,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Authenticated)]
         async fn create_project(
             &self,
             request: Request<CreateProjectRequest>,
@@ -56,6 +83,18 @@ mod routes {
                 .await
         }
 
+        #[requires(Authenticated)]
+        async fn create_project_with_queries(
+            &self,
+            request: Request<CreateProjectWithQueriesRequest>,
+        ) -> Result<Response<CreateProjectWithQueriesResponse>, Status> {
+            self.controllers
+                .project_controller
+                .create_project_with_queries(request)
+                .await
+        }
+
+        #[requires(Role::Editor)]
         async fn update_project(
             &self,
             request: Request<UpdateProjectRequest>,
@@ -66,6 +105,48 @@ mod routes {
                 .await
         }
 
+        #[requires(Role::Editor)]
+        async fn heartbeat(
+            &self,
+            request: Request<HeartbeatRequest>,
+        ) -> Result<Response<()>, Status> {
+            self.controllers.project_controller.heartbeat(request).await
+        }
+
+        #[requires(Role::Reader)]
+        async fn get_project_history(
+            &self,
+            request: Request<GetProjectHistoryRequest>,
+        ) -> Result<Response<GetProjectHistoryResponse>, Status> {
+            self.controllers
+                .project_controller
+                .get_project_history(request)
+                .await
+        }
+
+        #[requires(Role::Reader)]
+        async fn diff_project(
+            &self,
+            request: Request<DiffProjectRequest>,
+        ) -> Result<Response<DiffProjectResponse>, Status> {
+            self.controllers
+                .project_controller
+                .diff_project(request)
+                .await
+        }
+
+        #[requires(Role::Reader)]
+        async fn get_lock_status(
+            &self,
+            request: Request<GetLockStatusRequest>,
+        ) -> Result<Response<GetLockStatusResponse>, Status> {
+            self.controllers
+                .project_controller
+                .get_lock_status(request)
+                .await
+        }
+
+        #[requires(Owner)]
         async fn delete_project(
             &self,
             request: Request<DeleteProjectRequest>,
@@ -76,9 +157,21 @@ mod routes {
                 .await
         }
 
+        #[requires(Owner)]
+        async fn request_delete(
+            &self,
+            request: Request<RequestDeleteRequest>,
+        ) -> Result<Response<RequestDeleteResponse>, Status> {
+            self.controllers
+                .project_controller
+                .request_delete(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
         async fn list_projects_info(
             &self,
-            request: Request<()>,
+            request: Request<ListProjectsInfoRequest>,
         ) -> Result<Response<ListProjectsInfoResponse>, Status> {
             self.controllers
                 .project_controller
@@ -86,6 +179,103 @@ mod routes {
                 .await
         }
 
+        #[requires(Authenticated)]
+        async fn list_my_roles(
+            &self,
+            request: Request<ListMyRolesRequest>,
+        ) -> Result<Response<ListMyRolesResponse>, Status> {
+            self.controllers
+                .project_controller
+                .list_my_roles(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
+        async fn list_my_locks(
+            &self,
+            request: Request<()>,
+        ) -> Result<Response<ListMyLocksResponse>, Status> {
+            self.controllers
+                .project_controller
+                .list_my_locks(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
+        async fn get_dashboard(
+            &self,
+            request: Request<()>,
+        ) -> Result<Response<GetDashboardResponse>, Status> {
+            self.controllers
+                .project_controller
+                .get_dashboard(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
+        async fn list_projects(
+            &self,
+            request: Request<ListProjectsRequest>,
+        ) -> Result<Response<ListProjectsResponse>, Status> {
+            self.controllers
+                .project_controller
+                .list_projects(request)
+                .await
+        }
+
+        /// Deprecated alias of `get_project`, kept for clients from before models were renamed to projects.
+        #[requires(Role::Reader)]
+        async fn get_model(
+            &self,
+            request: Request<GetProjectRequest>,
+        ) -> Result<Response<GetProjectResponse>, Status> {
+            super::warn_deprecated("GetModel", "GetProject");
+            self.controllers
+                .project_controller
+                .get_project(request)
+                .await
+        }
+
+        /// Deprecated alias of `create_project`.
+        #[requires(Authenticated)]
+        async fn create_model(
+            &self,
+            request: Request<CreateProjectRequest>,
+        ) -> Result<Response<CreateProjectResponse>, Status> {
+            super::warn_deprecated("CreateModel", "CreateProject");
+            self.controllers
+                .project_controller
+                .create_project(request)
+                .await
+        }
+
+        /// Deprecated alias of `update_project`.
+        #[requires(Role::Editor)]
+        async fn update_model(
+            &self,
+            request: Request<UpdateProjectRequest>,
+        ) -> Result<Response<()>, Status> {
+            super::warn_deprecated("UpdateModel", "UpdateProject");
+            self.controllers
+                .project_controller
+                .update_project(request)
+                .await
+        }
+
+        /// Deprecated alias of `delete_project`.
+        #[requires(Owner)]
+        async fn delete_model(
+            &self,
+            request: Request<DeleteProjectRequest>,
+        ) -> Result<Response<()>, Status> {
+            super::warn_deprecated("DeleteModel", "DeleteProject");
+            self.controllers
+                .project_controller
+                .delete_project(request)
+                .await
+        }
+
+        #[requires(Role::Reader)]
         async fn list_access_info(
             &self,
             request: Request<ListAccessInfoRequest>,
@@ -96,6 +286,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Role::Editor)]
         async fn create_access(
             &self,
             request: Request<CreateAccessRequest>,
@@ -106,6 +297,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Role::Editor)]
         async fn update_access(
             &self,
             request: Request<UpdateAccessRequest>,
@@ -116,6 +308,29 @@ mod routes {
                 .await
         }
 
+        #[requires(Role::Editor)]
+        async fn update_accesses(
+            &self,
+            request: Request<UpdateAccessesRequest>,
+        ) -> Result<Response<UpdateAccessesResponse>, Status> {
+            self.controllers
+                .access_controller
+                .update_accesses(request)
+                .await
+        }
+
+        #[requires(Role::Editor)]
+        async fn get_accesses(
+            &self,
+            request: Request<GetAccessesRequest>,
+        ) -> Result<Response<GetAccessesResponse>, Status> {
+            self.controllers
+                .access_controller
+                .get_accesses(request)
+                .await
+        }
+
+        #[requires(Role::Editor)]
         async fn delete_access(
             &self,
             request: Request<DeleteAccessRequest>,
@@ -126,6 +341,18 @@ mod routes {
                 .await
         }
 
+        #[requires(Role::Editor)]
+        async fn remove_collaborator(
+            &self,
+            request: Request<RemoveCollaboratorRequest>,
+        ) -> Result<Response<()>, Status> {
+            self.controllers
+                .access_controller
+                .remove_collaborator(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
         async fn update_user(
             &self,
             request: Request<UpdateUserRequest>,
@@ -133,10 +360,23 @@ mod routes {
             self.controllers.user_controller.update_user(request).await
         }
 
+        #[requires(Authenticated)]
+        async fn get_current_user(
+            &self,
+            request: Request<()>,
+        ) -> Result<Response<GetCurrentUserResponse>, Status> {
+            self.controllers
+                .user_controller
+                .get_current_user(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
         async fn delete_user(&self, request: Request<()>) -> Result<Response<()>, Status> {
             self.controllers.user_controller.delete_user(request).await
         }
 
+        #[requires(Authenticated)]
         async fn get_users(
             &self,
             request: Request<GetUsersRequest>,
@@ -144,6 +384,16 @@ mod routes {
             self.controllers.user_controller.get_users(request).await
         }
 
+        #[requires(Authenticated)]
+        async fn resolve_user(
+            &self,
+            request: Request<ResolveUserRequest>,
+        ) -> Result<Response<ResolveUserResponse>, Status> {
+            self.controllers.user_controller.resolve_user(request).await
+        }
+
+        // The role needed to create, update and delete queries is configured by `QUERY_MUTATION_MIN_ROLE`,
+        // so the query controller checks it instead of an attribute
         async fn create_query(
             &self,
             request: Request<CreateQueryRequest>,
@@ -174,6 +424,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Role::Reader)]
         async fn send_query(
             &self,
             request: Request<SendQueryRequest>,
@@ -181,17 +432,82 @@ mod routes {
             self.controllers.query_controller.send_query(request).await
         }
 
+        #[requires(Role::Reader)]
+        async fn get_outdated_query_count(
+            &self,
+            request: Request<GetOutdatedQueryCountRequest>,
+        ) -> Result<Response<GetOutdatedQueryCountResponse>, Status> {
+            self.controllers
+                .query_controller
+                .get_outdated_query_count(request)
+                .await
+        }
+
+        #[requires(Role::Reader)]
+        async fn send_outdated_queries(
+            &self,
+            request: Request<SendOutdatedQueriesRequest>,
+        ) -> Result<Response<SendOutdatedQueriesResponse>, Status> {
+            self.controllers
+                .query_controller
+                .send_outdated_queries(request)
+                .await
+        }
+
+        #[requires(Role::Reader)]
+        async fn check_consistency(
+            &self,
+            request: Request<CheckConsistencyRequest>,
+        ) -> Result<Response<CheckConsistencyResponse>, Status> {
+            self.controllers
+                .reveaal_controller
+                .check_consistency(request)
+                .await
+        }
+
+        #[requires(Role::Reader)]
+        async fn get_query_history(
+            &self,
+            request: Request<GetQueryHistoryRequest>,
+        ) -> Result<Response<GetQueryHistoryResponse>, Status> {
+            self.controllers
+                .query_controller
+                .get_query_history(request)
+                .await
+        }
+
+        #[requires(Authenticated)]
+        async fn proxy_reveaal(
+            &self,
+            request: Request<ProxyReveaalRequest>,
+        ) -> Result<Response<ProxyReveaalResponse>, Status> {
+            self.controllers.reveaal_controller.proxy(request).await
+        }
+
+        #[requires(Authenticated)]
         async fn delete_session(&self, request: Request<()>) -> Result<Response<()>, Status> {
             self.controllers
                 .session_controller
                 .delete_session(request)
                 .await
         }
+
+        #[requires(Authenticated)]
+        async fn list_sessions(
+            &self,
+            request: Request<()>,
+        ) -> Result<Response<ListSessionsResponse>, Status> {
+            self.controllers
+                .session_controller
+                .list_sessions(request)
+                .await
+        }
     }
 
     /// Implementation of the EcdarBackend trait, which is used to ensure backwards compatability with the Reveaal engine.
     #[tonic::async_trait]
     impl EcdarBackend for super::ConcreteEcdarApi {
+        #[requires(Public)]
         async fn get_user_token(
             &self,
             _request: Request<()>,
@@ -202,6 +518,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Public)]
         async fn send_query(
             &self,
             request: Request<QueryRequest>,
@@ -212,6 +529,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Public)]
         async fn start_simulation(
             &self,
             request: Request<SimulationStartRequest>,
@@ -222,6 +540,7 @@ mod routes {
                 .await
         }
 
+        #[requires(Public)]
         async fn take_simulation_step(
             &self,
             request: Request<SimulationStepRequest>,
@@ -238,6 +557,7 @@ mod routes {
     /// Therefore if changed then the macro should be changed too, else you will only get compile errors.
     #[tonic::async_trait]
     impl EcdarApiAuth for super::ConcreteEcdarApi {
+        #[requires(Public)]
         async fn get_auth_token(
             &self,
             request: Request<GetAuthTokenRequest>,
@@ -248,11 +568,27 @@ mod routes {
                 .await
         }
 
+        #[requires(Public)]
         async fn create_user(
             &self,
             request: Request<CreateUserRequest>,
         ) -> Result<Response<()>, Status> {
             self.controllers.user_controller.create_user(request).await
         }
+
+        #[requires(Public)]
+        async fn get_server_info(
+            &self,
+            request: Request<()>,
+        ) -> Result<Response<GetServerInfoResponse>, Status> {
+            self.controllers
+                .server_info_controller
+                .get_server_info(request)
+                .await
+        }
     }
 }
+
+#[cfg(test)]
+#[path = "../tests/api/ecdar_api.rs"]
+mod tests;
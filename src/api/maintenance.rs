@@ -0,0 +1,83 @@
+//! Read-only mode for maintenance windows.
+//!
+//! While the API is in read-only mode, the [`ReadOnlyLayer`] rejects every request to an endpoint that mutates data
+//! with [`tonic::Code::Unavailable`], while requests to all other endpoints are served as usual.
+use futures::future::BoxFuture;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Endpoints whose name starts with one of these prefixes mutate data.
+const MUTATING_ENDPOINT_PREFIXES: [&str; 3] = ["Create", "Update", "Delete"];
+
+/// Returns true if the API is currently in read-only mode.
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Turns read-only mode on or off. This takes effect for all subsequent requests.
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+/// Returns true if the endpoint with the given gRPC path, e.g. `/ecdar_proto_buf.EcdarApi/CreateProject`, mutates data.
+pub fn is_mutating_endpoint(path: &str) -> bool {
+    let endpoint = path.rsplit('/').next().unwrap_or_default();
+
+    MUTATING_ENDPOINT_PREFIXES
+        .iter()
+        .any(|prefix| endpoint.starts_with(prefix))
+}
+
+/// A [`Layer`] that rejects requests to mutating endpoints while the API is in read-only mode.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOnlyLayer;
+
+impl<S> Layer<S> for ReadOnlyLayer {
+    type Service = ReadOnlyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReadOnlyService { inner }
+    }
+}
+
+/// The [`Service`] created by [`ReadOnlyLayer`].
+#[derive(Clone, Debug)]
+pub struct ReadOnlyService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for ReadOnlyService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if is_read_only() && is_mutating_endpoint(request.uri().path()) {
+            let response = Status::unavailable(
+                "The server is in read-only mode due to maintenance, please try again later",
+            )
+            .to_http();
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/api/maintenance.rs"]
+mod tests;
@@ -0,0 +1,170 @@
+//! The `/metrics` endpoint, serving the counters and gauges of the server in the Prometheus text exposition format.
+//!
+//! The endpoint is plain HTTP next to the gRPC services, so the [`MetricsLayer`] answers scrapes of it
+//! before they reach the gRPC router. Scrapes are authorized as configured by [`MetricsAccess`].
+use crate::api::metrics_auth::{authorize_metrics_request, MetricsAccess};
+use crate::contexts::context_traits::InUseContextTrait;
+use crate::controllers::controller_impls::project_controller::LockConfig;
+use crate::services::reveaal_metrics::ReveaalMetrics;
+use futures::future::BoxFuture;
+use http::{header, Method, StatusCode};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::{empty_body, BoxBody};
+use tonic::codegen::Body as _;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::transport::Body;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// The path scrapes are served at.
+pub const METRICS_PATH: &str = "/metrics";
+
+/// The content type of the Prometheus text exposition format.
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// The name of the gauge of projects whose lock is held.
+const ACTIVE_LOCKS_METRIC_NAME: &str = "ecdar_active_locks";
+
+/// The number of projects whose lock is held, counted from the in use rows at every scrape.
+#[derive(Clone)]
+pub struct ActiveLocksGauge {
+    in_use_context: Arc<dyn InUseContextTrait>,
+    timeout: chrono::Duration,
+}
+
+impl ActiveLocksGauge {
+    /// A lock counts as long as it is held under `locks`, including its grace period.
+    pub fn new(in_use_context: Arc<dyn InUseContextTrait>, locks: &LockConfig) -> Self {
+        ActiveLocksGauge {
+            in_use_context,
+            timeout: locks.duration + locks.grace_period,
+        }
+    }
+
+    /// Renders the gauge in the Prometheus text exposition format.
+    /// A failed count is logged and the gauge left out, so the other metrics are still served.
+    pub async fn render(&self) -> String {
+        match self.in_use_context.count_active_locks(self.timeout).await {
+            Ok(count) => format!(
+                "# HELP {0} Projects whose lock is held.\n# TYPE {0} gauge\n{0} {1}\n",
+                ACTIVE_LOCKS_METRIC_NAME, count
+            ),
+            Err(err) => {
+                log::warn!("failed to count the active locks for metrics: {}", err);
+                String::new()
+            }
+        }
+    }
+}
+
+/// A [`Layer`] that serves `GET /metrics` and passes every other request on.
+#[derive(Clone)]
+pub struct MetricsLayer {
+    access: Arc<MetricsAccess>,
+    reveaal_metrics: Arc<ReveaalMetrics>,
+    active_locks: ActiveLocksGauge,
+}
+
+impl MetricsLayer {
+    pub fn new(
+        access: MetricsAccess,
+        reveaal_metrics: Arc<ReveaalMetrics>,
+        active_locks: ActiveLocksGauge,
+    ) -> Self {
+        MetricsLayer {
+            access: Arc::new(access),
+            reveaal_metrics,
+            active_locks,
+        }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            access: self.access.clone(),
+            reveaal_metrics: self.reveaal_metrics.clone(),
+            active_locks: self.active_locks.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`MetricsLayer`].
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    access: Arc<MetricsAccess>,
+    reveaal_metrics: Arc<ReveaalMetrics>,
+    active_locks: ActiveLocksGauge,
+}
+
+impl<S> MetricsService<S> {
+    /// Answers a scrape, with the metrics if it is authorized and with the rejection otherwise.
+    fn scrape<B>(&self, request: &http::Request<B>) -> BoxFuture<'static, http::Response<BoxBody>> {
+        let remote_addr = request
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(TcpConnectInfo::remote_addr);
+
+        let authorization = authorize_metrics_request(&self.access, request, remote_addr);
+        let reveaal_metrics = self.reveaal_metrics.clone();
+        let active_locks = self.active_locks.clone();
+
+        Box::pin(async move {
+            let response = match authorization {
+                Ok(()) => {
+                    let mut metrics = reveaal_metrics.render();
+                    metrics.push_str(&active_locks.render().await);
+
+                    http::Response::builder()
+                        .header(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)
+                        .body(
+                            Body::from(metrics)
+                                .map_err(|err| Status::internal(err.to_string()))
+                                .boxed_unsync(),
+                        )
+                }
+                Err(StatusCode::UNAUTHORIZED) => http::Response::builder()
+                    .status(StatusCode::UNAUTHORIZED)
+                    .header(header::WWW_AUTHENTICATE, "Bearer")
+                    .body(empty_body()),
+                Err(status) => http::Response::builder().status(status).body(empty_body()),
+            };
+
+            // The parts of the response are all valid, so building it cannot fail
+            response.unwrap_or_else(|_| http::Response::new(empty_body()))
+        })
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for MetricsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if request.method() == Method::GET && request.uri().path() == METRICS_PATH {
+            let response = self.scrape(&request);
+
+            return Box::pin(async move { Ok(response.await) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/api/metrics.rs"]
+mod tests;
@@ -0,0 +1,65 @@
+//! Access control for the metrics endpoint, which must not be world-readable as it exposes internal behavior.
+//!
+//! When `METRICS_TOKEN` is set, scrapes must carry it as a bearer token in the `authorization` header.
+//! Otherwise only scrapes from the loopback interface are allowed.
+use http::{header, Request, StatusCode};
+use std::net::SocketAddr;
+
+/// How scrapes of the metrics endpoint are authorized.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricsAccess {
+    /// Scrapes must present this bearer token.
+    Token(String),
+    /// Scrapes must come from the loopback interface.
+    LocalhostOnly,
+}
+
+/// Parses the value of `METRICS_TOKEN` into the metrics access it configures.
+/// # Errors
+/// Returns a description of the problem if the token cannot be sent in an `authorization` header.
+pub fn parse_metrics_token(value: &str) -> Result<MetricsAccess, String> {
+    if !value.chars().all(|c| c.is_ascii_graphic()) {
+        return Err("the token must only contain visible ASCII characters".to_string());
+    }
+    Ok(MetricsAccess::Token(value.to_string()))
+}
+
+/// Checks whether a scrape of the metrics endpoint from `remote_addr` is allowed.
+/// # Errors
+/// Returns `401 Unauthorized` if the bearer token is missing or wrong,
+/// and `403 Forbidden` if the endpoint is localhost only and the scrape comes from elsewhere.
+pub fn authorize_metrics_request<B>(
+    access: &MetricsAccess,
+    request: &Request<B>,
+    remote_addr: Option<SocketAddr>,
+) -> Result<(), StatusCode> {
+    match access {
+        MetricsAccess::Token(token) => {
+            let presented = request
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+
+            match presented {
+                Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => {
+                    Ok(())
+                }
+                _ => Err(StatusCode::UNAUTHORIZED),
+            }
+        }
+        MetricsAccess::LocalhostOnly => match remote_addr {
+            Some(addr) if addr.ip().is_loopback() => Ok(()),
+            _ => Err(StatusCode::FORBIDDEN),
+        },
+    }
+}
+
+/// Compares two byte strings in time independent of where they differ, so the token cannot be guessed by timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+#[path = "../tests/api/metrics_auth.rs"]
+mod tests;
@@ -1,3 +1,10 @@
 pub mod auth;
+pub mod authentication;
 pub mod ecdar_api;
+pub mod maintenance;
+pub mod metrics;
+pub mod metrics_auth;
+pub mod rate_limit;
 pub mod server;
+pub mod timeouts;
+pub mod unknown_fields;
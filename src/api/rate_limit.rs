@@ -0,0 +1,193 @@
+//! Coarse per-IP rate limiting, protecting public endpoints such as sign-up and login from floods.
+//!
+//! The [`RateLimitLayer`] gives every client IP a token bucket and rejects requests with
+//! [`tonic::Code::ResourceExhausted`] once it is empty. This happens before authentication,
+//! so unauthenticated requests are limited as well.
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::body::BoxBody;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Buckets of clients that have been quiet long enough to be full again are dropped
+/// once this many clients are tracked.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// The rate every client IP is limited to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    /// How many requests a client may make per second on average
+    pub requests_per_second: u32,
+    /// How many requests a client may make at once after being idle
+    pub burst: u32,
+    /// Proxies whose `X-Forwarded-For` header is trusted to name the client
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// Parses the value of `RATE_LIMIT_TRUSTED_PROXIES`, a comma separated list of IP addresses.
+/// # Errors
+/// Returns a description of the problem if an entry is not an IP address.
+pub fn parse_trusted_proxies(value: &str) -> Result<Vec<IpAddr>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|proxy| !proxy.is_empty())
+        .map(|proxy| {
+            proxy
+                .parse()
+                .map_err(|_| format!("'{}' is not an IP address", proxy))
+        })
+        .collect()
+}
+
+/// Returns the IP of the client that sent the request, or `None` if the connection is unknown.
+///
+/// Requests arriving from a trusted proxy are attributed to the nearest address in `X-Forwarded-For`
+/// that is not itself a trusted proxy, so a client cannot pick its own address by adding entries.
+pub fn client_ip<B>(request: &http::Request<B>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<TcpConnectInfo>()?
+        .remote_addr()?
+        .ip();
+
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<&str> = request
+        .headers()
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    forwarded
+        .into_iter()
+        .rev()
+        .map_while(|ip| ip.parse::<IpAddr>().ok())
+        .find(|ip| !trusted_proxies.contains(ip))
+        .or(Some(peer))
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// The token buckets of every client, shared by all clones of the service.
+#[derive(Clone)]
+struct Buckets {
+    rate_limit: Arc<RateLimit>,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+impl Buckets {
+    /// Takes a token from the bucket of `ip`, returning false if it is empty.
+    fn try_acquire(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let burst = f64::from(self.rate_limit.burst);
+        let rate = f64::from(self.rate_limit.requests_per_second);
+        let refill = |bucket: &mut Bucket| {
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+            bucket.refilled_at = now;
+        };
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&ip) {
+            buckets.retain(|_, bucket| {
+                refill(bucket);
+                bucket.tokens < burst
+            });
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: burst,
+            refilled_at: now,
+        });
+        refill(bucket);
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+/// A [`Layer`] that, when configured, limits the rate of requests from every client IP.
+#[derive(Clone, Default)]
+pub struct RateLimitLayer {
+    buckets: Option<Buckets>,
+}
+
+impl RateLimitLayer {
+    pub fn new(rate_limit: Option<RateLimit>) -> Self {
+        RateLimitLayer {
+            buckets: rate_limit.map(|rate_limit| Buckets {
+                rate_limit: Arc::new(rate_limit),
+                buckets: Default::default(),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`RateLimitLayer`].
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    buckets: Option<Buckets>,
+}
+
+impl<S, B> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if let Some(buckets) = &self.buckets {
+            let ip = client_ip(&request, &buckets.rate_limit.trusted_proxies);
+
+            if ip.is_some_and(|ip| !buckets.try_acquire(ip)) {
+                let response =
+                    Status::resource_exhausted("Too many requests, try again later").to_http();
+
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/api/rate_limit.rs"]
+mod tests;
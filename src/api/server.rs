@@ -1,40 +1,107 @@
-use std::env;
-
+use tonic::codec::CompressionEncoding;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::Server;
 
 use crate::api::auth;
+use crate::api::authentication::RequireAuthLayer;
 use crate::api::ecdar_api::ConcreteEcdarApi;
+use crate::api::maintenance::{self, ReadOnlyLayer};
+use crate::api::metrics::{ActiveLocksGauge, MetricsLayer};
+use crate::api::rate_limit::RateLimitLayer;
 use crate::api::server::protobuf::ecdar_api_auth_server::EcdarApiAuthServer;
 use crate::api::server::protobuf::ecdar_api_server::EcdarApiServer;
 use crate::api::server::protobuf::ecdar_backend_server::EcdarBackendServer;
+use crate::api::timeouts::TimeoutLayer;
+use crate::api::unknown_fields::StrictFieldsLayer;
+use crate::config::Config;
+use crate::contexts::context_traits::InUseContextTrait;
 use crate::controllers::controller_collection::ControllerCollection;
+use crate::services::reveaal_metrics::ReveaalMetrics;
+use std::sync::Arc;
 
 pub mod protobuf {
     tonic::include_proto!("ecdar_proto_buf");
 }
-#[allow(clippy::expect_used)]
+
+/// Enables compression on a generated gRPC server if an encoding is configured.
+///
+/// Responses are only compressed for clients that list the encoding in their `grpc-accept-encoding` header,
+/// and requests compressed with the encoding are accepted.
+macro_rules! with_compression {
+    ($server:expr, $compression:expr) => {
+        match $compression {
+            Some(encoding) => $server
+                .send_compressed(encoding)
+                .accept_compressed(encoding),
+            None => $server,
+        }
+    };
+}
+
+/// Parses the value of `GRPC_COMPRESSION`, where `none` disables compression.
+/// # Errors
+/// Returns a description of the problem if the encoding is not supported.
+pub fn parse_compression(value: &str) -> Result<Option<CompressionEncoding>, String> {
+    match value {
+        "none" => Ok(None),
+        "gzip" => Ok(Some(CompressionEncoding::Gzip)),
+        _ => Err(format!(
+            "'{}' is not a supported compression, expected 'gzip' or 'none'",
+            value
+        )),
+    }
+}
+
+/// Serves the gRPC services, and the metrics of `reveaal_metrics` along with the number of locks held
+/// in `in_use_context` at `/metrics`, on the configured address.
 pub async fn start_grpc_server(
     controllers: ControllerCollection,
+    config: &Config,
+    reveaal_metrics: Arc<ReveaalMetrics>,
+    in_use_context: Arc<dyn InUseContextTrait>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // defining address for our service
-    let addr = env::var("API_ADDRESS")
-        .expect("Expected API_ADDRESS to be set.")
-        .parse()
-        .expect("failed to parse ip address from environment variable");
+    let addr = config.api_address;
+
+    maintenance::set_read_only(config.read_only);
 
     println!("Starting grpc protobuf on '{}'", addr);
 
     let svc = ConcreteEcdarApi::new(controllers);
+    let compression = config.compression;
+    let allowed_origins = config.allowed_origins.clone();
 
     // adding services to our protobuf.
+    // HTTP/1 is accepted for the metrics endpoint, as scrapers do not speak HTTP/2
     Server::builder()
-        .add_service(EcdarApiAuthServer::new(svc.clone()))
-        .add_service(EcdarApiServer::with_interceptor(
-            svc.clone(),
-            auth::validation_interceptor,
+        .accept_http1(true)
+        .layer(RateLimitLayer::new(config.rate_limit.clone()))
+        .layer(MetricsLayer::new(
+            config.metrics_access.clone(),
+            reveaal_metrics,
+            ActiveLocksGauge::new(in_use_context, &config.locks),
+        ))
+        .layer(ReadOnlyLayer)
+        .layer(RequireAuthLayer::new(config.require_authentication))
+        .layer(StrictFieldsLayer::new(config.strict_unknown_fields))
+        .layer(TimeoutLayer::new(config.endpoint_timeouts.clone()))
+        .add_service(with_compression!(
+            EcdarApiAuthServer::new(svc.clone()),
+            compression
+        ))
+        .add_service(InterceptedService::new(
+            with_compression!(EcdarApiServer::new(svc.clone()), compression),
+            move |req| auth::validation_interceptor(req, &allowed_origins),
+        ))
+        .add_service(with_compression!(
+            EcdarBackendServer::new(svc.clone()),
+            compression
         ))
-        .add_service(EcdarBackendServer::new(svc.clone()))
         .serve(addr)
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+#[path = "../tests/api/server.rs"]
+mod tests;
@@ -0,0 +1,149 @@
+//! Per-endpoint request timeouts.
+//!
+//! Endpoints take very different amounts of time, e.g. `send_query` waits for Reveaal while `get_project`
+//! only reads the database, so a single server-wide timeout fits neither. The [`TimeoutLayer`] fails
+//! requests that are not answered within the timeout of their endpoint with [`tonic::Code::DeadlineExceeded`].
+use crate::api::authentication::endpoint_name;
+use crate::api::ecdar_api::ENDPOINT_ROLES;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// The timeouts requests are answered within.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EndpointTimeouts {
+    /// The timeout of endpoints without a timeout of their own, if any
+    pub default: Option<Duration>,
+    /// The timeouts of single endpoints, by their name in [`ENDPOINT_ROLES`], e.g. `EcdarApi/SendQuery`
+    pub endpoints: HashMap<String, Duration>,
+}
+
+impl EndpointTimeouts {
+    /// Returns the timeout of the endpoint with the given gRPC path, if it has one.
+    pub fn timeout(&self, path: &str) -> Option<Duration> {
+        self.endpoints
+            .get(&endpoint_name(path))
+            .copied()
+            .or(self.default)
+    }
+}
+
+/// Parses the value of `ENDPOINT_TIMEOUTS`, a comma separated list of `endpoint=seconds` entries,
+/// e.g. `EcdarApi/GetProject=2,EcdarApi/SendQuery=300`. Seconds may have decimals.
+/// # Errors
+/// Returns a description of the problem if an entry is malformed, names an unknown endpoint
+/// or has a timeout that is not a positive number of seconds.
+pub fn parse_endpoint_timeouts(value: &str) -> Result<HashMap<String, Duration>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (endpoint, seconds) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("'{}' is not of the form endpoint=seconds", entry))?;
+            let endpoint = endpoint.trim();
+
+            if !ENDPOINT_ROLES.iter().any(|(name, _)| *name == endpoint) {
+                return Err(format!("'{}' is not an endpoint", endpoint));
+            }
+
+            let timeout = seconds
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| *seconds > 0.0)
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or_else(|| {
+                    format!(
+                        "the timeout of '{}' must be a positive number of seconds, got '{}'",
+                        endpoint,
+                        seconds.trim()
+                    )
+                })?;
+
+            Ok((endpoint.to_string(), timeout))
+        })
+        .collect()
+}
+
+/// A [`Layer`] that fails requests not answered within the timeout of their endpoint.
+#[derive(Clone, Debug, Default)]
+pub struct TimeoutLayer {
+    timeouts: Arc<EndpointTimeouts>,
+}
+
+impl TimeoutLayer {
+    pub fn new(timeouts: EndpointTimeouts) -> Self {
+        TimeoutLayer {
+            timeouts: Arc::new(timeouts),
+        }
+    }
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = TimeoutService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutService {
+            inner,
+            timeouts: self.timeouts.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`TimeoutLayer`].
+#[derive(Clone, Debug)]
+pub struct TimeoutService<S> {
+    inner: S,
+    timeouts: Arc<EndpointTimeouts>,
+}
+
+impl<S, B> Service<http::Request<B>> for TimeoutService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let path = request.uri().path().to_string();
+        let response = self.inner.call(request);
+
+        match self.timeouts.timeout(&path) {
+            Some(timeout) => Box::pin(async move {
+                match tokio::time::timeout(timeout, response).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        log::warn!(
+                            "{} was not answered within {} ms",
+                            path,
+                            timeout.as_millis()
+                        );
+                        Ok(Status::deadline_exceeded(format!(
+                            "The request was not answered within {} ms",
+                            timeout.as_millis()
+                        ))
+                        .to_http())
+                    }
+                }
+            }),
+            None => Box::pin(response),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/api/timeouts.rs"]
+mod tests;
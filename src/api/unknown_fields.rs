@@ -0,0 +1,147 @@
+//! Optional rejection of requests carrying fields the server does not know.
+//!
+//! prost silently drops unknown fields while decoding, which hides clients built against a newer protobuf
+//! than the server. When enabled, the [`StrictFieldsLayer`] checks each request before it is decoded and
+//! rejects it with [`tonic::Code::InvalidArgument`] if it carries such fields.
+//!
+//! A message carries unknown fields if decoding and encoding it again makes it shorter. Requests that are
+//! encoded non-canonically, e.g. with unpacked repeated numbers, are therefore rejected as well.
+//! Compressed messages cannot be inspected and are always accepted.
+use crate::api::authentication::endpoint_name;
+use crate::api::ecdar_api::ENDPOINT_UNKNOWN_FIELD_CHECKS;
+use futures::future::BoxFuture;
+use prost::Message;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::Body as _;
+use tonic::transport::Body;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// The length of the prefix of every gRPC message: a compression flag and a 4 byte length.
+const MESSAGE_PREFIX_LENGTH: usize = 5;
+
+/// Returns true if the encoded `message` has fields that `T` does not declare.
+/// Messages that cannot be decoded are left for the endpoint to reject.
+pub fn has_unknown_fields<T: Message + Default>(message: &[u8]) -> bool {
+    T::decode(message).is_ok_and(|decoded| decoded.encoded_len() < message.len())
+}
+
+/// Returns true if any uncompressed message in the gRPC request `body` is found to have unknown fields by `check`.
+pub fn body_has_unknown_fields(body: &[u8], check: fn(&[u8]) -> bool) -> bool {
+    let mut rest = body;
+
+    while rest.len() >= MESSAGE_PREFIX_LENGTH {
+        let compressed = rest[0] != 0;
+        let length = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let Some(message) = rest[MESSAGE_PREFIX_LENGTH..].get(..length) else {
+            return false;
+        };
+
+        if !compressed && check(message) {
+            return true;
+        }
+        rest = &rest[MESSAGE_PREFIX_LENGTH + length..];
+    }
+
+    false
+}
+
+/// Returns the check for unknown fields of the endpoint with the given gRPC path, if it is known.
+fn unknown_field_check(path: &str) -> Option<fn(&[u8]) -> bool> {
+    let endpoint = endpoint_name(path);
+
+    ENDPOINT_UNKNOWN_FIELD_CHECKS
+        .iter()
+        .find(|(name, _)| *name == endpoint)
+        .map(|(_, check)| *check)
+}
+
+/// A [`Layer`] that, when enabled, rejects requests carrying fields their message does not declare.
+#[derive(Clone, Debug, Default)]
+pub struct StrictFieldsLayer {
+    enabled: bool,
+}
+
+impl StrictFieldsLayer {
+    pub fn new(enabled: bool) -> Self {
+        StrictFieldsLayer { enabled }
+    }
+}
+
+impl<S> Layer<S> for StrictFieldsLayer {
+    type Service = StrictFieldsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StrictFieldsService {
+            inner,
+            enabled: self.enabled,
+        }
+    }
+}
+
+/// The [`Service`] created by [`StrictFieldsLayer`].
+#[derive(Clone, Debug)]
+pub struct StrictFieldsService<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S> Service<http::Request<Body>> for StrictFieldsService<S>
+where
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let check = match unknown_field_check(request.uri().path()) {
+            Some(check) if self.enabled => check,
+            _ => return Box::pin(self.inner.call(request)),
+        };
+
+        // The service that was polled ready handles this request, leaving a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (parts, mut body) = request.into_parts();
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = body.data().await {
+                match chunk {
+                    Ok(chunk) => bytes.extend_from_slice(&chunk),
+                    Err(err) => {
+                        return Ok(Status::internal(format!(
+                            "failed to read request, internal error: {}",
+                            err
+                        ))
+                        .to_http())
+                    }
+                }
+            }
+
+            if body_has_unknown_fields(&bytes, check) {
+                return Ok(Status::invalid_argument(
+                    "The request contains fields unknown to the server, \
+                     the client is likely built against a newer protobuf version",
+                )
+                .to_http());
+            }
+
+            inner
+                .call(http::Request::from_parts(parts, Body::from(bytes)))
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/api/unknown_fields.rs"]
+mod tests;
@@ -124,6 +124,17 @@ fn main() {
         .compile(&["Ecdar-ProtoBuf/services.proto"], &["Ecdar-ProtoBuf/"])
         .expect("failed to compile protobuf");
 
+    // Expose the commit of the protobuf repository that the API is built from
+    let protobuf_version = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD:Ecdar-ProtoBuf"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=PROTOBUF_VERSION={}", protobuf_version);
+
     // Tell cargo to invalidate the crate when the protobuf repository changes
     println!("cargo:rerun-if-changed=Ecdar-ProtoBuf");
 }
@@ -0,0 +1,570 @@
+//! Startup configuration read from environment variables.
+//!
+//! Every variable the server reads is checked once before it starts, so a missing or invalid value
+//! is reported up front instead of panicking in the middle of a request.
+
+use crate::api::auth::parse_allowed_origins;
+use crate::api::metrics_auth::{parse_metrics_token, MetricsAccess};
+use crate::api::rate_limit::{parse_trusted_proxies, RateLimit};
+use crate::api::server::parse_compression;
+use crate::api::server::protobuf::ComponentsInfo;
+use crate::api::timeouts::{parse_endpoint_timeouts, EndpointTimeouts};
+use crate::contexts::components_storage::ComponentsStorage;
+use crate::contexts::context_impls::query_result_context::DEFAULT_QUERY_RESULT_HISTORY_DEPTH;
+use crate::contexts::pagination::DEFAULT_MAX_PAGE_SIZE;
+use crate::contexts::readiness::DEFAULT_READY_TIMEOUT;
+use crate::controllers::controller_impls::access_controller::DuplicateAccessPolicy;
+use crate::controllers::controller_impls::project_controller::{
+    components_info_from_template, LockConfig, ProjectConfig,
+};
+use crate::controllers::controller_impls::query_controller::{
+    parse_allowed_settings, EmptyResultPolicy, QueryConfig, QueryMutationRole,
+};
+use crate::controllers::controller_impls::reveaal_controller::parse_proxy_methods;
+use crate::controllers::controller_impls::session_controller::{SessionConfig, SessionLimitPolicy};
+use crate::controllers::controller_impls::user_controller::{
+    parse_allowed_email_domains, UserConfig,
+};
+use crate::controllers::response_size::ResponseSizeWarning;
+use crate::services::service_impls::ReveaalConfig;
+use bcrypt::DEFAULT_COST;
+use migration::{EMAIL_MAX_LENGTH, USERNAME_MAX_LENGTH};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+use tonic::codec::CompressionEncoding;
+
+/// The settings of the server, read once when it starts and handed to the parts that use them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub database_url: String,
+    pub reveaal_address: String,
+    pub api_address: SocketAddr,
+    pub read_only: bool,
+    pub require_authentication: bool,
+    /// The encoding responses are compressed with for clients that accept it, if any
+    pub compression: Option<CompressionEncoding>,
+    /// The rate every client IP is limited to, if any
+    pub rate_limit: Option<RateLimit>,
+    /// Whether requests carrying fields unknown to the server are rejected
+    pub strict_unknown_fields: bool,
+    /// The timeouts requests to each endpoint are answered within
+    pub endpoint_timeouts: EndpointTimeouts,
+    /// How scrapes of the metrics endpoint are authorized
+    pub metrics_access: MetricsAccess,
+    /// How long startup waits for the database to become reachable
+    pub database_ready_timeout: Duration,
+    /// Whether every SQL statement sent to the database is logged, for debugging
+    pub log_statements: bool,
+    /// How the components of projects are stored. Projects written as `rows` cannot be read back as `json`.
+    pub components_storage: ComponentsStorage,
+    /// The number of results kept per query
+    pub query_result_history_depth: u64,
+    /// The largest page size any paginated endpoint serves
+    pub max_page_size: u64,
+    /// Whether list endpoints answer an empty result with `NotFound` instead of an empty list,
+    /// for clients that depend on the old behavior
+    pub legacy_not_found_on_empty_list: bool,
+    /// The origins browser clients may authenticate from. An empty list allows every origin.
+    pub allowed_origins: Vec<String>,
+    /// The bcrypt cost passwords are hashed with
+    pub bcrypt_cost: u32,
+    /// What `create_access` does when the user already has an access on the project
+    pub duplicate_access_policy: DuplicateAccessPolicy,
+    /// The Reveaal methods that may be called through the proxy endpoint. An empty list disables it.
+    pub reveaal_proxy_methods: Vec<String>,
+    /// When responses are reported as large
+    pub response_size_warning: ResponseSizeWarning,
+    pub reveaal: ReveaalConfig,
+    pub locks: LockConfig,
+    pub projects: ProjectConfig,
+    pub queries: QueryConfig,
+    pub sessions: SessionConfig,
+    pub users: UserConfig,
+}
+
+/// Every missing or invalid environment variable found while loading a [`Config`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Config {
+    /// Loads the configuration from the process environment.
+    /// # Errors
+    /// Returns a [`ConfigError`] listing every missing or invalid variable.
+    pub fn from_env() -> Result<Config, ConfigError> {
+        Config::from_lookup(|name| env::var(name).ok())
+    }
+
+    /// Loads the configuration using `lookup` to read each variable.
+    /// Optional variables that are set to an empty string are treated as unset.
+    /// # Errors
+    /// Returns a [`ConfigError`] listing every missing or invalid variable.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let mut reader = Reader {
+            lookup,
+            problems: vec![],
+        };
+
+        let database_url = reader.required("DATABASE_URL");
+        let reveaal_address = reader.required("REVEAAL_ADDRESS");
+        let api_address = reader.required_parsed::<SocketAddr>("API_ADDRESS");
+        reader.required("ACCESS_TOKEN_HS512_SECRET");
+        reader.required("REFRESH_TOKEN_HS512_SECRET");
+
+        let read_only = reader.optional_bool("READ_ONLY");
+        let require_authentication = reader.optional_bool("REQUIRE_AUTHENTICATION");
+        let strict_unknown_fields = reader.optional_bool("STRICT_UNKNOWN_FIELDS");
+        let legacy_not_found_on_empty_list = reader.optional_bool("LEGACY_NOT_FOUND_ON_EMPTY_LIST");
+        let log_statements = reader.optional_bool("DB_LOG_STATEMENTS");
+
+        let lock_defaults = LockConfig::default();
+        let duration = reader
+            .optional_duration("IN_USE_DURATION_MINUTES", chrono::Duration::try_minutes)
+            .unwrap_or(lock_defaults.duration);
+        let warn_after =
+            reader.optional_duration("IN_USE_WARN_MINUTES", chrono::Duration::try_minutes);
+        if let Some(warn) = warn_after {
+            if warn <= chrono::Duration::zero() || warn >= duration {
+                reader.problems.push(format!(
+                    "IN_USE_WARN_MINUTES ({}) must be positive and less than IN_USE_DURATION_MINUTES ({})",
+                    warn.num_minutes(),
+                    duration.num_minutes()
+                ));
+            }
+        }
+        let grace_period =
+            reader.optional_duration("IN_USE_GRACE_MINUTES", chrono::Duration::try_minutes);
+        if grace_period.is_some_and(|grace| grace < chrono::Duration::zero()) {
+            reader
+                .problems
+                .push("IN_USE_GRACE_MINUTES must not be negative".to_string());
+        }
+        let locks = LockConfig {
+            duration,
+            grace_period: grace_period.unwrap_or(lock_defaults.grace_period),
+            warn_after,
+            same_user_takeover: reader
+                .optional_bool("SAME_USER_LOCK_TAKEOVER")
+                .unwrap_or(lock_defaults.same_user_takeover),
+            recreate_missing_in_use: reader
+                .optional_bool("RECREATE_MISSING_IN_USE")
+                .unwrap_or(lock_defaults.recreate_missing_in_use),
+        };
+
+        let reveaal_defaults = ReveaalConfig::default();
+        let reveaal = ReveaalConfig {
+            keep_alive_interval: reader
+                .optional_parsed::<u64>("REVEAAL_KEEP_ALIVE_SECS")
+                .map_or(reveaal_defaults.keep_alive_interval, Duration::from_secs),
+            max_concurrent_calls: reader
+                .optional_parsed::<usize>("REVEAAL_MAX_CONCURRENT_CALLS")
+                .filter(|limit| *limit > 0)
+                .unwrap_or(reveaal_defaults.max_concurrent_calls),
+            permit_timeout: reader
+                .optional_parsed::<u64>("REVEAAL_PERMIT_TIMEOUT_SECS")
+                .map_or(reveaal_defaults.permit_timeout, Duration::from_secs),
+        };
+
+        let database_ready_timeout = reader
+            .optional_parsed::<u64>("DATABASE_READY_TIMEOUT_SECS")
+            .map_or(DEFAULT_READY_TIMEOUT, Duration::from_secs);
+        let query_result_history_depth = reader
+            .optional_parsed::<u64>("QUERY_RESULT_HISTORY_DEPTH")
+            .filter(|depth| *depth > 0)
+            .unwrap_or(DEFAULT_QUERY_RESULT_HISTORY_DEPTH);
+        let max_page_size = reader.optional_parsed::<u64>("MAX_PAGE_SIZE");
+        if max_page_size == Some(0) {
+            reader
+                .problems
+                .push("MAX_PAGE_SIZE must be at least 1".to_string());
+        }
+        let response_size_defaults = ResponseSizeWarning::default();
+        let response_size_warning = ResponseSizeWarning {
+            threshold: reader
+                .optional_parsed::<usize>("RESPONSE_SIZE_WARNING_BYTES")
+                .unwrap_or(response_size_defaults.threshold),
+            header: reader
+                .optional_bool("RESPONSE_SIZE_WARNING_HEADER")
+                .unwrap_or(response_size_defaults.header),
+        };
+
+        let session_defaults = SessionConfig::default();
+        let max_lifetime =
+            reader.optional_duration("SESSION_MAX_LIFETIME_HOURS", chrono::Duration::try_hours);
+        if max_lifetime.is_some_and(|lifetime| lifetime < chrono::Duration::zero()) {
+            reader
+                .problems
+                .push("SESSION_MAX_LIFETIME_HOURS must not be negative".to_string());
+        }
+        let sessions = SessionConfig {
+            max_sessions_per_user: reader
+                .optional_parsed::<u64>("MAX_SESSIONS_PER_USER")
+                .unwrap_or(session_defaults.max_sessions_per_user),
+            limit_policy: reader
+                .optional_parsed::<SessionLimitPolicy>("SESSION_LIMIT_POLICY")
+                .unwrap_or(session_defaults.limit_policy),
+            // A lifetime of 0 hours lets sessions last as long as they are refreshed
+            max_lifetime: max_lifetime.filter(|lifetime| *lifetime > chrono::Duration::zero()),
+        };
+
+        let mut column_lengths = vec![];
+        for (name, column_length) in [
+            ("USERNAME_MAX_LENGTH", USERNAME_MAX_LENGTH),
+            ("EMAIL_MAX_LENGTH", EMAIL_MAX_LENGTH),
+        ] {
+            let length = reader.optional_parsed::<u32>(name);
+            if let Some(length) = length.filter(|length| *length > column_length) {
+                reader.problems.push(format!(
+                    "{} must not be greater than the column size {}, got {}",
+                    name, column_length, length
+                ));
+            }
+            column_lengths.push(length.unwrap_or(column_length).min(column_length) as usize);
+        }
+        let allowed_email_domains = match reader.optional("ALLOWED_EMAIL_DOMAINS") {
+            Some(value) => parse_allowed_email_domains(&value).unwrap_or_else(|problem| {
+                reader
+                    .problems
+                    .push(format!("ALLOWED_EMAIL_DOMAINS: {}", problem));
+                vec![]
+            }),
+            None => vec![],
+        };
+        let users = UserConfig {
+            username_max_length: column_lengths[0],
+            email_max_length: column_lengths[1],
+            allowed_email_domains,
+        };
+
+        let duplicate_access_policy = reader
+            .optional_parsed::<DuplicateAccessPolicy>("DUPLICATE_ACCESS_POLICY")
+            .unwrap_or(DuplicateAccessPolicy::Reject);
+        let components_storage = reader
+            .optional_parsed::<ComponentsStorage>("COMPONENTS_STORAGE")
+            .unwrap_or(ComponentsStorage::Json);
+
+        let project_defaults = ProjectConfig::default();
+        let name_min_length = reader
+            .optional_parsed::<usize>("PROJECT_NAME_MIN_LENGTH")
+            .unwrap_or(project_defaults.name_min_length);
+        let name_max_length = reader
+            .optional_parsed::<usize>("PROJECT_NAME_MAX_LENGTH")
+            .unwrap_or(project_defaults.name_max_length);
+        if name_min_length > name_max_length {
+            reader.problems.push(format!(
+                "PROJECT_NAME_MIN_LENGTH ({}) must not be greater than PROJECT_NAME_MAX_LENGTH ({})",
+                name_min_length, name_max_length
+            ));
+        }
+        let name_disallowed_characters = reader
+            .optional("PROJECT_NAME_DISALLOWED_CHARACTERS")
+            .unwrap_or_default();
+        if name_disallowed_characters
+            .chars()
+            .any(|character| character.is_whitespace() || character.is_control())
+        {
+            reader.problems.push(
+                "PROJECT_NAME_DISALLOWED_CHARACTERS must not contain whitespace or control characters, which are trimmed or always rejected".to_string(),
+            );
+        }
+        let default_components_info = match reader.optional("DEFAULT_COMPONENTS_INFO") {
+            Some(template) => match serde_json::from_str::<ComponentsInfo>(&template) {
+                Ok(template) => components_info_from_template(template),
+                Err(err) => {
+                    reader.problems.push(format!(
+                        "DEFAULT_COMPONENTS_INFO is not a valid components info: {}",
+                        err
+                    ));
+                    project_defaults.default_components_info
+                }
+            },
+            None => project_defaults.default_components_info,
+        };
+        let projects = ProjectConfig {
+            name_min_length,
+            name_max_length,
+            name_disallowed_characters,
+            default_components_info,
+            components_json_max_depth: reader
+                .optional_parsed::<usize>("COMPONENTS_JSON_MAX_DEPTH")
+                .unwrap_or(project_defaults.components_json_max_depth),
+            owner_change_requires_access: reader
+                .optional_bool("OWNER_CHANGE_REQUIRES_ACCESS")
+                .unwrap_or(project_defaults.owner_change_requires_access),
+            require_delete_confirmation: reader
+                .optional_bool("REQUIRE_DELETE_CONFIRMATION")
+                .unwrap_or(project_defaults.require_delete_confirmation),
+            delete_confirmation_ttl: reader
+                .optional_duration(
+                    "DELETE_CONFIRMATION_TTL_SECS",
+                    chrono::Duration::try_seconds,
+                )
+                .unwrap_or(project_defaults.delete_confirmation_ttl),
+            dashboard_page_size: reader
+                .optional_parsed::<u64>("DASHBOARD_PAGE_SIZE")
+                .unwrap_or(project_defaults.dashboard_page_size),
+        };
+
+        let bcrypt_cost = reader
+            .optional_parsed::<u32>("BCRYPT_COST")
+            .unwrap_or(DEFAULT_COST);
+        if !(4..=31).contains(&bcrypt_cost) {
+            reader.problems.push(format!(
+                "BCRYPT_COST must be between 4 and 31, got {}",
+                bcrypt_cost
+            ));
+        }
+
+        let compression = match reader.optional("GRPC_COMPRESSION") {
+            Some(value) => parse_compression(&value).unwrap_or_else(|problem| {
+                reader
+                    .problems
+                    .push(format!("GRPC_COMPRESSION: {}", problem));
+                None
+            }),
+            None => None,
+        };
+
+        let query_defaults = QueryConfig::default();
+        let allowed_settings = match reader.optional("REVEAAL_ALLOWED_SETTINGS") {
+            Some(value) => parse_allowed_settings(&value).unwrap_or_else(|problem| {
+                reader
+                    .problems
+                    .push(format!("REVEAAL_ALLOWED_SETTINGS: {}", problem));
+                vec![]
+            }),
+            None => query_defaults.allowed_settings,
+        };
+        let queries = QueryConfig {
+            reveaal_timeout: reader
+                .optional_parsed::<u64>("REVEAAL_TIMEOUT_SECS")
+                .map_or(query_defaults.reveaal_timeout, Duration::from_secs),
+            max_queries_per_project: reader
+                .optional_parsed::<u64>("MAX_QUERIES_PER_PROJECT")
+                .unwrap_or(query_defaults.max_queries_per_project),
+            // An age that is not positive lets results live forever
+            result_max_age: reader
+                .optional_duration(
+                    "QUERY_RESULT_MAX_AGE_MINUTES",
+                    chrono::Duration::try_minutes,
+                )
+                .filter(|max_age| *max_age > chrono::Duration::zero()),
+            empty_result_policy: reader
+                .optional_parsed::<EmptyResultPolicy>("EMPTY_QUERY_RESULT_POLICY")
+                .unwrap_or(query_defaults.empty_result_policy),
+            mutation_role: reader
+                .optional_parsed::<QueryMutationRole>("QUERY_MUTATION_MIN_ROLE")
+                .unwrap_or(query_defaults.mutation_role),
+            outdated_queries_concurrency: reader
+                .optional_parsed::<usize>("OUTDATED_QUERIES_CONCURRENCY")
+                .filter(|limit| *limit > 0)
+                .unwrap_or(query_defaults.outdated_queries_concurrency),
+            result_save_attempts: reader
+                .optional_parsed::<u32>("QUERY_RESULT_SAVE_ATTEMPTS")
+                .filter(|attempts| *attempts > 0)
+                .unwrap_or(query_defaults.result_save_attempts),
+            allowed_settings,
+            deduplicate_concurrent: reader
+                .optional_bool("DEDUPLICATE_CONCURRENT_QUERIES")
+                .unwrap_or(query_defaults.deduplicate_concurrent),
+        };
+
+        let reveaal_proxy_methods = match reader.optional("REVEAAL_PROXY_METHODS") {
+            Some(value) => parse_proxy_methods(&value).unwrap_or_else(|problem| {
+                reader
+                    .problems
+                    .push(format!("REVEAAL_PROXY_METHODS: {}", problem));
+                vec![]
+            }),
+            None => vec![],
+        };
+
+        let allowed_origins = match reader.optional("ALLOWED_ORIGINS") {
+            Some(value) => parse_allowed_origins(&value).unwrap_or_else(|problem| {
+                reader
+                    .problems
+                    .push(format!("ALLOWED_ORIGINS: {}", problem));
+                vec![]
+            }),
+            None => vec![],
+        };
+
+        let trusted_proxies = match reader.optional("RATE_LIMIT_TRUSTED_PROXIES") {
+            Some(value) => parse_trusted_proxies(&value).unwrap_or_else(|problem| {
+                reader
+                    .problems
+                    .push(format!("RATE_LIMIT_TRUSTED_PROXIES: {}", problem));
+                vec![]
+            }),
+            None => vec![],
+        };
+        let burst = reader.optional_parsed::<u32>("RATE_LIMIT_BURST");
+        if burst == Some(0) {
+            reader
+                .problems
+                .push("RATE_LIMIT_BURST must be at least 1".to_string());
+        }
+        let rate_limit = reader
+            .optional_parsed::<u32>("RATE_LIMIT_REQUESTS_PER_SECOND")
+            .filter(|requests_per_second| *requests_per_second > 0)
+            .map(|requests_per_second| RateLimit {
+                requests_per_second,
+                burst: burst.unwrap_or(requests_per_second),
+                trusted_proxies,
+            });
+
+        let endpoint_timeouts = EndpointTimeouts {
+            default: reader
+                .optional_parsed::<u64>("ENDPOINT_TIMEOUT_DEFAULT_SECS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            endpoints: match reader.optional("ENDPOINT_TIMEOUTS") {
+                Some(value) => parse_endpoint_timeouts(&value).unwrap_or_else(|problem| {
+                    reader
+                        .problems
+                        .push(format!("ENDPOINT_TIMEOUTS: {}", problem));
+                    Default::default()
+                }),
+                None => Default::default(),
+            },
+        };
+
+        let metrics_access = match reader.optional("METRICS_TOKEN") {
+            Some(value) => parse_metrics_token(&value).unwrap_or_else(|problem| {
+                reader.problems.push(format!("METRICS_TOKEN: {}", problem));
+                MetricsAccess::LocalhostOnly
+            }),
+            None => MetricsAccess::LocalhostOnly,
+        };
+
+        match (database_url, reveaal_address, api_address) {
+            (Some(database_url), Some(reveaal_address), Some(api_address))
+                if reader.problems.is_empty() =>
+            {
+                Ok(Config {
+                    database_url,
+                    reveaal_address,
+                    api_address,
+                    read_only: read_only.unwrap_or(false),
+                    require_authentication: require_authentication.unwrap_or(false),
+                    compression,
+                    rate_limit,
+                    strict_unknown_fields: strict_unknown_fields.unwrap_or(false),
+                    endpoint_timeouts,
+                    metrics_access,
+                    database_ready_timeout,
+                    log_statements: log_statements.unwrap_or(false),
+                    components_storage,
+                    query_result_history_depth,
+                    max_page_size: max_page_size.unwrap_or(DEFAULT_MAX_PAGE_SIZE),
+                    legacy_not_found_on_empty_list: legacy_not_found_on_empty_list.unwrap_or(false),
+                    allowed_origins,
+                    bcrypt_cost,
+                    duplicate_access_policy,
+                    reveaal_proxy_methods,
+                    response_size_warning,
+                    reveaal,
+                    locks,
+                    projects,
+                    queries,
+                    sessions,
+                    users,
+                })
+            }
+            _ => Err(ConfigError {
+                problems: reader.problems,
+            }),
+        }
+    }
+}
+
+/// Reads variables through a lookup, collecting a problem for each one that is missing or invalid.
+struct Reader<F: Fn(&str) -> Option<String>> {
+    lookup: F,
+    problems: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Reader<F> {
+    fn optional(&self, name: &str) -> Option<String> {
+        (self.lookup)(name).filter(|value| !value.is_empty())
+    }
+
+    fn required(&mut self, name: &str) -> Option<String> {
+        let value = self.optional(name);
+        if value.is_none() {
+            self.problems.push(format!("{} is not set", name));
+        }
+        value
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, value: String) -> Option<T> {
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                self.problems
+                    .push(format!("{} has an invalid value '{}'", name, value));
+                None
+            }
+        }
+    }
+
+    fn required_parsed<T: FromStr>(&mut self, name: &str) -> Option<T> {
+        let value = self.required(name)?;
+        self.parse(name, value)
+    }
+
+    fn optional_parsed<T: FromStr>(&mut self, name: &str) -> Option<T> {
+        let value = self.optional(name)?;
+        self.parse(name, value)
+    }
+
+    /// Reads a whole number of some unit, converted to a duration by `unit`.
+    fn optional_duration(
+        &mut self,
+        name: &str,
+        unit: fn(i64) -> Option<chrono::Duration>,
+    ) -> Option<chrono::Duration> {
+        let value = self.optional_parsed::<i64>(name)?;
+        let duration = unit(value);
+        if duration.is_none() {
+            self.problems
+                .push(format!("{} is out of range, got {}", name, value));
+        }
+        duration
+    }
+
+    fn optional_bool(&mut self, name: &str) -> Option<bool> {
+        match self.optional(name)?.as_str() {
+            "true" => Some(true),
+            "false" => Some(false),
+            value => {
+                self.problems.push(format!(
+                    "{} must be 'true' or 'false', got '{}'",
+                    name, value
+                ));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/config.rs"]
+mod tests;
@@ -0,0 +1,87 @@
+//! Optional normalized storage of the components of a project.
+//!
+//! By default the components info of a project is stored as a single JSON blob in the project table.
+//! With [`ComponentsStorage::Rows`], every component is stored as its own row in the component table,
+//! and the project table only keeps the rest of the components info, such as its hash.
+//! The project context splits and assembles the components, so the rest of the API is unaffected.
+use crate::api::server::protobuf::Component;
+use crate::services::service_traits::reveaal_service_trait::component_name;
+use sea_orm::prelude::Json;
+use std::str::FromStr;
+
+const COMPONENTS_KEY: &str = "components";
+
+/// How the components of a project are stored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ComponentsStorage {
+    /// All components are stored in the components info of the project
+    Json,
+    /// Every component is stored as a row in the component table, so a change rewrites only the affected rows
+    Rows,
+}
+
+impl FromStr for ComponentsStorage {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(ComponentsStorage::Json),
+            "rows" => Ok(ComponentsStorage::Rows),
+            _ => Err(format!(
+                "'{}' is not a components storage, expected 'json' or 'rows'",
+                value
+            )),
+        }
+    }
+}
+
+impl ComponentsStorage {
+    /// Splits components info into the part stored in the project table and the components stored as rows.
+    /// With [`ComponentsStorage::Json`], nothing is stored as rows.
+    pub fn split(self, components_info: Json) -> (Json, Vec<Json>) {
+        match self {
+            ComponentsStorage::Json => (components_info, vec![]),
+            ComponentsStorage::Rows => split_components(components_info),
+        }
+    }
+}
+
+/// Splits stored components info into the components info without its components, and the components.
+pub fn split_components(components_info: Json) -> (Json, Vec<Json>) {
+    match components_info {
+        Json::Object(mut object) => {
+            let components = match object.insert(COMPONENTS_KEY.to_string(), Json::Array(vec![])) {
+                Some(Json::Array(components)) => components,
+                _ => vec![],
+            };
+            (Json::Object(object), components)
+        }
+        components_info => (components_info, vec![]),
+    }
+}
+
+/// Puts the components back into components info split by [`split_components`].
+///
+/// Without any `components`, the components info is returned as it is, so projects written before
+/// [`ComponentsStorage::Rows`] was enabled keep the components stored in their components info.
+pub fn assemble_components(components_info: Json, components: Vec<Json>) -> Json {
+    match components_info {
+        Json::Object(mut object) if !components.is_empty() => {
+            object.insert(COMPONENTS_KEY.to_string(), Json::Array(components));
+            Json::Object(object)
+        }
+        components_info => components_info,
+    }
+}
+
+/// Returns the name of a stored component, which is kept next to it so it can be looked up by name.
+pub fn stored_component_name(component: &Json) -> Option<String> {
+    serde_json::from_value::<Component>(component.clone())
+        .ok()
+        .as_ref()
+        .and_then(component_name)
+}
+
+#[cfg(test)]
+#[path = "../tests/contexts/components_storage.rs"]
+mod components_storage_tests;
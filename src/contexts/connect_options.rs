@@ -0,0 +1,21 @@
+//! Options shared by the connections to both database backends.
+use log::LevelFilter;
+use sea_orm::ConnectOptions;
+
+/// Returns the options for connecting to the database at `connection_string`.
+///
+/// With `log_statements`, sqlx logs every statement at debug level to the `sqlx::query` target through `tracing`,
+/// which passes it on to `log` when no `tracing` subscriber is installed. Only the SQL text is logged,
+/// with placeholders where values are bound, so values such as the password hashes written to the
+/// user table never reach the log. Without it, no statements are logged, not even slow ones.
+pub fn connect_options(connection_string: &str, log_statements: bool) -> ConnectOptions {
+    let mut options = ConnectOptions::new(connection_string);
+    options
+        .sqlx_logging(log_statements)
+        .sqlx_logging_level(LevelFilter::Debug);
+    options
+}
+
+#[cfg(test)]
+#[path = "../tests/contexts/connect_options.rs"]
+mod connect_options_tests;
@@ -6,7 +6,9 @@ pub struct ContextCollection {
     pub(crate) access_context: Arc<dyn AccessContextTrait>,
     pub(crate) in_use_context: Arc<dyn InUseContextTrait>,
     pub(crate) project_context: Arc<dyn ProjectContextTrait>,
+    pub(crate) project_event_context: Arc<dyn ProjectEventContextTrait>,
     pub(crate) query_context: Arc<dyn QueryContextTrait>,
+    pub(crate) query_result_context: Arc<dyn QueryResultContextTrait>,
     pub(crate) session_context: Arc<dyn SessionContextTrait>,
     pub(crate) user_context: Arc<dyn UserContextTrait>,
 }
@@ -2,14 +2,22 @@ use crate::api::server::protobuf::AccessInfo;
 use crate::contexts::context_traits::{
     AccessContextTrait, DatabaseContextTrait, EntityContextTrait,
 };
-use crate::entities::access;
+use crate::contexts::pagination::{get_paginated, Page, DEFAULT_MAX_PAGE_SIZE};
+use crate::contexts::sqlite_lock::retry_if_locked;
+use crate::contexts::timestamps::Timestamped;
+use crate::entities::{access, user};
 use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::sea_query::{Expr, Func};
 use sea_orm::ActiveValue::{Set, Unchanged};
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, JoinType,
+    NotSet, QueryFilter, QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
+};
 use std::sync::Arc;
 
 pub struct AccessContext {
     db_context: Arc<dyn DatabaseContextTrait>,
+    max_page_size: u64,
 }
 
 #[async_trait]
@@ -29,10 +37,99 @@ impl AccessContextTrait for AccessContext {
             .await
     }
 
-    async fn get_access_by_project_id(&self, project_id: i32) -> Result<Vec<AccessInfo>, DbErr> {
-        access::Entity::find()
+    async fn get_access_by_project_id(
+        &self,
+        project_id: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Page<AccessInfo>, DbErr> {
+        // Users are shown by their display name, falling back to their username
+        let select = access::Entity::find()
+            .column_as(
+                Func::coalesce([
+                    Expr::col((user::Entity, user::Column::DisplayName)).into(),
+                    Expr::col((user::Entity, user::Column::Username)).into(),
+                ]),
+                "display_name",
+            )
+            .join(JoinType::InnerJoin, access::Relation::User.def())
             .filter(access::Column::ProjectId.eq(project_id))
-            .into_model::<AccessInfo>()
+            .order_by_asc(access::Column::Role)
+            .order_by_asc(access::Column::Id)
+            .into_model::<AccessInfo>();
+
+        get_paginated(
+            select,
+            &self.db_context.get_connection(),
+            page,
+            page_size,
+            self.max_page_size,
+        )
+        .await
+    }
+
+    async fn update_roles(
+        &self,
+        entities: Vec<access::Model>,
+    ) -> Result<Vec<access::Model>, DbErr> {
+        let db = self.db_context.get_connection();
+
+        retry_if_locked(self.db_context.backend_kind(), || async {
+            let transaction = db.begin().await?;
+
+            let mut updated = vec![];
+            for entity in entities.clone() {
+                updated.push(
+                    access::ActiveModel {
+                        id: Unchanged(entity.id),
+                        role: Set(entity.role),
+                        project_id: Unchanged(entity.project_id),
+                        user_id: Unchanged(entity.user_id),
+                        created_at: NotSet,
+                        updated_at: NotSet,
+                    }
+                    .updated_now()
+                    .update(&transaction)
+                    .await?,
+                );
+            }
+
+            transaction.commit().await?;
+            Ok(updated)
+        })
+        .await
+    }
+
+    async fn get_accessible_project_ids(&self, uid: i32) -> Result<Vec<i32>, DbErr> {
+        access::Entity::find()
+            .select_only()
+            .column(access::Column::ProjectId)
+            .filter(access::Column::UserId.eq(uid))
+            .order_by_asc(access::Column::ProjectId)
+            .into_tuple()
+            .all(&self.db_context.get_connection())
+            .await
+    }
+
+    async fn get_access_by_ids(&self, ids: Vec<i32>) -> Result<Vec<access::Model>, DbErr> {
+        access::Entity::find()
+            .filter(access::Column::Id.is_in(ids))
+            .order_by_asc(access::Column::Id)
+            .all(&self.db_context.get_connection())
+            .await
+    }
+
+    async fn get_access_by_uid_and_project_ids(
+        &self,
+        uid: i32,
+        project_ids: Vec<i32>,
+    ) -> Result<Vec<access::Model>, DbErr> {
+        access::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(access::Column::UserId.eq(uid))
+                    .add(access::Column::ProjectId.is_in(project_ids)),
+            )
             .all(&self.db_context.get_connection())
             .await
     }
@@ -40,10 +137,39 @@ impl AccessContextTrait for AccessContext {
 
 impl AccessContext {
     pub fn new(db_context: Arc<dyn DatabaseContextTrait>) -> AccessContext {
-        AccessContext { db_context }
+        AccessContext::with_max_page_size(db_context, DEFAULT_MAX_PAGE_SIZE)
+    }
+
+    /// Creates an access context serving pages of at most `max_page_size` accesses.
+    pub fn with_max_page_size(
+        db_context: Arc<dyn DatabaseContextTrait>,
+        max_page_size: u64,
+    ) -> AccessContext {
+        AccessContext {
+            db_context,
+            max_page_size,
+        }
     }
 }
 
+/// Inserts an access on the given connection.
+pub(crate) async fn insert_access<C: ConnectionTrait>(
+    db: &C,
+    entity: access::Model,
+) -> Result<access::Model, DbErr> {
+    access::ActiveModel {
+        id: Default::default(),
+        role: Set(entity.role),
+        project_id: Set(entity.project_id),
+        user_id: Set(entity.user_id),
+        created_at: NotSet,
+        updated_at: NotSet,
+    }
+    .created_now()
+    .insert(db)
+    .await
+}
+
 #[async_trait]
 impl EntityContextTrait<access::Model> for AccessContext {
     /// Used for creating an [`access::Model`] entity
@@ -59,14 +185,7 @@ impl EntityContextTrait<access::Model> for AccessContext {
     /// context.create(model);
     /// ```
     async fn create(&self, entity: access::Model) -> Result<access::Model, DbErr> {
-        let access = access::ActiveModel {
-            id: Default::default(),
-            role: Set(entity.role),
-            project_id: Set(entity.project_id),
-            user_id: Set(entity.user_id),
-        };
-        let access: access::Model = access.insert(&self.db_context.get_connection()).await?;
-        Ok(access)
+        insert_access(&self.db_context.get_connection(), entity).await
     }
 
     /// Returns a single access entity (uses primary key)
@@ -113,7 +232,10 @@ impl EntityContextTrait<access::Model> for AccessContext {
             role: Set(entity.role),
             project_id: Unchanged(entity.project_id),
             user_id: Unchanged(entity.user_id),
+            created_at: NotSet,
+            updated_at: NotSet,
         }
+        .updated_now()
         .update(&self.db_context.get_connection())
         .await
     }
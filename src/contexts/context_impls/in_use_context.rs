@@ -1,34 +1,67 @@
 use crate::contexts::context_traits::{
     DatabaseContextTrait, EntityContextTrait, InUseContextTrait,
 };
-use crate::entities::in_use;
+use crate::entities::{in_use, session};
 use async_trait::async_trait;
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, Set, Unchanged};
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, JoinType, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, Unchanged,
+};
 use std::sync::Arc;
 
 pub struct InUseContext {
     db_context: Arc<dyn DatabaseContextTrait>,
 }
 
-impl InUseContextTrait for InUseContext {}
+#[async_trait]
+impl InUseContextTrait for InUseContext {
+    async fn count_active_locks(&self, timeout: Duration) -> Result<u64, DbErr> {
+        in_use::Entity::find()
+            .filter(in_use::Column::LatestActivity.gt(Utc::now().naive_utc() - timeout))
+            .count(&self.db_context.get_connection())
+            .await
+    }
+
+    async fn get_active_locks_by_uid(
+        &self,
+        uid: i32,
+        timeout: Duration,
+    ) -> Result<Vec<in_use::Model>, DbErr> {
+        in_use::Entity::find()
+            .join(JoinType::InnerJoin, in_use::Relation::Session.def())
+            .filter(session::Column::UserId.eq(uid))
+            .filter(in_use::Column::LatestActivity.gt(Utc::now().naive_utc() - timeout))
+            .order_by_asc(in_use::Column::ProjectId)
+            .all(&self.db_context.get_connection())
+            .await
+    }
+}
 
 impl InUseContext {
     pub fn new(db_context: Arc<dyn DatabaseContextTrait>) -> InUseContext {
         InUseContext { db_context }
     }
 }
+
+/// Inserts an in_use entity on the given connection, with the current time as its latest activity.
+pub(crate) async fn insert_in_use<C: ConnectionTrait>(
+    db: &C,
+    entity: in_use::Model,
+) -> Result<in_use::Model, DbErr> {
+    in_use::ActiveModel {
+        project_id: Set(entity.project_id),
+        session_id: Set(entity.session_id),
+        latest_activity: Set(Utc::now().naive_local()),
+    }
+    .insert(db)
+    .await
+}
 #[async_trait]
 impl EntityContextTrait<in_use::Model> for InUseContext {
     /// Used for creating a Model entity
     async fn create(&self, entity: in_use::Model) -> Result<in_use::Model, DbErr> {
-        let in_use = in_use::ActiveModel {
-            project_id: Set(entity.project_id),
-            session_id: Set(entity.session_id),
-            latest_activity: Set(Utc::now().naive_local()),
-        };
-        let in_use: in_use::Model = in_use.insert(&self.db_context.get_connection()).await?;
-        Ok(in_use)
+        insert_in_use(&self.db_context.get_connection(), entity).await
     }
 
     async fn get_by_id(&self, entity_id: i32) -> Result<Option<in_use::Model>, DbErr> {
@@ -2,7 +2,9 @@ pub mod access_context;
 pub mod in_use_context;
 pub mod postgres_database_context;
 pub mod project_context;
+pub mod project_event_context;
 pub mod query_context;
+pub mod query_result_context;
 pub mod session_context;
 pub mod sqlite_database_context;
 pub mod user_context;
@@ -11,7 +13,9 @@ pub use access_context::AccessContext;
 pub use in_use_context::InUseContext;
 pub use postgres_database_context::PostgresDatabaseContext;
 pub use project_context::ProjectContext;
+pub use project_event_context::ProjectEventContext;
 pub use query_context::QueryContext;
+pub use query_result_context::QueryResultContext;
 pub use session_context::SessionContext;
 pub use sqlite_database_context::SQLiteDatabaseContext;
 pub use user_context::UserContext;
@@ -1,3 +1,4 @@
+use crate::contexts::connect_options::connect_options;
 use crate::contexts::context_traits::DatabaseContextTrait;
 use async_trait::async_trait;
 use migration::{Migrator, MigratorTrait};
@@ -8,8 +9,12 @@ pub struct PostgresDatabaseContext {
     pub(crate) db_connection: DatabaseConnection,
 }
 impl PostgresDatabaseContext {
-    pub async fn new(connection_string: &str) -> Result<PostgresDatabaseContext, DbErr> {
-        let db = Database::connect(connection_string).await?;
+    /// Connects to the database at `connection_string`, logging every statement if `log_statements` is set.
+    pub async fn new(
+        connection_string: &str,
+        log_statements: bool,
+    ) -> Result<PostgresDatabaseContext, DbErr> {
+        let db = Database::connect(connect_options(connection_string, log_statements)).await?;
 
         let db = match db.get_database_backend() {
             DbBackend::Postgres => db,
@@ -30,7 +35,15 @@ impl DatabaseContextTrait for PostgresDatabaseContext {
         }))
     }
 
+    async fn ping(&self) -> Result<(), DbErr> {
+        self.db_connection.ping().await
+    }
+
     fn get_connection(&self) -> DatabaseConnection {
         self.db_connection.clone()
     }
+
+    fn backend_kind(&self) -> DbBackend {
+        self.db_connection.get_database_backend()
+    }
 }
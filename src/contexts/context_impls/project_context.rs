@@ -1,44 +1,330 @@
+use crate::contexts::components_storage::{
+    assemble_components, stored_component_name, ComponentsStorage,
+};
+use crate::contexts::context_impls::access_context::insert_access;
+use crate::contexts::context_impls::in_use_context::insert_in_use;
+use crate::contexts::context_impls::project_event_context::{insert_event, prune_events};
+use crate::contexts::context_impls::query_context::insert_query;
 use crate::contexts::context_traits::{
     DatabaseContextTrait, EntityContextTrait, ProjectContextTrait,
 };
-use crate::entities::{access, project, query};
+use crate::contexts::pagination::{get_paginated, Page, DEFAULT_MAX_PAGE_SIZE};
+use crate::contexts::sqlite_lock::retry_if_locked;
+use crate::entities::{access, component, in_use, project, project_event, query, user};
 
 use crate::api::server::protobuf::ProjectInfo;
 use async_trait::async_trait;
+use sea_orm::prelude::Json;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, IntoActiveModel, JoinType, ModelTrait,
-    QueryFilter, QuerySelect, RelationTrait, Set, Unchanged,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, JoinType, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait, Set, TransactionTrait, Unchanged,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct ProjectContext {
     db_context: Arc<dyn DatabaseContextTrait>,
+    components_storage: ComponentsStorage,
+    max_page_size: u64,
 }
 
 #[async_trait]
 impl ProjectContextTrait for ProjectContext {
-    async fn get_project_info_by_uid(&self, uid: i32) -> Result<Vec<ProjectInfo>, DbErr> {
-        //join project, access and role tables
-        access::Entity::find()
+    async fn get_project_info_by_uid(
+        &self,
+        uid: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Page<ProjectInfo>, DbErr> {
+        //join project, access and role tables, and the owner's user row if it still exists
+        let select = access::Entity::find()
             .select_only()
             .column_as(project::Column::Id, "project_id")
             .column_as(project::Column::Name, "project_name")
             .column_as(project::Column::OwnerId, "project_owner_id")
+            .column_as(user::Column::Username, "project_owner_username")
             .column_as(access::Column::Role, "user_role_on_project")
             .join(JoinType::InnerJoin, access::Relation::Project.def())
             .join(JoinType::InnerJoin, access::Relation::Role.def())
+            .join(JoinType::LeftJoin, project::Relation::User.def())
             .group_by(project::Column::Id)
             .group_by(access::Column::Role)
+            .group_by(user::Column::Id)
+            .filter(access::Column::UserId.eq(uid))
+            .order_by_asc(project::Column::Name)
+            .order_by_asc(project::Column::Id)
+            .into_model::<ProjectInfo>();
+
+        get_paginated(
+            select,
+            &self.db_context.get_connection(),
+            page,
+            page_size,
+            self.max_page_size,
+        )
+        .await
+    }
+
+    async fn get_projects_by_uid(
+        &self,
+        uid: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Page<project::Model>, DbErr> {
+        let db = self.db_context.get_connection();
+        let select = project::Entity::find()
+            .join(JoinType::InnerJoin, project::Relation::Access.def())
             .filter(access::Column::UserId.eq(uid))
-            .into_model::<ProjectInfo>()
-            .all(&self.db_context.get_connection())
-            .await
+            .order_by_asc(project::Column::Name)
+            .order_by_asc(project::Column::Id);
+
+        let page = get_paginated(select, &db, page, page_size, self.max_page_size).await?;
+        Ok(Page {
+            items: load_components(&db, self.components_storage, page.items).await?,
+            ..page
+        })
+    }
+
+    async fn update_with_events(
+        &self,
+        entity: project::Model,
+        events: Vec<project_event::Model>,
+    ) -> Result<project::Model, DbErr> {
+        let db = self.db_context.get_connection();
+
+        retry_if_locked(self.db_context.backend_kind(), || async {
+            let transaction = db.begin().await?;
+
+            let project =
+                update_project(&transaction, entity.clone(), self.components_storage).await?;
+            for event in events.clone() {
+                insert_event(&transaction, event).await?;
+            }
+            prune_events(&transaction, project.id).await?;
+
+            transaction.commit().await?;
+            Ok(project)
+        })
+        .await
+    }
+
+    async fn create_with_queries(
+        &self,
+        entity: project::Model,
+        access: access::Model,
+        in_use: Option<in_use::Model>,
+        queries: Vec<query::Model>,
+    ) -> Result<(project::Model, Vec<query::Model>), DbErr> {
+        let db = self.db_context.get_connection();
+
+        retry_if_locked(self.db_context.backend_kind(), || async {
+            let transaction = db.begin().await?;
+
+            let project =
+                create_project(&transaction, entity.clone(), self.components_storage).await?;
+            insert_access(
+                &transaction,
+                access::Model {
+                    project_id: project.id,
+                    ..access.clone()
+                },
+            )
+            .await?;
+            if let Some(in_use) = in_use.clone() {
+                insert_in_use(
+                    &transaction,
+                    in_use::Model {
+                        project_id: project.id,
+                        ..in_use
+                    },
+                )
+                .await?;
+            }
+
+            let mut created_queries = vec![];
+            for query in queries.clone() {
+                created_queries.push(
+                    insert_query(
+                        &transaction,
+                        query::Model {
+                            project_id: project.id,
+                            ..query
+                        },
+                    )
+                    .await?,
+                );
+            }
+
+            transaction.commit().await?;
+            Ok((project, created_queries))
+        })
+        .await
     }
 }
 
 impl ProjectContext {
     pub fn new(db_context: Arc<dyn DatabaseContextTrait>) -> ProjectContext {
-        ProjectContext { db_context }
+        ProjectContext::with_settings(db_context, ComponentsStorage::Json, DEFAULT_MAX_PAGE_SIZE)
+    }
+
+    /// Creates a project context storing the components of projects as given by `components_storage`,
+    /// and serving pages of at most `max_page_size` projects.
+    pub fn with_settings(
+        db_context: Arc<dyn DatabaseContextTrait>,
+        components_storage: ComponentsStorage,
+        max_page_size: u64,
+    ) -> ProjectContext {
+        ProjectContext {
+            db_context,
+            components_storage,
+            max_page_size,
+        }
+    }
+}
+
+/// Puts the components stored as rows back into the components info of the given projects.
+async fn load_components<C: ConnectionTrait>(
+    db: &C,
+    components_storage: ComponentsStorage,
+    projects: Vec<project::Model>,
+) -> Result<Vec<project::Model>, DbErr> {
+    if components_storage == ComponentsStorage::Json || projects.is_empty() {
+        return Ok(projects);
+    }
+
+    let mut components: HashMap<i32, Vec<Json>> = HashMap::new();
+    for row in component::Entity::find()
+        .filter(component::Column::ProjectId.is_in(projects.iter().map(|project| project.id)))
+        .order_by_asc(component::Column::Position)
+        .all(db)
+        .await?
+    {
+        components
+            .entry(row.project_id)
+            .or_default()
+            .push(row.component);
+    }
+
+    Ok(projects
+        .into_iter()
+        .map(|project| project::Model {
+            components_info: assemble_components(
+                project.components_info,
+                components.remove(&project.id).unwrap_or_default(),
+            ),
+            ..project
+        })
+        .collect())
+}
+
+/// Makes the component rows of a project match `components`,
+/// only writing the rows whose component has changed.
+async fn store_components<C: ConnectionTrait>(
+    db: &C,
+    project_id: i32,
+    components: &[Json],
+) -> Result<(), DbErr> {
+    let mut existing: HashMap<i32, component::Model> = component::Entity::find()
+        .filter(component::Column::ProjectId.eq(project_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| (row.position, row))
+        .collect();
+
+    for (position, json) in (0..).zip(components) {
+        match existing.remove(&position) {
+            Some(row) if &row.component == json => {}
+            Some(row) => {
+                component::ActiveModel {
+                    id: Unchanged(row.id),
+                    name: Set(stored_component_name(json)),
+                    component: Set(json.clone()),
+                    ..Default::default()
+                }
+                .update(db)
+                .await?;
+            }
+            None => {
+                component::ActiveModel {
+                    id: Default::default(),
+                    project_id: Set(project_id),
+                    position: Set(position),
+                    name: Set(stored_component_name(json)),
+                    component: Set(json.clone()),
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+    }
+
+    if !existing.is_empty() {
+        component::Entity::delete_many()
+            .filter(component::Column::Id.is_in(existing.into_values().map(|row| row.id)))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Creates a project on the given connection.
+async fn create_project<C: ConnectionTrait>(
+    db: &C,
+    entity: project::Model,
+    components_storage: ComponentsStorage,
+) -> Result<project::Model, DbErr> {
+    let (components_info, components) = components_storage.split(entity.components_info);
+
+    let project = project::ActiveModel {
+        id: Default::default(),
+        name: Set(entity.name),
+        components_info: Set(components_info),
+        owner_id: Set(entity.owner_id),
+    }
+    .insert(db)
+    .await?;
+
+    if components_storage == ComponentsStorage::Rows {
+        store_components(db, project.id, &components).await?;
+    }
+    Ok(project::Model {
+        components_info: assemble_components(project.components_info, components),
+        ..project
+    })
+}
+
+/// Updates a project on the given connection.
+///
+/// Its queries are left as they are; the caller marks the ones affected by a components change as outdated.
+async fn update_project<C: ConnectionTrait>(
+    db: &C,
+    entity: project::Model,
+    components_storage: ComponentsStorage,
+) -> Result<project::Model, DbErr> {
+    let existing_project = project::Entity::find_by_id(entity.id).one(db).await?;
+
+    match existing_project {
+        None => Err(DbErr::RecordNotUpdated),
+        Some(_) => {
+            let (components_info, components) = components_storage.split(entity.components_info);
+            let project = project::ActiveModel {
+                id: Unchanged(entity.id),
+                name: Set(entity.name),
+                components_info: Set(components_info),
+                owner_id: Set(entity.owner_id),
+            }
+            .update(db)
+            .await?;
+
+            if components_storage == ComponentsStorage::Rows {
+                store_components(db, project.id, &components).await?;
+            }
+            Ok(project::Model {
+                components_info: assemble_components(project.components_info, components),
+                ..project
+            })
+        }
     }
 }
 
@@ -57,14 +343,12 @@ impl EntityContextTrait<project::Model> for ProjectContext {
     /// project_context.create(project);
     /// ```
     async fn create(&self, entity: project::Model) -> Result<project::Model, DbErr> {
-        let project = project::ActiveModel {
-            id: Default::default(),
-            name: Set(entity.name),
-            components_info: Set(entity.components_info),
-            owner_id: Set(entity.owner_id),
-        };
-        let project: project::Model = project.insert(&self.db_context.get_connection()).await?;
-        Ok(project)
+        create_project(
+            &self.db_context.get_connection(),
+            entity,
+            self.components_storage,
+        )
+        .await
     }
 
     /// Returns a single project entity (Uses primary key)
@@ -74,9 +358,14 @@ impl EntityContextTrait<project::Model> for ProjectContext {
     /// let project = project_context.get_by_id(1).unwrap();
     /// ```
     async fn get_by_id(&self, entity_id: i32) -> Result<Option<project::Model>, DbErr> {
-        project::Entity::find_by_id(entity_id)
-            .one(&self.db_context.get_connection())
-            .await
+        let db = self.db_context.get_connection();
+        let project = project::Entity::find_by_id(entity_id).one(&db).await?;
+
+        Ok(
+            load_components(&db, self.components_storage, project.into_iter().collect())
+                .await?
+                .pop(),
+        )
     }
 
     /// Returns a all project entities (Uses primary key)
@@ -86,9 +375,10 @@ impl EntityContextTrait<project::Model> for ProjectContext {
     /// let project = project_context.get_all().unwrap();
     /// ```
     async fn get_all(&self) -> Result<Vec<project::Model>, DbErr> {
-        project::Entity::find()
-            .all(&self.db_context.get_connection())
-            .await
+        let db = self.db_context.get_connection();
+        let projects = project::Entity::find().all(&db).await?;
+
+        load_components(&db, self.components_storage, projects).await
     }
 
     /// Updates a single project entity
@@ -103,30 +393,12 @@ impl EntityContextTrait<project::Model> for ProjectContext {
     /// let project = project_context.update(update_project).unwrap();
     /// ```
     async fn update(&self, entity: project::Model) -> Result<project::Model, DbErr> {
-        let existing_project = self.get_by_id(entity.id).await?;
-
-        return match existing_project {
-            None => Err(DbErr::RecordNotUpdated),
-            Some(existing_project) => {
-                let queries: Vec<query::Model> = existing_project
-                    .find_related(query::Entity)
-                    .all(&self.db_context.get_connection())
-                    .await?;
-                for q in queries.iter() {
-                    let mut aq = q.clone().into_active_model();
-                    aq.outdated = Set(true);
-                    aq.update(&self.db_context.get_connection()).await?;
-                }
-                project::ActiveModel {
-                    id: Unchanged(entity.id),
-                    name: Set(entity.name),
-                    components_info: Set(entity.components_info),
-                    owner_id: Unchanged(entity.id),
-                }
-                .update(&self.db_context.get_connection())
-                .await
-            }
-        };
+        update_project(
+            &self.db_context.get_connection(),
+            entity,
+            self.components_storage,
+        )
+        .await
     }
 
     /// Returns and deletes a single project entity
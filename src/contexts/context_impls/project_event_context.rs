@@ -0,0 +1,150 @@
+use crate::contexts::context_traits::{
+    DatabaseContextTrait, EntityContextTrait, ProjectEventContextTrait,
+};
+use crate::entities::project_event;
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, Unchanged,
+};
+use std::sync::Arc;
+
+/// The number of events kept per project. Older events are pruned when new ones are logged.
+pub const PROJECT_EVENT_RETENTION: u64 = 100;
+
+pub struct ProjectEventContext {
+    db_context: Arc<dyn DatabaseContextTrait>,
+}
+
+#[async_trait]
+impl ProjectEventContextTrait for ProjectEventContext {
+    async fn get_by_project_id(&self, project_id: i32) -> Result<Vec<project_event::Model>, DbErr> {
+        project_event::Entity::find()
+            .filter(project_event::Column::ProjectId.eq(project_id))
+            .order_by_desc(project_event::Column::CreatedAt)
+            .order_by_desc(project_event::Column::Id)
+            .all(&self.db_context.get_connection())
+            .await
+    }
+}
+
+impl ProjectEventContext {
+    pub fn new(db_context: Arc<dyn DatabaseContextTrait>) -> ProjectEventContext {
+        ProjectEventContext { db_context }
+    }
+}
+
+/// Inserts a project event on the given connection, timestamped with the current time.
+pub(crate) async fn insert_event<C: ConnectionTrait>(
+    db: &C,
+    entity: project_event::Model,
+) -> Result<project_event::Model, DbErr> {
+    project_event::ActiveModel {
+        id: Default::default(),
+        project_id: Set(entity.project_id),
+        user_id: Set(entity.user_id),
+        event_type: Set(entity.event_type),
+        description: Set(entity.description),
+        created_at: Set(Utc::now().naive_utc()),
+        components_snapshot: Set(entity.components_snapshot),
+    }
+    .insert(db)
+    .await
+}
+
+/// Deletes all but the [`PROJECT_EVENT_RETENTION`] newest events of a project on the given connection.
+pub(crate) async fn prune_events<C: ConnectionTrait>(db: &C, project_id: i32) -> Result<(), DbErr> {
+    let expired: Vec<i32> = project_event::Entity::find()
+        .select_only()
+        .column(project_event::Column::Id)
+        .filter(project_event::Column::ProjectId.eq(project_id))
+        .order_by_desc(project_event::Column::CreatedAt)
+        .order_by_desc(project_event::Column::Id)
+        .offset(PROJECT_EVENT_RETENTION)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    if !expired.is_empty() {
+        project_event::Entity::delete_many()
+            .filter(project_event::Column::Id.is_in(expired))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl EntityContextTrait<project_event::Model> for ProjectEventContext {
+    /// Used for creating a project_event::Model entity. The timestamp is always set to the current time.
+    /// # Example
+    /// ```
+    /// let event = project_event::Model {
+    ///     id: Default::default(),
+    ///     project_id: 1,
+    ///     user_id: 1,
+    ///     event_type: "NameChanged".into(),
+    ///     description: "Renamed project from \"a\" to \"b\"".into(),
+    ///     created_at: Default::default(),
+    ///     components_snapshot: None,
+    /// };
+    /// let project_event_context: ProjectEventContext = ProjectEventContext::new(...);
+    /// project_event_context.create(event);
+    /// ```
+    async fn create(&self, entity: project_event::Model) -> Result<project_event::Model, DbErr> {
+        let db = self.db_context.get_connection();
+        let event = insert_event(&db, entity).await?;
+        prune_events(&db, event.project_id).await?;
+        Ok(event)
+    }
+
+    /// Returns a single project event entity (Uses primary key)
+    async fn get_by_id(&self, entity_id: i32) -> Result<Option<project_event::Model>, DbErr> {
+        project_event::Entity::find_by_id(entity_id)
+            .one(&self.db_context.get_connection())
+            .await
+    }
+
+    /// Returns all project event entities
+    async fn get_all(&self) -> Result<Vec<project_event::Model>, DbErr> {
+        project_event::Entity::find()
+            .all(&self.db_context.get_connection())
+            .await
+    }
+
+    /// Updates the type and description of a single project event entity
+    /// ## Note
+    /// The project, user, timestamp and components snapshot of an event are never changed.
+    async fn update(&self, entity: project_event::Model) -> Result<project_event::Model, DbErr> {
+        project_event::ActiveModel {
+            id: Unchanged(entity.id),
+            project_id: Unchanged(entity.project_id),
+            user_id: Unchanged(entity.user_id),
+            event_type: Set(entity.event_type),
+            description: Set(entity.description),
+            created_at: Unchanged(entity.created_at),
+            components_snapshot: Unchanged(entity.components_snapshot),
+        }
+        .update(&self.db_context.get_connection())
+        .await
+    }
+
+    /// Returns and deletes a single project event entity
+    async fn delete(&self, entity_id: i32) -> Result<project_event::Model, DbErr> {
+        let event = self.get_by_id(entity_id).await?;
+        match event {
+            None => Err(DbErr::RecordNotFound("No record was deleted".into())),
+            Some(event) => {
+                project_event::Entity::delete_by_id(entity_id)
+                    .exec(&self.db_context.get_connection())
+                    .await?;
+                Ok(event)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/contexts/project_event_context.rs"]
+mod project_event_context_tests;
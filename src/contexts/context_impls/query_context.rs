@@ -1,10 +1,16 @@
 use crate::contexts::context_traits::{
     DatabaseContextTrait, EntityContextTrait, QueryContextTrait,
 };
+use crate::contexts::sqlite_lock::retry_if_locked;
+use crate::contexts::timestamps::Timestamped;
 use crate::entities::query;
 use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::{Set, Unchanged};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, NotSet, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, NotSet, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect,
+};
 use std::sync::Arc;
 
 pub struct QueryContext {
@@ -12,12 +18,47 @@ pub struct QueryContext {
 }
 #[async_trait]
 impl QueryContextTrait for QueryContext {
-    async fn get_all_by_project_id(&self, project_id: i32) -> Result<Vec<query::Model>, DbErr> {
+    async fn get_all_by_project_id(
+        &self,
+        project_id: i32,
+        limit: Option<u64>,
+    ) -> Result<Vec<query::Model>, DbErr> {
         query::Entity::find()
             .filter(query::Column::ProjectId.eq(project_id))
+            .order_by_asc(query::Column::Id)
+            .limit(limit)
             .all(&self.db_context.get_connection())
             .await
     }
+
+    async fn count_outdated_queries(&self, project_id: i32) -> Result<u64, DbErr> {
+        query::Entity::find()
+            .filter(query::Column::ProjectId.eq(project_id))
+            .filter(query::Column::Outdated.eq(true))
+            .count(&self.db_context.get_connection())
+            .await
+    }
+
+    async fn count_by_project_id(&self, project_id: i32) -> Result<u64, DbErr> {
+        query::Entity::find()
+            .filter(query::Column::ProjectId.eq(project_id))
+            .count(&self.db_context.get_connection())
+            .await
+    }
+
+    async fn mark_outdated(&self, query_ids: Vec<i32>) -> Result<u64, DbErr> {
+        let db = self.db_context.get_connection();
+
+        retry_if_locked(self.db_context.backend_kind(), || async {
+            let result = query::Entity::update_many()
+                .col_expr(query::Column::Outdated, Expr::value(true))
+                .filter(query::Column::Id.is_in(query_ids.clone()))
+                .exec(&db)
+                .await?;
+            Ok(result.rows_affected)
+        })
+        .await
+    }
 }
 
 impl QueryContext {
@@ -26,6 +67,29 @@ impl QueryContext {
     }
 }
 
+/// Inserts a query on the given connection. The result, settings and outdated flag are left to their defaults.
+pub(crate) async fn insert_query<C: ConnectionTrait>(
+    db: &C,
+    entity: query::Model,
+) -> Result<query::Model, DbErr> {
+    query::ActiveModel {
+        id: Default::default(),
+        string: Set(entity.string),
+        title: Set(entity.title),
+        project_id: Set(entity.project_id),
+        result: NotSet,
+        settings: NotSet,
+        outdated: NotSet,
+        last_run_ms: NotSet,
+        result_computed_at: NotSet,
+        created_at: NotSet,
+        updated_at: NotSet,
+    }
+    .created_now()
+    .insert(db)
+    .await
+}
+
 #[async_trait]
 impl EntityContextTrait<query::Model> for QueryContext {
     /// Used for creating a query entity
@@ -42,15 +106,7 @@ impl EntityContextTrait<query::Model> for QueryContext {
     /// context.create(model);
     /// ```
     async fn create(&self, entity: query::Model) -> Result<query::Model, DbErr> {
-        let query = query::ActiveModel {
-            id: Default::default(),
-            string: Set(entity.string),
-            project_id: Set(entity.project_id),
-            result: NotSet,
-            outdated: NotSet,
-        };
-        let query = query.insert(&self.db_context.get_connection()).await?;
-        Ok(query)
+        insert_query(&self.db_context.get_connection(), entity).await
     }
 
     /// Returns a single query entity (uses primary key)
@@ -101,15 +157,27 @@ impl EntityContextTrait<query::Model> for QueryContext {
     /// ```
     /// ## Note
     /// The user entity's id will never be changed. If this behavior is wanted, delete the old user and create a one.
+    /// The update sets every column, so it is retried if SQLite is locked.
     async fn update(&self, entity: query::Model) -> Result<query::Model, DbErr> {
-        query::ActiveModel {
-            id: Unchanged(entity.id),
-            string: Set(entity.string),
-            result: Set(entity.result),
-            outdated: Set(entity.outdated),
-            project_id: Unchanged(entity.project_id),
-        }
-        .update(&self.db_context.get_connection())
+        let db = self.db_context.get_connection();
+
+        retry_if_locked(self.db_context.backend_kind(), || {
+            query::ActiveModel {
+                id: Unchanged(entity.id),
+                string: Set(entity.string.clone()),
+                title: Set(entity.title.clone()),
+                result: Set(entity.result.clone()),
+                settings: Set(entity.settings.clone()),
+                outdated: Set(entity.outdated),
+                project_id: Unchanged(entity.project_id),
+                last_run_ms: Set(entity.last_run_ms),
+                result_computed_at: Set(entity.result_computed_at),
+                created_at: NotSet,
+                updated_at: NotSet,
+            }
+            .updated_now()
+            .update(&db)
+        })
         .await
     }
 
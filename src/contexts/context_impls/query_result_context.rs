@@ -0,0 +1,153 @@
+use crate::contexts::context_traits::{
+    DatabaseContextTrait, EntityContextTrait, QueryResultContextTrait,
+};
+use crate::entities::query_result;
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, Unchanged,
+};
+use std::sync::Arc;
+
+/// The number of results kept per query when `QUERY_RESULT_HISTORY_DEPTH` is not set.
+pub const DEFAULT_QUERY_RESULT_HISTORY_DEPTH: u64 = 20;
+
+pub struct QueryResultContext {
+    db_context: Arc<dyn DatabaseContextTrait>,
+    history_depth: u64,
+}
+
+#[async_trait]
+impl QueryResultContextTrait for QueryResultContext {
+    async fn get_by_query_id(&self, query_id: i32) -> Result<Vec<query_result::Model>, DbErr> {
+        query_result::Entity::find()
+            .filter(query_result::Column::QueryId.eq(query_id))
+            .order_by_desc(query_result::Column::CreatedAt)
+            .order_by_desc(query_result::Column::Id)
+            .all(&self.db_context.get_connection())
+            .await
+    }
+}
+
+impl QueryResultContext {
+    pub fn new(db_context: Arc<dyn DatabaseContextTrait>) -> QueryResultContext {
+        Self::with_history_depth(db_context, DEFAULT_QUERY_RESULT_HISTORY_DEPTH)
+    }
+
+    /// Creates a context keeping the `history_depth` newest results of every query.
+    pub fn with_history_depth(
+        db_context: Arc<dyn DatabaseContextTrait>,
+        history_depth: u64,
+    ) -> QueryResultContext {
+        QueryResultContext {
+            db_context,
+            history_depth,
+        }
+    }
+}
+
+/// Deletes all but the `history_depth` newest results of a query on the given connection.
+async fn prune_results<C: ConnectionTrait>(
+    db: &C,
+    query_id: i32,
+    history_depth: u64,
+) -> Result<(), DbErr> {
+    let expired: Vec<i32> = query_result::Entity::find()
+        .select_only()
+        .column(query_result::Column::Id)
+        .filter(query_result::Column::QueryId.eq(query_id))
+        .order_by_desc(query_result::Column::CreatedAt)
+        .order_by_desc(query_result::Column::Id)
+        .offset(history_depth)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    if !expired.is_empty() {
+        query_result::Entity::delete_many()
+            .filter(query_result::Column::Id.is_in(expired))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl EntityContextTrait<query_result::Model> for QueryResultContext {
+    /// Records a result of a query, timestamped with the current time.
+    /// Results beyond the history depth of the query are pruned, oldest first.
+    /// # Example
+    /// ```
+    /// let result = query_result::Model {
+    ///     id: Default::default(),
+    ///     query_id: 1,
+    ///     result: Some(json!({ "Success": {} })),
+    ///     last_run_ms: Some(42),
+    ///     created_at: Default::default(),
+    /// };
+    /// let query_result_context: QueryResultContext = QueryResultContext::new(...);
+    /// query_result_context.create(result);
+    /// ```
+    async fn create(&self, entity: query_result::Model) -> Result<query_result::Model, DbErr> {
+        let db = self.db_context.get_connection();
+        let result = query_result::ActiveModel {
+            id: Default::default(),
+            query_id: Set(entity.query_id),
+            result: Set(entity.result),
+            last_run_ms: Set(entity.last_run_ms),
+            created_at: Set(Utc::now().naive_utc()),
+        }
+        .insert(&db)
+        .await?;
+        prune_results(&db, result.query_id, self.history_depth).await?;
+        Ok(result)
+    }
+
+    /// Returns a single query result entity (Uses primary key)
+    async fn get_by_id(&self, entity_id: i32) -> Result<Option<query_result::Model>, DbErr> {
+        query_result::Entity::find_by_id(entity_id)
+            .one(&self.db_context.get_connection())
+            .await
+    }
+
+    /// Returns all query result entities
+    async fn get_all(&self) -> Result<Vec<query_result::Model>, DbErr> {
+        query_result::Entity::find()
+            .all(&self.db_context.get_connection())
+            .await
+    }
+
+    /// Updates the result and run time of a single query result entity
+    /// ## Note
+    /// The query and timestamp of a result are never changed.
+    async fn update(&self, entity: query_result::Model) -> Result<query_result::Model, DbErr> {
+        query_result::ActiveModel {
+            id: Unchanged(entity.id),
+            query_id: Unchanged(entity.query_id),
+            result: Set(entity.result),
+            last_run_ms: Set(entity.last_run_ms),
+            created_at: Unchanged(entity.created_at),
+        }
+        .update(&self.db_context.get_connection())
+        .await
+    }
+
+    /// Returns and deletes a single query result entity
+    async fn delete(&self, entity_id: i32) -> Result<query_result::Model, DbErr> {
+        let result = self.get_by_id(entity_id).await?;
+        match result {
+            None => Err(DbErr::RecordNotFound("No record was deleted".into())),
+            Some(result) => {
+                query_result::Entity::delete_by_id(entity_id)
+                    .exec(&self.db_context.get_connection())
+                    .await?;
+                Ok(result)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/contexts/query_result_context.rs"]
+mod query_result_context_tests;
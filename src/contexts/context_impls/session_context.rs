@@ -6,7 +6,7 @@ use crate::entities::session;
 use chrono::Local;
 use sea_orm::prelude::async_trait::async_trait;
 use sea_orm::ActiveValue::{Set, Unchanged};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, NotSet, QueryFilter};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, NotSet, QueryFilter, QueryOrder};
 use std::sync::Arc;
 
 pub struct SessionContext {
@@ -54,6 +54,15 @@ impl SessionContextTrait for SessionContext {
 
         Ok(session)
     }
+
+    async fn get_by_user_id(&self, user_id: i32) -> Result<Vec<session::Model>, DbErr> {
+        session::Entity::find()
+            .filter(session::Column::UserId.eq(user_id))
+            .order_by_asc(session::Column::UpdatedAt)
+            .order_by_asc(session::Column::Id)
+            .all(&self.db_context.get_connection())
+            .await
+    }
 }
 
 impl SessionContext {
@@ -83,6 +92,7 @@ impl EntityContextTrait<session::Model> for SessionContext {
             refresh_token: Set(entity.refresh_token),
             access_token: Set(entity.access_token),
             user_id: Set(entity.user_id),
+            created_at: Set(Some(Local::now().naive_local())),
             updated_at: NotSet,
         };
 
@@ -141,6 +151,7 @@ impl EntityContextTrait<session::Model> for SessionContext {
             refresh_token: Set(entity.refresh_token),
             access_token: Set(entity.access_token),
             user_id: Unchanged(entity.user_id),
+            created_at: NotSet,
             updated_at: Set(Local::now().naive_local()),
         }
         .update(&self.db_context.get_connection())
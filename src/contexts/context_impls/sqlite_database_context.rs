@@ -1,3 +1,4 @@
+use crate::contexts::connect_options::connect_options;
 use crate::contexts::context_traits::DatabaseContextTrait;
 use migration::{Migrator, MigratorTrait};
 use sea_orm::prelude::async_trait::async_trait;
@@ -11,8 +12,12 @@ pub struct SQLiteDatabaseContext {
 }
 
 impl SQLiteDatabaseContext {
-    pub async fn new(connection_string: &str) -> Result<SQLiteDatabaseContext, DbErr> {
-        let db = Database::connect(connection_string).await?;
+    /// Connects to the database at `connection_string`, logging every statement if `log_statements` is set.
+    pub async fn new(
+        connection_string: &str,
+        log_statements: bool,
+    ) -> Result<SQLiteDatabaseContext, DbErr> {
+        let db = Database::connect(connect_options(connection_string, log_statements)).await?;
 
         let db = match db.get_database_backend() {
             DbBackend::Sqlite => db,
@@ -33,7 +38,15 @@ impl DatabaseContextTrait for SQLiteDatabaseContext {
         }))
     }
 
+    async fn ping(&self) -> Result<(), DbErr> {
+        self.db_connection.ping().await
+    }
+
     fn get_connection(&self) -> DatabaseConnection {
         self.db_connection.clone()
     }
+
+    fn backend_kind(&self) -> DbBackend {
+        self.db_connection.get_database_backend()
+    }
 }
@@ -58,6 +58,7 @@ impl EntityContextTrait<user::Model> for UserContext {
             email: Set(entity.email),
             username: Set(entity.username),
             password: Set(entity.password),
+            display_name: Set(entity.display_name),
         };
         let user = user.insert(&self.db_context.get_connection()).await?;
         Ok(user)
@@ -115,6 +116,7 @@ impl EntityContextTrait<user::Model> for UserContext {
             email: Set(entity.email),
             username: Set(entity.username),
             password: Set(entity.password),
+            display_name: Set(entity.display_name),
         }
         .update(&self.db_context.get_connection())
         .await
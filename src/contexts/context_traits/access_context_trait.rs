@@ -1,5 +1,6 @@
 use crate::api::server::protobuf::AccessInfo;
 use crate::contexts::context_traits::EntityContextTrait;
+use crate::contexts::pagination::Page;
 use crate::entities::access;
 use async_trait::async_trait;
 use sea_orm::DbErr;
@@ -15,6 +16,28 @@ pub trait AccessContextTrait: EntityContextTrait<access::Model> {
         uid: i32,
         project_id: i32,
     ) -> Result<Option<access::Model>, DbErr>;
-    /// Returns all [`access::Model`] that are associated with a given `Project``
-    async fn get_access_by_project_id(&self, project_id: i32) -> Result<Vec<AccessInfo>, DbErr>;
+    /// Returns a page of the [`access::Model`] that are associated with a given `Project`, ordered by role
+    async fn get_access_by_project_id(
+        &self,
+        project_id: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Page<AccessInfo>, DbErr>;
+    /// Updates the roles of several access entities in a single transaction
+    /// # Errors
+    /// Errors on failed connection or if any of the entities does not exist,
+    /// in which case none of the roles are updated.
+    async fn update_roles(&self, entities: Vec<access::Model>)
+        -> Result<Vec<access::Model>, DbErr>;
+    /// Returns the ids of every `Project` the given `User` has an access entity on, in ascending order.
+    /// Only the id column is selected, so this is cheaper than fetching the accesses themselves.
+    async fn get_accessible_project_ids(&self, uid: i32) -> Result<Vec<i32>, DbErr>;
+    /// Returns the access entities with the given ids, ordered by id. Unknown ids are ignored.
+    async fn get_access_by_ids(&self, ids: Vec<i32>) -> Result<Vec<access::Model>, DbErr>;
+    /// Returns the access entities the given `User` has on any of the given `Project` ids
+    async fn get_access_by_uid_and_project_ids(
+        &self,
+        uid: i32,
+        project_ids: Vec<i32>,
+    ) -> Result<Vec<access::Model>, DbErr>;
 }
@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use sea_orm::{DatabaseConnection, DbErr};
+use sea_orm::{DatabaseConnection, DbBackend, DbErr};
 use std::fmt::Debug;
 use std::sync::Arc;
 
@@ -9,6 +9,12 @@ pub trait DatabaseContextTrait: Send + Sync + Debug {
     /// # Errors
     /// Errors on failed connection or execution error.
     async fn reset(&self) -> Result<Arc<dyn DatabaseContextTrait>, DbErr>;
+    /// Checks that the database is reachable and answering
+    /// # Errors
+    /// Errors if the database cannot be reached.
+    async fn ping(&self) -> Result<(), DbErr>;
     /// Gets the connection to the database
     fn get_connection(&self) -> DatabaseConnection;
+    /// Gets the kind of database backend the context is connected to
+    fn backend_kind(&self) -> DbBackend;
 }
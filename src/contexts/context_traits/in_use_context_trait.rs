@@ -1,4 +1,24 @@
 use crate::contexts::context_traits::EntityContextTrait;
 use crate::entities::in_use;
+use async_trait::async_trait;
+use chrono::Duration;
+use sea_orm::DbErr;
 
-pub trait InUseContextTrait: EntityContextTrait<in_use::Model> {}
+#[async_trait]
+pub trait InUseContextTrait: EntityContextTrait<in_use::Model> {
+    /// Returns the number of projects that are currently in use,
+    /// i.e. whose `latest_activity` lies within the given `timeout`
+    /// # Errors
+    /// Errors on failed connection or execution error.
+    async fn count_active_locks(&self, timeout: Duration) -> Result<u64, DbErr>;
+
+    /// Returns the locks held by any session of the user with the given `uid`
+    /// whose `latest_activity` lies within the given `timeout`, ordered by project id.
+    /// # Errors
+    /// Errors on failed connection or execution error.
+    async fn get_active_locks_by_uid(
+        &self,
+        uid: i32,
+        timeout: Duration,
+    ) -> Result<Vec<in_use::Model>, DbErr>;
+}
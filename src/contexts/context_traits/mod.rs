@@ -3,7 +3,9 @@ pub mod database_context_trait;
 pub mod entity_context_trait;
 pub mod in_use_context_trait;
 pub mod project_context_trait;
+pub mod project_event_context_trait;
 pub mod query_context_trait;
+pub mod query_result_context_trait;
 pub mod session_context_trait;
 pub mod user_context_trait;
 
@@ -12,6 +14,8 @@ pub use database_context_trait::DatabaseContextTrait;
 pub use entity_context_trait::EntityContextTrait;
 pub use in_use_context_trait::InUseContextTrait;
 pub use project_context_trait::ProjectContextTrait;
+pub use project_event_context_trait::ProjectEventContextTrait;
 pub use query_context_trait::QueryContextTrait;
+pub use query_result_context_trait::QueryResultContextTrait;
 pub use session_context_trait::SessionContextTrait;
 pub use user_context_trait::UserContextTrait;
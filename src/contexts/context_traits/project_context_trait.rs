@@ -1,13 +1,59 @@
 use crate::api::server::protobuf::ProjectInfo;
 use crate::contexts::context_traits::EntityContextTrait;
-use crate::entities::project;
+use crate::contexts::pagination::Page;
+use crate::entities::{access, in_use, project, project_event, query};
 use async_trait::async_trait;
 use sea_orm::DbErr;
 
 #[async_trait]
 pub trait ProjectContextTrait: EntityContextTrait<project::Model> {
-    /// Returns the projects owned by a given user id
+    /// Returns a page of the projects that a given user id has access to, ordered by project name
+    ///
+    /// Each [`ProjectInfo`] includes the owner's username, or `None` if the owner's user row is missing.
     /// # Errors
     /// Errors on failed connection, execution error or constraint violations.
-    async fn get_project_info_by_uid(&self, uid: i32) -> Result<Vec<ProjectInfo>, DbErr>;
+    async fn get_project_info_by_uid(
+        &self,
+        uid: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Page<ProjectInfo>, DbErr>;
+
+    /// Returns a page of the full projects that a given user id has access to, ordered by project name
+    /// # Errors
+    /// Errors on failed connection or execution error.
+    async fn get_projects_by_uid(
+        &self,
+        uid: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Page<project::Model>, DbErr>;
+
+    /// Updates a project and logs the given events for it in a single transaction.
+    ///
+    /// Only the newest events of the project are kept, older ones are pruned.
+    /// # Errors
+    /// Errors on failed connection, execution error or if the project does not exist,
+    /// in which case neither the project nor the events are written.
+    async fn update_with_events(
+        &self,
+        entity: project::Model,
+        events: Vec<project_event::Model>,
+    ) -> Result<project::Model, DbErr>;
+
+    /// Creates a project together with its access, in_use and initial queries in a single transaction.
+    ///
+    /// The project id of the access, in_use and queries is set to the id of the new project.
+    /// No in_use is created if it is [`None`].
+    /// Returns the created project and queries.
+    /// # Errors
+    /// Errors on failed connection, execution error or constraint violations,
+    /// in which case nothing is written.
+    async fn create_with_queries(
+        &self,
+        entity: project::Model,
+        access: access::Model,
+        in_use: Option<in_use::Model>,
+        queries: Vec<query::Model>,
+    ) -> Result<(project::Model, Vec<query::Model>), DbErr>;
 }
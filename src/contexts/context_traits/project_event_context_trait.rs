@@ -0,0 +1,12 @@
+use crate::contexts::context_traits::EntityContextTrait;
+use crate::entities::project_event;
+use async_trait::async_trait;
+use sea_orm::DbErr;
+
+#[async_trait]
+pub trait ProjectEventContextTrait: EntityContextTrait<project_event::Model> {
+    /// Returns the events logged for a given project id, newest first
+    /// # Errors
+    /// Errors on failed connection or execution error.
+    async fn get_by_project_id(&self, project_id: i32) -> Result<Vec<project_event::Model>, DbErr>;
+}
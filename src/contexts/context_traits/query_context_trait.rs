@@ -5,6 +5,17 @@ use sea_orm::DbErr;
 
 #[async_trait]
 pub trait QueryContextTrait: EntityContextTrait<query::Model> {
-    /// Returns the queries associated with a given project id
-    async fn get_all_by_project_id(&self, project_id: i32) -> Result<Vec<query::Model>, DbErr>;
+    /// Returns the queries associated with a given project id, ordered by id so the oldest query comes first.
+    /// At most `limit` queries are returned, if given.
+    async fn get_all_by_project_id(
+        &self,
+        project_id: i32,
+        limit: Option<u64>,
+    ) -> Result<Vec<query::Model>, DbErr>;
+    /// Returns the number of outdated queries associated with a given project id
+    async fn count_outdated_queries(&self, project_id: i32) -> Result<u64, DbErr>;
+    /// Returns the number of queries associated with a given project id
+    async fn count_by_project_id(&self, project_id: i32) -> Result<u64, DbErr>;
+    /// Marks the queries with the given ids as outdated, returning the number of queries marked
+    async fn mark_outdated(&self, query_ids: Vec<i32>) -> Result<u64, DbErr>;
 }
@@ -0,0 +1,12 @@
+use crate::contexts::context_traits::EntityContextTrait;
+use crate::entities::query_result;
+use async_trait::async_trait;
+use sea_orm::DbErr;
+
+#[async_trait]
+pub trait QueryResultContextTrait: EntityContextTrait<query_result::Model> {
+    /// Returns the results recorded for a given query id, newest first
+    /// # Errors
+    /// Errors on failed connection or execution error.
+    async fn get_by_query_id(&self, query_id: i32) -> Result<Vec<query_result::Model>, DbErr>;
+}
@@ -23,4 +23,8 @@ pub trait SessionContextTrait: EntityContextTrait<session::Model> {
         token_type: TokenType,
         token: String,
     ) -> Result<session::Model, DbErr>;
+    /// Returns all sessions of a user, least recently updated first
+    /// # Errors
+    /// Errors on failed connection, execution error or constraint violations.
+    async fn get_by_user_id(&self, user_id: i32) -> Result<Vec<session::Model>, DbErr>;
 }
@@ -1,3 +1,9 @@
+pub mod components_storage;
+pub mod connect_options;
 pub mod context_collection;
 pub mod context_impls;
 pub mod context_traits;
+pub mod pagination;
+pub mod readiness;
+pub mod sqlite_lock;
+pub mod timestamps;
@@ -0,0 +1,70 @@
+//! Helpers for splitting the result of a select statement into pages.
+use crate::api::server::protobuf::PageInfo;
+use sea_orm::{ConnectionTrait, DbErr, PaginatorTrait, SelectorTrait};
+
+/// The page size used when a request does not specify one.
+pub const DEFAULT_PAGE_SIZE: u64 = 20;
+
+/// The largest page size served when `MAX_PAGE_SIZE` is not set.
+pub const DEFAULT_MAX_PAGE_SIZE: u64 = 100;
+
+/// Returns the page size to serve for a requested `page_size`.
+///
+/// A `page_size` of `0` is replaced by [`DEFAULT_PAGE_SIZE`], and sizes above `max_page_size`
+/// are clamped to it rather than rejected.
+pub fn effective_page_size(page_size: u64, max_page_size: u64) -> u64 {
+    match page_size {
+        0 => DEFAULT_PAGE_SIZE,
+        page_size => page_size,
+    }
+    .min(max_page_size)
+}
+
+/// A single page of items together with the [`PageInfo`] describing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page_info: PageInfo,
+}
+
+/// Fetches the (zero-indexed) `page` of the given select statement,
+/// counting the total number of items in the same go.
+///
+/// The page size is chosen by [`effective_page_size`] with the given `max_page_size`,
+/// so the returned [`PageInfo`] tells the client if its page size was clamped.
+/// The select statement should end its ordering with a unique column, such as the id,
+/// as rows that tie on the ordering can otherwise be skipped or repeated across pages.
+/// # Errors
+/// Errors on failed connection or execution error.
+pub async fn get_paginated<'db, C, S>(
+    select: S,
+    db: &'db C,
+    page: u64,
+    page_size: u64,
+    max_page_size: u64,
+) -> Result<Page<<S::Selector as SelectorTrait>::Item>, DbErr>
+where
+    C: ConnectionTrait,
+    S: PaginatorTrait<'db, C>,
+{
+    let page_size = effective_page_size(page_size, max_page_size);
+
+    let paginator = select.paginate(db, page_size);
+
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(page).await?;
+
+    Ok(Page {
+        items,
+        page_info: PageInfo {
+            total,
+            page,
+            page_size,
+            has_next: page.saturating_add(1).saturating_mul(page_size) < total,
+        },
+    })
+}
+
+#[cfg(test)]
+#[path = "../tests/contexts/pagination.rs"]
+mod pagination_tests;
@@ -0,0 +1,69 @@
+//! Waiting for the database to become reachable before serving requests.
+use crate::contexts::context_traits::DatabaseContextTrait;
+use sea_orm::DbErr;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// How long to keep retrying before giving up, when `DATABASE_READY_TIMEOUT_SECS` is not set.
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Connects to the database and pings it, retrying with exponential backoff until it answers
+/// or `timeout` has passed.
+///
+/// Useful when the database and the API are started together and the database is not ready yet.
+/// # Errors
+/// Returns the error of the last attempt if the database did not become ready in time.
+pub async fn connect_with_retry<F, Fut>(
+    mut connect: F,
+    timeout: Duration,
+) -> Result<Arc<dyn DatabaseContextTrait>, DbErr>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Arc<dyn DatabaseContextTrait>, DbErr>>,
+{
+    let deadline = Instant::now() + timeout;
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt: u32 = 1;
+
+    loop {
+        let result = match connect().await {
+            Ok(db_context) => db_context.ping().await.map(|_| db_context),
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(db_context) => {
+                log::info!("Database is ready after {} attempt(s)", attempt);
+                return Ok(db_context);
+            }
+            Err(error) if Instant::now() + backoff < deadline => {
+                log::warn!(
+                    "Database not ready (attempt {}): {}. Retrying in {} ms",
+                    attempt,
+                    error,
+                    backoff.as_millis()
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                attempt += 1;
+            }
+            Err(error) => {
+                log::error!(
+                    "Database not ready after {} attempt(s), giving up: {}",
+                    attempt,
+                    error
+                );
+                return Err(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/contexts/readiness.rs"]
+mod readiness_tests;
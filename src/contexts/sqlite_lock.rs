@@ -0,0 +1,56 @@
+//! Retrying database operations that fail because SQLite is locked by a concurrent writer.
+use sea_orm::{DbBackend, DbErr};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How many times an operation is attempted before the lock error is returned.
+pub const MAX_LOCKED_ATTEMPTS: u32 = 4;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Returns whether an error was caused by SQLite being locked by another connection.
+pub fn is_database_locked(error: &DbErr) -> bool {
+    let message = error.to_string().to_lowercase();
+    message.contains("database is locked") || message.contains("database table is locked")
+}
+
+/// Runs `operation`, retrying it with a small exponential backoff while SQLite reports that
+/// the database is locked.
+///
+/// Only use this for operations that are safe to run again, such as idempotent updates or
+/// work done in a single transaction. Other backends and other errors are never retried.
+/// # Errors
+/// Returns the error of the last attempt.
+pub async fn retry_if_locked<T, F, Fut>(backend: DbBackend, mut operation: F) -> Result<T, DbErr>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DbErr>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt: u32 = 1;
+
+    loop {
+        match operation().await {
+            Err(error)
+                if backend == DbBackend::Sqlite
+                    && is_database_locked(&error)
+                    && attempt < MAX_LOCKED_ATTEMPTS =>
+            {
+                log::warn!(
+                    "Database is locked (attempt {}), retrying in {} ms",
+                    attempt,
+                    backoff.as_millis()
+                );
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/contexts/sqlite_lock.rs"]
+mod sqlite_lock_tests;
@@ -0,0 +1,43 @@
+use crate::entities::{access, query};
+use chrono::{Local, NaiveDateTime};
+use sea_orm::ActiveValue::Set;
+
+/// Active models carrying `created_at`/`updated_at` audit columns.
+/// Contexts call [`Timestamped::created_now`] when inserting and [`Timestamped::updated_now`] when updating,
+/// so the columns are populated without callers having to supply them.
+pub trait Timestamped: Sized {
+    fn set_created_at(&mut self, at: NaiveDateTime);
+    fn set_updated_at(&mut self, at: NaiveDateTime);
+
+    /// Stamps both columns with the current time.
+    fn created_now(mut self) -> Self {
+        let now = Local::now().naive_local();
+        self.set_created_at(now);
+        self.set_updated_at(now);
+        self
+    }
+
+    /// Bumps `updated_at` to the current time, leaving `created_at` untouched.
+    fn updated_now(mut self) -> Self {
+        self.set_updated_at(Local::now().naive_local());
+        self
+    }
+}
+
+macro_rules! impl_timestamped {
+    ($($entity:ident),*) => {
+        $(
+            impl Timestamped for $entity::ActiveModel {
+                fn set_created_at(&mut self, at: NaiveDateTime) {
+                    self.created_at = Set(Some(at));
+                }
+
+                fn set_updated_at(&mut self, at: NaiveDateTime) {
+                    self.updated_at = Set(Some(at));
+                }
+            }
+        )*
+    };
+}
+
+impl_timestamped!(access, query);
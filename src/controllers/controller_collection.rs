@@ -1,4 +1,3 @@
-use crate::api::server::protobuf::ecdar_backend_server::EcdarBackend;
 use crate::controllers::controller_traits::*;
 use std::sync::Arc;
 
@@ -9,5 +8,6 @@ pub struct ControllerCollection {
     pub(crate) query_controller: Arc<dyn QueryControllerTrait>,
     pub(crate) session_controller: Arc<dyn SessionControllerTrait>,
     pub(crate) user_controller: Arc<dyn UserControllerTrait>,
-    pub(crate) reveaal_controller: Arc<dyn EcdarBackend>,
+    pub(crate) reveaal_controller: Arc<dyn ReveaalControllerTrait>,
+    pub(crate) server_info_controller: Arc<dyn ServerInfoControllerTrait>,
 }
@@ -1,26 +1,130 @@
 use crate::api::auth::RequestExt;
 use crate::api::server::protobuf::create_access_request::User;
+use crate::api::server::protobuf::remove_collaborator_request;
 use crate::api::server::protobuf::{
-    CreateAccessRequest, DeleteAccessRequest, ListAccessInfoRequest, ListAccessInfoResponse,
-    UpdateAccessRequest,
+    AccessRecord, AccessUpdateResult, CreateAccessRequest, DeleteAccessRequest, GetAccessesRequest,
+    GetAccessesResponse, ListAccessInfoRequest, ListAccessInfoResponse, RemoveCollaboratorRequest,
+    UpdateAccessRequest, UpdateAccessesRequest, UpdateAccessesResponse,
 };
+use crate::config::Config;
 use crate::contexts::context_collection::ContextCollection;
 use crate::contexts::context_traits::{AccessContextTrait, UserContextTrait};
 use crate::controllers::controller_traits::AccessControllerTrait;
+use crate::controllers::error_details::database_busy_error;
+use crate::controllers::sanitization::sanitize;
+use crate::entities::role::Role;
 use crate::entities::{access, user};
+use crate::services::access_events::{AccessEvent, AccessEventBus};
 use async_trait::async_trait;
+use sea_orm::SqlErr;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use std::sync::Arc;
 use tonic::{Code, Request, Response, Status};
 
+/// The maximum number of access ids that can be resolved in a single `get_accesses` request
+const MAX_ACCESS_BATCH_SIZE: usize = 100;
+
+/// The error reported by `update_accesses` for an access that does not exist or is on another project
+const ACCESS_NOT_FOUND_ERROR: &str = "No access entity found with given id";
+
+/// What `create_access` does when the user already has an access on the project.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicateAccessPolicy {
+    /// The request fails with `AlreadyExists` and the existing access is left unchanged
+    Reject,
+    /// The role of the existing access is changed to the requested one, exactly as `update_access` would
+    UpdateRole,
+}
+
+impl FromStr for DuplicateAccessPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(DuplicateAccessPolicy::Reject),
+            "update_role" => Ok(DuplicateAccessPolicy::UpdateRole),
+            _ => Err(format!(
+                "'{}' is not a duplicate access policy, expected 'reject' or 'update_role'",
+                value
+            )),
+        }
+    }
+}
+
 pub struct AccessController {
     contexts: ContextCollection,
+    config: Config,
+    events: AccessEventBus,
 }
 
 impl AccessController {
-    pub fn new(contexts: ContextCollection) -> Self {
-        AccessController { contexts }
+    pub fn new(contexts: ContextCollection, config: Config) -> Self {
+        AccessController {
+            contexts,
+            config,
+            events: AccessEventBus::default(),
+        }
+    }
+
+    /// Returns the bus that every stored change to an access is published to
+    // Subscribed to once the watch stream and audit log exist
+    #[allow(dead_code)]
+    pub fn events(&self) -> &AccessEventBus {
+        &self.events
+    }
+
+    /// Changes the role of an access found by `create_access`, protecting the owner's access like `update_access`.
+    async fn update_existing_role(
+        &self,
+        existing: access::Model,
+        role: String,
+        uid: i32,
+    ) -> Result<Response<()>, Status> {
+        let model = self
+            .contexts
+            .project_context
+            .get_by_id(existing.project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| Status::new(Code::NotFound, "No model found for access".to_string()))?;
+
+        if model.owner_id == existing.user_id {
+            return Err(Status::new(
+                Code::PermissionDenied,
+                "Requester does not have permission to update access for this user",
+            ));
+        }
+
+        match self
+            .contexts
+            .access_context
+            .update(access::Model {
+                role: role.clone(),
+                ..existing
+            })
+            .await
+        {
+            Ok(_) => {
+                self.events.publish(AccessEvent::RoleChanged {
+                    project_id: existing.project_id,
+                    user_id: existing.user_id,
+                    role,
+                    changed_by: uid,
+                });
+                Ok(Response::new(()))
+            }
+            Err(error) => Err(Status::new(Code::Internal, sanitize(&error.to_string()))),
+        }
     }
 }
+
+fn access_already_exists() -> Status {
+    Status::new(
+        Code::AlreadyExists,
+        "The user already has access to this project",
+    )
+}
 #[async_trait]
 impl AccessControllerTrait for AccessController {
     async fn list_access_info(
@@ -59,17 +163,20 @@ impl AccessControllerTrait for AccessController {
         match self
             .contexts
             .access_context
-            .get_access_by_project_id(message.project_id)
+            .get_access_by_project_id(message.project_id, message.page, message.page_size)
             .await
         {
-            Ok(access_info_list) => {
-                if access_info_list.is_empty() {
+            Ok(page) => {
+                if page.items.is_empty() && self.config.legacy_not_found_on_empty_list {
                     return Err(Status::new(
                         Code::NotFound,
                         "No access found for given user",
                     ));
                 } else {
-                    Ok(Response::new(ListAccessInfoResponse { access_info_list }))
+                    Ok(Response::new(ListAccessInfoResponse {
+                        access_info_list: page.items,
+                        page_info: Some(page.page_info),
+                    }))
                 }
             }
             Err(error) => Err(Status::new(Code::Internal, error.to_string())),
@@ -105,16 +212,48 @@ impl AccessControllerTrait for AccessController {
                 create_access_find_user_helper(Arc::clone(&self.contexts.user_context), user)
                     .await?;
 
+            let existing = self
+                .contexts
+                .access_context
+                .get_access_by_uid_and_project_id(user_from_db.id, message.project_id)
+                .await
+                .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+            if let Some(existing) = existing {
+                return match self.config.duplicate_access_policy {
+                    DuplicateAccessPolicy::Reject => Err(access_already_exists()),
+                    DuplicateAccessPolicy::UpdateRole => {
+                        self.update_existing_role(existing, message.role, uid).await
+                    }
+                };
+            }
+
             let access = access::Model {
                 id: Default::default(),
                 role: message.role.to_string(),
                 project_id: message.project_id,
                 user_id: user_from_db.id,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             };
 
             match self.contexts.access_context.create(access).await {
-                Ok(_) => Ok(Response::new(())),
-                Err(error) => Err(Status::new(Code::Internal, error.to_string())),
+                Ok(access) => {
+                    self.events.publish(AccessEvent::Granted {
+                        project_id: access.project_id,
+                        user_id: access.user_id,
+                        role: access.role,
+                        changed_by: uid,
+                    });
+                    Ok(Response::new(()))
+                }
+                // Another request created the access since it was looked up
+                Err(error)
+                    if matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) =>
+                {
+                    Err(access_already_exists())
+                }
+                Err(error) => Err(Status::new(Code::Internal, sanitize(&error.to_string()))),
             }
         } else {
             Err(Status::new(
@@ -169,7 +308,7 @@ impl AccessControllerTrait for AccessController {
             .ok_or_else(|| Status::new(Code::NotFound, "No model found for access".to_string()))?;
 
         // Check that the requester is not trying to update the owner's access
-        if model.owner_id == message.id {
+        if model.owner_id == user_access.user_id {
             return Err(Status::new(
                 Code::PermissionDenied,
                 "Requester does not have permission to update access for this user",
@@ -178,15 +317,233 @@ impl AccessControllerTrait for AccessController {
 
         let access = access::Model {
             id: message.id,
-            role: message.role,
+            role: message.role.clone(),
             project_id: Default::default(),
             user_id: Default::default(),
+            created_at: Default::default(),
+            updated_at: Default::default(),
         };
 
         match self.contexts.access_context.update(access).await {
-            Ok(_) => Ok(Response::new(())),
-            Err(error) => Err(Status::new(Code::Internal, error.to_string())),
+            Ok(_) => {
+                self.events.publish(AccessEvent::RoleChanged {
+                    project_id: user_access.project_id,
+                    user_id: user_access.user_id,
+                    role: message.role,
+                    changed_by: uid,
+                });
+                Ok(Response::new(()))
+            }
+            Err(error) => Err(Status::new(Code::Internal, sanitize(&error.to_string()))),
+        }
+    }
+
+    async fn update_accesses(
+        &self,
+        request: Request<UpdateAccessesRequest>,
+    ) -> Result<Response<UpdateAccessesResponse>, Status> {
+        let message = request.get_ref().clone();
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let mut accesses = vec![];
+        for update in message.updates {
+            let access = self
+                .contexts
+                .access_context
+                .get_by_id(update.access_id)
+                .await
+                .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+            accesses.push((update, access));
+        }
+
+        // The project of the first known access is the one being edited, and the requester's role on it
+        // is checked before anything is reported about the individual accesses
+        let project_id = match accesses.iter().find_map(|(_, access)| access.as_ref()) {
+            Some(access) => access.project_id,
+            None => {
+                return Ok(Response::new(UpdateAccessesResponse {
+                    results: accesses
+                        .into_iter()
+                        .map(|(update, _)| AccessUpdateResult {
+                            access_id: update.access_id,
+                            success: false,
+                            error: ACCESS_NOT_FOUND_ERROR.to_string(),
+                        })
+                        .collect(),
+                }))
+            }
+        };
+
+        check_editor_role_helper(Arc::clone(&self.contexts.access_context), uid, project_id)
+            .await?;
+
+        let model = self
+            .contexts
+            .project_context
+            .get_by_id(project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| Status::new(Code::NotFound, "No model found for access".to_string()))?;
+
+        let mut results = vec![];
+        let mut updates = vec![];
+        for (update, access) in accesses {
+            // Accesses on other projects are reported as unknown, so their existence is not revealed
+            let access = match access {
+                Some(access) if access.project_id == project_id => access,
+                _ => {
+                    results.push(AccessUpdateResult {
+                        access_id: update.access_id,
+                        success: false,
+                        error: ACCESS_NOT_FOUND_ERROR.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            // The owner's access is protected, exactly as in update_access
+            let error = if model.owner_id == access.user_id {
+                Some(
+                    "Requester does not have permission to update access for this user".to_string(),
+                )
+            } else {
+                update.new_role.parse::<Role>().err()
+            };
+
+            results.push(AccessUpdateResult {
+                access_id: access.id,
+                success: error.is_none(),
+                error: error.clone().unwrap_or_default(),
+            });
+
+            if error.is_none() {
+                updates.push(access::Model {
+                    role: update.new_role,
+                    ..access
+                });
+            }
         }
+
+        if !updates.is_empty() {
+            self.contexts
+                .access_context
+                .update_roles(updates.clone())
+                .await
+                .map_err(|err| {
+                    database_busy_error(&err)
+                        .unwrap_or_else(|| Status::new(Code::Internal, err.to_string()))
+                })?;
+
+            for access in updates {
+                self.events.publish(AccessEvent::RoleChanged {
+                    project_id: access.project_id,
+                    user_id: access.user_id,
+                    role: access.role,
+                    changed_by: uid,
+                });
+            }
+        }
+
+        Ok(Response::new(UpdateAccessesResponse { results }))
+    }
+
+    async fn get_accesses(
+        &self,
+        request: Request<GetAccessesRequest>,
+    ) -> Result<Response<GetAccessesResponse>, Status> {
+        let message = request.get_ref().clone();
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        if message.ids.len() > MAX_ACCESS_BATCH_SIZE {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!(
+                    "At most {} accesses can be requested at once",
+                    MAX_ACCESS_BATCH_SIZE
+                ),
+            ));
+        }
+
+        if message.ids.is_empty() {
+            return Ok(Response::new(GetAccessesResponse { accesses: vec![] }));
+        }
+
+        let accesses = self
+            .contexts
+            .access_context
+            .get_access_by_ids(message.ids)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+        let project_ids: HashSet<i32> = accesses.iter().map(|access| access.project_id).collect();
+        let editable_projects: HashSet<i32> = self
+            .contexts
+            .access_context
+            .get_access_by_uid_and_project_ids(uid, project_ids.into_iter().collect())
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .into_iter()
+            .filter(|access| access.has_role_at_least(Role::Editor))
+            .map(|access| access.project_id)
+            .collect();
+
+        // Accesses on projects the requester cannot edit are skipped rather than reported
+        let accesses: Vec<access::Model> = accesses
+            .into_iter()
+            .filter(|access| editable_projects.contains(&access.project_id))
+            .collect();
+
+        if accesses.is_empty() {
+            return Ok(Response::new(GetAccessesResponse { accesses: vec![] }));
+        }
+
+        let user_ids: HashSet<i32> = accesses.iter().map(|access| access.user_id).collect();
+        let users: HashMap<i32, user::Model> = self
+            .contexts
+            .user_context
+            .get_by_ids(user_ids.into_iter().collect())
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+
+        let accesses = accesses
+            .into_iter()
+            .map(|access| {
+                let user = users.get(&access.user_id);
+                AccessRecord {
+                    id: access.id,
+                    username: user.map(|user| user.username.clone()).unwrap_or_default(),
+                    display_name: user
+                        .map(|user| user.shown_name().to_string())
+                        .unwrap_or_default(),
+                    role: access.role,
+                    project_id: access.project_id,
+                    user_id: access.user_id,
+                }
+            })
+            .collect();
+
+        Ok(Response::new(GetAccessesResponse { accesses }))
     }
 
     async fn delete_access(
@@ -234,7 +591,7 @@ impl AccessControllerTrait for AccessController {
             .ok_or_else(|| Status::new(Code::NotFound, "No model found for access".to_string()))?;
 
         // Check that the requester is not trying to delete the owner's access
-        if model.owner_id == message.id {
+        if model.owner_id == user_access.user_id {
             return Err(Status::new(
                 Code::PermissionDenied,
                 "You cannot delete the access entity for this user",
@@ -242,7 +599,100 @@ impl AccessControllerTrait for AccessController {
         }
 
         match self.contexts.access_context.delete(message.id).await {
-            Ok(_) => Ok(Response::new(())),
+            Ok(_) => {
+                self.events.publish(AccessEvent::Revoked {
+                    project_id: user_access.project_id,
+                    user_id: user_access.user_id,
+                    changed_by: uid,
+                });
+                Ok(Response::new(()))
+            }
+            Err(error) => match error {
+                sea_orm::DbErr::RecordNotFound(message) => {
+                    Err(Status::new(Code::NotFound, message))
+                }
+                _ => Err(Status::new(Code::Internal, error.to_string())),
+            },
+        }
+    }
+
+    async fn remove_collaborator(
+        &self,
+        request: Request<RemoveCollaboratorRequest>,
+    ) -> Result<Response<()>, Status> {
+        let message = request.get_ref().clone();
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, inner error: {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        check_editor_role_helper(
+            Arc::clone(&self.contexts.access_context),
+            uid,
+            message.project_id,
+        )
+        .await?;
+
+        // The user is identified the same way as when the access was created
+        let user = match message.user {
+            Some(remove_collaborator_request::User::UserId(user_id)) => User::UserId(user_id),
+            Some(remove_collaborator_request::User::Username(username)) => User::Username(username),
+            Some(remove_collaborator_request::User::Email(email)) => User::Email(email),
+            None => {
+                return Err(Status::new(
+                    Code::InvalidArgument,
+                    "No user identification provided",
+                ))
+            }
+        };
+
+        let user_from_db =
+            create_access_find_user_helper(Arc::clone(&self.contexts.user_context), user).await?;
+
+        let user_access = self
+            .contexts
+            .access_context
+            .get_access_by_uid_and_project_id(user_from_db.id, message.project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| {
+                Status::new(
+                    Code::NotFound,
+                    "User is not a collaborator on this model".to_string(),
+                )
+            })?;
+
+        let model = self
+            .contexts
+            .project_context
+            .get_by_id(message.project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| Status::new(Code::NotFound, "No model found for access".to_string()))?;
+
+        // The owner's access is protected, exactly as in delete_access
+        if model.owner_id == user_access.user_id {
+            return Err(Status::new(
+                Code::PermissionDenied,
+                "You cannot delete the access entity for this user",
+            ));
+        }
+
+        match self.contexts.access_context.delete(user_access.id).await {
+            Ok(_) => {
+                self.events.publish(AccessEvent::Revoked {
+                    project_id: user_access.project_id,
+                    user_id: user_access.user_id,
+                    changed_by: uid,
+                });
+                Ok(Response::new(()))
+            }
             Err(error) => match error {
                 sea_orm::DbErr::RecordNotFound(message) => {
                     Err(Status::new(Code::NotFound, message))
@@ -269,7 +719,7 @@ async fn check_editor_role_helper(
         })?;
 
     // Check if the requester has role 'Editor'
-    if access.role != "Editor" {
+    if !access.has_role_at_least(Role::Editor) {
         return Err(Status::new(
             Code::PermissionDenied,
             "User does not have 'Editor' role for this model",
@@ -293,13 +743,13 @@ async fn create_access_find_user_helper(
         User::Username(username) => Ok(user_context
             .get_by_username(username)
             .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .map_err(|err| Status::new(Code::Internal, sanitize(&err.to_string())))?
             .ok_or_else(|| Status::new(Code::NotFound, "No user found with given username"))?),
 
         User::Email(email) => Ok(user_context
             .get_by_email(email)
             .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .map_err(|err| Status::new(Code::Internal, sanitize(&err.to_string())))?
             .ok_or_else(|| Status::new(Code::NotFound, "No user found with given email"))?),
     }
 }
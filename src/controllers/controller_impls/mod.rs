@@ -2,6 +2,7 @@ pub mod access_controller;
 pub mod project_controller;
 pub mod query_controller;
 pub mod reveaal_controller;
+pub mod server_info_controller;
 pub mod session_controller;
 pub mod user_controller;
 
@@ -9,5 +10,6 @@ pub use access_controller::AccessController;
 pub use project_controller::ProjectController;
 pub use query_controller::QueryController;
 pub use reveaal_controller::ReveaalController;
+pub use server_info_controller::ServerInfoController;
 pub use session_controller::SessionController;
 pub use user_controller::UserController;
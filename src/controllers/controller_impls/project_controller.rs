@@ -1,25 +1,854 @@
-use crate::api::auth::{RequestExt, TokenType};
+use crate::api::auth::{DeleteConfirmationClaims, RequestExt, TokenError, TokenType};
 use crate::api::server::protobuf::{
-    CreateProjectRequest, CreateProjectResponse, DeleteProjectRequest, GetProjectRequest,
-    GetProjectResponse, ListProjectsInfoResponse, Project, Query, UpdateProjectRequest,
+    component::Rep, Component, ComponentsInfo, CreateProjectRequest, CreateProjectResponse,
+    CreateProjectWithQueriesRequest, CreateProjectWithQueriesResponse, DeleteProjectRequest,
+    DiffProjectRequest, DiffProjectResponse, GetDashboardResponse, GetLockStatusRequest,
+    GetLockStatusResponse, GetProjectHistoryRequest, GetProjectHistoryResponse, GetProjectRequest,
+    GetProjectResponse, HeartbeatRequest, ListMyLocksResponse, ListMyRolesRequest,
+    ListMyRolesResponse, ListProjectsInfoRequest, ListProjectsInfoResponse, ListProjectsRequest,
+    ListProjectsResponse, Project, ProjectEvent, ProjectLock, ProjectRole, Query,
+    RequestDeleteRequest, RequestDeleteResponse, UpdateProjectRequest,
 };
+use crate::config::Config;
 use crate::contexts::context_collection::ContextCollection;
+use crate::contexts::pagination::DEFAULT_PAGE_SIZE;
+use crate::controllers::controller_impls::query_controller::{
+    result_is_stale, result_timed_out, stored_result_to_protobuf, validate_query_string,
+};
+use crate::controllers::controller_impls::user_controller::current_user_to_protobuf;
 use crate::controllers::controller_traits::ProjectControllerTrait;
-use crate::entities::{access, in_use, project};
+use crate::controllers::error_details::{database_busy_error, status_with_reason, ReasonCode};
+use crate::controllers::project_access::load_project_with_role;
+use crate::controllers::query_invalidation::invalidate_affected_queries;
+use crate::controllers::response_size::check_response_size;
+use crate::controllers::sanitization::sanitize;
+use crate::entities::role::Role;
+use crate::entities::{access, in_use, project, project_event, query, session};
+use crate::services::service_traits::reveaal_service_trait::component_name;
 use async_trait::async_trait;
-use chrono::{Duration, Utc};
-use sea_orm::SqlErr;
+use chrono::{Duration, NaiveDateTime, Utc};
+use prost::Message;
+use sea_orm::{DbErr, SqlErr};
+use std::collections::BTreeMap;
 use tonic::{Code, Request, Response, Status};
 
-const IN_USE_DURATION_MINUTES: i64 = 10;
+pub const DEFAULT_IN_USE_DURATION_MINUTES: i64 = 10;
+const DEFAULT_PROJECT_NAME_MIN_LENGTH: usize = 1;
+const DEFAULT_PROJECT_NAME_MAX_LENGTH: usize = 255;
+const DEFAULT_COMPONENTS_JSON_MAX_DEPTH: usize = 64;
+const DEFAULT_DELETE_CONFIRMATION_TTL_SECS: i64 = 60;
+
+/// How projects are locked by the session editing them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockConfig {
+    /// How long a project stays in use after the latest activity of its session, 10 minutes by default
+    pub duration: Duration,
+    /// How long a lock stays with its session after `duration` has passed, before another session may take it.
+    /// An editor whose connection drops briefly can pick the lock up again in this time,
+    /// instead of losing it to a collaborator the moment it expires. There is no grace period by default.
+    pub grace_period: Duration,
+    /// How long a session may be inactive before its lock is reported as about to expire, if ever
+    pub warn_after: Option<Duration>,
+    /// Whether a user may take over a live lock held by another of their own sessions
+    pub same_user_takeover: bool,
+    /// Whether `get_project` recreates the in use row of a project that has lost it, instead of failing.
+    /// Likewise, `update_project` creates the row locked by the requester's session and goes on with the update.
+    /// When enabled, creating a project also succeeds if the requester's session cannot be found yet,
+    /// leaving the in use row to be created the first time a session loads or updates the project.
+    pub recreate_missing_in_use: bool,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        LockConfig {
+            duration: Duration::minutes(DEFAULT_IN_USE_DURATION_MINUTES),
+            grace_period: Duration::zero(),
+            warn_after: None,
+            same_user_takeover: true,
+            recreate_missing_in_use: true,
+        }
+    }
+}
+
+impl LockConfig {
+    /// Returns the state at `now` of a lock whose session was last active at `latest_activity`.
+    pub fn state(&self, latest_activity: NaiveDateTime, now: NaiveDateTime) -> LockState {
+        LockState::after_inactivity(
+            now - latest_activity,
+            self.duration,
+            self.warn_after,
+            self.grace_period,
+        )
+    }
+
+    /// Returns when a lock whose session was last active at `latest_activity` expires and any session may take it.
+    pub fn expires_at(&self, latest_activity: NaiveDateTime) -> NaiveDateTime {
+        LockState::free_at(latest_activity, self.duration, self.grace_period)
+    }
+}
+
+/// How a lock stands, given the latest activity of the session holding it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockState {
+    /// The session is active
+    Held,
+    /// The session has been inactive for [`LockConfig::warn_after`], so the lock expires soon
+    Expiring,
+    /// The lock has expired, but stays with the session until the [`LockConfig::grace_period`] is over
+    InGrace,
+    /// The lock has expired, and any session may take it
+    Free,
+}
+
+impl LockState {
+    /// Returns the state of a lock whose session has been inactive for `inactive`, given the lock `duration`,
+    /// the threshold after which it is reported as expiring and the grace period that follows the duration.
+    pub fn after_inactivity(
+        inactive: Duration,
+        duration: Duration,
+        warn_after: Option<Duration>,
+        grace_period: Duration,
+    ) -> LockState {
+        if inactive >= duration + grace_period {
+            LockState::Free
+        } else if inactive >= duration {
+            LockState::InGrace
+        } else if warn_after.is_some_and(|warn_after| inactive >= warn_after) {
+            LockState::Expiring
+        } else {
+            LockState::Held
+        }
+    }
+
+    /// Returns whether the lock keeps other sessions from taking it
+    pub fn is_held(self) -> bool {
+        self != LockState::Free
+    }
+
+    /// Returns when a lock whose session was last active at `latest_activity` becomes [`LockState::Free`],
+    /// given the lock `duration` and the grace period that follows it.
+    pub fn free_at(
+        latest_activity: NaiveDateTime,
+        duration: Duration,
+        grace_period: Duration,
+    ) -> NaiveDateTime {
+        latest_activity + duration + grace_period
+    }
+}
+
+/// The rules for creating, changing and deleting projects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectConfig {
+    /// The minimum length of a project name, 1 by default
+    pub name_min_length: usize,
+    /// The maximum length of a project name, 255 by default
+    pub name_max_length: usize,
+    /// The characters a project name must not contain, besides control characters
+    pub name_disallowed_characters: String,
+    /// The components info of new projects created without one, with its hash computed from the components
+    pub default_components_info: ComponentsInfo,
+    /// The maximum nesting depth allowed in the JSON representation of a component, 64 by default
+    pub components_json_max_depth: usize,
+    /// Whether the owner of a project can only be changed to a user who already has access to it,
+    /// so a project is never handed to someone who has not been working on it
+    pub owner_change_requires_access: bool,
+    /// Whether deleting a project requires a confirmation token from `request_delete`
+    pub require_delete_confirmation: bool,
+    /// How long a deletion confirmation token stays valid, 60 seconds by default
+    pub delete_confirmation_ttl: Duration,
+    /// The number of projects in the first page of `get_dashboard`.
+    /// It is clamped to the maximum page size like any other page.
+    pub dashboard_page_size: u64,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        ProjectConfig {
+            name_min_length: DEFAULT_PROJECT_NAME_MIN_LENGTH,
+            name_max_length: DEFAULT_PROJECT_NAME_MAX_LENGTH,
+            name_disallowed_characters: String::new(),
+            default_components_info: components_info_from_template(ComponentsInfo::default()),
+            components_json_max_depth: DEFAULT_COMPONENTS_JSON_MAX_DEPTH,
+            owner_change_requires_access: true,
+            require_delete_confirmation: false,
+            delete_confirmation_ttl: Duration::seconds(DEFAULT_DELETE_CONFIRMATION_TTL_SECS),
+            dashboard_page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// Checks that `token` confirms the deletion of `project_id` by `uid`.
+/// # Errors
+/// Returns `FailedPrecondition` if the token is missing, invalid or expired,
+/// and `PermissionDenied` if it was issued for another project or user.
+pub fn check_delete_confirmation(
+    token: Option<&str>,
+    uid: i32,
+    project_id: i32,
+) -> Result<(), Status> {
+    let token = token.filter(|token| !token.is_empty()).ok_or_else(|| {
+        Status::failed_precondition(
+            "Deleting a project requires a confirmation token, request one with request_delete",
+        )
+    })?;
+
+    let claims = DeleteConfirmationClaims::validate(token).map_err(|err| match err {
+        TokenError::ExpiredSignature => Status::failed_precondition(
+            "The confirmation token has expired, request a new one with request_delete",
+        ),
+        _ => Status::failed_precondition("The confirmation token is invalid"),
+    })?;
+
+    if claims.uid != uid || claims.project_id != project_id {
+        return Err(Status::permission_denied(
+            "The confirmation token was not issued for this project",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates a project name and returns it without surrounding whitespace.
+///
+/// The trimmed name must be between the minimum and maximum length of `config` characters long
+/// and must not contain control characters or any of its disallowed characters.
+/// # Errors
+/// Returns an `InvalidArgument` status with the reason if the name is invalid.
+pub fn validate_project_name(name: &str, config: &ProjectConfig) -> Result<String, Status> {
+    let name = name.trim();
+
+    let (min_length, max_length) = (config.name_min_length, config.name_max_length);
+
+    let length = name.chars().count();
+
+    if length < min_length {
+        return Err(invalid_project_name(format!(
+            "Project name must be at least {} characters long",
+            min_length
+        )));
+    }
+
+    if length > max_length {
+        return Err(invalid_project_name(format!(
+            "Project name must be at most {} characters long",
+            max_length
+        )));
+    }
+
+    if name.chars().any(char::is_control) {
+        return Err(invalid_project_name(
+            "Project name must not contain control characters",
+        ));
+    }
+
+    if let Some(character) = name
+        .chars()
+        .find(|character| config.name_disallowed_characters.contains(*character))
+    {
+        return Err(invalid_project_name(format!(
+            "Project name must not contain '{}'",
+            sanitize(&character.to_string())
+        )));
+    }
+
+    Ok(name.to_string())
+}
+
+fn invalid_project_name(message: impl Into<String>) -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        message,
+        ReasonCode::InvalidProjectName,
+        Some("name"),
+    )
+}
+
+/// A 64-bit FNV-1a digest.
+///
+/// Unlike [`DefaultHasher`](std::collections::hash_map::DefaultHasher), its output is the same
+/// across builds, platforms and Rust versions, so it can be stored and compared by clients.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Computes the hash of a list of components from their JSON representation.
+///
+/// The hash is stable, so the same components hash the same on every server and after restarts.
+pub fn compute_components_hash(components: &[Component]) -> u32 {
+    let mut digest = Fnv1a::new();
+    digest.write(
+        serde_json::to_string(components)
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    digest.finish() as u32
+}
+
+/// Recursively sorts the keys of every object in a JSON value.
+fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries: Vec<_> = object.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_json_keys(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(sort_json_keys).collect())
+        }
+        value => value,
+    }
+}
+
+/// Normalizes a components info so that equivalent components always hash the same.
+///
+/// The JSON representation of each component is re-serialized with sorted keys,
+/// and the client-provided hash is replaced with one computed from the normalized components.
+/// Representations that are not valid JSON are kept as is.
+pub fn normalize_components_info(components_info: ComponentsInfo) -> ComponentsInfo {
+    let components: Vec<Component> = components_info
+        .components
+        .into_iter()
+        .map(|mut component| {
+            component.rep = match component.rep.take() {
+                Some(Rep::Json(json)) => Some(Rep::Json(
+                    serde_json::from_str(&json)
+                        .map(|value| sort_json_keys(value).to_string())
+                        .unwrap_or(json),
+                )),
+                rep => rep,
+            };
+            component
+        })
+        .collect();
+
+    ComponentsInfo {
+        components_hash: compute_components_hash(&components),
+        components,
+    }
+}
+
+/// Returns the components info used for new projects created without one, given the template
+/// configured in `DEFAULT_COMPONENTS_INFO`. The hash of the template is ignored and computed from its components.
+pub fn components_info_from_template(template: ComponentsInfo) -> ComponentsInfo {
+    ComponentsInfo {
+        components_hash: compute_components_hash(&template.components),
+        components: template.components,
+    }
+}
+
+/// Returns whether a JSON document nests arrays and objects deeper than `max_depth`.
+///
+/// The document is only scanned, not parsed, so it does not have to be valid JSON
+/// and an over-deep document is rejected without recursing into it.
+pub fn json_depth_exceeds(json: &str, max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for character in json.chars() {
+        if in_string {
+            match character {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match character {
+            '"' => in_string = true,
+            '[' | '{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Checks that no component's JSON representation is nested deeper than `max_depth`.
+/// # Errors
+/// Returns an `InvalidArgument` status if a representation is nested too deeply.
+pub fn validate_components_depth(
+    components_info: &ComponentsInfo,
+    max_depth: usize,
+) -> Result<(), Status> {
+    if components_info.components.iter().any(|component| {
+        matches!(&component.rep, Some(Rep::Json(json)) if json_depth_exceeds(json, max_depth))
+    }) {
+        return Err(status_with_reason(
+            Code::InvalidArgument,
+            format!(
+                "Components info contains a component nested deeper than {} levels",
+                max_depth
+            ),
+            ReasonCode::InvalidComponentsInfo,
+            Some("components_info"),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that every component in a components info has a representation
+/// and is not nested deeper than `max_depth`.
+/// # Errors
+/// Returns an `InvalidArgument` status if a component is empty or nested too deeply.
+pub fn validate_components_info(
+    components_info: &ComponentsInfo,
+    max_depth: usize,
+) -> Result<(), Status> {
+    validate_components_depth(components_info, max_depth)?;
+
+    if components_info
+        .components
+        .iter()
+        .any(|component| component.rep.is_none())
+    {
+        return Err(status_with_reason(
+            Code::InvalidArgument,
+            "Components info contains a component without a representation",
+            ReasonCode::InvalidComponentsInfo,
+            Some("components_info"),
+        ));
+    }
+    Ok(())
+}
+
+/// Builds a new project owned by `uid` from a requested name and components info,
+/// returning it together with its canonical components hash.
+/// # Errors
+/// Returns an `InvalidArgument` status if the name or components info is invalid.
+fn new_project(
+    name: &str,
+    components_info: Option<ComponentsInfo>,
+    uid: i32,
+    config: &ProjectConfig,
+) -> Result<(project::Model, u32), Status> {
+    let name = validate_project_name(name, config)?;
+
+    let components_info = match components_info {
+        Some(components_info) => {
+            validate_components_info(&components_info, config.components_json_max_depth)?;
+            normalize_components_info(components_info)
+        }
+        None => config.default_components_info.clone(),
+    };
+    let components_hash = components_info.components_hash;
+    let components_info = serde_json::to_value(components_info).map_err(|err| {
+        Status::internal(format!(
+            "failed to parse components info object, internal error: {}",
+            err
+        ))
+    })?;
+
+    Ok((
+        project::Model {
+            id: Default::default(),
+            name,
+            components_info,
+            owner_id: uid,
+        },
+        components_hash,
+    ))
+}
+
+/// Maps an error from creating a project to the status returned to the client.
+fn create_project_error(error: DbErr) -> Status {
+    match error.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(e)) => {
+            let (error_msg, reason, field) = match e.to_lowercase() {
+                _ if e.contains("name") => (
+                    "A project with that name already exists",
+                    ReasonCode::DuplicateProjectName,
+                    Some("name"),
+                ),
+                _ => ("Model already exists", ReasonCode::DuplicateProject, None),
+            };
+            println!("{}", sanitize(&e));
+            status_with_reason(Code::AlreadyExists, error_msg, reason, field)
+        }
+        Some(SqlErr::ForeignKeyConstraintViolation(e)) => {
+            println!("{}", sanitize(&e));
+            if e.contains("owner_id") {
+                status_with_reason(
+                    Code::InvalidArgument,
+                    "No user with that id exists",
+                    ReasonCode::UnknownOwner,
+                    Some("owner_id"),
+                )
+            } else {
+                Status::invalid_argument("Could not create project")
+            }
+        }
+        _ => database_busy_error(&error)
+            .unwrap_or_else(|| Status::internal(sanitize(&error.to_string()))),
+    }
+}
+
+/// Converts a project entity to its protobuf representation.
+/// # Errors
+/// Returns a `DataLoss` status if the stored components info cannot be parsed,
+/// e.g. because it was stored in a legacy format, so clients can tell corrupt data
+/// apart from errors that are worth retrying.
+fn project_to_protobuf(project: project::Model) -> Result<Project, Status> {
+    let components_info = serde_json::from_value(project.components_info).map_err(|err| {
+        log::error!(
+            "stored components info of project {} cannot be parsed, inner error: {}",
+            project.id,
+            err
+        );
+        status_with_reason(
+            Code::DataLoss,
+            "The stored components info of the project is corrupt",
+            ReasonCode::CorruptComponentsInfo,
+            Some("components_info"),
+        )
+    })?;
+
+    Ok(Project {
+        id: project.id,
+        name: project.name,
+        components_info,
+        owner_id: project.owner_id,
+    })
+}
+
+/// Computes the version of a project as returned by `get_project`.
+///
+/// The version changes whenever the project or any of its queries change,
+/// so clients can skip downloading a project they already have.
+/// The version is stable, so it stays valid across restarts and between servers.
+pub fn project_version(project: &Project, queries: &[Query]) -> u64 {
+    let mut digest = Fnv1a::new();
+    for message in std::iter::once(project.encode_to_vec())
+        .chain(queries.iter().map(|query| query.encode_to_vec()))
+    {
+        // Prefixing every message with its length keeps the boundaries between them apart
+        digest.write(&(message.len() as u64).to_le_bytes());
+        digest.write(&message);
+    }
+    digest.finish()
+}
+
+/// Returns the name identifying a component, read from its JSON representation.
+/// Components without a name are identified by their position, e.g. `#2`.
+fn component_id(index: usize, component: &Component) -> String {
+    component_name(component).unwrap_or_else(|| format!("#{}", index))
+}
+
+/// Compares two sets of components by their id, returning the sorted ids of the components
+/// that only exist in `to`, only exist in `from`, or exist in both with a different representation.
+pub fn diff_components(from: &ComponentsInfo, to: &ComponentsInfo) -> DiffProjectResponse {
+    let index = |components_info: &ComponentsInfo| -> BTreeMap<String, Option<Rep>> {
+        components_info
+            .components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| (component_id(i, component), component.rep.clone()))
+            .collect()
+    };
+    let (from, to) = (index(from), index(to));
+
+    DiffProjectResponse {
+        added: to
+            .keys()
+            .filter(|id| !from.contains_key(*id))
+            .cloned()
+            .collect(),
+        removed: from
+            .keys()
+            .filter(|id| !to.contains_key(*id))
+            .cloned()
+            .collect(),
+        modified: from
+            .iter()
+            .filter(|(id, rep)| to.get(*id).is_some_and(|to_rep| to_rep != *rep))
+            .map(|(id, _)| id.clone())
+            .collect(),
+    }
+}
+
+/// Converts query entities to their protobuf representation.
+///
+/// A query whose result is older than `max_age` is returned as outdated, and one Reveaal gave no result for as timed out.
+fn queries_to_protobuf(
+    queries: Vec<query::Model>,
+    max_age: Option<chrono::Duration>,
+) -> Vec<Query> {
+    let now = Utc::now().naive_utc();
+
+    queries
+        .into_iter()
+        .map(|query| {
+            let outdated = query.outdated || result_is_stale(&query, max_age, now);
+            let timed_out = result_timed_out(&query);
+            let result = stored_result_to_protobuf(query.id, query.result);
+
+            Query {
+                id: query.id,
+                project_id: query.project_id,
+                query: query.string,
+                title: query.title,
+                result,
+                outdated,
+                last_run_ms: query.last_run_ms,
+                timed_out,
+            }
+        })
+        .collect()
+}
 
 pub struct ProjectController {
     contexts: ContextCollection,
+    config: Config,
 }
 
 impl ProjectController {
-    pub fn new(contexts: ContextCollection) -> Self {
-        ProjectController { contexts }
+    pub fn new(contexts: ContextCollection, config: Config) -> Self {
+        ProjectController { contexts, config }
+    }
+
+    /// Returns the unexpired locks held by any session of the user with the given `uid`, ordered by project id.
+    async fn active_locks(&self, uid: i32) -> Result<Vec<ProjectLock>, Status> {
+        Ok(self
+            .contexts
+            .in_use_context
+            .get_active_locks_by_uid(
+                uid,
+                self.config.locks.duration + self.config.locks.grace_period,
+            )
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .into_iter()
+            .map(|in_use| ProjectLock {
+                project_id: in_use.project_id,
+                expires_at: self
+                    .config
+                    .locks
+                    .expires_at(in_use.latest_activity)
+                    .timestamp(),
+            })
+            .collect())
+    }
+
+    /// Checks that the owner of the project with the given id may be changed to the user with `new_owner_id`.
+    /// # Errors
+    /// Returns `InvalidArgument` if the user does not exist, or if [`ProjectConfig::owner_change_requires_access`]
+    /// is enabled and the user has no access to the project.
+    async fn validate_new_owner(&self, project_id: i32, new_owner_id: i32) -> Result<(), Status> {
+        self.contexts
+            .user_context
+            .get_by_id(new_owner_id)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .ok_or_else(|| {
+                status_with_reason(
+                    Code::InvalidArgument,
+                    "No user with that id exists",
+                    ReasonCode::UnknownOwner,
+                    Some("owner_id"),
+                )
+            })?;
+
+        if self.config.projects.owner_change_requires_access
+            && self
+                .contexts
+                .access_context
+                .get_access_by_uid_and_project_id(new_owner_id, project_id)
+                .await
+                .map_err(|err| Status::internal(err.to_string()))?
+                .is_none()
+        {
+            return Err(status_with_reason(
+                Code::InvalidArgument,
+                "The new owner must already have access to the project",
+                ReasonCode::OwnerWithoutAccess,
+                Some("owner_id"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the components of a project as they were at the given version.
+    /// # Errors
+    /// Returns `NotFound` if the version is not an event of the project with a components snapshot.
+    async fn components_snapshot(
+        &self,
+        project_id: i32,
+        version: i32,
+    ) -> Result<ComponentsInfo, Status> {
+        let snapshot = self
+            .contexts
+            .project_event_context
+            .get_by_id(version)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .filter(|event| event.project_id == project_id)
+            .and_then(|event| event.components_snapshot)
+            .ok_or_else(|| {
+                Status::not_found(format!("No version {} found for the project", version))
+            })?;
+
+        serde_json::from_value(snapshot).map_err(|err| {
+            Status::internal(format!(
+                "failed to parse components snapshot, internal error: {}",
+                err
+            ))
+        })
+    }
+
+    /// Returns whether the lock in `in_use` keeps `session` from editing the project.
+    ///
+    /// An expired lock, or one held by `session` itself, never blocks. A live lock held by another
+    /// session blocks unless that session belongs to the same user and [`LockConfig::same_user_takeover`]
+    /// is enabled, in which case the caller may move the lock to `session`. The previous session
+    /// then no longer holds the lock, so its heartbeats fail until it edits the project again.
+    async fn lock_blocks_session(
+        &self,
+        in_use: &in_use::Model,
+        session: &session::Model,
+    ) -> Result<bool, Status> {
+        if !self
+            .config
+            .locks
+            .state(in_use.latest_activity, Utc::now().naive_utc())
+            .is_held()
+            || in_use.session_id == session.id
+        {
+            return Ok(false);
+        }
+        if !self.config.locks.same_user_takeover {
+            return Ok(true);
+        }
+
+        let holder = self
+            .contexts
+            .session_context
+            .get_by_id(in_use.session_id)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(!holder.is_some_and(|holder| holder.user_id == session.user_id))
+    }
+
+    /// Returns the session of the access token in the request metadata, or `None` if there is none.
+    async fn find_session<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<Option<session::Model>, Status> {
+        self.contexts
+            .session_context
+            .get_by_token(
+                TokenType::AccessToken,
+                request
+                    .token_string()
+                    .map_err(|err| {
+                        Status::internal(format!(
+                            "could not stringify user id in request metadata, internal error {}",
+                            err
+                        ))
+                    })?
+                    .ok_or(Status::internal(
+                        "failed to get token from request metadata",
+                    ))?,
+            )
+            .await
+            .map_err(|_err| Status::internal("failed to query database"))
+    }
+
+    /// Returns the session to hold the in use row of a project created by the request.
+    ///
+    /// A session that cannot be found yet, e.g. right after signing up, does not keep the project
+    /// from being created as long as [`LockConfig::recreate_missing_in_use`] is enabled. In that case `None` is
+    /// returned, the project is created without an in use row, and the first session loading or
+    /// updating the project creates one.
+    /// # Errors
+    /// Returns a `NotFound` status if there is no session and missing in use rows are not recreated.
+    async fn session_for_new_project<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<Option<session::Model>, Status> {
+        match self.find_session(request).await? {
+            Some(session) => Ok(Some(session)),
+            None if self.config.locks.recreate_missing_in_use => {
+                log::info!("no session found for new project, creating it without an in use row");
+                Ok(None)
+            }
+            None => Err(Status::not_found("token not found")),
+        }
+    }
+
+    /// Creates a new, unlocked in use row for a project whose row is missing,
+    /// so the inconsistency does not keep the project from loading.
+    /// The row is owned by the requester's session, but expired, so anyone may lock the project.
+    ///
+    /// Returns `None` without creating a row if the requester has no session, like the creator of a
+    /// project created without one. The row is then created by the first session touching the project.
+    async fn recreate_in_use<T>(
+        &self,
+        request: &Request<T>,
+        project_id: i32,
+    ) -> Result<Option<in_use::Model>, Status> {
+        let Some(session) = self.find_session(request).await? else {
+            log::info!(
+                "project {} has no in use row and the requester has no session to hold one",
+                project_id
+            );
+            return Ok(None);
+        };
+
+        log::warn!(
+            "project {} has no in use row, creating a new one",
+            project_id
+        );
+
+        let in_use = self
+            .contexts
+            .in_use_context
+            .create(in_use::Model {
+                project_id,
+                session_id: session.id,
+                latest_activity: Default::default(),
+            })
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+        self.contexts
+            .in_use_context
+            .update(in_use::Model {
+                latest_activity: Default::default(),
+                ..in_use
+            })
+            .await
+            .map(Some)
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))
     }
 }
 
@@ -43,119 +872,105 @@ impl ProjectControllerTrait for ProjectController {
             })?
             .ok_or(Status::internal("Could not get uid from request metadata"))?;
 
-        let access = self
-            .contexts
-            .access_context
-            .get_access_by_uid_and_project_id(uid, project_id)
-            .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
-            .ok_or_else(|| {
-                Status::new(
-                    Code::PermissionDenied,
-                    "User does not have access to project",
-                )
-            })?;
+        let (project, access) =
+            load_project_with_role(&self.contexts, uid, project_id, Role::Reader).await?;
 
-        let project = self
-            .contexts
-            .project_context
-            .get_by_id(project_id)
-            .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
-            .ok_or_else(|| Status::new(Code::Internal, "Model not found"))?;
+        let project = project_to_protobuf(project)?;
 
-        let project = Project {
-            id: project.id,
-            name: project.name,
-            components_info: serde_json::from_value(project.components_info).map_err(|err| {
-                Status::internal(format!(
-                    "failed to parse components info object, internal error: {}",
-                    err
-                ))
-            })?,
-            owner_id: project.owner_id,
+        let in_use = match self.contexts.in_use_context.get_by_id(project_id).await {
+            Ok(Some(in_use)) => Some(in_use),
+            Ok(None) if self.config.locks.recreate_missing_in_use => {
+                self.recreate_in_use(&request, project_id).await?
+            }
+            Ok(None) => return Err(Status::new(Code::Internal, "No in use found for project")),
+            Err(err) => return Err(Status::new(Code::Internal, err.to_string())),
         };
 
         let mut in_use_bool = true;
-        match self.contexts.in_use_context.get_by_id(project_id).await {
-            Ok(Some(in_use)) => {
-                // If project is not in use and user is an Editor, update the in use with the users session.
-                if in_use.latest_activity
-                    <= (Utc::now().naive_utc() - Duration::minutes(IN_USE_DURATION_MINUTES))
-                {
-                    in_use_bool = false;
-
-                    if access.role == "Editor" {
-                        let session = self
-                            .contexts
-                            .session_context
-                            .get_by_token(
-                                TokenType::AccessToken,
-                                request
-                                    .token_string()
-                                    .map_err(|err| Status::internal(format!("could not stringify user id in request metadata, internal error {}",err)))?
-                                    .ok_or(Status::invalid_argument("failed to get token from request metadata"))?,
-                            )
-                            .await
-                            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
-                            .ok_or_else(|| {
-                                Status::new(
-                                    Code::Unauthenticated,
-                                    "No session found with given access token",
-                                )
-                            })?;
-
-                        let in_use = in_use::Model {
-                            project_id: in_use.project_id,
-                            session_id: session.id,
-                            latest_activity: Utc::now().naive_utc(),
-                        };
-
-                        self.contexts
-                            .in_use_context
-                            .update(in_use)
-                            .await
-                            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
-                    }
-                }
+        // If project is not in use and user is an Editor, update the in use with the users session.
+        // A project without an in use row is not in use, and is left to the first session touching it.
+        if !in_use.as_ref().is_some_and(|in_use| {
+            self.config
+                .locks
+                .state(in_use.latest_activity, Utc::now().naive_utc())
+                .is_held()
+        }) {
+            in_use_bool = false;
+
+            if let Some(in_use) = in_use.filter(|_| access.has_role_at_least(Role::Editor)) {
+                let session = self
+                    .contexts
+                    .session_context
+                    .get_by_token(
+                        TokenType::AccessToken,
+                        request
+                            .token_string()
+                            .map_err(|err| Status::internal(format!("could not stringify user id in request metadata, internal error {}",err)))?
+                            .ok_or(Status::invalid_argument("failed to get token from request metadata"))?,
+                    )
+                    .await
+                    .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+                    .ok_or_else(|| {
+                        Status::new(
+                            Code::Unauthenticated,
+                            "No session found with given access token",
+                        )
+                    })?;
+
+                let in_use = in_use::Model {
+                    project_id: in_use.project_id,
+                    session_id: session.id,
+                    latest_activity: Utc::now().naive_utc(),
+                };
+
+                self.contexts
+                    .in_use_context
+                    .update(in_use)
+                    .await
+                    .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
             }
-            Ok(None) => return Err(Status::new(Code::Internal, "No in use found for project")),
-            Err(err) => return Err(Status::new(Code::Internal, err.to_string())),
         }
 
         let queries = self
             .contexts
             .query_context
-            .get_all_by_project_id(project_id)
+            .get_all_by_project_id(project_id, None)
             .await
             .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+        let mut queries = queries_to_protobuf(queries, self.config.queries.result_max_age);
 
-        let queries = queries
-            .into_iter()
-            .map(|query| {
-                let result = serde_json::from_value(query.result.unwrap_or_else(|| "".into()))?;
-
-                Ok(Query {
-                    id: query.id,
-                    project_id: query.project_id,
-                    query: query.string,
-                    result,
-                    outdated: query.outdated,
-                })
-            })
-            .collect::<Result<Vec<Query>, serde_json::Error>>()
-            .map_err(|err| {
-                Status::internal(format!(
-                    "failed to parse json result, inner error:  {}",
-                    err
-                ))
-            })?;
+        let version = project_version(&project, &queries);
+        if message.known_version == Some(version) {
+            return Ok(Response::new(GetProjectResponse {
+                project: None,
+                queries: vec![],
+                in_use: in_use_bool,
+                version,
+                not_modified: true,
+            }));
+        }
 
-        Ok(Response::new(GetProjectResponse {
+        // Results are left out after computing the version, so it is the same either way
+        if !message.include_query_results.unwrap_or(true) {
+            for query in queries.iter_mut() {
+                query.result.clear();
+            }
+        }
+
+        let mut response = Response::new(GetProjectResponse {
             project: Some(project),
             queries,
             in_use: in_use_bool,
-        }))
+            version,
+            not_modified: false,
+        });
+        check_response_size(
+            "get_project",
+            &mut response,
+            &self.config.response_size_warning,
+        );
+
+        Ok(response)
     }
 
     async fn create_project(
@@ -173,55 +988,379 @@ impl ProjectControllerTrait for ProjectController {
             })?
             .ok_or(Status::internal("Could not get uid from request metadata"))?;
 
-        let components_info = match message.clone().components_info {
-            Some(components_info) => serde_json::to_value(components_info).map_err(|err| {
+        let (project, components_hash) = new_project(
+            &message.name,
+            message.components_info,
+            uid,
+            &self.config.projects,
+        )?;
+
+        let session = self.session_for_new_project(&request).await?;
+
+        let project = self
+            .contexts
+            .project_context
+            .create(project)
+            .await
+            .map_err(create_project_error)?;
+
+        let access = access::Model {
+            id: Default::default(),
+            role: Role::Editor.to_string(),
+            project_id: project.clone().id,
+            user_id: uid,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        };
+
+        if let Some(session) = session {
+            let in_use = in_use::Model {
+                project_id: project.clone().id,
+                session_id: session.id,
+                latest_activity: Default::default(),
+            };
+
+            self.contexts
+                .in_use_context
+                .create(in_use)
+                .await
+                .map_err(|err| {
+                    Status::internal(format!("a database error occured, internal error: {}", err))
+                })?;
+        }
+        self.contexts
+            .access_context
+            .create(access)
+            .await
+            .map_err(|err| {
+                Status::internal(format!("a database error occured, internal error: {}", err))
+            })?;
+
+        Ok(Response::new(CreateProjectResponse {
+            id: project.id,
+            components_hash,
+        }))
+    }
+
+    async fn create_project_with_queries(
+        &self,
+        request: Request<CreateProjectWithQueriesRequest>,
+    ) -> Result<Response<CreateProjectWithQueriesResponse>, Status> {
+        let message = request.get_ref().clone();
+        let uid = request
+            .uid()
+            .map_err(|err| {
                 Status::internal(format!(
-                    "failed to parse components info object, internal error: {}",
+                    "could not stringify user id in request metadata, internal error {}",
                     err
                 ))
-            })?,
-            None => return Err(Status::invalid_argument("No components info provided")),
-        };
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let (project, components_hash) = new_project(
+            &message.name,
+            message.components_info,
+            uid,
+            &self.config.projects,
+        )?;
+
+        if message.queries.len() as u64 > self.config.queries.max_queries_per_project {
+            return Err(Status::new(
+                Code::ResourceExhausted,
+                "Project would exceed the maximum number of queries",
+            ));
+        }
+
+        // Every query is validated before anything is written
+        let queries = message
+            .queries
+            .iter()
+            .map(|string| {
+                Ok(query::Model {
+                    id: Default::default(),
+                    string: validate_query_string(string)?,
+                    title: Default::default(),
+                    result: Default::default(),
+                    outdated: Default::default(),
+                    project_id: Default::default(),
+                    last_run_ms: Default::default(),
+                    result_computed_at: Default::default(),
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                    settings: Default::default(),
+                })
+            })
+            .collect::<Result<Vec<query::Model>, Status>>()?;
+
+        let session = self.session_for_new_project(&request).await?;
 
-        let mut project = project::Model {
+        let access = access::Model {
             id: Default::default(),
-            name: message.clone().name,
-            components_info,
-            owner_id: uid,
+            role: Role::Editor.to_string(),
+            project_id: Default::default(),
+            user_id: uid,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        };
+        let in_use = session.map(|session| in_use::Model {
+            project_id: Default::default(),
+            session_id: session.id,
+            latest_activity: Default::default(),
+        });
+
+        let (project, queries) = self
+            .contexts
+            .project_context
+            .create_with_queries(project, access, in_use, queries)
+            .await
+            .map_err(create_project_error)?;
+
+        Ok(Response::new(CreateProjectWithQueriesResponse {
+            id: project.id,
+            components_hash,
+            query_ids: queries.into_iter().map(|query| query.id).collect(),
+        }))
+    }
+
+    async fn update_project(
+        &self,
+        request: Request<UpdateProjectRequest>,
+    ) -> Result<Response<()>, Status> {
+        let message = request.get_ref().clone();
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let name = match message.name {
+            Some(ref name) => Some(validate_project_name(name, &self.config.projects)?),
+            None => None,
         };
 
-        project = match self.contexts.project_context.create(project).await {
-            Ok(project) => project,
-            Err(error) => {
-                return match error.sql_err() {
-                    Some(SqlErr::UniqueConstraintViolation(e)) => {
-                        let error_msg = match e.to_lowercase() {
-                            _ if e.contains("name") => "A project with that name already exists",
-                            _ => "Model already exists",
-                        };
-                        println!("{}", e);
-                        Err(Status::already_exists(error_msg))
-                    }
-                    Some(SqlErr::ForeignKeyConstraintViolation(e)) => {
-                        let error_msg = match e.to_lowercase() {
-                            _ if e.contains("owner_id") => "No user with that id exists",
-                            _ => "Could not create project",
-                        };
-                        println!("{}", e);
-                        Err(Status::invalid_argument(error_msg))
-                    }
-                    _ => Err(Status::internal(error.to_string())),
+        if let Some(ref components_info) = message.components_info {
+            validate_components_depth(
+                components_info,
+                self.config.projects.components_json_max_depth,
+            )?;
+        }
+
+        let (project, _) =
+            load_project_with_role(&self.contexts, uid, message.id, Role::Editor).await?;
+
+        if let Some(new_owner_id) = message.owner_id {
+            if project.owner_id != uid {
+                return Err(Status::permission_denied(
+                    "You do not have permission to change the owner of this project",
+                ));
+            }
+            if new_owner_id != project.owner_id {
+                self.validate_new_owner(project.id, new_owner_id).await?;
+            }
+        }
+
+        // Get user session
+        let session = match self
+            .contexts
+            .session_context
+            .get_by_token(
+                TokenType::AccessToken,
+                request
+                    .token_string()
+                    .map_err(|err| {
+                        Status::internal(format!(
+                            "could not stringify user id in request metadata, internal error {}",
+                            err
+                        ))
+                    })?
+                    .ok_or(Status::internal(
+                        "failed to get token from request metadata",
+                    ))?,
+            )
+            .await
+        {
+            Ok(Some(session)) => session,
+            Ok(None) => {
+                return Err(Status::unauthenticated(
+                    "No session found with given access token",
+                ));
+            }
+            Err(error) => return Err(Status::internal(error.to_string())),
+        };
+
+        // Get in_use for project
+        match self.contexts.in_use_context.get_by_id(project.id).await {
+            Ok(Some(in_use)) => {
+                if self.lock_blocks_session(&in_use, &session).await? {
+                    return Err(Status::failed_precondition(
+                        "Model is currently in use by another session",
+                    ));
+                }
+
+                let new_in_use = in_use::Model {
+                    project_id: in_use.project_id,
+                    session_id: session.id,
+                    latest_activity: Utc::now().naive_utc(),
                 };
+
+                match self.contexts.in_use_context.update(new_in_use).await {
+                    Ok(_) => (),
+                    Err(error) => return Err(Status::internal(error.to_string())),
+                }
+            }
+            Ok(None) if self.config.locks.recreate_missing_in_use => {
+                log::warn!(
+                    "project {} has no in use row, locking it for session {}",
+                    project.id,
+                    session.id
+                );
+
+                self.contexts
+                    .in_use_context
+                    .create(in_use::Model {
+                        project_id: project.id,
+                        session_id: session.id,
+                        latest_activity: Default::default(),
+                    })
+                    .await
+                    .map_err(|error| Status::internal(error.to_string()))?;
             }
+            Ok(None) => return Err(Status::internal("No in_use found for project")),
+            Err(error) => return Err(Status::internal(error.to_string())),
         };
 
-        let access = access::Model {
-            id: Default::default(),
-            role: "Editor".to_string(), //todo!("Use role enum")
-            project_id: project.clone().id,
-            user_id: uid,
+        let normalized_components_info = message
+            .clone()
+            .components_info
+            .map(normalize_components_info);
+        let components_info = match normalized_components_info {
+            Some(ref components_info) => {
+                Some(serde_json::to_value(components_info).map_err(|err| {
+                    Status::internal(format!(
+                        "failed to parse components info object, internal error: {}",
+                        err
+                    ))
+                })?)
+            }
+            None => None,
+        };
+
+        // Marked before the update, so a failed update leaves extra queries outdated rather than stale results
+        if let Some(ref new_components_info) = normalized_components_info {
+            invalidate_affected_queries(
+                &self.contexts,
+                project.id,
+                &project.components_info,
+                new_components_info,
+            )
+            .await
+            .map_err(|err| {
+                database_busy_error(&err)
+                    .unwrap_or_else(|| Status::internal(sanitize(&err.to_string())))
+            })?;
+        }
+
+        let mut events = vec![];
+        if let Some(ref name) = name {
+            if *name != project.name {
+                events.push(project_event::Model {
+                    id: Default::default(),
+                    project_id: project.id,
+                    user_id: uid,
+                    event_type: "NameChanged".into(),
+                    description: format!(
+                        "Renamed project from \"{}\" to \"{}\"",
+                        project.name, name
+                    ),
+                    created_at: Default::default(),
+                    components_snapshot: None,
+                });
+            }
+        }
+        if let Some(new_owner_id) = message.owner_id {
+            if new_owner_id != project.owner_id {
+                events.push(project_event::Model {
+                    id: Default::default(),
+                    project_id: project.id,
+                    user_id: uid,
+                    event_type: "OwnerChanged".into(),
+                    description: format!(
+                        "Transferred ownership from user {} to user {}",
+                        project.owner_id, new_owner_id
+                    ),
+                    created_at: Default::default(),
+                    components_snapshot: None,
+                });
+            }
+        }
+        if let Some(ref components_info) = components_info {
+            // The snapshot makes this event a version that can be compared with diff_project
+            events.push(project_event::Model {
+                id: Default::default(),
+                project_id: project.id,
+                user_id: uid,
+                event_type: "ComponentsChanged".into(),
+                description: "Updated the components of the project".into(),
+                created_at: Default::default(),
+                components_snapshot: Some(components_info.clone()),
+            });
+        }
+
+        let new_project = project::Model {
+            id: project.id,
+            name: name.unwrap_or(project.name),
+            components_info: components_info.unwrap_or(project.components_info),
+            owner_id: message.owner_id.unwrap_or(project.owner_id),
         };
 
+        match self
+            .contexts
+            .project_context
+            .update_with_events(new_project, events)
+            .await
+        {
+            Ok(_) => Ok(Response::new(())),
+            Err(error) => Err(database_busy_error(&error)
+                .unwrap_or_else(|| Status::new(Code::Internal, sanitize(&error.to_string())))),
+        }
+    }
+
+    async fn heartbeat(&self, request: Request<HeartbeatRequest>) -> Result<Response<()>, Status> {
+        let project_id = request.get_ref().project_id;
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let access = self
+            .contexts
+            .access_context
+            .get_access_by_uid_and_project_id(uid, project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| {
+                Status::new(
+                    Code::PermissionDenied,
+                    "User does not have access to project",
+                )
+            })?;
+
+        if !access.has_role_at_least(Role::Editor) {
+            return Err(Status::permission_denied(
+                "You do not have permission to edit this project",
+            ));
+        }
+
         let session = self
             .contexts
             .session_context
@@ -240,38 +1379,104 @@ impl ProjectControllerTrait for ProjectController {
                     ))?,
             )
             .await
-            .map_err(|_err| Status::internal("failed to query database"))?
-            .ok_or(Status::not_found("token not found"))?;
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| {
+                Status::new(
+                    Code::Unauthenticated,
+                    "No session found with given access token",
+                )
+            })?;
 
-        let in_use = in_use::Model {
-            project_id: project.clone().id,
-            session_id: session.id,
-            latest_activity: Default::default(),
-        };
+        // A project without an in use row is not locked by anyone, so neither by the requester
+        let in_use = self
+            .contexts
+            .in_use_context
+            .get_by_id(project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| {
+                Status::failed_precondition("You do not hold the lock on this project")
+            })?;
+
+        // Only the session holding the lock may keep it alive, also during the grace period
+        if in_use.session_id != session.id
+            || !self
+                .config
+                .locks
+                .state(in_use.latest_activity, Utc::now().naive_utc())
+                .is_held()
+        {
+            return Err(Status::failed_precondition(
+                "You do not hold the lock on this project",
+            ));
+        }
 
         self.contexts
             .in_use_context
-            .create(in_use)
+            .update(in_use::Model {
+                latest_activity: Utc::now().naive_utc(),
+                ..in_use
+            })
             .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn get_project_history(
+        &self,
+        request: Request<GetProjectHistoryRequest>,
+    ) -> Result<Response<GetProjectHistoryResponse>, Status> {
+        let project_id = request.get_ref().project_id;
+
+        let uid = request
+            .uid()
             .map_err(|err| {
-                Status::internal(format!("a database error occured, internal error: {}", err))
-            })?;
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
         self.contexts
             .access_context
-            .create(access)
+            .get_access_by_uid_and_project_id(uid, project_id)
             .await
-            .map_err(|err| {
-                Status::internal(format!("a database error occured, internal error: {}", err))
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| {
+                Status::new(
+                    Code::PermissionDenied,
+                    "User does not have access to project",
+                )
             })?;
 
-        Ok(Response::new(CreateProjectResponse { id: project.id }))
+        let events = self
+            .contexts
+            .project_event_context
+            .get_by_project_id(project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .into_iter()
+            .map(|event| ProjectEvent {
+                id: event.id,
+                project_id: event.project_id,
+                user_id: event.user_id,
+                event_type: event.event_type,
+                description: event.description,
+                created_at: event.created_at.timestamp(),
+            })
+            .collect();
+
+        Ok(Response::new(GetProjectHistoryResponse { events }))
     }
 
-    async fn update_project(
+    async fn diff_project(
         &self,
-        request: Request<UpdateProjectRequest>,
-    ) -> Result<Response<()>, Status> {
+        request: Request<DiffProjectRequest>,
+    ) -> Result<Response<DiffProjectResponse>, Status> {
         let message = request.get_ref().clone();
+
         let uid = request
             .uid()
             .map_err(|err| {
@@ -282,129 +1487,75 @@ impl ProjectControllerTrait for ProjectController {
             })?
             .ok_or(Status::internal("Could not get uid from request metadata"))?;
 
-        // Check if the project exists
-        let project = match self.contexts.project_context.get_by_id(message.id).await {
-            Ok(Some(project)) => project,
-            Ok(None) => return Err(Status::not_found("No project found with given id")),
-            Err(error) => return Err(Status::internal(error.to_string())),
-        };
+        load_project_with_role(&self.contexts, uid, message.project_id, Role::Reader).await?;
 
-        // Check if the user has access to the project
-        match self
-            .contexts
-            .access_context
-            .get_access_by_uid_and_project_id(uid, project.id)
-            .await
-        {
-            Ok(access) => {
-                let mut is_editor = false;
-                let access = match access {
-                    Some(access) => {
-                        is_editor = access.role == "Editor";
-                        Some(access)
-                    }
-                    None => None,
-                };
+        let from = self
+            .components_snapshot(message.project_id, message.from_version)
+            .await?;
+        let to = self
+            .components_snapshot(message.project_id, message.to_version)
+            .await?;
 
-                if !is_editor || access.is_none() {
-                    return Err(Status::permission_denied(
-                        "You do not have permission to update this project",
-                    ));
-                }
-            }
-            Err(error) => return Err(Status::internal(error.to_string())),
-        };
+        Ok(Response::new(diff_components(&from, &to)))
+    }
 
-        // Get user session
-        let session = match self
-            .contexts
-            .session_context
-            .get_by_token(
-                TokenType::AccessToken,
-                request
-                    .token_string()
-                    .map_err(|err| {
-                        Status::internal(format!(
-                            "could not stringify user id in request metadata, internal error {}",
-                            err
-                        ))
-                    })?
-                    .ok_or(Status::internal(
-                        "failed to get token from request metadata",
-                    ))?,
-            )
-            .await
-        {
-            Ok(Some(session)) => session,
-            Ok(None) => {
-                return Err(Status::unauthenticated(
-                    "No session found with given access token",
-                ));
-            }
-            Err(error) => return Err(Status::internal(error.to_string())),
-        };
+    async fn get_lock_status(
+        &self,
+        request: Request<GetLockStatusRequest>,
+    ) -> Result<Response<GetLockStatusResponse>, Status> {
+        let project_id = request.get_ref().project_id;
 
-        // Get in_use for project
-        match self.contexts.in_use_context.get_by_id(project.id).await {
-            Ok(Some(in_use)) => {
-                // Check if in_use latest activity is older than the max allowed
-                if in_use.latest_activity
-                    > (Utc::now().naive_utc() - Duration::minutes(IN_USE_DURATION_MINUTES))
-                    && in_use.session_id != session.id
-                {
-                    return Err(Status::failed_precondition(
-                        "Model is currently in use by another session",
-                    ));
-                }
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
 
-                let new_in_use = in_use::Model {
-                    project_id: in_use.project_id,
-                    session_id: session.id,
-                    latest_activity: Utc::now().naive_utc(),
-                };
+        load_project_with_role(&self.contexts, uid, project_id, Role::Reader).await?;
 
-                match self.contexts.in_use_context.update(new_in_use).await {
-                    Ok(_) => (),
-                    Err(error) => return Err(Status::internal(error.to_string())),
-                }
-            }
-            Ok(None) => return Err(Status::internal("No in_use found for project")),
-            Err(error) => return Err(Status::internal(error.to_string())),
+        // A project loses its in use row when the session holding it ends, so no row means no lock
+        let in_use = self
+            .contexts
+            .in_use_context
+            .get_by_id(project_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .map(|in_use| {
+                let state = self
+                    .config
+                    .locks
+                    .state(in_use.latest_activity, Utc::now().naive_utc());
+                (in_use, state)
+            })
+            .filter(|(_, state)| state.is_held());
+        let Some((in_use, state)) = in_use else {
+            return Ok(Response::new(GetLockStatusResponse {
+                locked: false,
+                holder_user_id: None,
+                expires_at: None,
+                expiring: false,
+            }));
         };
+        let expires_at = self.config.locks.expires_at(in_use.latest_activity);
 
-        let new_project = project::Model {
-            id: project.id,
-            name: match message.clone().name {
-                Some(name) => name,
-                None => project.name,
-            },
-            components_info: match message.clone().components_info {
-                Some(components_info) => serde_json::to_value(components_info).map_err(|err| {
-                    Status::internal(format!(
-                        "failed to parse components info object, internal error: {}",
-                        err
-                    ))
-                })?,
-                None => project.components_info,
-            },
-            owner_id: match message.clone().owner_id {
-                Some(new_owner_id) => {
-                    if project.owner_id == uid {
-                        new_owner_id
-                    } else {
-                        return Err(Status::permission_denied(
-                            "You do not have permission to change the owner of this project",
-                        ));
-                    }
-                }
-                None => project.owner_id,
-            },
-        };
+        // The lock is held by a session, so the holder is the user owning that session
+        let holder = self
+            .contexts
+            .session_context
+            .get_by_id(in_use.session_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
 
-        match self.contexts.project_context.update(new_project).await {
-            Ok(_) => Ok(Response::new(())),
-            Err(error) => Err(Status::new(Code::Internal, error.to_string())),
-        }
+        Ok(Response::new(GetLockStatusResponse {
+            locked: true,
+            holder_user_id: holder.map(|session| session.user_id),
+            expires_at: Some(expires_at.timestamp()),
+            expiring: matches!(state, LockState::Expiring | LockState::InGrace),
+        }))
     }
 
     async fn delete_project(
@@ -441,6 +1592,14 @@ impl ProjectControllerTrait for ProjectController {
             ));
         }
 
+        if self.config.projects.require_delete_confirmation {
+            check_delete_confirmation(
+                request.get_ref().confirmation_token.as_deref(),
+                uid,
+                project_id,
+            )?;
+        }
+
         match self.contexts.project_context.delete(project_id).await {
             Ok(_) => Ok(Response::new(())),
             Err(error) => match error {
@@ -452,10 +1611,57 @@ impl ProjectControllerTrait for ProjectController {
         }
     }
 
+    async fn request_delete(
+        &self,
+        request: Request<RequestDeleteRequest>,
+    ) -> Result<Response<RequestDeleteResponse>, Status> {
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+        let project_id = request.get_ref().project_id;
+
+        let project = match self.contexts.project_context.get_by_id(project_id).await {
+            Ok(Some(project)) => project,
+            Ok(None) => {
+                return Err(Status::new(
+                    Code::NotFound,
+                    "No project found with given id",
+                ));
+            }
+            Err(err) => return Err(Status::new(Code::Internal, err.to_string())),
+        };
+
+        if project.owner_id != uid {
+            return Err(Status::new(
+                Code::PermissionDenied,
+                "You do not have permission to delete this project",
+            ));
+        }
+
+        let (confirmation_token, expires_at) = DeleteConfirmationClaims::new_token(
+            uid,
+            project_id,
+            self.config.projects.delete_confirmation_ttl,
+        )?;
+
+        Ok(Response::new(RequestDeleteResponse {
+            confirmation_token,
+            expires_at,
+        }))
+    }
+
     async fn list_projects_info(
         &self,
-        request: Request<()>,
+        request: Request<ListProjectsInfoRequest>,
     ) -> Result<Response<ListProjectsInfoResponse>, Status> {
+        let message = request.get_ref().clone();
+
         let uid = request
             .uid()
             .map_err(|err| {
@@ -469,24 +1675,198 @@ impl ProjectControllerTrait for ProjectController {
         match self
             .contexts
             .project_context
-            .get_project_info_by_uid(uid)
+            .get_project_info_by_uid(uid, message.page, message.page_size)
             .await
         {
-            Ok(project_info_list) => {
-                if project_info_list.is_empty() {
+            Ok(page) => {
+                if page.items.is_empty() && self.config.legacy_not_found_on_empty_list {
                     return Err(Status::new(
                         Code::NotFound,
                         "No access found for given user",
                     ));
                 } else {
                     Ok(Response::new(ListProjectsInfoResponse {
-                        project_info_list,
+                        project_info_list: page.items,
+                        page_info: Some(page.page_info),
                     }))
                 }
             }
             Err(error) => Err(Status::new(Code::Internal, error.to_string())),
         }
     }
+
+    async fn list_my_roles(
+        &self,
+        request: Request<ListMyRolesRequest>,
+    ) -> Result<Response<ListMyRolesResponse>, Status> {
+        let message = request.get_ref().clone();
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let page = self
+            .contexts
+            .project_context
+            .get_project_info_by_uid(uid, message.page, message.page_size)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+        let roles = page
+            .items
+            .into_iter()
+            .map(|info| ProjectRole {
+                project_id: info.project_id,
+                project_name: info.project_name,
+                role: info.user_role_on_project,
+                is_owner: info.project_owner_id == uid,
+            })
+            .collect();
+
+        Ok(Response::new(ListMyRolesResponse {
+            roles,
+            page_info: Some(page.page_info),
+        }))
+    }
+
+    async fn list_my_locks(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<ListMyLocksResponse>, Status> {
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let locks = self.active_locks(uid).await?;
+
+        Ok(Response::new(ListMyLocksResponse { locks }))
+    }
+
+    async fn get_dashboard(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<GetDashboardResponse>, Status> {
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let user = self
+            .contexts
+            .user_context
+            .get_by_id(uid)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| Status::new(Code::NotFound, "No user found with given uid"))?;
+
+        let page = self
+            .contexts
+            .project_context
+            .get_project_info_by_uid(uid, 0, self.config.projects.dashboard_page_size)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+        let locks = self.active_locks(uid).await?;
+
+        Ok(Response::new(GetDashboardResponse {
+            user: Some(current_user_to_protobuf(user)),
+            projects: Some(ListProjectsInfoResponse {
+                project_info_list: page.items,
+                page_info: Some(page.page_info),
+            }),
+            locks: Some(ListMyLocksResponse { locks }),
+        }))
+    }
+
+    async fn list_projects(
+        &self,
+        request: Request<ListProjectsRequest>,
+    ) -> Result<Response<ListProjectsResponse>, Status> {
+        let message = request.get_ref().clone();
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let page = self
+            .contexts
+            .project_context
+            .get_projects_by_uid(uid, message.page, message.page_size)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+        let mut projects = vec![];
+        for project in page.items {
+            let project_id = project.id;
+
+            let queries = self
+                .contexts
+                .query_context
+                .get_all_by_project_id(project_id, None)
+                .await
+                .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+
+            // Unlike get_project, listing never claims the lock on a project
+            let in_use = self
+                .contexts
+                .in_use_context
+                .get_by_id(project_id)
+                .await
+                .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+                .is_some_and(|in_use| {
+                    self.config
+                        .locks
+                        .state(in_use.latest_activity, Utc::now().naive_utc())
+                        .is_held()
+                });
+
+            let project = project_to_protobuf(project)?;
+            let queries = queries_to_protobuf(queries, self.config.queries.result_max_age);
+
+            projects.push(GetProjectResponse {
+                version: project_version(&project, &queries),
+                project: Some(project),
+                queries,
+                in_use,
+                not_modified: false,
+            });
+        }
+
+        let mut response = Response::new(ListProjectsResponse {
+            projects,
+            page_info: Some(page.page_info),
+        });
+        check_response_size(
+            "list_projects",
+            &mut response,
+            &self.config.response_size_warning,
+        );
+
+        Ok(response)
+    }
 }
 
 #[cfg(test)]
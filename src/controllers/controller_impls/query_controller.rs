@@ -1,23 +1,821 @@
 use crate::api::auth::RequestExt;
 use crate::api::server::protobuf::{
-    CreateQueryRequest, DeleteQueryRequest, QueryRequest, SendQueryRequest, SendQueryResponse,
-    UpdateQueryRequest,
+    query_request::Settings, query_response, ComponentsInfo, CreateQueryRequest,
+    DeleteQueryRequest, GetOutdatedQueryCountRequest, GetOutdatedQueryCountResponse,
+    GetQueryHistoryRequest, GetQueryHistoryResponse, OutdatedQueryResult, QueryRequest,
+    QueryResponse, QueryRun, SendOutdatedQueriesRequest, SendOutdatedQueriesResponse,
+    SendQueryRequest, SendQueryResponse, UpdateQueryRequest,
 };
+use crate::config::Config;
 use crate::contexts::context_collection::ContextCollection;
+use crate::contexts::sqlite_lock::is_database_locked;
+use crate::controllers::controller_impls::project_controller::validate_components_depth;
 use crate::controllers::controller_traits::QueryControllerTrait;
-use crate::entities::query;
+use crate::controllers::error_details::{database_busy_error, status_with_reason, ReasonCode};
+use crate::controllers::project_access::load_project_with_role;
+use crate::controllers::project_activity::touch_in_use;
+use crate::controllers::sanitization::sanitize;
+use crate::entities::role::Role;
+use crate::entities::{access, project, query, query_result};
 use crate::services::service_collection::ServiceCollection;
 use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use futures::stream::{self, StreamExt};
+use migration::QUERY_TITLE_MAX_LENGTH;
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 use tonic::{Code, Request, Response, Status};
 
+const DEFAULT_REVEAAL_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_QUERIES_PER_PROJECT: u64 = 100;
+const COMPONENTS_CACHE_CAPACITY: usize = 128;
+const DEFAULT_OUTDATED_QUERIES_CONCURRENCY: usize = 4;
+const DEFAULT_RESULT_SAVE_ATTEMPTS: u32 = 3;
+const RESULT_SAVE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// The warning returned alongside a result that was computed but could not be saved.
+pub const RESULT_NOT_SAVED_WARNING: &str =
+    "The result could not be saved, so the query is still outdated and has to be run again to store it";
+
+/// How queries are run on Reveaal and stored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryConfig {
+    /// The maximum time to wait for Reveaal to answer a query, 30 seconds by default
+    pub reveaal_timeout: Duration,
+    /// The maximum number of queries a single project may hold, 100 by default
+    pub max_queries_per_project: u64,
+    /// How old a query result may get before it is treated as outdated, or `None` if results never expire
+    pub result_max_age: Option<chrono::Duration>,
+    /// How a response from Reveaal without a result is handled
+    pub empty_result_policy: EmptyResultPolicy,
+    /// Who may create, update and delete queries, `Editor` by default
+    pub mutation_role: QueryMutationRole,
+    /// How many outdated queries of a project are run on Reveaal at the same time, 4 by default
+    pub outdated_queries_concurrency: usize,
+    /// How many times saving the result of a query is attempted when the database fails transiently, 3 by default
+    pub result_save_attempts: u32,
+    /// The Reveaal settings that clients may change from their defaults when sending a query.
+    /// None may be changed by default, so a shared Reveaal only runs queries with the settings
+    /// it has been configured to accept.
+    pub allowed_settings: Vec<String>,
+    /// Whether concurrent `send_query` calls for the same query share a single run on Reveaal
+    /// instead of each running the query
+    pub deduplicate_concurrent: bool,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        QueryConfig {
+            reveaal_timeout: Duration::from_secs(DEFAULT_REVEAAL_TIMEOUT_SECS),
+            max_queries_per_project: DEFAULT_MAX_QUERIES_PER_PROJECT,
+            result_max_age: None,
+            empty_result_policy: EmptyResultPolicy::Error,
+            mutation_role: QueryMutationRole::Role(Role::Editor),
+            outdated_queries_concurrency: DEFAULT_OUTDATED_QUERIES_CONCURRENCY,
+            result_save_attempts: DEFAULT_RESULT_SAVE_ATTEMPTS,
+            allowed_settings: vec![],
+            deduplicate_concurrent: true,
+        }
+    }
+}
+
+/// How a response from Reveaal without a result is handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmptyResultPolicy {
+    /// The response is treated as a protocol error and the stored result is left untouched
+    Error,
+    /// The query is stored as timed out, without a result, so clients can tell the computation gave up
+    Timeout,
+}
+
+impl FromStr for EmptyResultPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "error" => Ok(EmptyResultPolicy::Error),
+            "timeout" => Ok(EmptyResultPolicy::Timeout),
+            _ => Err(format!(
+                "'{}' is not an empty result policy, expected 'error' or 'timeout'",
+                value
+            )),
+        }
+    }
+}
+
+/// Who may create, update and delete the queries of a project.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueryMutationRole {
+    /// Every user with at least the given role on the project
+    Role(Role),
+    /// Only the owner of the project
+    Owner,
+}
+
+impl QueryMutationRole {
+    /// Returns the minimum role of the user's access. The owner always has `Editor` access to the project.
+    pub fn min_role(&self) -> Role {
+        match self {
+            QueryMutationRole::Role(role) => *role,
+            QueryMutationRole::Owner => Role::Editor,
+        }
+    }
+}
+
+impl FromStr for QueryMutationRole {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Owner" => Ok(QueryMutationRole::Owner),
+            _ => value.parse().map(QueryMutationRole::Role).map_err(|_| {
+                format!(
+                    "'{}' is not a query mutation role, expected 'Reader', 'Commenter', 'Editor' or 'Owner'",
+                    value
+                )
+            }),
+        }
+    }
+}
+
+/// Checks that `uid` owns the project if the `required` role only lets the owner change its queries.
+/// # Errors
+/// Returns `PermissionDenied` if the user is not the owner.
+fn check_query_mutation_owner(
+    required: QueryMutationRole,
+    project: &project::Model,
+    uid: i32,
+) -> Result<(), Status> {
+    if required == QueryMutationRole::Owner && project.owner_id != uid {
+        return Err(Status::permission_denied(
+            "Only the owner of the project can change its queries",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Converts the result of a Reveaal response to the JSON stored for the query,
+/// or `None` if the query is stored as timed out.
+/// # Errors
+/// Returns an `Internal` status if the result cannot be stored, or if it is missing
+/// and the `policy` is [`EmptyResultPolicy::Error`].
+pub fn response_result_to_json(
+    result: Option<query_response::Result>,
+    policy: EmptyResultPolicy,
+) -> Result<Option<serde_json::Value>, Status> {
+    match (result, policy) {
+        (None, EmptyResultPolicy::Timeout) => Ok(None),
+        (result, _) => query_result_to_json(result).map(Some),
+    }
+}
+
+/// Returns whether a query was run but Reveaal gave no result, see [`EmptyResultPolicy::Timeout`].
+///
+/// Such a query has a computation time but no result. It is not outdated, so it is only run again on request.
+pub fn result_timed_out(query: &query::Model) -> bool {
+    query.result.is_none() && query.result_computed_at.is_some()
+}
+
+/// Returns whether the stored result of a query is older than `max_age` at `now`.
+///
+/// Results without a computation time predate the timestamp and are treated as stale.
+/// A query without a result, or any query when `max_age` is `None`, is never stale.
+pub fn result_is_stale(
+    query: &query::Model,
+    max_age: Option<chrono::Duration>,
+    now: NaiveDateTime,
+) -> bool {
+    match (max_age, &query.result) {
+        (Some(max_age), Some(_)) => query
+            .result_computed_at
+            .map_or(true, |computed_at| computed_at <= now - max_age),
+        _ => false,
+    }
+}
+
+/// Returns whether a database error may not happen again when retrying,
+/// such as a lost connection or SQLite being locked by another writer.
+pub fn is_transient_db_error(error: &DbErr) -> bool {
+    matches!(error, DbErr::ConnectionAcquire(_) | DbErr::Conn(_)) || is_database_locked(error)
+}
+
+/// Checks that a query string is not blank and contains no control characters, returning it trimmed.
+/// # Errors
+/// Returns an `InvalidArgument` status if the query string is invalid.
+pub fn validate_query_string(string: &str) -> Result<String, Status> {
+    let string = string.trim();
+
+    if string.is_empty() {
+        return Err(invalid_query("Query must not be empty"));
+    }
+
+    if string.chars().any(char::is_control) {
+        return Err(invalid_query("Query must not contain control characters"));
+    }
+
+    Ok(string.to_string())
+}
+
+fn invalid_query(message: &str) -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        message,
+        ReasonCode::InvalidQuery,
+        Some("queries"),
+    )
+}
+
+/// Trims a requested query title, returning `None` if it is empty so the title is cleared.
+/// # Errors
+/// Returns an `InvalidArgument` status if the title is longer than [`QUERY_TITLE_MAX_LENGTH`]
+/// or contains control characters.
+pub fn parse_query_title(title: &str) -> Result<Option<String>, Status> {
+    let title = title.trim();
+
+    if title.chars().count() > QUERY_TITLE_MAX_LENGTH as usize {
+        return Err(invalid_query_title(format!(
+            "Query title must be at most {} characters long",
+            QUERY_TITLE_MAX_LENGTH
+        )));
+    }
+    if title.chars().any(char::is_control) {
+        return Err(invalid_query_title(
+            "Query title must not contain control characters".to_string(),
+        ));
+    }
+
+    Ok((!title.is_empty()).then(|| title.to_string()))
+}
+
+fn invalid_query_title(message: String) -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        message,
+        ReasonCode::InvalidQueryTitle,
+        Some("title"),
+    )
+}
+
+/// The names of the Reveaal settings that clients may ask for, as listed in `REVEAAL_ALLOWED_SETTINGS`.
+pub const REVEAAL_SETTINGS: [&str; 1] = ["disable_clock_reduction"];
+
+/// Returns the names of the settings that `settings` changes from their defaults.
+fn changed_settings(settings: &Settings) -> Vec<&'static str> {
+    [("disable_clock_reduction", settings.disable_clock_reduction)]
+        .into_iter()
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect()
+}
+
+/// Parses the value of `REVEAAL_ALLOWED_SETTINGS`, a comma separated list of setting names.
+/// # Errors
+/// Returns a description of the problem if an entry is not one of [`REVEAAL_SETTINGS`].
+pub fn parse_allowed_settings(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| {
+            REVEAAL_SETTINGS
+                .contains(&setting)
+                .then(|| setting.to_string())
+                .ok_or_else(|| format!("'{}' is not a Reveaal setting", setting))
+        })
+        .collect()
+}
+
+/// Checks that `settings` only changes settings in `allowed` from their defaults.
+/// # Errors
+/// Returns an `InvalidArgument` status naming the first setting that is not allowed.
+pub fn validate_settings(settings: &Settings, allowed: &[String]) -> Result<(), Status> {
+    match changed_settings(settings)
+        .into_iter()
+        .find(|setting| !allowed.iter().any(|allowed| allowed == setting))
+    {
+        Some(setting) => Err(status_with_reason(
+            Code::InvalidArgument,
+            format!("The Reveaal setting '{}' is not allowed", setting),
+            ReasonCode::DisallowedSetting,
+            Some("settings"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The Reveaal settings a query is run with. They are stored with the query when its result is,
+/// so running it again without settings reuses the ones it was last run with.
+///
+/// Each setting defaults to Reveaal's own default, which also applies to a query that was never
+/// run with settings, and to stored settings from before a setting was added:
+/// - `disable_clock_reduction`: `false`, so the clocks of the components are reduced before the query is run
+///
+/// Settings asked for by clients are checked with [`validate_settings`] before they are used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuerySettings {
+    /// Whether Reveaal skips reducing the clocks of the components before running the query
+    pub disable_clock_reduction: bool,
+}
+
+impl QuerySettings {
+    /// Returns the settings stored with the query, or the defaults if it has none.
+    /// # Errors
+    /// Returns an `Internal` status if the stored settings cannot be parsed.
+    pub fn of_query(query: &query::Model) -> Result<Self, Status> {
+        match &query.settings {
+            Some(settings) => serde_json::from_value(settings.clone()).map_err(|err| {
+                Status::internal(format!(
+                    "error parsing settings of query {}, internal error: {}",
+                    query.id, err
+                ))
+            }),
+            None => Ok(QuerySettings::default()),
+        }
+    }
+
+    /// Returns the settings as the JSON stored with a query.
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
+}
+
+impl From<&Settings> for QuerySettings {
+    fn from(settings: &Settings) -> Self {
+        QuerySettings {
+            disable_clock_reduction: settings.disable_clock_reduction,
+        }
+    }
+}
+
+impl From<QuerySettings> for Settings {
+    fn from(settings: QuerySettings) -> Self {
+        Settings {
+            disable_clock_reduction: settings.disable_clock_reduction,
+        }
+    }
+}
+
+/// Converts a result returned by Reveaal to the JSON stored for the query.
+///
+/// The result is checked to be a single variant of the `result` oneof, and every variant but a success
+/// must carry details, such as the error or the failing state, so that a malformed result is never stored.
+/// # Errors
+/// Returns an `Internal` status if Reveaal returned no result or one that cannot be stored.
+pub fn query_result_to_json(
+    result: Option<query_response::Result>,
+) -> Result<serde_json::Value, Status> {
+    let result =
+        result.ok_or_else(|| Status::internal("Reveaal returned a response without a result"))?;
+
+    let value = serde_json::to_value(result).map_err(|err| {
+        Status::internal(format!(
+            "Reveaal returned a result that cannot be stored, internal error: {}",
+            err
+        ))
+    })?;
+
+    let variant = value
+        .as_object()
+        .filter(|object| object.len() == 1)
+        .and_then(|object| object.iter().next());
+    match variant {
+        None => Err(Status::internal(
+            "Reveaal returned a result that is not a single query result",
+        )),
+        Some((name, details)) if name != "Success" && !has_details(details) => {
+            Err(Status::internal(format!(
+                "Reveaal returned a malformed {} result without any details",
+                name
+            )))
+        }
+        Some(_) => Ok(value),
+    }
+}
+
+/// Returns whether a serialized protobuf message has any field set to other than its default value.
+fn has_details(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(value) => *value,
+        serde_json::Value::Number(value) => value.as_f64() != Some(0.0),
+        serde_json::Value::String(value) => !value.is_empty(),
+        serde_json::Value::Array(values) => !values.is_empty(),
+        serde_json::Value::Object(fields) => fields.values().any(has_details),
+    }
+}
+
+/// Converts a result stored by [`query_result_to_json`] back into its protobuf form, the result as JSON.
+///
+/// Results stored as a plain string are returned as they are, and a missing result as an empty string.
+/// A result that cannot be serialized is logged and returned as an empty string.
+pub fn stored_result_to_protobuf(query_id: i32, result: Option<serde_json::Value>) -> String {
+    match result {
+        None => String::new(),
+        Some(serde_json::Value::String(result)) => result,
+        Some(result) => serde_json::to_string(&result).unwrap_or_else(|err| {
+            log::warn!(
+                "failed to serialize result of query {}, inner error: {}",
+                query_id,
+                err
+            );
+            String::new()
+        }),
+    }
+}
+
+/// Parsed components info of recently queried projects, so queries run in succession
+/// do not deserialize the same components again.
+///
+/// Entries are keyed by project id and only reused while the stored `components_hash` is unchanged.
+#[derive(Default)]
+pub struct ComponentsCache {
+    entries: Mutex<HashMap<i32, (u64, Option<ComponentsInfo>)>>,
+}
+
+impl ComponentsCache {
+    /// Returns the parsed components info of a project, reusing the cached one if its hash matches.
+    /// Components info without a hash is always parsed and never cached.
+    /// # Errors
+    /// Returns an `Internal` status if the components info cannot be parsed,
+    /// and an `InvalidArgument` status if a component is nested deeper than `max_depth`.
+    pub fn get_or_parse(
+        &self,
+        project_id: i32,
+        components_info: serde_json::Value,
+        max_depth: usize,
+    ) -> Result<Option<ComponentsInfo>, Status> {
+        let hash = components_info
+            .get("components_hash")
+            .and_then(serde_json::Value::as_u64);
+
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some((cached_hash, cached)) = entries.get(&project_id) {
+            if Some(*cached_hash) == hash {
+                return Ok(cached.clone());
+            }
+        }
+
+        let parsed: Option<ComponentsInfo> =
+            serde_json::from_value(components_info).map_err(|err| {
+                Status::internal(format!(
+                    "error parsing components info, internal error: {}",
+                    err
+                ))
+            })?;
+
+        // Components stored before the depth limit existed are checked before being sent to Reveaal
+        if let Some(ref components_info) = parsed {
+            validate_components_depth(components_info, max_depth)?;
+        }
+
+        match hash {
+            Some(hash) => {
+                // Dropping every entry keeps the cache bounded without tracking usage
+                if entries.len() >= COMPONENTS_CACHE_CAPACITY && !entries.contains_key(&project_id)
+                {
+                    entries.clear();
+                }
+                entries.insert(project_id, (hash, parsed.clone()));
+            }
+            None => {
+                entries.remove(&project_id);
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// The outcome of a query run: Reveaal's response, the warning returned with it
+/// and the correlation id of the run.
+type QueryOutcome = Result<(QueryResponse, Option<String>, String), Status>;
+
+/// Registry of the queries currently running on Reveaal, so concurrent `send_query` calls
+/// for the same query wait for the run in flight instead of starting another.
+///
+/// Entries are keyed by project and query id, and a run is only shared with calls using the same settings.
+/// Every caller sharing a run receives its outcome, including its error if the run failed.
+/// The entry is removed as soon as the run ends, so a failed run is retried by the next call.
+#[derive(Default)]
+struct InFlightQueries {
+    entries: Mutex<HashMap<InFlightKey, (QuerySettings, watch::Receiver<Option<QueryOutcome>>)>>,
+}
+
+/// The project id and query id a run in flight is registered under.
+type InFlightKey = (i32, i32);
+
+/// The part a caller plays in running a query, as assigned by [`InFlightQueries::join`].
+enum InFlightRun<'a> {
+    /// No run of the query is in flight, so the caller runs it and shares the outcome.
+    Leader(InFlightLeader<'a>),
+    /// The query is already running with the same settings, so the caller waits for its outcome.
+    Follower(watch::Receiver<Option<QueryOutcome>>),
+    /// The query is already running with other settings, or deduplication is disabled,
+    /// so the caller runs it without sharing the outcome.
+    Alone,
+}
+
+/// The caller running a query on behalf of everyone waiting for it.
+///
+/// Dropping it removes the run from the registry. If that happens before [`InFlightLeader::finish`],
+/// such as when the caller disconnects, the waiting callers start the query again.
+struct InFlightLeader<'a> {
+    queries: &'a InFlightQueries,
+    key: InFlightKey,
+    sender: watch::Sender<Option<QueryOutcome>>,
+}
+
+impl InFlightQueries {
+    /// Joins the run of a query in flight with the same settings, or starts one.
+    /// Without `deduplicate`, the caller always runs the query alone.
+    fn join(
+        &self,
+        key: InFlightKey,
+        settings: QuerySettings,
+        deduplicate: bool,
+    ) -> InFlightRun<'_> {
+        if !deduplicate {
+            return InFlightRun::Alone;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some((running_settings, receiver)) = entries.get(&key) {
+            return if *running_settings == settings {
+                InFlightRun::Follower(receiver.clone())
+            } else {
+                InFlightRun::Alone
+            };
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        entries.insert(key, (settings, receiver));
+
+        InFlightRun::Leader(InFlightLeader {
+            queries: self,
+            key,
+            sender,
+        })
+    }
+}
+
+impl InFlightLeader<'_> {
+    /// Hands the outcome of the run to every caller waiting for it.
+    fn finish(self, outcome: QueryOutcome) {
+        self.sender.send_replace(Some(outcome));
+    }
+}
+
+impl Drop for InFlightLeader<'_> {
+    fn drop(&mut self) {
+        self.queries
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.key);
+    }
+}
+
+/// The message returned when Reveaal cannot be reached.
+pub const REVEAAL_UNAVAILABLE_MESSAGE: &str =
+    "verification backend is unavailable; contact your administrator";
+
+/// Maps a transport error from Reveaal, such as a refused connection or a failed DNS lookup,
+/// to an `Unavailable` status telling the user that the backend is down.
+///
+/// The original error is logged for the administrator. Errors about the query itself are returned as is.
+pub fn reveaal_error(status: Status) -> Status {
+    if status.code() != Code::Unavailable {
+        return status;
+    }
+
+    log::error!("Reveaal is unavailable: {}", status.message());
+    status_with_reason(
+        Code::Unavailable,
+        REVEAAL_UNAVAILABLE_MESSAGE,
+        ReasonCode::BackendUnavailable,
+        None,
+    )
+}
+
+/// The metadata key of the id that correlates a query's log lines in the API and in Reveaal.
+pub const CORRELATION_ID_KEY: &str = "x-correlation-id";
+
+/// Returns a new id for correlating the log lines of a single query run.
+pub fn new_correlation_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
 pub struct QueryController {
     contexts: ContextCollection,
     services: ServiceCollection,
+    config: Config,
+    components_cache: ComponentsCache,
+    in_flight_queries: InFlightQueries,
 }
 
 impl QueryController {
-    pub fn new(contexts: ContextCollection, services: ServiceCollection) -> Self {
-        Self { contexts, services }
+    pub fn new(contexts: ContextCollection, services: ServiceCollection, config: Config) -> Self {
+        Self {
+            contexts,
+            services,
+            config,
+            components_cache: ComponentsCache::default(),
+            in_flight_queries: InFlightQueries::default(),
+        }
+    }
+
+    /// Checks that a user with the given access to a project may update and delete its queries,
+    /// as configured by [`QueryConfig::mutation_role`]. The project is only loaded if its owner is required.
+    /// # Errors
+    /// Returns `PermissionDenied` if the user is not allowed to, and `Internal` if a context fails.
+    async fn check_query_mutation_allowed(
+        &self,
+        uid: i32,
+        access: &access::Model,
+    ) -> Result<(), Status> {
+        let required = self.config.queries.mutation_role;
+
+        if !access.has_role_at_least(required.min_role()) {
+            return Err(Status::new(
+                Code::PermissionDenied,
+                "Role does not have permission to update query",
+            ));
+        }
+
+        if required == QueryMutationRole::Owner {
+            let project = self
+                .contexts
+                .project_context
+                .get_by_id(access.project_id)
+                .await
+                .map_err(|err| Status::internal(err.to_string()))?
+                .ok_or_else(|| Status::not_found("No project found with given id"))?;
+            check_query_mutation_owner(required, &project, uid)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs a query on Reveaal and stores its result, returning Reveaal's response.
+    /// The request to Reveaal carries `correlation_id` under [`CORRELATION_ID_KEY`], and both the request
+    /// and its outcome are logged with it. The `settings` are stored together with the result.
+    ///
+    /// If the result cannot be saved, the response is still returned together with [`RESULT_NOT_SAVED_WARNING`],
+    /// so the computation is not lost.
+    /// # Errors
+    /// Returns `DeadlineExceeded` if Reveaal does not respond in time, `Unavailable` if it cannot be reached,
+    /// or the error returned by Reveaal. In all of these cases the stored result is left untouched.
+    async fn run_query(
+        &self,
+        uid: i32,
+        components_info: Option<ComponentsInfo>,
+        query: query::Model,
+        settings: QuerySettings,
+        correlation_id: &str,
+    ) -> Result<(QueryResponse, Option<String>), Status> {
+        // Construct query request to send to Reveaal
+        let mut query_request = Request::new(QueryRequest {
+            user_id: uid,
+            query_id: query.id,
+            query: query.string.clone(),
+            components_info,
+            settings: Some(settings.into()),
+        });
+        query_request.metadata_mut().insert(
+            CORRELATION_ID_KEY,
+            correlation_id.parse().map_err(|_| {
+                Status::internal(format!("invalid correlation id '{}'", correlation_id))
+            })?,
+        );
+
+        log::info!(
+            "sending query {} to Reveaal, correlation id {}",
+            query.id,
+            correlation_id
+        );
+
+        // Run query on Reveaal, giving up before the stored result is touched if it does not answer in time
+        let timeout = self.config.queries.reveaal_timeout;
+        let started_at = Instant::now();
+        let query_result = tokio::time::timeout(
+            timeout,
+            self.services.reveaal_service.send_query(query_request),
+        )
+        .await
+        .map_err(|_| {
+            Status::deadline_exceeded(format!(
+                "Reveaal did not respond within {} seconds",
+                timeout.as_secs()
+            ))
+        })
+        .and_then(|response| response.map_err(reveaal_error))
+        .map_err(|status| {
+            log::warn!(
+                "query {} failed, correlation id {}: {}",
+                query.id,
+                correlation_id,
+                status.message()
+            );
+            status
+        })?;
+        let last_run_ms = i64::try_from(started_at.elapsed().as_millis()).unwrap_or(i64::MAX);
+
+        log::info!(
+            "Reveaal answered query {} in {} ms, correlation id {}",
+            query.id,
+            last_run_ms,
+            correlation_id
+        );
+
+        // Validate the result before it replaces the stored one
+        let result = response_result_to_json(
+            query_result.get_ref().result.clone(),
+            self.config.queries.empty_result_policy,
+        )?;
+        if result.is_none() {
+            log::info!(
+                "Reveaal returned no result for query {}, storing it as timed out, correlation id {}",
+                query.id,
+                correlation_id
+            );
+        }
+
+        let query_id = query.id;
+
+        // Update query result in contexts, handing back the computed result even if it cannot be saved
+        if let Err(err) = self
+            .save_result(query::Model {
+                result: result.clone(),
+                settings: Some(settings.to_json()),
+                outdated: false,
+                last_run_ms: Some(last_run_ms),
+                result_computed_at: Some(Utc::now().naive_utc()),
+                ..query
+            })
+            .await
+        {
+            log::error!(
+                "failed to save result of query {}, correlation id {}: {}",
+                query_id,
+                correlation_id,
+                err
+            );
+            return Ok((
+                query_result.into_inner(),
+                Some(RESULT_NOT_SAVED_WARNING.to_string()),
+            ));
+        }
+
+        // The result is already stored, so failing to add it to the history does not fail the run
+        if let Err(err) = self
+            .contexts
+            .query_result_context
+            .create(query_result::Model {
+                id: Default::default(),
+                query_id,
+                result,
+                last_run_ms: Some(last_run_ms),
+                created_at: Default::default(),
+            })
+            .await
+        {
+            log::error!("failed to record result of query {}: {}", query_id, err);
+        }
+
+        Ok((query_result.into_inner(), None))
+    }
+
+    /// Saves a query with its new result, retrying up to [`QueryConfig::result_save_attempts`] times
+    /// while the database fails transiently.
+    /// # Errors
+    /// Returns the error of the last attempt.
+    async fn save_result(&self, query: query::Model) -> Result<query::Model, DbErr> {
+        let attempts = self.config.queries.result_save_attempts;
+        let mut attempt: u32 = 1;
+
+        loop {
+            match self.contexts.query_context.update(query.clone()).await {
+                Err(err) if attempt < attempts && is_transient_db_error(&err) => {
+                    log::warn!(
+                        "failed to save result of query {} (attempt {}), retrying: {}",
+                        query.id,
+                        attempt,
+                        err
+                    );
+                    tokio::time::sleep(RESULT_SAVE_BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
     }
 }
 
@@ -29,50 +827,73 @@ impl QueryControllerTrait for QueryController {
     ) -> Result<Response<()>, Status> {
         let query_request = request.get_ref();
 
-        let access = self
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::invalid_argument(format!(
+                    "could not stringify user id in request metadata, inner error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::invalid_argument(
+                "failed to get user id from request metadata",
+            ))?;
+
+        let required = self.config.queries.mutation_role;
+        let (project, _) = load_project_with_role(
+            &self.contexts,
+            uid,
+            query_request.project_id,
+            required.min_role(),
+        )
+        .await?;
+        check_query_mutation_owner(required, &project, uid)?;
+
+        let query_count = self
             .contexts
-            .access_context
-            .get_access_by_uid_and_project_id(
-                request
-                    .uid()
-                    .map_err(|err| {
-                        Status::invalid_argument(format!(
-                            "could not stringify user id in request metadata, inner error {}",
-                            err
-                        ))
-                    })?
-                    .ok_or(Status::invalid_argument(
-                        "failed to get user id from request metadata",
-                    ))?,
-                query_request.project_id,
-            )
+            .query_context
+            .count_by_project_id(query_request.project_id)
             .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
-            .ok_or_else(|| {
-                Status::new(
-                    Code::PermissionDenied,
-                    "User does not have access to project",
-                )
-            })?;
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
 
-        if access.role != "Editor" {
+        if query_count >= self.config.queries.max_queries_per_project {
             return Err(Status::new(
-                Code::PermissionDenied,
-                "Role does not have permission to create query",
+                Code::ResourceExhausted,
+                "Project has reached the maximum number of queries",
             ));
         }
 
+        let title = match query_request.title {
+            Some(ref title) => parse_query_title(title)?,
+            None => None,
+        };
+
         let query = query::Model {
             id: Default::default(),
             string: query_request.string.to_string(),
+            title,
             result: Default::default(),
             outdated: Default::default(),
             project_id: query_request.project_id,
+            last_run_ms: Default::default(),
+            result_computed_at: Default::default(),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            settings: Default::default(),
         };
 
         match self.contexts.query_context.create(query).await {
-            Ok(_) => Ok(Response::new(())),
-            Err(error) => Err(Status::new(Code::Internal, error.to_string())),
+            Ok(_) => {
+                touch_in_use(
+                    &self.contexts,
+                    &self.config.locks,
+                    &request,
+                    query_request.project_id,
+                )
+                .await;
+                Ok(Response::new(()))
+            }
+            Err(error) => Err(Status::new(Code::Internal, sanitize(&error.to_string()))),
         }
     }
 
@@ -94,23 +915,22 @@ impl QueryControllerTrait for QueryController {
             None => return Err(Status::new(Code::NotFound, "Query not found".to_string())),
         };
 
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+
         let access = self
             .contexts
             .access_context
-            .get_access_by_uid_and_project_id(
-                request
-                    .uid()
-                    .map_err(|err| {
-                        Status::internal(format!(
-                            "could not stringify user id in request metadata, internal error {}",
-                            err
-                        ))
-                    })?
-                    .ok_or(Status::internal(
-                        "failed to get user id from request metadata",
-                    ))?,
-                old_query.project_id,
-            )
+            .get_access_by_uid_and_project_id(uid, old_query.project_id)
             .await
             .map_err(|err| Status::new(Code::Internal, err.to_string()))?
             .ok_or_else(|| {
@@ -120,24 +940,43 @@ impl QueryControllerTrait for QueryController {
                 )
             })?;
 
-        if access.role != "Editor" {
-            return Err(Status::new(
-                Code::PermissionDenied,
-                "Role does not have permission to update query",
-            ));
-        }
+        self.check_query_mutation_allowed(uid, &access).await?;
+
+        // The title is only a label, so changing it leaves the result and outdated flag as they are.
+        // A changed query string no longer matches the result, which is kept but marked outdated.
+        let title = match message.title {
+            Some(ref title) => parse_query_title(title)?,
+            None => old_query.title,
+        };
+        let outdated = old_query.outdated || message.string != old_query.string;
 
         let query = query::Model {
             id: message.id,
             project_id: Default::default(),
             string: message.string,
+            title,
             result: old_query.result,
-            outdated: old_query.outdated,
+            settings: old_query.settings,
+            outdated,
+            last_run_ms: old_query.last_run_ms,
+            result_computed_at: old_query.result_computed_at,
+            created_at: Default::default(),
+            updated_at: Default::default(),
         };
 
         match self.contexts.query_context.update(query).await {
-            Ok(_) => Ok(Response::new(())),
-            Err(error) => Err(Status::new(Code::Internal, error.to_string())),
+            Ok(_) => {
+                touch_in_use(
+                    &self.contexts,
+                    &self.config.locks,
+                    &request,
+                    old_query.project_id,
+                )
+                .await;
+                Ok(Response::new(()))
+            }
+            Err(error) => Err(database_busy_error(&error)
+                .unwrap_or_else(|| Status::new(Code::Internal, sanitize(&error.to_string())))),
         }
     }
 
@@ -155,23 +994,22 @@ impl QueryControllerTrait for QueryController {
             .map_err(|err| Status::new(Code::Internal, err.to_string()))?
             .ok_or_else(|| Status::new(Code::NotFound, "Query not found"))?;
 
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+
         let access = self
             .contexts
             .access_context
-            .get_access_by_uid_and_project_id(
-                request
-                    .uid()
-                    .map_err(|err| {
-                        Status::internal(format!(
-                            "could not stringify user id in request metadata, internal error {}",
-                            err
-                        ))
-                    })?
-                    .ok_or(Status::internal(
-                        "failed to get user id from request metadata",
-                    ))?,
-                query.project_id,
-            )
+            .get_access_by_uid_and_project_id(uid, query.project_id)
             .await
             .map_err(|err| Status::new(Code::Internal, err.to_string()))?
             .ok_or_else(|| {
@@ -181,12 +1019,7 @@ impl QueryControllerTrait for QueryController {
                 )
             })?;
 
-        if access.role != "Editor" {
-            return Err(Status::new(
-                Code::PermissionDenied,
-                "Role does not have permission to update query",
-            ));
-        }
+        self.check_query_mutation_allowed(uid, &access).await?;
 
         match self.contexts.query_context.delete(message.id).await {
             Ok(_) => Ok(Response::new(())),
@@ -217,10 +1050,110 @@ impl QueryControllerTrait for QueryController {
                 "failed to get user id from request metadata",
             ))?;
 
-        // Verify user access
+        let (project, _) =
+            load_project_with_role(&self.contexts, uid, message.project_id, Role::Reader).await?;
+
+        if let Some(settings) = &message.settings {
+            validate_settings(settings, &self.config.queries.allowed_settings)?;
+        }
+
+        // Get query from contexts, only from the project the requester was checked against
+        let query = self
+            .contexts
+            .query_context
+            .get_by_id(message.id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .filter(|query| query.project_id == project.id)
+            .ok_or_else(|| Status::new(Code::NotFound, "Query not found"))?;
+
+        let components_info = self.components_cache.get_or_parse(
+            project.id,
+            project.components_info,
+            self.config.projects.components_json_max_depth,
+        )?;
+
+        // Settings sent with the request replace the stored ones, which are used otherwise
+        let settings = match &message.settings {
+            Some(settings) => QuerySettings::from(settings),
+            None => QuerySettings::of_query(&query)?,
+        };
+
+        // Share the outcome of an identical run already in flight rather than running the query again
+        let (query_response, warning, correlation_id) = loop {
+            let leader = match self.in_flight_queries.join(
+                (project.id, query.id),
+                settings,
+                self.config.queries.deduplicate_concurrent,
+            ) {
+                InFlightRun::Leader(leader) => Some(leader),
+                InFlightRun::Alone => None,
+                InFlightRun::Follower(mut receiver) => {
+                    log::info!(
+                        "query {} is already running, waiting for its result",
+                        query.id
+                    );
+                    match receiver
+                        .wait_for(Option::is_some)
+                        .await
+                        .map(|outcome| outcome.clone())
+                    {
+                        Ok(Some(outcome)) => break outcome?,
+                        // The run was abandoned before it finished, so the query is started again
+                        _ => continue,
+                    }
+                }
+            };
+
+            let correlation_id = new_correlation_id();
+            let outcome = self
+                .run_query(
+                    uid,
+                    components_info.clone(),
+                    query.clone(),
+                    settings,
+                    &correlation_id,
+                )
+                .await
+                .map(|(query_response, warning)| (query_response, warning, correlation_id));
+
+            if let Some(leader) = leader {
+                leader.finish(outcome.clone());
+            }
+
+            break outcome?;
+        };
+
+        touch_in_use(&self.contexts, &self.config.locks, &request, project.id).await;
+
+        Ok(Response::new(SendQueryResponse {
+            response: Some(query_response),
+            correlation_id,
+            warning,
+        }))
+    }
+
+    async fn get_outdated_query_count(
+        &self,
+        request: Request<GetOutdatedQueryCountRequest>,
+    ) -> Result<Response<GetOutdatedQueryCountResponse>, Status> {
+        let project_id = request.get_ref().project_id;
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+
         self.contexts
             .access_context
-            .get_access_by_uid_and_project_id(uid, message.project_id)
+            .get_access_by_uid_and_project_id(uid, project_id)
             .await
             .map_err(|err| Status::new(Code::Internal, err.to_string()))?
             .ok_or_else(|| {
@@ -230,75 +1163,147 @@ impl QueryControllerTrait for QueryController {
                 )
             })?;
 
-        // Get project from contexts
-        let project = self
+        let count = self
             .contexts
-            .project_context
-            .get_by_id(message.project_id)
+            .query_context
+            .count_outdated_queries(project_id)
             .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
-            .ok_or_else(|| Status::new(Code::NotFound, "Model not found"))?;
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
 
-        // Get query from contexts
-        let query = self
+        Ok(Response::new(GetOutdatedQueryCountResponse { count }))
+    }
+
+    async fn send_outdated_queries(
+        &self,
+        request: Request<SendOutdatedQueriesRequest>,
+    ) -> Result<Response<SendOutdatedQueriesResponse>, Status> {
+        let project_id = request.get_ref().project_id;
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+
+        let (project, _) =
+            load_project_with_role(&self.contexts, uid, project_id, Role::Reader).await?;
+
+        let (max_age, now) = (self.config.queries.result_max_age, Utc::now().naive_utc());
+        let outdated_queries: Vec<query::Model> = self
             .contexts
             .query_context
-            .get_by_id(message.id)
+            .get_all_by_project_id(project_id, None)
             .await
             .map_err(|err| Status::new(Code::Internal, err.to_string()))?
-            .ok_or_else(|| Status::new(Code::NotFound, "Query not found"))?;
+            .into_iter()
+            .filter(|query| query.outdated || result_is_stale(query, max_age, now))
+            .collect();
 
-        // Construct query request to send to Reveaal
-        let query_request = Request::new(QueryRequest {
-            user_id: uid,
-            query_id: message.id,
-            query: query.string.clone(),
-            components_info: serde_json::from_value(project.components_info).map_err(|err| {
+        if outdated_queries.is_empty() {
+            return Ok(Response::new(SendOutdatedQueriesResponse {
+                results: vec![],
+            }));
+        }
+
+        let components_info = self.components_cache.get_or_parse(
+            project.id,
+            project.components_info,
+            self.config.projects.components_json_max_depth,
+        )?;
+
+        // A failing query is reported in its result instead of aborting the others
+        let mut results: Vec<OutdatedQueryResult> = stream::iter(outdated_queries)
+            .map(|query| {
+                let query_id = query.id;
+                let components_info = components_info.clone();
+                async move {
+                    let correlation_id = new_correlation_id();
+                    let outcome = match QuerySettings::of_query(&query) {
+                        Ok(settings) => {
+                            self.run_query(uid, components_info, query, settings, &correlation_id)
+                                .await
+                        }
+                        Err(status) => Err(status),
+                    };
+                    match outcome {
+                        Ok((_, None)) => OutdatedQueryResult {
+                            query_id,
+                            success: true,
+                            error: String::new(),
+                        },
+                        Ok((_, Some(warning))) => OutdatedQueryResult {
+                            query_id,
+                            success: false,
+                            error: warning,
+                        },
+                        Err(status) => OutdatedQueryResult {
+                            query_id,
+                            success: false,
+                            error: status.message().to_string(),
+                        },
+                    }
+                }
+            })
+            .buffer_unordered(self.config.queries.outdated_queries_concurrency)
+            .collect()
+            .await;
+
+        results.sort_by_key(|result| result.query_id);
+
+        Ok(Response::new(SendOutdatedQueriesResponse { results }))
+    }
+
+    async fn get_query_history(
+        &self,
+        request: Request<GetQueryHistoryRequest>,
+    ) -> Result<Response<GetQueryHistoryResponse>, Status> {
+        let message = request.get_ref();
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
                 Status::internal(format!(
-                    "error parsing query result, internal error: {}",
+                    "could not stringify user id in request metadata, internal error {}",
                     err
                 ))
-            })?,
-            settings: Default::default(), //TODO
-        });
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
 
-        // Run query on Reveaal
-        let query_result = self
-            .services
-            .reveaal_service
-            .send_query(query_request)
-            .await?;
+        let (project, _) =
+            load_project_with_role(&self.contexts, uid, message.project_id, Role::Reader).await?;
 
-        // Update query result in contexts
         self.contexts
             .query_context
-            .update(query::Model {
-                id: query.id,
-                string: query.string.clone(),
-                result: Some(
-                    serde_json::to_value(
-                        query_result
-                            .get_ref()
-                            .result
-                            .clone()
-                            .ok_or(Status::internal("failed to get query result"))?, //TODO better error message ?
-                    )
-                    .map_err(|err| {
-                        Status::internal(format!(
-                            "error parsing query result, internal error: {}",
-                            err
-                        ))
-                    })?,
-                ),
-                outdated: false,
-                project_id: query.project_id,
-            })
+            .get_by_id(message.query_id)
             .await
-            .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .filter(|query| query.project_id == project.id)
+            .ok_or_else(|| Status::new(Code::NotFound, "Query not found"))?;
 
-        Ok(Response::new(SendQueryResponse {
-            response: Some(query_result.into_inner()),
-        }))
+        let runs = self
+            .contexts
+            .query_result_context
+            .get_by_query_id(message.query_id)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .into_iter()
+            .map(|run| QueryRun {
+                id: run.id,
+                result: stored_result_to_protobuf(run.query_id, run.result),
+                last_run_ms: run.last_run_ms,
+                created_at: run.created_at.timestamp(),
+            })
+            .collect();
+
+        Ok(Response::new(GetQueryHistoryResponse { runs }))
     }
 }
 
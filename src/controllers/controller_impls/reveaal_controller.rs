@@ -1,19 +1,169 @@
+use crate::api::auth::RequestExt;
 use crate::api::server::protobuf::ecdar_backend_server::EcdarBackend;
 use crate::api::server::protobuf::{
+    CheckConsistencyRequest, CheckConsistencyResponse, ProxyReveaalRequest, ProxyReveaalResponse,
     QueryRequest, QueryResponse, SimulationStartRequest, SimulationStepRequest,
     SimulationStepResponse, UserTokenResponse,
 };
+use crate::config::Config;
+use crate::contexts::context_collection::ContextCollection;
+use crate::controllers::controller_impls::query_controller::{reveaal_error, ComponentsCache};
+use crate::controllers::controller_traits::ReveaalControllerTrait;
+use crate::controllers::error_details::{status_with_reason, ReasonCode};
+use crate::controllers::project_access::load_project_with_role;
+use crate::entities::role::Role;
 use crate::services::service_collection::ServiceCollection;
 use async_trait::async_trait;
-use tonic::{Request, Response, Status};
+use tonic::{Code, Request, Response, Status};
+
+/// Parses the value of `REVEAAL_PROXY_METHODS`, a comma separated list of Reveaal method names, e.g. `SendQuery`.
+/// # Errors
+/// Returns a description of the problem if an entry is not a method name.
+pub fn parse_proxy_methods(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|method| !method.is_empty())
+        .map(|method| {
+            let is_method_name = method.starts_with(|c: char| c.is_ascii_alphabetic())
+                && method
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            is_method_name
+                .then(|| method.to_string())
+                .ok_or_else(|| format!("'{}' is not a method name", method))
+        })
+        .collect()
+}
+
+/// Checks that `method` is one of the `allowed` methods.
+/// # Errors
+/// Returns a `PermissionDenied` status naming the method if it is not allowed.
+pub fn check_proxy_method(method: &str, allowed: &[String]) -> Result<(), Status> {
+    if allowed.iter().any(|allowed| allowed == method) {
+        Ok(())
+    } else {
+        Err(status_with_reason(
+            Code::PermissionDenied,
+            format!("The Reveaal method '{}' may not be called", method),
+            ReasonCode::DisallowedReveaalMethod,
+            Some("method"),
+        ))
+    }
+}
 
 pub struct ReveaalController {
+    contexts: ContextCollection,
     services: ServiceCollection,
+    config: Config,
+    components_cache: ComponentsCache,
 }
 
 impl ReveaalController {
-    pub fn new(services: ServiceCollection) -> Self {
-        Self { services }
+    pub fn new(contexts: ContextCollection, services: ServiceCollection, config: Config) -> Self {
+        Self {
+            contexts,
+            services,
+            config,
+            components_cache: ComponentsCache::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl ReveaalControllerTrait for ReveaalController {
+    async fn proxy(
+        &self,
+        request: Request<ProxyReveaalRequest>,
+    ) -> Result<Response<ProxyReveaalResponse>, Status> {
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+        let message = request.into_inner();
+
+        check_proxy_method(&message.method, &self.config.reveaal_proxy_methods)?;
+
+        // Calls are always made on behalf of a project, so they are limited to the projects the user can read
+        let project_id = message
+            .project_id
+            .ok_or_else(|| Status::invalid_argument("A project id is required"))?;
+        load_project_with_role(&self.contexts, uid, project_id, Role::Reader).await?;
+
+        let timeout = self.config.queries.reveaal_timeout;
+        let payload = tokio::time::timeout(
+            timeout,
+            self.services
+                .reveaal_service
+                .call_raw(&message.method, message.payload),
+        )
+        .await
+        .map_err(|_| {
+            Status::deadline_exceeded(format!(
+                "Reveaal did not respond within {} seconds",
+                timeout.as_secs()
+            ))
+        })??;
+
+        Ok(Response::new(ProxyReveaalResponse { payload }))
+    }
+
+    async fn check_consistency(
+        &self,
+        request: Request<CheckConsistencyRequest>,
+    ) -> Result<Response<CheckConsistencyResponse>, Status> {
+        let project_id = request.get_ref().project_id;
+
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+
+        let (project, _) =
+            load_project_with_role(&self.contexts, uid, project_id, Role::Reader).await?;
+
+        let Some(components_info) = self.components_cache.get_or_parse(
+            project.id,
+            project.components_info,
+            self.config.projects.components_json_max_depth,
+        )?
+        else {
+            return Ok(Response::new(CheckConsistencyResponse {
+                components: vec![],
+            }));
+        };
+
+        let timeout = self.config.queries.reveaal_timeout;
+        let components = tokio::time::timeout(
+            timeout,
+            self.services
+                .reveaal_service
+                .check_consistency(uid, components_info),
+        )
+        .await
+        .map_err(|_| {
+            Status::deadline_exceeded(format!(
+                "Reveaal did not respond within {} seconds",
+                timeout.as_secs()
+            ))
+        })?
+        .map_err(reveaal_error)?;
+
+        Ok(Response::new(CheckConsistencyResponse { components }))
     }
 }
 
@@ -58,3 +208,7 @@ impl EcdarBackend for ReveaalController {
             .await
     }
 }
+
+#[cfg(test)]
+#[path = "../../tests/controllers/reveaal_controller.rs"]
+mod tests;
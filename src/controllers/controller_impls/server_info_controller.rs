@@ -0,0 +1,88 @@
+use crate::api::maintenance;
+use crate::api::server::protobuf::GetServerInfoResponse;
+use crate::config::Config;
+use crate::controllers::controller_traits::ServerInfoControllerTrait;
+use async_trait::async_trait;
+use sea_orm::DbBackend;
+use tonic::{Request, Response, Status};
+
+/// The version of the API, as given in `Cargo.toml`.
+pub const API_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The commit of the protobuf repository the API is built from, or `unknown`.
+pub const PROTOBUF_VERSION: &str = env!("PROTOBUF_VERSION");
+
+/// Returns the names of the optional features that are currently enabled.
+pub fn enabled_features(config: &Config) -> Vec<String> {
+    let compression = config
+        .compression
+        .map(|encoding| format!("compression:{}", encoding));
+
+    [
+        maintenance::is_read_only().then(|| "read_only".to_string()),
+        config
+            .require_authentication
+            .then(|| "require_authentication".to_string()),
+        compression,
+        config.rate_limit.as_ref().map(|_| "rate_limit".to_string()),
+        config
+            .strict_unknown_fields
+            .then(|| "strict_unknown_fields".to_string()),
+        config
+            .locks
+            .same_user_takeover
+            .then(|| "same_user_lock_takeover".to_string()),
+        config
+            .legacy_not_found_on_empty_list
+            .then(|| "legacy_not_found_on_empty_list".to_string()),
+        (!config.users.allowed_email_domains.is_empty())
+            .then(|| "allowed_email_domains".to_string()),
+        (!config.allowed_origins.is_empty()).then(|| "allowed_origins".to_string()),
+        (!config.reveaal_proxy_methods.is_empty()).then(|| "reveaal_proxy".to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Returns the name of a database backend as reported to clients.
+pub fn database_backend_name(backend: DbBackend) -> &'static str {
+    match backend {
+        DbBackend::Postgres => "postgres",
+        DbBackend::Sqlite => "sqlite",
+        DbBackend::MySql => "mysql",
+    }
+}
+
+pub struct ServerInfoController {
+    config: Config,
+    database_backend: DbBackend,
+}
+
+impl ServerInfoController {
+    pub fn new(config: Config, database_backend: DbBackend) -> Self {
+        ServerInfoController {
+            config,
+            database_backend,
+        }
+    }
+}
+
+#[async_trait]
+impl ServerInfoControllerTrait for ServerInfoController {
+    async fn get_server_info(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<GetServerInfoResponse>, Status> {
+        Ok(Response::new(GetServerInfoResponse {
+            version: API_VERSION.to_string(),
+            protobuf_version: PROTOBUF_VERSION.to_string(),
+            features: enabled_features(&self.config),
+            database_backend: database_backend_name(self.database_backend).to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/controllers/server_info_controller.rs"]
+mod server_info_controller_tests;
@@ -1,22 +1,93 @@
 use crate::api::auth::{RequestExt, Token, TokenError, TokenType};
 use crate::api::server::protobuf::get_auth_token_request::{user_credentials, UserCredentials};
-use crate::api::server::protobuf::{GetAuthTokenRequest, GetAuthTokenResponse};
+use crate::api::server::protobuf::{
+    GetAuthTokenRequest, GetAuthTokenResponse, ListSessionsResponse, SessionInfo,
+};
+use crate::config::Config;
 use crate::contexts::context_collection::ContextCollection;
 use crate::controllers::controller_traits::SessionControllerTrait;
 use crate::entities::{session, user};
 use crate::services::service_collection::ServiceCollection;
 use async_trait::async_trait;
+use chrono::{Duration, Local, NaiveDateTime};
 use sea_orm::DbErr;
+use std::str::FromStr;
 use tonic::{Code, Request, Response, Status};
 
+const DEFAULT_MAX_SESSIONS_PER_USER: u64 = 20;
+
+/// What happens when a user who already has the maximum number of sessions logs in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionLimitPolicy {
+    /// The least recently updated sessions are deleted to make room for the new one
+    EvictOldest,
+    /// The login is refused until the user logs out of another session
+    Reject,
+}
+
+impl FromStr for SessionLimitPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "evict_oldest" => Ok(SessionLimitPolicy::EvictOldest),
+            "reject" => Ok(SessionLimitPolicy::Reject),
+            _ => Err(format!(
+                "'{}' is not a session limit policy, expected 'evict_oldest' or 'reject'",
+                value
+            )),
+        }
+    }
+}
+
+/// How many sessions users may have and how long they last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionConfig {
+    /// The maximum number of sessions a single user may have at once, 20 by default. A limit of 0 disables it.
+    pub max_sessions_per_user: u64,
+    /// What happens when a user at the session limit logs in
+    pub limit_policy: SessionLimitPolicy,
+    /// How long a session may last from when it was created, however recently it was refreshed.
+    /// Sessions last as long as they are refreshed if it is `None`, the default.
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            max_sessions_per_user: DEFAULT_MAX_SESSIONS_PER_USER,
+            limit_policy: SessionLimitPolicy::EvictOldest,
+            max_lifetime: None,
+        }
+    }
+}
+
+/// Returns whether `session` has outlived `max_lifetime` at `now`.
+///
+/// A session without a creation time is taken to be created when it was last refreshed.
+pub fn session_outlived(
+    session: &session::Model,
+    max_lifetime: Option<Duration>,
+    now: NaiveDateTime,
+) -> bool {
+    max_lifetime.is_some_and(|max_lifetime| {
+        now - session.created_at.unwrap_or(session.updated_at) >= max_lifetime
+    })
+}
+
 pub struct SessionController {
     contexts: ContextCollection,
     services: ServiceCollection,
+    config: Config,
 }
 
 impl SessionController {
-    pub fn new(contexts: ContextCollection, services: ServiceCollection) -> Self {
-        Self { contexts, services }
+    pub fn new(contexts: ContextCollection, services: ServiceCollection, config: Config) -> Self {
+        Self {
+            contexts,
+            services,
+            config,
+        }
     }
 
     async fn user_from_user_credentials(
@@ -34,8 +105,71 @@ impl SessionController {
         }
     }
 
+    /// Makes room for a new session of the user if they are at the session limit.
+    /// Depending on the [`SessionLimitPolicy`], their least recently updated sessions are deleted,
+    /// along with their locks on projects, or the login is refused with `ResourceExhausted`.
+    async fn enforce_session_limit(&self, user_id: i32) -> Result<(), Status> {
+        let limit = self.config.sessions.max_sessions_per_user;
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let sessions = self
+            .contexts
+            .session_context
+            .get_by_user_id(user_id)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let excess = (sessions.len() as u64 + 1).saturating_sub(limit);
+        if excess == 0 {
+            return Ok(());
+        }
+
+        match self.config.sessions.limit_policy {
+            SessionLimitPolicy::Reject => Err(Status::resource_exhausted(format!(
+                "The user already has the maximum of {} sessions, log out of one of them first",
+                limit
+            ))),
+            SessionLimitPolicy::EvictOldest => {
+                for session in sessions.into_iter().take(excess as usize) {
+                    self.contexts
+                        .session_context
+                        .delete(session.id)
+                        .await
+                        .map_err(|err| Status::internal(err.to_string()))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Ends `session` if it has outlived `max_lifetime`, releasing its locks like a logout would.
+    /// # Errors
+    /// Returns `Unauthenticated` if the session was ended, so the user has to log in again.
+    async fn end_outlived_session(
+        &self,
+        session: &session::Model,
+        max_lifetime: Option<Duration>,
+    ) -> Result<(), Status> {
+        if !session_outlived(session, max_lifetime, Local::now().naive_local()) {
+            return Ok(());
+        }
+
+        self.contexts
+            .session_context
+            .delete(session.id)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Err(Status::unauthenticated(
+            "The session has reached its maximum lifetime, log in again",
+        ))
+    }
+
     /// Updates the session given by refresh token in the contexts.
     /// Returns the new access and refresh token i.e. a tuple `(Token, Token)` where the 0th element is the access token and the 1st element refresh token.
+    /// A session that has outlived [`SessionConfig::max_lifetime`] is ended instead of updated.
     pub async fn update_session(&self, refresh_token: String) -> Result<(Token, Token), Status> {
         let session = match self
             .contexts
@@ -52,6 +186,9 @@ impl SessionController {
             Err(err) => return Err(Status::internal(err.to_string())),
         };
 
+        self.end_outlived_session(&session, self.config.sessions.max_lifetime)
+            .await?;
+
         let uid = session.user_id.to_string();
 
         let access_token = Token::access(&uid)?;
@@ -63,6 +200,7 @@ impl SessionController {
                 id: session.id,
                 access_token: access_token.to_string(),
                 refresh_token: refresh_token.to_string(),
+                created_at: session.created_at,
                 updated_at: Default::default(),
                 user_id: session.user_id,
             })
@@ -102,6 +240,41 @@ impl SessionControllerTrait for SessionController {
         }
     }
 
+    async fn list_sessions(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal(
+                "failed to get user id from request metadata",
+            ))?;
+        let access_token = request.token_string().ok().flatten();
+
+        let sessions = self
+            .contexts
+            .session_context
+            .get_by_user_id(uid)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .into_iter()
+            .map(|session| SessionInfo {
+                id: session.id,
+                created_at: session.created_at.map(|created_at| created_at.timestamp()),
+                updated_at: session.updated_at.timestamp(),
+                current: access_token.as_deref() == Some(session.access_token.as_str()),
+            })
+            .collect();
+
+        Ok(Response::new(ListSessionsResponse { sessions }))
+    }
+
     async fn get_auth_token(
         &self,
         request: Request<GetAuthTokenRequest>,
@@ -159,6 +332,8 @@ impl SessionControllerTrait for SessionController {
                     return Err(Status::unauthenticated("Wrong username or password"));
                 }
 
+                self.enforce_session_limit(user.id).await?;
+
                 let uid = user.id.to_string();
 
                 let access_token = Token::access(&uid)?;
@@ -170,6 +345,7 @@ impl SessionControllerTrait for SessionController {
                         id: Default::default(),
                         access_token: access_token.to_string(),
                         refresh_token: refresh_token.to_string(),
+                        created_at: Default::default(),
                         updated_at: Default::default(),
                         user_id: uid.parse().map_err(|err| {
                             Status::internal(format!(
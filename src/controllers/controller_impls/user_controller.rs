@@ -1,25 +1,68 @@
 use crate::api::auth::RequestExt;
 use crate::api::server::protobuf::get_users_response::UserInfo;
+use crate::api::server::protobuf::resolve_user_request::Identifier;
 use crate::api::server::protobuf::{
-    CreateUserRequest, GetUsersRequest, GetUsersResponse, UpdateUserRequest,
+    CreateUserRequest, GetCurrentUserResponse, GetUsersRequest, GetUsersResponse,
+    ResolveUserRequest, ResolveUserResponse, UpdateUserRequest,
 };
+use crate::config::Config;
 use crate::contexts::context_collection::ContextCollection;
 use crate::controllers::controller_traits::UserControllerTrait;
+use crate::controllers::error_details::{status_with_reason, ReasonCode};
+use crate::controllers::sanitization::sanitize;
 use crate::entities::user;
 use crate::services::service_collection::ServiceCollection;
 use async_trait::async_trait;
+use migration::{DISPLAY_NAME_MAX_LENGTH, EMAIL_MAX_LENGTH, USERNAME_MAX_LENGTH};
 use regex::Regex;
-use sea_orm::SqlErr;
+use sea_orm::{DbErr, SqlErr};
 use tonic::{Code, Request, Response, Status};
 
+/// The rules users sign up and change their details by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserConfig {
+    /// The maximum length of a username, at most the size of the username column ([`USERNAME_MAX_LENGTH`])
+    pub username_max_length: usize,
+    /// The maximum length of an email address, at most the size of the email column ([`EMAIL_MAX_LENGTH`])
+    pub email_max_length: usize,
+    /// The email domains users may sign up with, in lowercase. An empty list, the default, allows every domain.
+    pub allowed_email_domains: Vec<String>,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        UserConfig {
+            username_max_length: USERNAME_MAX_LENGTH as usize,
+            email_max_length: EMAIL_MAX_LENGTH as usize,
+            allowed_email_domains: vec![],
+        }
+    }
+}
+
+/// Converts the requesting user to the response of `get_current_user`,
+/// falling back to the username if the user has not set a display name.
+pub(crate) fn current_user_to_protobuf(user: user::Model) -> GetCurrentUserResponse {
+    GetCurrentUserResponse {
+        id: user.id,
+        display_name: user.shown_name().to_string(),
+        username: user.username,
+        email: user.email,
+    }
+}
+
 pub struct UserController {
     contexts: ContextCollection,
     services: ServiceCollection,
+    config: Config,
 }
 
 impl UserController {
-    pub fn new(contexts: ContextCollection, services: ServiceCollection) -> Self {
-        UserController { contexts, services }
+    pub fn new(contexts: ContextCollection, services: ServiceCollection, config: Config) -> Self {
+        UserController {
+            contexts,
+            services,
+            config,
+        }
     }
 
     /// Returns true if the given email is a valid format.
@@ -30,13 +73,99 @@ impl UserController {
             .is_match(email)
     }
 
-    /// Returns true if the given username is a valid format, i.e. only contains letters and numbers and a length from 3 to 32.
+    /// Returns true if the given username is a valid format, i.e. only contains letters and numbers and is at least 3 long.
     #[allow(clippy::expect_used)]
     fn is_valid_username(&self, username: &str) -> bool {
-        Regex::new(r"^[a-zA-Z0-9_]{3,32}$")
+        Regex::new(r"^[a-zA-Z0-9_]{3,}$")
             .expect("failed to compile regex")
             .is_match(username)
     }
+
+    /// Checks that a username fits its column and has a valid format.
+    /// # Errors
+    /// Returns an `InvalidArgument` status if it does not.
+    fn validate_username(&self, username: &str) -> Result<(), Status> {
+        let max_length = self.config.users.username_max_length;
+        if username.chars().count() > max_length {
+            return Err(username_too_long(max_length));
+        }
+        if !self.is_valid_username(username) {
+            return Err(invalid_username());
+        }
+        Ok(())
+    }
+
+    /// Checks that an email fits its column, has a valid format and belongs to an allowed domain.
+    /// # Errors
+    /// Returns an `InvalidArgument` status if it does not.
+    fn validate_email(&self, email: &str) -> Result<(), Status> {
+        let max_length = self.config.users.email_max_length;
+        if email.chars().count() > max_length {
+            return Err(email_too_long(max_length));
+        }
+        if !self.is_valid_email(email) {
+            return Err(invalid_email());
+        }
+        if !is_allowed_email_domain(email, &self.config.users.allowed_email_domains) {
+            return Err(disallowed_email_domain());
+        }
+        Ok(())
+    }
+}
+
+/// Parses the value of `ALLOWED_EMAIL_DOMAINS`, a comma separated list of domains, returning them in lowercase.
+/// # Errors
+/// Returns a description of the problem if an entry is not a domain name, e.g. if it is an email address.
+pub fn parse_allowed_email_domains(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(|domain| domain.trim().to_lowercase())
+        .filter(|domain| !domain.is_empty())
+        .map(|domain| {
+            let is_domain = domain.split('.').all(|label| {
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+            if is_domain {
+                Ok(domain)
+            } else {
+                Err(format!("'{}' is not a domain name", domain))
+            }
+        })
+        .collect()
+}
+
+/// Returns true if the domain of `email` is one of `allowed_domains`, ignoring case,
+/// or if `allowed_domains` is empty. Subdomains of an allowed domain are not allowed themselves.
+pub fn is_allowed_email_domain(email: &str, allowed_domains: &[String]) -> bool {
+    if allowed_domains.is_empty() {
+        return true;
+    }
+    email.rsplit_once('@').is_some_and(|(_, domain)| {
+        let domain = domain.to_lowercase();
+        allowed_domains.iter().any(|allowed| *allowed == domain)
+    })
+}
+
+/// Trims a requested display name, returning `None` if it is empty so the display name is cleared.
+/// # Errors
+/// Returns an `InvalidArgument` status if the display name is longer than [`DISPLAY_NAME_MAX_LENGTH`]
+/// or contains control characters.
+pub fn parse_display_name(display_name: &str) -> Result<Option<String>, Status> {
+    let display_name = display_name.trim();
+
+    if display_name.chars().count() > DISPLAY_NAME_MAX_LENGTH as usize {
+        return Err(invalid_display_name(format!(
+            "Display name must be at most {} characters long",
+            DISPLAY_NAME_MAX_LENGTH
+        )));
+    }
+    if display_name.chars().any(char::is_control) {
+        return Err(invalid_display_name(
+            "Display name must not contain control characters".to_string(),
+        ));
+    }
+
+    Ok((!display_name.is_empty()).then(|| display_name.to_string()))
 }
 
 #[async_trait]
@@ -47,13 +176,8 @@ impl UserControllerTrait for UserController {
     ) -> Result<Response<()>, Status> {
         let message = request.into_inner().clone();
 
-        if !self.is_valid_username(message.clone().username.as_str()) {
-            return Err(Status::new(Code::InvalidArgument, "Invalid username"));
-        }
-
-        if !self.is_valid_email(message.clone().email.as_str()) {
-            return Err(Status::new(Code::InvalidArgument, "Invalid email"));
-        }
+        self.validate_username(&message.username)?;
+        self.validate_email(&message.email)?;
 
         let hashed_password = self
             .services
@@ -66,21 +190,13 @@ impl UserControllerTrait for UserController {
             username: message.clone().username,
             password: hashed_password,
             email: message.clone().email,
+            display_name: None,
         };
 
         match self.contexts.user_context.create(user).await {
             Ok(_) => Ok(Response::new(())),
-            Err(e) => match e.sql_err() {
-                Some(SqlErr::UniqueConstraintViolation(e)) => {
-                    let error_msg = match e.to_lowercase() {
-                        _ if e.contains("username") => "A user with that username already exists",
-                        _ if e.contains("email") => "A user with that email already exists",
-                        _ => "User already exists",
-                    };
-                    Err(Status::new(Code::AlreadyExists, error_msg))
-                }
-                _ => Err(Status::new(Code::Internal, "Could not create user")),
-            },
+            Err(e) => Err(duplicate_user_error(&e)
+                .unwrap_or_else(|| Status::new(Code::Internal, "Could not create user"))),
         }
     }
 
@@ -118,21 +234,15 @@ impl UserControllerTrait for UserController {
             id: uid,
             username: match message.clone().username {
                 Some(username) => {
-                    if self.is_valid_username(username.as_str()) {
-                        username
-                    } else {
-                        return Err(Status::new(Code::InvalidArgument, "Invalid username"));
-                    }
+                    self.validate_username(&username)?;
+                    username
                 }
                 None => user.username,
             },
             email: match message.clone().email {
                 Some(email) => {
-                    if self.is_valid_email(email.as_str()) {
-                        email
-                    } else {
-                        return Err(Status::new(Code::InvalidArgument, "Invalid email"));
-                    }
+                    self.validate_email(&email)?;
+                    email
                 }
                 None => user.email,
             },
@@ -144,15 +254,50 @@ impl UserControllerTrait for UserController {
                     .map_err(|_err| Status::internal("failed to hash password"))?,
                 None => user.password,
             },
+            display_name: match message.display_name {
+                Some(display_name) => parse_display_name(&display_name)?,
+                None => user.display_name,
+            },
         };
 
         // Update user in contexts
         match self.contexts.user_context.update(new_user).await {
             Ok(_) => Ok(Response::new(())),
-            Err(error) => Err(Status::new(Code::Internal, error.to_string())),
+            Err(error) => Err(duplicate_user_error(&error)
+                .unwrap_or_else(|| Status::new(Code::Internal, sanitize(&error.to_string())))),
         }
     }
 
+    /// Returns the user making the request.
+    /// The display name falls back to the username if the user has not set one.
+    /// # Errors
+    /// Returns an error if the user does not exist or
+    /// if the uid could not be parsed from the request metadata.
+    async fn get_current_user(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<GetCurrentUserResponse>, Status> {
+        let uid = request
+            .uid()
+            .map_err(|err| {
+                Status::internal(format!(
+                    "could not stringify user id in request metadata, internal error {}",
+                    err
+                ))
+            })?
+            .ok_or(Status::internal("Could not get uid from request metadata"))?;
+
+        let user = self
+            .contexts
+            .user_context
+            .get_by_id(uid)
+            .await
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))?
+            .ok_or_else(|| Status::new(Code::NotFound, "No user found with given uid"))?;
+
+        Ok(Response::new(current_user_to_protobuf(user)))
+    }
+
     /// Deletes a user from the contexts.
     /// # Errors
     /// Returns an error if the contexts context fails to delete the user or
@@ -200,6 +345,112 @@ impl UserControllerTrait for UserController {
 
         Ok(Response::new(GetUsersResponse { users: users_info }))
     }
+
+    /// Resolves an exact username or email to the id and username of a user.
+    /// The email of the user is never returned.
+    /// # Errors
+    /// Returns `NotFound` if no user matches the given username or email.
+    async fn resolve_user(
+        &self,
+        request: Request<ResolveUserRequest>,
+    ) -> Result<Response<ResolveUserResponse>, Status> {
+        let user = match request.into_inner().identifier {
+            Some(Identifier::Username(username)) => {
+                self.contexts.user_context.get_by_username(username).await
+            }
+            Some(Identifier::Email(email)) => self.contexts.user_context.get_by_email(email).await,
+            None => return Err(Status::invalid_argument("No username or email provided")),
+        }
+        .map_err(|err| Status::internal(err.to_string()))?
+        .ok_or_else(|| Status::not_found("No user found with given username or email"))?;
+
+        Ok(Response::new(ResolveUserResponse {
+            id: user.id,
+            username: user.username,
+        }))
+    }
+}
+
+fn invalid_username() -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        "Invalid username",
+        ReasonCode::InvalidUsername,
+        Some("username"),
+    )
+}
+
+fn invalid_display_name(message: String) -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        message,
+        ReasonCode::InvalidDisplayName,
+        Some("display_name"),
+    )
+}
+
+fn username_too_long(max_length: usize) -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        format!("Username must be at most {} characters long", max_length),
+        ReasonCode::InvalidUsername,
+        Some("username"),
+    )
+}
+
+fn email_too_long(max_length: usize) -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        format!("Email must be at most {} characters long", max_length),
+        ReasonCode::InvalidEmail,
+        Some("email"),
+    )
+}
+
+fn disallowed_email_domain() -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        "Email addresses from this domain are not allowed",
+        ReasonCode::DisallowedEmailDomain,
+        Some("email"),
+    )
+}
+
+fn invalid_email() -> Status {
+    status_with_reason(
+        Code::InvalidArgument,
+        "Invalid email",
+        ReasonCode::InvalidEmail,
+        Some("email"),
+    )
+}
+
+/// Converts a unique constraint violation on the user table to an `AlreadyExists` status
+/// naming the duplicated field, or returns `None` for any other error.
+fn duplicate_user_error(error: &DbErr) -> Option<Status> {
+    match error.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(e)) => Some(match e.to_lowercase() {
+            _ if e.contains("username") => status_with_reason(
+                Code::AlreadyExists,
+                "A user with that username already exists",
+                ReasonCode::DuplicateUsername,
+                Some("username"),
+            ),
+            _ if e.contains("email") => status_with_reason(
+                Code::AlreadyExists,
+                "A user with that email already exists",
+                ReasonCode::DuplicateEmail,
+                Some("email"),
+            ),
+            _ => status_with_reason(
+                Code::AlreadyExists,
+                "User already exists",
+                ReasonCode::DuplicateUser,
+                None,
+            ),
+        }),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
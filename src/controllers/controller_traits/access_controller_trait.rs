@@ -1,6 +1,7 @@
 use crate::api::server::protobuf::{
-    CreateAccessRequest, DeleteAccessRequest, ListAccessInfoRequest, ListAccessInfoResponse,
-    UpdateAccessRequest,
+    CreateAccessRequest, DeleteAccessRequest, GetAccessesRequest, GetAccessesResponse,
+    ListAccessInfoRequest, ListAccessInfoResponse, RemoveCollaboratorRequest, UpdateAccessRequest,
+    UpdateAccessesRequest, UpdateAccessesResponse,
 };
 use async_trait::async_trait;
 use tonic::{Request, Response, Status};
@@ -15,8 +16,12 @@ pub trait AccessControllerTrait: Send + Sync {
         request: Request<ListAccessInfoRequest>,
     ) -> Result<Response<ListAccessInfoResponse>, Status>;
     /// Creates an access in the contexts.
+    ///
+    /// If the user already has an access on the project, the request is rejected or the role
+    /// of the existing access is updated, depending on `DUPLICATE_ACCESS_POLICY`.
     /// # Errors
-    /// Returns an error if the contexts context fails to create the access
+    /// Returns an error if the contexts context fails to create the access,
+    /// or `AlreadyExists` if the user already has an access and the policy is `reject`.
     async fn create_access(
         &self,
         request: Request<CreateAccessRequest>,
@@ -34,6 +39,30 @@ pub trait AccessControllerTrait: Send + Sync {
         request: Request<UpdateAccessRequest>,
     ) -> Result<Response<()>, Status>;
 
+    /// Updates the roles of several accesses on a single project at once.
+    ///
+    /// The project is the one of the first known access, on which the requester must be an Editor.
+    /// Each entry is reported as a success or failure, in the order of the request. Unknown access ids,
+    /// accesses on other projects, invalid roles and the owner's access fail individually,
+    /// while all valid changes are applied in one transaction.
+    /// # Errors
+    /// Returns an error if the requester is not an Editor on the project.
+    async fn update_accesses(
+        &self,
+        request: Request<UpdateAccessesRequest>,
+    ) -> Result<Response<UpdateAccessesResponse>, Status>;
+
+    /// Returns the access records with the given ids together with the username of their user.
+    ///
+    /// Accesses on projects where the requester is not an Editor are left out,
+    /// as are unknown ids.
+    /// # Errors
+    /// Returns an error if more than `MAX_ACCESS_BATCH_SIZE` ids are requested.
+    async fn get_accesses(
+        &self,
+        request: Request<GetAccessesRequest>,
+    ) -> Result<Response<GetAccessesResponse>, Status>;
+
     /// Deletes the an Access from the contexts. This has no sideeffects.
     ///
     /// # Errors
@@ -42,4 +71,14 @@ pub trait AccessControllerTrait: Send + Sync {
         &self,
         request: Request<DeleteAccessRequest>,
     ) -> Result<Response<()>, Status>;
+
+    /// Deletes a collaborator's access to a project, finding the user by id, username or email.
+    ///
+    /// # Errors
+    /// This function will return an error if the requester is not an Editor on the project,
+    /// if the user is unknown or not a collaborator, or if the user owns the project.
+    async fn remove_collaborator(
+        &self,
+        request: Request<RemoveCollaboratorRequest>,
+    ) -> Result<Response<()>, Status>;
 }
@@ -1,11 +1,15 @@
 mod access_controller_trait;
 mod project_controller_trait;
 mod query_controller_trait;
+mod reveaal_controller_trait;
+mod server_info_controller_trait;
 mod session_controller_trait;
 mod user_controller_trait;
 
 pub use access_controller_trait::AccessControllerTrait;
 pub use project_controller_trait::ProjectControllerTrait;
 pub use query_controller_trait::QueryControllerTrait;
+pub use reveaal_controller_trait::ReveaalControllerTrait;
+pub use server_info_controller_trait::ServerInfoControllerTrait;
 pub use session_controller_trait::SessionControllerTrait;
 pub use user_controller_trait::UserControllerTrait;
@@ -1,6 +1,11 @@
 use crate::api::server::protobuf::{
-    CreateProjectRequest, CreateProjectResponse, DeleteProjectRequest, GetProjectRequest,
-    GetProjectResponse, ListProjectsInfoResponse, UpdateProjectRequest,
+    CreateProjectRequest, CreateProjectResponse, CreateProjectWithQueriesRequest,
+    CreateProjectWithQueriesResponse, DeleteProjectRequest, DiffProjectRequest,
+    DiffProjectResponse, GetDashboardResponse, GetLockStatusRequest, GetLockStatusResponse,
+    GetProjectHistoryRequest, GetProjectHistoryResponse, GetProjectRequest, GetProjectResponse,
+    HeartbeatRequest, ListMyLocksResponse, ListMyRolesRequest, ListMyRolesResponse,
+    ListProjectsInfoRequest, ListProjectsInfoResponse, ListProjectsRequest, ListProjectsResponse,
+    RequestDeleteRequest, RequestDeleteResponse, UpdateProjectRequest,
 };
 use async_trait::async_trait;
 use tonic::{Request, Response, Status};
@@ -11,29 +16,105 @@ pub trait ProjectControllerTrait: Send + Sync {
     ///
     /// If the project is not in use, it will now be in use by the requester's session,
     /// given that they are an Editor.
+    /// A project missing its in use row gets a new, unlocked one unless `RECREATE_MISSING_IN_USE` is `false`.
+    ///
+    /// If the request's `known_version` matches the project's current version,
+    /// the response only sets `not_modified` and leaves out the project and its queries.
+    ///
+    /// If the request's `include_query_results` is false, the results of the queries are left out.
     async fn get_project(
         &self,
         request: Request<GetProjectRequest>,
     ) -> Result<Response<GetProjectResponse>, Status>;
 
     /// Creates a project from [`CreateProjectRequest`]
+    ///
+    /// If no components info is given, the project starts from the default components info template.
     /// # Errors
-    /// Errors on invalid JSON, invalid user id or if a project already exists
+    /// Errors on malformed components info, invalid user id or if a project already exists
     async fn create_project(
         &self,
         request: Request<CreateProjectRequest>,
     ) -> Result<Response<CreateProjectResponse>, Status>;
 
+    /// Creates a project from [`CreateProjectWithQueriesRequest`] together with its initial queries,
+    /// all in a single transaction.
+    /// # Errors
+    /// Errors on the same conditions as [`ProjectControllerTrait::create_project`] or on an invalid query string,
+    /// in which case nothing is created.
+    async fn create_project_with_queries(
+        &self,
+        request: Request<CreateProjectWithQueriesRequest>,
+    ) -> Result<Response<CreateProjectWithQueriesResponse>, Status>;
+
     /// Updates a Model in the contexts given its id.
     ///
+    /// Name, owner and component changes are logged in the project history together with the update.
+    /// When the components change, the queries that reference a changed component are marked as outdated,
+    /// or every query if it cannot be told which components changed.
+    /// Only the owner may change the owner, and only to an existing user who, unless disabled,
+    /// already has access to the project.
+    ///
     /// # Errors
-    /// This function will return an error if the project does not exist in the contexts
-    /// or if the user does not have access to the project with role 'Editor'.
+    /// This function will return an error if the project does not exist in the contexts,
+    /// if the user does not have access to the project with role 'Editor',
+    /// or if the owner is changed to a user who may not own the project.
     async fn update_project(
         &self,
         request: Request<UpdateProjectRequest>,
     ) -> Result<Response<()>, Status>;
 
+    /// Keeps the requester's lock on a project alive by refreshing its latest activity.
+    ///
+    /// # Errors
+    /// This function will return an error if the user is not an Editor on the project
+    /// or if their session does not currently hold the lock on the project.
+    async fn heartbeat(&self, request: Request<HeartbeatRequest>) -> Result<Response<()>, Status>;
+
+    /// Gets the history of changes to a project, newest first.
+    ///
+    /// # Errors
+    /// This function will return an error if the user does not have access to the project.
+    async fn get_project_history(
+        &self,
+        request: Request<GetProjectHistoryRequest>,
+    ) -> Result<Response<GetProjectHistoryResponse>, Status>;
+
+    /// Compares the components of two versions of a project, returning the names of the components
+    /// that were added, removed or modified between them.
+    ///
+    /// A version is the id of a `ComponentsChanged` event in the project's history.
+    /// # Errors
+    /// Returns `PermissionDenied` if the user cannot read the project, and `NotFound` if either version
+    /// does not exist for the project, for example because it has been pruned from the history.
+    async fn diff_project(
+        &self,
+        request: Request<DiffProjectRequest>,
+    ) -> Result<Response<DiffProjectResponse>, Status>;
+
+    /// Gets whether a project is locked for editing, by which user and until when, without claiming the lock.
+    ///
+    /// The lock is reported as `expiring` once its session has been inactive for `IN_USE_WARN_MINUTES`.
+    /// `expires_at` is when any session may take the lock, which includes the `IN_USE_GRACE_MINUTES`
+    /// after the lock duration during which only its holder can use it.
+    ///
+    /// # Errors
+    /// This function will return an error if the project does not exist or the user does not have access to it.
+    async fn get_lock_status(
+        &self,
+        request: Request<GetLockStatusRequest>,
+    ) -> Result<Response<GetLockStatusResponse>, Status>;
+
+    /// Issues a short-lived token confirming that the user wants to delete a project.
+    /// When `REQUIRE_DELETE_CONFIRMATION` is enabled, `delete_project` only accepts the request with this token.
+    ///
+    /// # Errors
+    /// This function will return an error if the project does not exist or if the user is not the project owner.
+    async fn request_delete(
+        &self,
+        request: Request<RequestDeleteRequest>,
+    ) -> Result<Response<RequestDeleteResponse>, Status>;
+
     /// Deletes a Model from the contexts.
     ///
     /// # Errors
@@ -44,8 +125,50 @@ pub trait ProjectControllerTrait: Send + Sync {
         request: Request<DeleteProjectRequest>,
     ) -> Result<Response<()>, Status>;
 
+    /// Lists a page of the projects that the requester has access to.
+    /// # Errors
+    /// This function will return an error if the requester does not have access to any projects.
     async fn list_projects_info(
         &self,
-        request: Request<()>,
+        request: Request<ListProjectsInfoRequest>,
     ) -> Result<Response<ListProjectsInfoResponse>, Status>;
+
+    /// Lists a page of the requester's role on every project they have access to,
+    /// and whether they own it, ordered by project name.
+    ///
+    /// If the requester has no projects, an empty list is returned.
+    async fn list_my_roles(
+        &self,
+        request: Request<ListMyRolesRequest>,
+    ) -> Result<Response<ListMyRolesResponse>, Status>;
+
+    /// Lists the projects locked by any of the requester's sessions, with the time each lock expires,
+    /// ordered by project id. Expired locks are left out.
+    ///
+    /// If the requester holds no locks, an empty list is returned.
+    async fn list_my_locks(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<ListMyLocksResponse>, Status>;
+
+    /// Returns everything the frontend needs on its first load in a single call:
+    /// the requester's profile, the first page of their projects and the locks they hold.
+    ///
+    /// The size of the project page is set by `DASHBOARD_PAGE_SIZE`.
+    /// Unlike `list_projects_info`, an empty project list is never answered with `NotFound`.
+    /// # Errors
+    /// This function will return an error if the requester does not exist.
+    async fn get_dashboard(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<GetDashboardResponse>, Status>;
+
+    /// Lists a page of the full projects, including their queries, that the requester has access to.
+    ///
+    /// Unlike [`get_project`](Self::get_project), this never marks a project as in use by the requester.
+    /// If the requester has no projects, an empty list is returned.
+    async fn list_projects(
+        &self,
+        request: Request<ListProjectsRequest>,
+    ) -> Result<Response<ListProjectsResponse>, Status>;
 }
@@ -1,5 +1,8 @@
 use crate::api::server::protobuf::{
-    CreateQueryRequest, DeleteQueryRequest, SendQueryRequest, SendQueryResponse, UpdateQueryRequest,
+    CreateQueryRequest, DeleteQueryRequest, GetOutdatedQueryCountRequest,
+    GetOutdatedQueryCountResponse, GetQueryHistoryRequest, GetQueryHistoryResponse,
+    SendOutdatedQueriesRequest, SendOutdatedQueriesResponse, SendQueryRequest, SendQueryResponse,
+    UpdateQueryRequest,
 };
 use async_trait::async_trait;
 use tonic::{Request, Response, Status};
@@ -7,14 +10,20 @@ use tonic::{Request, Response, Status};
 #[async_trait]
 pub trait QueryControllerTrait: Send + Sync {
     /// Creates a query in the contexts
+    ///
+    /// Creating, updating and deleting queries requires the role set in `QUERY_MUTATION_MIN_ROLE`,
+    /// `Editor` by default, or ownership of the project if it is set to `Owner`.
     /// # Errors
-    /// Returns an error if the contexts context fails to create the query or
+    /// Returns an error if the contexts context fails to create the query,
+    /// `PermissionDenied` if the user may not change the project's queries, or
+    /// `ResourceExhausted` if the project already holds `MAX_QUERIES_PER_PROJECT` queries.
     async fn create_query(
         &self,
         request: Request<CreateQueryRequest>,
     ) -> Result<Response<()>, Status>;
 
     /// Endpoint for updating a query record.
+    /// Changing the query string marks its result outdated, while changing only the title does not.
     /// # Errors
     /// Errors on non existent entity, parsing error or invalid rights
     async fn update_query(
@@ -33,9 +42,51 @@ pub trait QueryControllerTrait: Send + Sync {
     /// Sends a query to be run on Reveaal.
     /// After query is run the result is stored in the contexts.
     ///  
-    /// Returns the response that is received from Reveaal.
+    /// Returns the response that is received from Reveaal, along with the correlation id
+    /// that the run is logged with in both the API and Reveaal.
+    /// If the result cannot be saved, even after retrying, the response still carries it along with a `warning`.
+    ///
+    /// The request's `settings` are passed on to Reveaal, but may only change the settings
+    /// listed in `REVEAAL_ALLOWED_SETTINGS` from their defaults. The settings are stored with the result,
+    /// and a request without settings runs the query with the stored ones, or the defaults if there are none.
+    ///
+    /// Calls for a query that is already running with the same settings wait for that run and return its
+    /// response, correlation id and error, unless `DEDUPLICATE_CONCURRENT_QUERIES` is set to `false`.
+    /// # Errors
+    /// Returns `InvalidArgument` if the settings are not allowed,
+    /// `DeadlineExceeded` if Reveaal does not respond within `REVEAAL_TIMEOUT_SECS`,
+    /// `Unavailable` if Reveaal cannot be reached, and `Internal` if Reveaal returns a result that cannot be stored.
+    /// In all of these cases the stored result is left untouched.
     async fn send_query(
         &self,
         request: Request<SendQueryRequest>,
     ) -> Result<Response<SendQueryResponse>, Status>;
+
+    /// Returns the number of outdated queries in a project, i.e. queries that need to be run again.
+    /// # Errors
+    /// Returns `PermissionDenied` if the user does not have access to the project.
+    async fn get_outdated_query_count(
+        &self,
+        request: Request<GetOutdatedQueryCountRequest>,
+    ) -> Result<Response<GetOutdatedQueryCountResponse>, Status>;
+
+    /// Runs every outdated or expired query in a project on Reveaal, at most `OUTDATED_QUERIES_CONCURRENCY` at a time,
+    /// with the settings stored for each, and stores their results.
+    /// A query that fails is reported in its result and does not stop the others.
+    /// # Errors
+    /// Returns `NotFound` if the project does not exist and `PermissionDenied` if the user does not have access to it.
+    async fn send_outdated_queries(
+        &self,
+        request: Request<SendOutdatedQueriesRequest>,
+    ) -> Result<Response<SendOutdatedQueriesResponse>, Status>;
+
+    /// Returns the recent results of a query, newest first, each with the time it was computed.
+    /// At most `QUERY_RESULT_HISTORY_DEPTH` results are kept per query.
+    /// # Errors
+    /// Returns `PermissionDenied` if the user does not have access to the project
+    /// and `NotFound` if the query does not belong to it.
+    async fn get_query_history(
+        &self,
+        request: Request<GetQueryHistoryRequest>,
+    ) -> Result<Response<GetQueryHistoryResponse>, Status>;
 }
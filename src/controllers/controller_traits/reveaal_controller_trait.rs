@@ -0,0 +1,35 @@
+use crate::api::server::protobuf::ecdar_backend_server::EcdarBackend;
+use crate::api::server::protobuf::{
+    CheckConsistencyRequest, CheckConsistencyResponse, ProxyReveaalRequest, ProxyReveaalResponse,
+};
+use async_trait::async_trait;
+use tonic::{Request, Response, Status};
+
+#[async_trait]
+pub trait ReveaalControllerTrait: EcdarBackend {
+    /// Passes a call to the Reveaal RPC named in the request on to Reveaal, and returns its response,
+    /// so Reveaal features can be used before the API has endpoints of its own for them.
+    ///
+    /// Both the request and the response are encoded protobuf messages, which are not inspected.
+    /// Only the methods listed in `REVEAAL_PROXY_METHODS` may be called, and the request must name a project
+    /// on which the user has at least the `Reader` role.
+    /// Reveaal must answer within `REVEAAL_TIMEOUT_SECS`, as for queries.
+    /// # Errors
+    /// Returns `InvalidArgument` if no project is named, `PermissionDenied` if the method is not allowed
+    /// or the user may not read the project, `DeadlineExceeded` if Reveaal does not answer in time,
+    /// and otherwise the error of Reveaal.
+    async fn proxy(
+        &self,
+        request: Request<ProxyReveaalRequest>,
+    ) -> Result<Response<ProxyReveaalResponse>, Status>;
+
+    /// Checks each component of a project for consistency on Reveaal, without running or storing any query,
+    /// and returns the result for every component.
+    /// # Errors
+    /// Returns `PermissionDenied` if the user does not have access to the project, `DeadlineExceeded`
+    /// if Reveaal does not respond within `REVEAAL_TIMEOUT_SECS` and `Unavailable` if Reveaal cannot be reached.
+    async fn check_consistency(
+        &self,
+        request: Request<CheckConsistencyRequest>,
+    ) -> Result<Response<CheckConsistencyResponse>, Status>;
+}
@@ -0,0 +1,15 @@
+use crate::api::server::protobuf::GetServerInfoResponse;
+use async_trait::async_trait;
+use tonic::{Request, Response, Status};
+
+#[async_trait]
+pub trait ServerInfoControllerTrait: Send + Sync {
+    /// Returns the version of the API and of the protobuf it is built from,
+    /// the optional features that are enabled, and the database backend in use.
+    ///
+    /// The endpoint is public, so the response must never contain secrets such as connection strings.
+    async fn get_server_info(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<GetServerInfoResponse>, Status>;
+}
@@ -1,14 +1,26 @@
-use crate::api::server::protobuf::{GetAuthTokenRequest, GetAuthTokenResponse};
+use crate::api::server::protobuf::{
+    GetAuthTokenRequest, GetAuthTokenResponse, ListSessionsResponse,
+};
 use async_trait::async_trait;
 use tonic::{Request, Response, Status};
 
 #[async_trait]
 pub trait SessionControllerTrait: Send + Sync {
     /// Deletes the requester's session, found by their access token.
+    /// The project locks held by the session are released with it, as the in use rows of a session
+    /// are deleted along with it by the database.
     ///  
     /// Returns the response that is received from Reveaal.
     async fn delete_session(&self, _request: Request<()>) -> Result<Response<()>, Status>;
 
+    /// Lists the sessions of the requester, least recently updated first, with when each was created
+    /// and last refreshed, and whether it is the session the request is made with.
+    /// The tokens of the sessions are never returned.
+    async fn list_sessions(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<ListSessionsResponse>, Status>;
+
     /// This method is used to get a new access and refresh token for a user.
     ///
     /// # Errors
@@ -1,5 +1,6 @@
 use crate::api::server::protobuf::{
-    CreateUserRequest, GetUsersRequest, GetUsersResponse, UpdateUserRequest,
+    CreateUserRequest, GetCurrentUserResponse, GetUsersRequest, GetUsersResponse,
+    ResolveUserRequest, ResolveUserResponse, UpdateUserRequest,
 };
 use async_trait::async_trait;
 use tonic::{Request, Response, Status};
@@ -14,9 +15,17 @@ pub trait UserControllerTrait: Send + Sync {
         &self,
         request: Request<UpdateUserRequest>,
     ) -> Result<Response<()>, Status>;
+    async fn get_current_user(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<GetCurrentUserResponse>, Status>;
     async fn delete_user(&self, request: Request<()>) -> Result<Response<()>, Status>;
     async fn get_users(
         &self,
         request: Request<GetUsersRequest>,
     ) -> Result<Response<GetUsersResponse>, Status>;
+    async fn resolve_user(
+        &self,
+        request: Request<ResolveUserRequest>,
+    ) -> Result<Response<ResolveUserResponse>, Status>;
 }
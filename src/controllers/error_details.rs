@@ -0,0 +1,98 @@
+//! Machine-readable details attached to error statuses.
+//!
+//! Clients can decode an [`ErrorDetail`] from a status' details and match on its reason code
+//! instead of on the free-text message, which may change.
+
+use crate::api::server::protobuf::ErrorDetail;
+use crate::contexts::sqlite_lock::is_database_locked;
+use prost::bytes::Bytes;
+use prost::Message;
+use sea_orm::DbErr;
+use tonic::{Code, Status};
+
+/// The stable reasons a request can be rejected for.
+///
+/// The string returned by [`ReasonCode::as_str`] is part of the API and must not change.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReasonCode {
+    InvalidUsername,
+    InvalidEmail,
+    InvalidDisplayName,
+    DisallowedEmailDomain,
+    DuplicateUsername,
+    DuplicateEmail,
+    DuplicateUser,
+    InvalidProjectName,
+    DuplicateProjectName,
+    DuplicateProject,
+    UnknownOwner,
+    OwnerWithoutAccess,
+    InvalidComponentsInfo,
+    CorruptComponentsInfo,
+    InvalidQuery,
+    InvalidQueryTitle,
+    DisallowedSetting,
+    DisallowedReveaalMethod,
+    DatabaseBusy,
+    BackendUnavailable,
+}
+
+impl ReasonCode {
+    /// Returns the reason code as sent to clients
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasonCode::InvalidUsername => "INVALID_USERNAME",
+            ReasonCode::InvalidEmail => "INVALID_EMAIL",
+            ReasonCode::InvalidDisplayName => "INVALID_DISPLAY_NAME",
+            ReasonCode::DisallowedEmailDomain => "DISALLOWED_EMAIL_DOMAIN",
+            ReasonCode::DuplicateUsername => "DUPLICATE_USERNAME",
+            ReasonCode::DuplicateEmail => "DUPLICATE_EMAIL",
+            ReasonCode::DuplicateUser => "DUPLICATE_USER",
+            ReasonCode::InvalidProjectName => "INVALID_PROJECT_NAME",
+            ReasonCode::DuplicateProjectName => "DUPLICATE_PROJECT_NAME",
+            ReasonCode::DuplicateProject => "DUPLICATE_PROJECT",
+            ReasonCode::UnknownOwner => "UNKNOWN_OWNER",
+            ReasonCode::OwnerWithoutAccess => "OWNER_WITHOUT_ACCESS",
+            ReasonCode::InvalidComponentsInfo => "INVALID_COMPONENTS_INFO",
+            ReasonCode::CorruptComponentsInfo => "CORRUPT_COMPONENTS_INFO",
+            ReasonCode::InvalidQuery => "INVALID_QUERY",
+            ReasonCode::InvalidQueryTitle => "INVALID_QUERY_TITLE",
+            ReasonCode::DisallowedSetting => "DISALLOWED_SETTING",
+            ReasonCode::DisallowedReveaalMethod => "DISALLOWED_REVEAAL_METHOD",
+            ReasonCode::DatabaseBusy => "DATABASE_BUSY",
+            ReasonCode::BackendUnavailable => "BACKEND_UNAVAILABLE",
+        }
+    }
+}
+
+/// Creates a status carrying an [`ErrorDetail`] with the given reason and, if any, the offending request field.
+pub fn status_with_reason(
+    code: Code,
+    message: impl Into<String>,
+    reason: ReasonCode,
+    field: Option<&str>,
+) -> Status {
+    let detail = ErrorDetail {
+        reason_code: reason.as_str().to_string(),
+        field: field.unwrap_or_default().to_string(),
+    };
+
+    Status::with_details(code, message, Bytes::from(detail.encode_to_vec()))
+}
+
+/// Maps an error caused by the database staying locked, even after retrying, to an `Unavailable` status.
+/// Returns `None` for any other error.
+pub fn database_busy_error(error: &DbErr) -> Option<Status> {
+    is_database_locked(error).then(|| {
+        status_with_reason(
+            Code::Unavailable,
+            "The database is busy, try again later",
+            ReasonCode::DatabaseBusy,
+            None,
+        )
+    })
+}
+
+#[cfg(test)]
+#[path = "../tests/controllers/error_details.rs"]
+mod tests;
@@ -1,3 +1,9 @@
 pub mod controller_collection;
 pub mod controller_impls;
 pub mod controller_traits;
+pub mod error_details;
+pub mod project_access;
+pub mod project_activity;
+pub mod query_invalidation;
+pub mod response_size;
+pub mod sanitization;
@@ -0,0 +1,44 @@
+//! Shared guard for endpoints that operate on a single project.
+
+use crate::contexts::context_collection::ContextCollection;
+use crate::entities::role::Role;
+use crate::entities::{access, project};
+use tonic::Status;
+
+/// Loads a project together with the user's access to it, checking that the access grants at least `min_role`.
+/// # Errors
+/// Returns `NotFound` if the project does not exist, `PermissionDenied` if the user has no access
+/// to the project or a role below `min_role`, and `Internal` if a context fails.
+pub async fn load_project_with_role(
+    contexts: &ContextCollection,
+    uid: i32,
+    project_id: i32,
+    min_role: Role,
+) -> Result<(project::Model, access::Model), Status> {
+    let project = contexts
+        .project_context
+        .get_by_id(project_id)
+        .await
+        .map_err(|err| Status::internal(err.to_string()))?
+        .ok_or_else(|| Status::not_found("No project found with given id"))?;
+
+    let access = contexts
+        .access_context
+        .get_access_by_uid_and_project_id(uid, project_id)
+        .await
+        .map_err(|err| Status::internal(err.to_string()))?
+        .ok_or_else(|| Status::permission_denied("User does not have access to project"))?;
+
+    if !access.has_role_at_least(min_role) {
+        return Err(Status::permission_denied(format!(
+            "Role {} or higher is required for this action",
+            min_role
+        )));
+    }
+
+    Ok((project, access))
+}
+
+#[cfg(test)]
+#[path = "../tests/controllers/project_access.rs"]
+mod tests;
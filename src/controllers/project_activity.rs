@@ -0,0 +1,71 @@
+//! Keeping the lock on a project alive while its holder works on it.
+//!
+//! Besides `update_project` and `heartbeat`, which refresh the lock themselves, these endpoints count as
+//! activity on their project: `create_query`, `update_query` and `send_query`.
+
+use crate::api::auth::{RequestExt, TokenType};
+use crate::contexts::context_collection::ContextCollection;
+use crate::controllers::controller_impls::project_controller::LockConfig;
+use crate::entities::in_use;
+use chrono::Utc;
+use sea_orm::DbErr;
+use tonic::Request;
+
+/// Refreshes the `latest_activity` of the lock on `project_id` if the session of the request holds it,
+/// judged by the lock settings in `locks`.
+///
+/// Does nothing if the request has no access token, or if its session does not hold the lock,
+/// since activity never claims a lock. A lock in its grace period is still held by its session. The activity itself has already succeeded when this is called,
+/// so a failure to refresh the lock is logged instead of returned.
+pub async fn touch_in_use<T>(
+    contexts: &ContextCollection,
+    locks: &LockConfig,
+    request: &Request<T>,
+    project_id: i32,
+) {
+    let Ok(Some(token)) = request.token_string() else {
+        return;
+    };
+
+    if let Err(err) = refresh_held_lock(contexts, locks, token, project_id).await {
+        log::warn!(
+            "failed to refresh the lock on project {}: {}",
+            project_id,
+            err
+        );
+    }
+}
+
+async fn refresh_held_lock(
+    contexts: &ContextCollection,
+    locks: &LockConfig,
+    token: String,
+    project_id: i32,
+) -> Result<(), DbErr> {
+    let Some(session) = contexts
+        .session_context
+        .get_by_token(TokenType::AccessToken, token)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let Some(in_use) = contexts.in_use_context.get_by_id(project_id).await? else {
+        return Ok(());
+    };
+
+    let now = Utc::now().naive_utc();
+    if in_use.session_id != session.id || !locks.state(in_use.latest_activity, now).is_held() {
+        return Ok(());
+    }
+
+    contexts
+        .in_use_context
+        .update(in_use::Model {
+            latest_activity: now,
+            ..in_use
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Marking the queries of a project as outdated when its components change.
+//!
+//! Only the queries that reference a changed component are invalidated. A query is taken to reference
+//! every component whose name appears as an identifier in it. This may include components Reveaal
+//! does not need for the query, but never misses one it does. When it cannot be told which components
+//! changed, or which components a query references, the query is invalidated to be safe.
+
+use crate::api::server::protobuf::{Component, ComponentsInfo};
+use crate::contexts::context_collection::ContextCollection;
+use crate::services::service_traits::reveaal_service_trait::component_name;
+use sea_orm::DbErr;
+use std::collections::{HashMap, HashSet};
+
+/// Returns whether `name` is a single identifier, so it can be found in a query string.
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Returns the names of the components that were added, removed or changed between `old` and `new`.
+///
+/// Returns `None` if a component has no name, has the name of another component in the same components info,
+/// or has a name that is not an identifier, since changes to it cannot be traced to queries.
+pub fn changed_component_names(
+    old: &ComponentsInfo,
+    new: &ComponentsInfo,
+) -> Option<HashSet<String>> {
+    fn by_name(components_info: &ComponentsInfo) -> Option<HashMap<String, &Component>> {
+        let mut components = HashMap::new();
+        for component in &components_info.components {
+            let name = component_name(component).filter(|name| is_identifier(name))?;
+            if components.insert(name, component).is_some() {
+                return None;
+            }
+        }
+        Some(components)
+    }
+
+    let old = by_name(old)?;
+    let new = by_name(new)?;
+
+    Some(
+        old.keys()
+            .chain(new.keys())
+            .filter(|name| old.get(*name) != new.get(*name))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// Returns the identifiers in a query string, such as `refinement`, `A` and `B` in `refinement: A <= B`.
+pub fn query_identifiers(query: &str) -> HashSet<&str> {
+    query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|identifier| !identifier.is_empty())
+        .collect()
+}
+
+/// Returns whether a query has to be invalidated when the components in `changed` change.
+///
+/// A query without identifiers references no component that can be told, so it is always invalidated.
+pub fn query_references_any(query: &str, changed: &HashSet<String>) -> bool {
+    let identifiers = query_identifiers(query);
+    identifiers.is_empty()
+        || identifiers
+            .iter()
+            .any(|identifier| changed.contains(*identifier))
+}
+
+/// Marks the queries of a project that are affected by replacing its components as outdated,
+/// and returns their ids.
+///
+/// `old_components_info` is the stored components info of the project. If it cannot be parsed,
+/// or the changed components cannot be told apart by name, every query of the project is marked.
+/// Queries that are already outdated are left alone.
+/// # Errors
+/// Returns the error of the query context.
+pub async fn invalidate_affected_queries(
+    contexts: &ContextCollection,
+    project_id: i32,
+    old_components_info: &serde_json::Value,
+    new_components_info: &ComponentsInfo,
+) -> Result<Vec<i32>, DbErr> {
+    let changed = serde_json::from_value::<ComponentsInfo>(old_components_info.clone())
+        .ok()
+        .and_then(|old| changed_component_names(&old, new_components_info));
+
+    if changed.as_ref().is_some_and(HashSet::is_empty) {
+        return Ok(vec![]);
+    }
+
+    let affected: Vec<i32> = contexts
+        .query_context
+        .get_all_by_project_id(project_id, None)
+        .await?
+        .into_iter()
+        .filter(|query| !query.outdated)
+        .filter(|query| {
+            changed
+                .as_ref()
+                .map_or(true, |changed| query_references_any(&query.string, changed))
+        })
+        .map(|query| query.id)
+        .collect();
+
+    if !affected.is_empty() {
+        contexts
+            .query_context
+            .mark_outdated(affected.clone())
+            .await?;
+    }
+
+    Ok(affected)
+}
+
+#[cfg(test)]
+#[path = "../tests/controllers/query_invalidation.rs"]
+mod tests;
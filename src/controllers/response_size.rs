@@ -0,0 +1,71 @@
+//! Diagnostics for responses large enough to slow clients down, so operators can spot pathological projects.
+//!
+//! Unlike a hard message size limit, an oversized response is still sent; it is only logged,
+//! and optionally marked with a metadata header telling the client its size.
+use prost::Message;
+use tonic::metadata::MetadataValue;
+use tonic::Response;
+
+const DEFAULT_RESPONSE_SIZE_WARNING_BYTES: usize = 1024 * 1024;
+
+/// The metadata header carrying the encoded size of a response above the warning threshold.
+pub const LARGE_RESPONSE_HEADER: &str = "x-ecdar-large-response-bytes";
+
+/// When responses are reported as large.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResponseSizeWarning {
+    /// The encoded size in bytes above which a response is reported as large, 1 MiB by default.
+    /// A size of 0 disables the warning.
+    pub threshold: usize,
+    /// Whether large responses are marked with the [`LARGE_RESPONSE_HEADER`]
+    pub header: bool,
+}
+
+impl Default for ResponseSizeWarning {
+    fn default() -> Self {
+        ResponseSizeWarning {
+            threshold: DEFAULT_RESPONSE_SIZE_WARNING_BYTES,
+            header: false,
+        }
+    }
+}
+
+/// Logs a warning if the `response` of `endpoint` encodes to more than the threshold of `warning`,
+/// and marks it with the [`LARGE_RESPONSE_HEADER`] if its header is enabled.
+///
+/// The size is that of the serialized message, before any compression.
+/// Returns whether the response was reported as large.
+pub fn check_response_size<M: Message>(
+    endpoint: &str,
+    response: &mut Response<M>,
+    warning: &ResponseSizeWarning,
+) -> bool {
+    let threshold = warning.threshold;
+    if threshold == 0 {
+        return false;
+    }
+
+    let size = response.get_ref().encoded_len();
+    if size <= threshold {
+        return false;
+    }
+
+    log::warn!(
+        "{} response of {} bytes exceeds the warning threshold of {} bytes",
+        endpoint,
+        size,
+        threshold
+    );
+
+    if warning.header {
+        response
+            .metadata_mut()
+            .insert(LARGE_RESPONSE_HEADER, MetadataValue::from(size as u64));
+    }
+
+    true
+}
+
+#[cfg(test)]
+#[path = "../tests/controllers/response_size.rs"]
+mod tests;
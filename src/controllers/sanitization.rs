@@ -0,0 +1,26 @@
+//! Helpers for embedding user-controlled values in error messages and logs.
+//!
+//! Values such as project names and usernames, or database errors quoting them, may contain newlines
+//! and other control characters that can forge log lines, and may be arbitrarily long.
+
+/// The maximum number of characters kept from a sanitized value.
+pub const MAX_SANITIZED_LENGTH: usize = 128;
+
+/// Makes a user-controlled value safe to embed in an error message or log line.
+///
+/// Control characters are removed and the value is capped at [`MAX_SANITIZED_LENGTH`] characters,
+/// marking a truncated value with a trailing `...`.
+pub fn sanitize(input: &str) -> String {
+    let mut characters = input.chars().filter(|character| !character.is_control());
+    let mut sanitized: String = characters.by_ref().take(MAX_SANITIZED_LENGTH).collect();
+
+    if characters.next().is_some() {
+        sanitized.push_str("...");
+    }
+
+    sanitized
+}
+
+#[cfg(test)]
+#[path = "../tests/controllers/sanitization.rs"]
+mod tests;
@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "access")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -10,6 +10,8 @@ pub struct Model {
     pub role: String,
     pub project_id: i32,
     pub user_id: i32,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -59,3 +61,13 @@ impl Related<super::user::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Returns whether the access grants at least the given role.
+    /// Unknown role names never satisfy a guard.
+    pub fn has_role_at_least(&self, minimum: super::role::Role) -> bool {
+        self.role
+            .parse::<super::role::Role>()
+            .is_ok_and(|role| role >= minimum)
+    }
+}
@@ -1,11 +1,19 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.4
+//!
+//! Models with an auto-incremented `id` implement [`Default`], which sets the `id` to 0,
+//! strings to empty, JSON to `null`, timestamps to the unix epoch and optional fields to `None`.
+//! An `id` of 0 never refers to a stored row: the contexts leave the `id` out when inserting a model,
+//! so the database assigns it, and `create` returns the model with the assigned `id`.
 
 pub mod prelude;
 
 pub mod access;
+pub mod component;
 pub mod in_use;
 pub mod project;
+pub mod project_event;
 pub mod query;
+pub mod query_result;
 pub mod role;
 pub mod session;
 pub mod user;
@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "project")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -16,8 +16,12 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::access::Entity")]
     Access,
+    #[sea_orm(has_many = "super::component::Entity")]
+    Component,
     #[sea_orm(has_many = "super::in_use::Entity")]
     InUse,
+    #[sea_orm(has_many = "super::project_event::Entity")]
+    ProjectEvent,
     #[sea_orm(has_many = "super::query::Entity")]
     Query,
     #[sea_orm(
@@ -36,12 +40,24 @@ impl Related<super::access::Entity> for Entity {
     }
 }
 
+impl Related<super::component::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Component.def()
+    }
+}
+
 impl Related<super::in_use::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::InUse.def()
     }
 }
 
+impl Related<super::project_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProjectEvent.def()
+    }
+}
+
 impl Related<super::query::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Query.def()
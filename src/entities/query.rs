@@ -2,15 +2,21 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "query")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub string: String,
+    pub title: Option<String>,
     pub result: Option<Json>,
+    pub settings: Option<Json>,
     pub outdated: bool,
     pub project_id: i32,
+    pub last_run_ms: Option<i64>,
+    pub result_computed_at: Option<DateTime>,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -23,6 +29,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Project,
+    #[sea_orm(has_many = "super::query_result::Entity")]
+    QueryResult,
 }
 
 impl Related<super::project::Entity> for Entity {
@@ -31,4 +39,10 @@ impl Related<super::project::Entity> for Entity {
     }
 }
 
+impl Related<super::query_result::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::QueryResult.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
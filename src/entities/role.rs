@@ -22,3 +22,49 @@ impl Related<super::access::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+/// The roles a user can have on a project, ordered from least to most privileged:
+/// `Reader < Commenter < Editor`.
+///
+/// A role includes every permission of the roles below it, so guards can check
+/// for a minimum role with `role >= Role::Commenter`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    Reader,
+    Commenter,
+    Editor,
+}
+
+impl Role {
+    /// Returns the name of the role as stored in the `role` table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Reader => "Reader",
+            Role::Commenter => "Commenter",
+            Role::Editor => "Editor",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Reader" => Ok(Role::Reader),
+            "Commenter" => Ok(Role::Commenter),
+            "Editor" => Ok(Role::Editor),
+            _ => Err(format!("'{}' is not a valid role", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/entities/role.rs"]
+mod role_tests;
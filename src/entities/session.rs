@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "session")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -11,6 +11,7 @@ pub struct Model {
     pub refresh_token: String,
     #[sea_orm(unique)]
     pub access_token: String,
+    pub created_at: Option<DateTime>,
     pub updated_at: DateTime,
     pub user_id: i32,
 }
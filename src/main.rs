@@ -7,67 +7,140 @@
 //! # Notes
 //! Currently, the only supported databases are `PostgreSQL` and `SQLite`
 mod api;
+mod config;
 mod contexts;
 mod controllers;
 mod entities;
 mod services;
 mod tests;
 
+use crate::config::Config;
+use crate::contexts::connect_options::connect_options;
 use crate::contexts::context_collection::ContextCollection;
 use crate::contexts::context_impls::*;
 use crate::contexts::context_traits::DatabaseContextTrait;
+use crate::contexts::readiness::connect_with_retry;
 use crate::controllers::controller_collection::ControllerCollection;
 use crate::controllers::controller_impls::*;
 use crate::services::service_collection::ServiceCollection;
+use crate::services::service_impls::hashing_service::DEFAULT_CALIBRATION_TARGET;
 use crate::services::service_impls::{HashingService, ReveaalService};
 use api::server::start_grpc_server;
 use dotenv::dotenv;
-use sea_orm::{ConnectionTrait, Database, DbBackend};
+use sea_orm::{ConnectionTrait, Database, DbBackend, DbErr};
 use std::env;
 use std::error::Error;
 use std::sync::Arc;
 
+/// Connects to the database at `db_url`, picking the context matching its backend.
+async fn connect_database(
+    db_url: &str,
+    log_statements: bool,
+) -> Result<Arc<dyn DatabaseContextTrait>, DbErr> {
+    let db = Database::connect(connect_options(db_url, log_statements)).await?;
+    Ok(match db.get_database_backend() {
+        DbBackend::Sqlite => Arc::new(SQLiteDatabaseContext::new(db_url, log_statements).await?),
+        DbBackend::Postgres => {
+            Arc::new(PostgresDatabaseContext::new(db_url, log_statements).await?)
+        }
+        _ => panic!("Database protocol not supported"),
+    })
+}
+
 #[tokio::main]
 #[allow(clippy::expect_used)]
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenv().ok();
 
-    let reveaal_addr = env::var("REVEAAL_ADDRESS").expect("Expected REVEAAL_ADDRESS to be set.");
-    let db_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL to be set.");
+    // `server calibrate-hashing` suggests a BCRYPT_COST for this machine instead of starting the server
+    if env::args().nth(1).as_deref() == Some("calibrate-hashing") {
+        let calibration = HashingService::calibrate(4..=15, DEFAULT_CALIBRATION_TARGET)?;
+        for (cost, time) in calibration.measurements {
+            println!("cost {:>2}: {} ms", cost, time.as_millis());
+        }
+        println!("Suggested BCRYPT_COST={}", calibration.suggested_cost);
+        return Ok(());
+    }
 
-    let db = Database::connect(&db_url).await?;
-    let db_context: Arc<dyn DatabaseContextTrait> = match db.get_database_backend() {
-        DbBackend::Sqlite => Arc::new(SQLiteDatabaseContext::new(&db_url).await?),
-        DbBackend::Postgres => Arc::new(PostgresDatabaseContext::new(&db_url).await?),
-        _ => panic!("Database protocol not supported"),
-    };
+    // Report every missing or invalid variable before anything is started
+    let config = Config::from_env()?;
+
+    // Wait for the database instead of crash-looping when it is started alongside the API
+    let db_context = connect_with_retry(
+        || connect_database(&config.database_url, config.log_statements),
+        config.database_ready_timeout,
+    )
+    .await?;
 
     let contexts = ContextCollection {
-        access_context: Arc::new(AccessContext::new(db_context.clone())),
+        access_context: Arc::new(AccessContext::with_max_page_size(
+            db_context.clone(),
+            config.max_page_size,
+        )),
         in_use_context: Arc::new(InUseContext::new(db_context.clone())),
-        project_context: Arc::new(ProjectContext::new(db_context.clone())),
+        project_context: Arc::new(ProjectContext::with_settings(
+            db_context.clone(),
+            config.components_storage,
+            config.max_page_size,
+        )),
+        project_event_context: Arc::new(ProjectEventContext::new(db_context.clone())),
         query_context: Arc::new(QueryContext::new(db_context.clone())),
+        query_result_context: Arc::new(QueryResultContext::with_history_depth(
+            db_context.clone(),
+            config.query_result_history_depth,
+        )),
         session_context: Arc::new(SessionContext::new(db_context.clone())),
         user_context: Arc::new(UserContext::new(db_context.clone())),
     };
 
+    let reveaal_service = Arc::new(ReveaalService::new(
+        &config.reveaal_address,
+        &config.reveaal,
+    ));
+    let reveaal_metrics = reveaal_service.metrics();
+
     let services = ServiceCollection {
-        hashing_service: Arc::new(HashingService),
-        reveaal_service: Arc::new(ReveaalService::new(&reveaal_addr)),
+        hashing_service: Arc::new(HashingService::new(config.bcrypt_cost)),
+        reveaal_service,
     };
 
     let controllers = ControllerCollection {
-        access_controller: Arc::new(AccessController::new(contexts.clone())),
-        project_controller: Arc::new(ProjectController::new(contexts.clone())),
-        query_controller: Arc::new(QueryController::new(contexts.clone(), services.clone())),
-        session_controller: Arc::new(SessionController::new(contexts.clone(), services.clone())),
-        user_controller: Arc::new(UserController::new(contexts.clone(), services.clone())),
-        reveaal_controller: Arc::new(ReveaalController::new(services.clone())),
+        access_controller: Arc::new(AccessController::new(contexts.clone(), config.clone())),
+        project_controller: Arc::new(ProjectController::new(contexts.clone(), config.clone())),
+        query_controller: Arc::new(QueryController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        session_controller: Arc::new(SessionController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        user_controller: Arc::new(UserController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        reveaal_controller: Arc::new(ReveaalController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        server_info_controller: Arc::new(ServerInfoController::new(
+            config.clone(),
+            db_context.backend_kind(),
+        )),
     };
 
-    start_grpc_server(controllers)
-        .await
-        .expect("failed to start grpc server");
+    start_grpc_server(
+        controllers,
+        &config,
+        reveaal_metrics,
+        contexts.in_use_context.clone(),
+    )
+    .await
+    .expect("failed to start grpc server");
 
     Ok(())
 }
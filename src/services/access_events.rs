@@ -0,0 +1,67 @@
+//! An in-process event bus announcing changes to who has access to a project, so side effects
+//! such as notifications or an audit log can react without being part of the mutation itself.
+//!
+//! Events are only published after the change has been stored. Subscribers that fall more than
+//! the capacity of the bus behind miss the oldest events, as with any broadcast channel.
+use tokio::sync::broadcast;
+
+/// The number of events kept for subscribers that have not received them yet.
+const DEFAULT_CAPACITY: usize = 256;
+
+/// A change to the access of a user on a project.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessEvent {
+    /// The user was given access to the project with the given role
+    Granted {
+        project_id: i32,
+        user_id: i32,
+        role: String,
+        changed_by: i32,
+    },
+    /// The role of the user on the project was changed to the given role
+    RoleChanged {
+        project_id: i32,
+        user_id: i32,
+        role: String,
+        changed_by: i32,
+    },
+    /// The user no longer has access to the project
+    Revoked {
+        project_id: i32,
+        user_id: i32,
+        changed_by: i32,
+    },
+}
+
+/// Publishes [`AccessEvent`]s to every current subscriber.
+///
+/// Cloning the bus gives another handle to the same channel.
+#[derive(Clone, Debug)]
+pub struct AccessEventBus {
+    sender: broadcast::Sender<AccessEvent>,
+}
+
+impl AccessEventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        AccessEventBus { sender }
+    }
+
+    /// Returns a receiver of every event published from now on
+    // Subscribed to once the watch stream and audit log exist
+    #[allow(dead_code)]
+    pub fn subscribe(&self) -> broadcast::Receiver<AccessEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publishes the event to the current subscribers. Having no subscribers is not an error.
+    pub fn publish(&self, event: AccessEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for AccessEventBus {
+    fn default() -> Self {
+        AccessEventBus::new(DEFAULT_CAPACITY)
+    }
+}
@@ -1,3 +1,5 @@
+pub mod access_events;
+pub mod reveaal_metrics;
 pub mod service_collection;
 pub mod service_impls;
 pub mod service_traits;
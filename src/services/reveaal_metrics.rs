@@ -0,0 +1,162 @@
+//! Counters of the outcomes of queries and other calls sent to Reveaal, to monitor the health of the backend
+//! separately from the API itself.
+//!
+//! There is no metrics registry yet, so the counters render themselves in the Prometheus text
+//! exposition format, which is served at `/metrics` by [`MetricsLayer`](crate::api::metrics::MetricsLayer).
+use crate::api::server::protobuf::{query_response, QueryResponse};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tonic::{Code, Response, Status};
+
+/// The name of the counter in the Prometheus exposition format.
+const METRIC_NAME: &str = "ecdar_reveaal_queries_total";
+
+/// How a query sent to Reveaal ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReveaalOutcome {
+    /// Reveaal answered the query, whether the answer is a success or a failure of the verification
+    Success,
+    /// Reveaal did not answer in time, or the call was abandoned before it answered
+    Timeout,
+    /// Reveaal could not be reached
+    TransportError,
+    /// Reveaal could not parse the query or rejected it as invalid
+    InvalidQuery,
+    /// Reveaal responded with any other error
+    Error,
+}
+
+impl ReveaalOutcome {
+    const ALL: [ReveaalOutcome; 5] = [
+        ReveaalOutcome::Success,
+        ReveaalOutcome::Timeout,
+        ReveaalOutcome::TransportError,
+        ReveaalOutcome::InvalidQuery,
+        ReveaalOutcome::Error,
+    ];
+
+    /// Returns the value of the `outcome` label
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReveaalOutcome::Success => "success",
+            ReveaalOutcome::Timeout => "timeout",
+            ReveaalOutcome::TransportError => "transport_error",
+            ReveaalOutcome::InvalidQuery => "invalid_query",
+            ReveaalOutcome::Error => "error",
+        }
+    }
+
+    /// Classifies the result of a query sent to Reveaal.
+    pub fn of_query(result: &Result<Response<QueryResponse>, Status>) -> Self {
+        match result {
+            Ok(response) => match response.get_ref().result {
+                Some(query_response::Result::ParsingError(_)) => ReveaalOutcome::InvalidQuery,
+                _ => ReveaalOutcome::Success,
+            },
+            Err(status) => ReveaalOutcome::of_error(status),
+        }
+    }
+
+    /// Classifies the result of a raw call passed on to Reveaal, whose response is not inspected.
+    pub fn of_raw_call(result: &Result<Vec<u8>, Status>) -> Self {
+        match result {
+            Ok(_) => ReveaalOutcome::Success,
+            Err(status) => ReveaalOutcome::of_error(status),
+        }
+    }
+
+    fn of_error(status: &Status) -> Self {
+        match status.code() {
+            Code::DeadlineExceeded | Code::Cancelled => ReveaalOutcome::Timeout,
+            Code::Unavailable => ReveaalOutcome::TransportError,
+            Code::InvalidArgument => ReveaalOutcome::InvalidQuery,
+            _ => ReveaalOutcome::Error,
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// The number of queries sent to Reveaal by outcome.
+#[derive(Default)]
+pub struct ReveaalMetrics {
+    counts: [AtomicU64; ReveaalOutcome::ALL.len()],
+}
+
+impl ReveaalMetrics {
+    /// Counts a query that ended with `outcome`.
+    pub fn record(&self, outcome: ReveaalOutcome) {
+        self.counts[outcome.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns how many queries ended with `outcome`.
+    pub fn count(&self, outcome: ReveaalOutcome) -> u64 {
+        self.counts[outcome.index()].load(Ordering::Relaxed)
+    }
+
+    /// Starts counting a query, returning a guard that counts it as a [`ReveaalOutcome::Timeout`]
+    /// unless [`QueryGuard::finish`] is called with its result first.
+    ///
+    /// Callers enforce `REVEAAL_TIMEOUT_SECS` by dropping the call, so a query that never finishes
+    /// is only noticed when the guard is dropped.
+    pub fn start_query(&self) -> QueryGuard<'_> {
+        QueryGuard {
+            metrics: self,
+            finished: false,
+        }
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = format!(
+            "# HELP {0} Queries sent to Reveaal by outcome.\n# TYPE {0} counter\n",
+            METRIC_NAME
+        );
+        for outcome in ReveaalOutcome::ALL {
+            // Writing to a string cannot fail
+            let _ = writeln!(
+                output,
+                "{}{{outcome=\"{}\"}} {}",
+                METRIC_NAME,
+                outcome.label(),
+                self.count(outcome)
+            );
+        }
+        output
+    }
+}
+
+/// Counts a query in progress, see [`ReveaalMetrics::start_query`].
+pub struct QueryGuard<'a> {
+    metrics: &'a ReveaalMetrics,
+    finished: bool,
+}
+
+impl QueryGuard<'_> {
+    /// Counts the query by the outcome of its result, which is passed through.
+    pub fn finish(
+        mut self,
+        result: Result<Response<QueryResponse>, Status>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        self.metrics.record(ReveaalOutcome::of_query(&result));
+        self.finished = true;
+        result
+    }
+
+    /// Counts a raw call passed on to Reveaal by the outcome of its result, which is passed through.
+    pub fn finish_raw(mut self, result: Result<Vec<u8>, Status>) -> Result<Vec<u8>, Status> {
+        self.metrics.record(ReveaalOutcome::of_raw_call(&result));
+        self.finished = true;
+        result
+    }
+}
+
+impl Drop for QueryGuard<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.metrics.record(ReveaalOutcome::Timeout);
+        }
+    }
+}
@@ -1,11 +1,62 @@
 use crate::services::service_traits::hashing_service_trait::HashingServiceTrait;
-use bcrypt::{hash, verify, BcryptError, DEFAULT_COST};
+use bcrypt::{hash, verify, BcryptError};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 
-pub struct HashingService;
+/// The hashing time that [`HashingService::calibrate`] aims for by default.
+pub const DEFAULT_CALIBRATION_TARGET: Duration = Duration::from_millis(250);
+
+/// The measured hashing time for each cost, and the cost suggested for the target time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    pub measurements: Vec<(u32, Duration)>,
+    pub suggested_cost: u32,
+}
+
+pub struct HashingService {
+    cost: u32,
+}
+
+impl HashingService {
+    /// Creates a service hashing passwords with the given bcrypt `cost`.
+    pub fn new(cost: u32) -> Self {
+        HashingService { cost }
+    }
+
+    /// Measures how long hashing takes at each of the given costs on this machine,
+    /// and suggests the highest cost that hashes within `target`.
+    ///
+    /// If every cost exceeds the target, the lowest cost is suggested.
+    /// # Errors
+    /// Errors if a cost is outside the range bcrypt accepts.
+    pub fn calibrate(
+        costs: RangeInclusive<u32>,
+        target: Duration,
+    ) -> Result<Calibration, BcryptError> {
+        let mut measurements = vec![];
+        for cost in costs.clone() {
+            let started_at = Instant::now();
+            hash("calibration password", cost)?;
+            measurements.push((cost, started_at.elapsed()));
+        }
+
+        let suggested_cost = measurements
+            .iter()
+            .filter(|(_, time)| *time <= target)
+            .map(|(cost, _)| *cost)
+            .max()
+            .unwrap_or(*costs.start());
+
+        Ok(Calibration {
+            measurements,
+            suggested_cost,
+        })
+    }
+}
 
 impl HashingServiceTrait for HashingService {
     fn hash_password(&self, password: String) -> Result<String, BcryptError> {
-        hash(password, DEFAULT_COST)
+        hash(password, self.cost)
     }
 
     fn verify_password(&self, password: String, hash: &str) -> Result<bool, BcryptError> {
@@ -2,4 +2,4 @@ pub mod hashing_service;
 mod reveaal_service;
 
 pub use hashing_service::HashingService;
-pub use reveaal_service::ReveaalService;
+pub use reveaal_service::{ReveaalConfig, ReveaalService};
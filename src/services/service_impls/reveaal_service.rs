@@ -3,26 +3,177 @@ use crate::api::server::protobuf::{
     QueryRequest, QueryResponse, SimulationStartRequest, SimulationStepRequest,
     SimulationStepResponse, UserTokenResponse,
 };
+use crate::services::reveaal_metrics::ReveaalMetrics;
 use crate::services::service_traits::ReveaalServiceTrait;
 use async_trait::async_trait;
-use tonic::transport::Channel;
-use tonic::{Request, Response, Status};
+use prost::bytes::{Buf, BufMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tonic::client::Grpc;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Response, Status};
+
+const DEFAULT_REVEAAL_KEEP_ALIVE_SECS: u64 = 30;
+const DEFAULT_REVEAAL_MAX_CONCURRENT_CALLS: usize = 8;
+const DEFAULT_REVEAAL_PERMIT_TIMEOUT_SECS: u64 = 30;
+/// The path of the gRPC service Reveaal serves, which method names are appended to.
+const REVEAAL_SERVICE_PATH: &str = "/ecdar_proto_buf.EcdarBackend";
+
+/// How the connection to Reveaal is kept and shared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReveaalConfig {
+    /// The interval between HTTP/2 keep-alive pings sent to Reveaal, 30 seconds by default
+    pub keep_alive_interval: Duration,
+    /// How many queries and simulation steps may be outstanding on Reveaal at once, 8 by default
+    pub max_concurrent_calls: usize,
+    /// How long a call waits for one of the outstanding calls to Reveaal to finish, 30 seconds by default
+    pub permit_timeout: Duration,
+}
+
+impl Default for ReveaalConfig {
+    fn default() -> Self {
+        ReveaalConfig {
+            keep_alive_interval: Duration::from_secs(DEFAULT_REVEAAL_KEEP_ALIVE_SECS),
+            max_concurrent_calls: DEFAULT_REVEAAL_MAX_CONCURRENT_CALLS,
+            permit_timeout: Duration::from_secs(DEFAULT_REVEAAL_PERMIT_TIMEOUT_SECS),
+        }
+    }
+}
+
+/// A codec passing already encoded messages on as is, for calls whose message types are not known.
+#[derive(Clone, Copy, Debug, Default)]
+struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = Vec<u8>;
+    type Decode = Vec<u8>;
+    type Encoder = RawCodec;
+    type Decoder = RawCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RawCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawCodec
+    }
+}
+
+impl Encoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(src.copy_to_bytes(src.remaining()).to_vec()))
+    }
+}
 
 pub struct ReveaalService {
     address: String,
+    channel: Mutex<Option<Channel>>,
+    connections: AtomicUsize,
+    permits: Semaphore,
+    permit_timeout: Duration,
+    keep_alive_interval: Duration,
+    metrics: Arc<ReveaalMetrics>,
 }
 
 impl ReveaalService {
-    pub fn new(address: &str) -> Self {
+    pub fn new(address: &str, config: &ReveaalConfig) -> Self {
         Self {
             address: address.to_string(),
+            channel: Mutex::new(None),
+            connections: AtomicUsize::new(0),
+            permits: Semaphore::new(config.max_concurrent_calls),
+            permit_timeout: config.permit_timeout,
+            keep_alive_interval: config.keep_alive_interval,
+            metrics: Arc::new(ReveaalMetrics::default()),
         }
     }
 
-    async fn get_connection(&self) -> Result<EcdarBackendClient<Channel>, Status> {
-        EcdarBackendClient::connect(self.address.clone())
+    /// Creates a service allowing at most `limit` outstanding calls to Reveaal,
+    /// where further calls wait up to `permit_timeout` for one of them to finish.
+    pub fn with_concurrency_limit(address: &str, limit: usize, permit_timeout: Duration) -> Self {
+        Self::new(
+            address,
+            &ReveaalConfig {
+                max_concurrent_calls: limit,
+                permit_timeout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Returns the counters of the outcomes of the queries sent through this service
+    pub fn metrics(&self) -> Arc<ReveaalMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Waits for a free slot among the outstanding calls to Reveaal.
+    /// The slot is released when the returned permit is dropped.
+    /// # Errors
+    /// Returns `ResourceExhausted` if no slot frees up within the permit timeout.
+    pub(crate) async fn acquire_permit(&self) -> Result<SemaphorePermit<'_>, Status> {
+        tokio::time::timeout(self.permit_timeout, self.permits.acquire())
             .await
-            .map_err(|err| Status::internal(format!("{err}")))
+            .map_err(|_| Status::resource_exhausted("Reveaal is busy, try again later"))?
+            .map_err(|err| Status::internal(err.to_string()))
+    }
+
+    /// Returns the shared channel to Reveaal, building it if there is none.
+    ///
+    /// The channel connects lazily and keeps the connection alive with HTTP/2 pings.
+    fn get_channel(&self) -> Result<Channel, Status> {
+        let mut shared = self.channel.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(channel) = shared.as_ref() {
+            return Ok(channel.clone());
+        }
+
+        let channel = Endpoint::from_shared(self.address.clone())
+            .map_err(|err| Status::internal(format!("{err}")))?
+            .http2_keep_alive_interval(self.keep_alive_interval)
+            .keep_alive_while_idle(true)
+            .connect_lazy();
+        self.connections.fetch_add(1, Ordering::SeqCst);
+
+        *shared = Some(channel.clone());
+        Ok(channel)
+    }
+
+    /// Returns a client on the shared channel to Reveaal.
+    async fn get_connection(&self) -> Result<EcdarBackendClient<Channel>, Status> {
+        Ok(EcdarBackendClient::new(self.get_channel()?))
+    }
+
+    /// Drops the shared channel on connection-level errors, so the next call rebuilds it.
+    fn reconnect_on_error<T>(&self, result: Result<T, Status>) -> Result<T, Status> {
+        if let Err(status) = &result {
+            if status.code() == Code::Unavailable {
+                *self.channel.lock().unwrap_or_else(PoisonError::into_inner) = None;
+            }
+        }
+        result
+    }
+
+    /// Returns how many times a channel to Reveaal has been built
+    #[cfg(test)]
+    pub(crate) fn connection_count(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
     }
 }
 
@@ -32,30 +183,65 @@ impl ReveaalServiceTrait for ReveaalService {
         &self,
         request: Request<()>,
     ) -> Result<Response<UserTokenResponse>, Status> {
-        self.get_connection().await?.get_user_token(request).await
+        let result = self.get_connection().await?.get_user_token(request).await;
+        self.reconnect_on_error(result)
     }
 
     async fn send_query(
         &self,
         request: Request<QueryRequest>,
     ) -> Result<Response<QueryResponse>, Status> {
-        self.get_connection().await?.send_query(request).await
+        let _permit = self.acquire_permit().await?;
+        let query = self.metrics.start_query();
+        let result = match self.get_connection().await {
+            Ok(mut connection) => connection.send_query(request).await,
+            Err(status) => Err(status),
+        };
+        query.finish(self.reconnect_on_error(result))
     }
 
     async fn start_simulation(
         &self,
         request: Request<SimulationStartRequest>,
     ) -> Result<Response<SimulationStepResponse>, Status> {
-        self.get_connection().await?.start_simulation(request).await
+        let _permit = self.acquire_permit().await?;
+        let result = self.get_connection().await?.start_simulation(request).await;
+        self.reconnect_on_error(result)
     }
 
     async fn take_simulation_step(
         &self,
         request: Request<SimulationStepRequest>,
     ) -> Result<Response<SimulationStepResponse>, Status> {
-        self.get_connection()
+        let _permit = self.acquire_permit().await?;
+        let result = self
+            .get_connection()
             .await?
             .take_simulation_step(request)
-            .await
+            .await;
+        self.reconnect_on_error(result)
+    }
+
+    async fn call_raw(&self, method: &str, payload: Vec<u8>) -> Result<Vec<u8>, Status> {
+        let path = PathAndQuery::try_from(format!("{}/{}", REVEAAL_SERVICE_PATH, method))
+            .map_err(|_| Status::invalid_argument(format!("'{}' is not a method name", method)))?;
+
+        let _permit = self.acquire_permit().await?;
+        let call = self.metrics.start_query();
+        let mut grpc = match self.get_channel() {
+            Ok(channel) => Grpc::new(channel),
+            Err(status) => return call.finish_raw(Err(status)),
+        };
+        let result = match grpc.ready().await {
+            Ok(()) => grpc
+                .unary(Request::new(payload), path, RawCodec)
+                .await
+                .map(Response::into_inner),
+            Err(err) => Err(Status::unavailable(format!(
+                "Reveaal is not ready: {}",
+                err
+            ))),
+        };
+        call.finish_raw(self.reconnect_on_error(result))
     }
 }
@@ -1,10 +1,21 @@
 use crate::api::server::protobuf::{
-    QueryRequest, QueryResponse, SimulationStartRequest, SimulationStepRequest,
-    SimulationStepResponse, UserTokenResponse,
+    component::Rep, query_response, Component, ComponentConsistency, ComponentsInfo, QueryRequest,
+    QueryResponse, SimulationStartRequest, SimulationStepRequest, SimulationStepResponse,
+    UserTokenResponse,
 };
 use async_trait::async_trait;
 use tonic::{Request, Response, Status};
 
+/// Returns the name of a component, read from its JSON representation.
+pub fn component_name(component: &Component) -> Option<String> {
+    match &component.rep {
+        Some(Rep::Json(json)) => serde_json::from_str::<serde_json::Value>(json)
+            .ok()
+            .and_then(|value| value.get("name")?.as_str().map(str::to_string)),
+        _ => None,
+    }
+}
+
 #[async_trait]
 pub trait ReveaalServiceTrait: Send + Sync {
     async fn get_user_token(
@@ -23,4 +34,61 @@ pub trait ReveaalServiceTrait: Send + Sync {
         &self,
         request: Request<SimulationStepRequest>,
     ) -> Result<Response<SimulationStepResponse>, Status>;
+
+    /// Calls the Reveaal RPC named `method`, e.g. `SendQuery`, with `payload` as the encoded request message,
+    /// and returns the encoded response message.
+    ///
+    /// The payload is passed on as is, so callers must check that the method may be called.
+    /// # Errors
+    /// Returns `Unimplemented` unless the service can pass on raw calls, and otherwise the error of Reveaal.
+    async fn call_raw(&self, method: &str, _payload: Vec<u8>) -> Result<Vec<u8>, Status> {
+        Err(Status::unimplemented(format!(
+            "Raw calls to the Reveaal method '{}' are not supported",
+            method
+        )))
+    }
+
+    /// Checks every component for consistency on its own, independent of any stored query.
+    ///
+    /// Reveaal has no dedicated call for this, so a `consistency:` query is sent per component.
+    /// Components without a name cannot be referred to in a query and are reported as inconsistent
+    /// without a response.
+    /// # Errors
+    /// Returns the first error Reveaal responds with.
+    async fn check_consistency(
+        &self,
+        user_id: i32,
+        components_info: ComponentsInfo,
+    ) -> Result<Vec<ComponentConsistency>, Status> {
+        let mut results = vec![];
+        for (index, component) in components_info.components.iter().enumerate() {
+            let Some(name) = component_name(component) else {
+                results.push(ComponentConsistency {
+                    component_name: format!("#{}", index),
+                    consistent: false,
+                    response: None,
+                });
+                continue;
+            };
+
+            let response = self
+                .send_query(Request::new(QueryRequest {
+                    user_id,
+                    // The check is not a stored query, so there is no query id to report back
+                    query_id: 0,
+                    query: format!("consistency: {}", name),
+                    components_info: Some(components_info.clone()),
+                    settings: Default::default(),
+                }))
+                .await?
+                .into_inner();
+
+            results.push(ComponentConsistency {
+                component_name: name,
+                consistent: matches!(response.result, Some(query_response::Result::Success(_))),
+                response: Some(response),
+            });
+        }
+        Ok(results)
+    }
 }
@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod auth {
-    use crate::api::auth::{RequestExt, Token, TokenError, TokenType};
+    use crate::api::auth::{
+        check_origin, parse_allowed_origins, validation_interceptor, RequestExt, Token, TokenError,
+        TokenType, VerifiedUid,
+    };
     use std::{env, str::FromStr};
     use tonic::{metadata::MetadataValue, Request};
 
@@ -138,4 +141,105 @@ mod auth {
 
         assert!(result.is_err());
     }
+
+    fn request_from_origin(origin: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(origin) = origin {
+            request
+                .metadata_mut()
+                .insert("origin", MetadataValue::from_str(origin).unwrap());
+        }
+        request
+    }
+
+    fn origins(origins: &[&str]) -> Vec<String> {
+        origins.iter().map(|origin| origin.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn check_origin_allowed_origin_returns_ok() {
+        let request = request_from_origin(Some("https://ecdar.example.com/"));
+
+        let result = check_origin(&request, &origins(&["https://ecdar.example.com"]));
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn check_origin_disallowed_origin_returns_permission_denied() {
+        let request = request_from_origin(Some("https://evil.example.com"));
+
+        let result = check_origin(&request, &origins(&["https://ecdar.example.com"]));
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn check_origin_no_allowed_origins_allows_every_origin() {
+        let request = request_from_origin(Some("https://evil.example.com"));
+
+        let result = check_origin(&request, &[]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn parse_allowed_origins_normalizes_origins() {
+        assert_eq!(
+            parse_allowed_origins(" HTTPS://Ecdar.example.com/ ,http://localhost:8080,"),
+            Ok(vec![
+                "https://ecdar.example.com".to_string(),
+                "http://localhost:8080".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_allowed_origins_not_an_origin_returns_err() {
+        assert!(parse_allowed_origins("ecdar.example.com").is_err());
+        assert!(parse_allowed_origins("https://ecdar.example.com/app").is_err());
+    }
+
+    #[tokio::test]
+    async fn check_origin_without_origin_returns_ok() {
+        let request = request_from_origin(None);
+
+        let result = check_origin(&request, &origins(&["https://ecdar.example.com"]));
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn validation_interceptor_ignores_client_supplied_uid() {
+        env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+
+        let token = Token::access("1").unwrap();
+        let mut request = Request::new(());
+        request.metadata_mut().insert(
+            "authorization",
+            MetadataValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        request
+            .metadata_mut()
+            .insert("uid", MetadataValue::from_str("2").unwrap());
+
+        let request = validation_interceptor(request, &[]).unwrap();
+
+        assert_eq!(request.uid().unwrap(), Some(1));
+        assert_eq!(
+            request.extensions().get::<VerifiedUid>(),
+            Some(&VerifiedUid(1))
+        );
+        assert!(request.metadata().get("uid").is_none());
+    }
+
+    #[tokio::test]
+    async fn request_uid_without_verified_uid_returns_none() {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("uid", MetadataValue::from_str("2").unwrap());
+
+        assert_eq!(request.uid().unwrap(), None);
+    }
 }
@@ -0,0 +1,83 @@
+use crate::api::auth::Token;
+use crate::api::authentication::{is_public_endpoint, RequireAuthLayer};
+use std::convert::Infallible;
+use std::env;
+use tonic::body::empty_body;
+use tonic::{Code, Status};
+use tower::{service_fn, Layer, ServiceExt};
+
+async fn call_endpoint(enabled: bool, path: &str, token: Option<&str>) -> Option<Status> {
+    let service = RequireAuthLayer::new(enabled).layer(service_fn(|_: http::Request<()>| async {
+        Ok::<_, Infallible>(http::Response::new(empty_body()))
+    }));
+
+    let mut request = http::Request::builder().uri(path);
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let response = service.oneshot(request.body(()).unwrap()).await.unwrap();
+
+    Status::from_header_map(response.headers())
+}
+
+#[tokio::test]
+async fn enabled_public_endpoint_without_token_is_served() {
+    let status = call_endpoint(true, "/ecdar_proto_buf.EcdarApiAuth/GetAuthToken", None).await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn enabled_protected_endpoint_without_token_returns_unauthenticated() {
+    let status = call_endpoint(true, "/ecdar_proto_buf.EcdarApi/GetProject", None).await;
+
+    assert_eq!(status.unwrap().code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn enabled_protected_endpoint_invalid_token_returns_unauthenticated() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+
+    let status = call_endpoint(
+        true,
+        "/ecdar_proto_buf.EcdarApi/GetProject",
+        Some("not a token"),
+    )
+    .await;
+
+    assert_eq!(status.unwrap().code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn enabled_protected_endpoint_valid_token_is_served() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+    let token = Token::access("1").unwrap();
+
+    let status = call_endpoint(
+        true,
+        "/ecdar_proto_buf.EcdarApi/GetProject",
+        Some(token.as_str()),
+    )
+    .await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn disabled_protected_endpoint_without_token_is_served() {
+    let status = call_endpoint(false, "/ecdar_proto_buf.EcdarApi/GetProject", None).await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn is_public_endpoint_classifies_endpoints() {
+    assert!(is_public_endpoint(
+        "/ecdar_proto_buf.EcdarApiAuth/CreateUser"
+    ));
+    assert!(!is_public_endpoint(
+        "/ecdar_proto_buf.EcdarApi/DeleteProject"
+    ));
+    assert!(!is_public_endpoint("/ecdar_proto_buf.Unknown/Endpoint"));
+    assert!(!is_public_endpoint("/"));
+}
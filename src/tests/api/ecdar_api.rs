@@ -0,0 +1,179 @@
+use crate::api::auth::VerifiedUid;
+use crate::api::ecdar_api::routes::ENDPOINT_ROLES;
+use crate::api::ecdar_api::ConcreteEcdarApi;
+use crate::api::server::protobuf::ecdar_api_server::EcdarApi;
+use crate::api::server::protobuf::{
+    CreateProjectRequest, DeleteProjectRequest, GetProjectRequest, UpdateProjectRequest,
+};
+use crate::controllers::controller_collection::ControllerCollection;
+use crate::controllers::controller_impls::*;
+use crate::tests::controllers::helpers::{
+    disguise_context_mocks, disguise_service_mocks, get_mock_contexts, get_mock_services,
+    get_test_config, MockContexts,
+};
+use mockall::predicate;
+use sea_orm::DbBackend;
+use std::sync::Arc;
+use tonic::{Code, Request};
+
+fn required_role(endpoint: &str) -> Option<&'static str> {
+    ENDPOINT_ROLES
+        .iter()
+        .find(|(name, _)| *name == endpoint)
+        .map(|(_, role)| *role)
+}
+
+#[tokio::test]
+async fn update_project_requires_editor() {
+    assert_eq!(required_role("EcdarApi/UpdateProject"), Some("Editor"));
+}
+
+#[tokio::test]
+async fn delete_project_requires_owner() {
+    assert_eq!(required_role("EcdarApi/DeleteProject"), Some("Owner"));
+}
+
+#[tokio::test]
+async fn create_user_is_public() {
+    assert_eq!(required_role("EcdarApiAuth/CreateUser"), Some("Public"));
+}
+
+#[tokio::test]
+async fn get_users_requires_authentication() {
+    assert_eq!(required_role("EcdarApi/GetUsers"), Some("Authenticated"));
+}
+
+#[tokio::test]
+async fn query_mutation_endpoints_leave_their_configurable_role_to_the_controller() {
+    for endpoint in ["CreateQuery", "UpdateQuery", "DeleteQuery"] {
+        assert_eq!(
+            required_role(&format!("EcdarApi/{}", endpoint)),
+            Some("Authenticated")
+        );
+    }
+}
+
+#[tokio::test]
+async fn deprecated_model_endpoints_require_the_roles_of_their_project_endpoints() {
+    for (model, project) in [
+        ("GetModel", "GetProject"),
+        ("CreateModel", "CreateProject"),
+        ("UpdateModel", "UpdateProject"),
+        ("DeleteModel", "DeleteProject"),
+    ] {
+        assert_eq!(
+            required_role(&format!("EcdarApi/{}", model)),
+            required_role(&format!("EcdarApi/{}", project))
+        );
+    }
+}
+
+fn api(mock_contexts: MockContexts) -> ConcreteEcdarApi {
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(get_mock_services());
+    let config = get_test_config();
+
+    ConcreteEcdarApi::new(ControllerCollection {
+        access_controller: Arc::new(AccessController::new(contexts.clone(), config.clone())),
+        project_controller: Arc::new(ProjectController::new(contexts.clone(), config.clone())),
+        query_controller: Arc::new(QueryController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        session_controller: Arc::new(SessionController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        user_controller: Arc::new(UserController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        reveaal_controller: Arc::new(ReveaalController::new(contexts, services, config.clone())),
+        server_info_controller: Arc::new(ServerInfoController::new(config, DbBackend::Sqlite)),
+    })
+}
+
+/// Returns the API with mocked contexts, where project 1 must be looked up exactly once and does not exist.
+fn api_with_missing_project() -> ConcreteEcdarApi {
+    let mut mock_contexts = get_mock_contexts();
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(None));
+
+    api(mock_contexts)
+}
+
+fn request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(VerifiedUid(1));
+    request
+}
+
+#[tokio::test]
+async fn get_model_routes_to_get_project() {
+    let res = api_with_missing_project()
+        .get_model(request(GetProjectRequest {
+            id: 1,
+            known_version: None,
+            include_query_results: None,
+        }))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+    assert_eq!(res.message(), "No project found with given id");
+}
+
+#[tokio::test]
+async fn update_model_routes_to_update_project() {
+    let res = api_with_missing_project()
+        .update_model(request(UpdateProjectRequest {
+            id: 1,
+            name: None,
+            components_info: None,
+            owner_id: None,
+        }))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+    assert_eq!(res.message(), "No project found with given id");
+}
+
+#[tokio::test]
+async fn delete_model_routes_to_delete_project() {
+    let res = api_with_missing_project()
+        .delete_model(request(DeleteProjectRequest {
+            id: 1,
+            confirmation_token: None,
+        }))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+    assert_eq!(res.message(), "No project found with given id");
+}
+
+#[tokio::test]
+async fn create_model_routes_to_create_project() {
+    let res = api(get_mock_contexts())
+        .create_model(request(CreateProjectRequest {
+            name: " ".to_string(),
+            components_info: None,
+        }))
+        .await
+        .unwrap_err();
+
+    // The name is validated by create_project before anything is looked up
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(
+        res.message(),
+        "Project name must be at least 1 characters long"
+    );
+}
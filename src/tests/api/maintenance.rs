@@ -0,0 +1,63 @@
+use crate::api::maintenance::{is_mutating_endpoint, set_read_only, ReadOnlyLayer};
+use std::convert::Infallible;
+use tonic::body::empty_body;
+use tonic::{Code, Status};
+use tower::{service_fn, Layer, ServiceExt};
+
+async fn call_endpoint(path: &str) -> Option<Status> {
+    let service = ReadOnlyLayer.layer(service_fn(|_: http::Request<()>| async {
+        Ok::<_, Infallible>(http::Response::new(empty_body()))
+    }));
+
+    let request = http::Request::builder().uri(path).body(()).unwrap();
+    let response = service.oneshot(request).await.unwrap();
+
+    Status::from_header_map(response.headers())
+}
+
+#[tokio::test]
+async fn read_only_write_returns_unavailable() {
+    set_read_only(true);
+
+    let status = call_endpoint("/ecdar_proto_buf.EcdarApi/CreateProject").await;
+
+    set_read_only(false);
+
+    assert_eq!(status.unwrap().code(), Code::Unavailable);
+}
+
+#[tokio::test]
+async fn read_only_read_is_served() {
+    set_read_only(true);
+
+    let status = call_endpoint("/ecdar_proto_buf.EcdarApi/GetProject").await;
+
+    set_read_only(false);
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn not_read_only_write_is_served() {
+    set_read_only(false);
+
+    let status = call_endpoint("/ecdar_proto_buf.EcdarApi/DeleteProject").await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn is_mutating_endpoint_classifies_endpoints() {
+    assert!(is_mutating_endpoint(
+        "/ecdar_proto_buf.EcdarApi/UpdateAccess"
+    ));
+    assert!(is_mutating_endpoint(
+        "/ecdar_proto_buf.EcdarApiAuth/CreateUser"
+    ));
+    assert!(!is_mutating_endpoint(
+        "/ecdar_proto_buf.EcdarApi/ListProjectsInfo"
+    ));
+    assert!(!is_mutating_endpoint(
+        "/ecdar_proto_buf.EcdarBackend/SendQuery"
+    ));
+}
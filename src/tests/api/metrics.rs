@@ -0,0 +1,270 @@
+use crate::api::metrics::{ActiveLocksGauge, MetricsLayer, METRICS_PATH};
+use crate::api::metrics_auth::MetricsAccess;
+use crate::controllers::controller_impls::project_controller::LockConfig;
+use crate::services::reveaal_metrics::{ReveaalMetrics, ReveaalOutcome};
+use crate::services::service_impls::ReveaalService;
+use crate::services::service_traits::ReveaalServiceTrait;
+use crate::tests::controllers::helpers::MockInUseContext;
+use http::{Method, StatusCode};
+use sea_orm::DbErr;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::body::{empty_body, BoxBody};
+use tonic::codegen::Body as _;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::Request;
+use tower::{service_fn, Layer, Service, ServiceExt};
+
+fn request(
+    method: Method,
+    path: &str,
+    peer: &str,
+    authorization: Option<&str>,
+) -> http::Request<()> {
+    let mut request = http::Request::builder().method(method).uri(path);
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization);
+    }
+    let mut request = request.body(()).unwrap();
+
+    request.extensions_mut().insert(TcpConnectInfo {
+        local_addr: None,
+        remote_addr: Some(SocketAddr::new(peer.parse().unwrap(), 40000)),
+    });
+    request
+}
+
+/// A gauge counting `count` active locks, whatever the lock settings.
+fn active_locks(count: u64) -> ActiveLocksGauge {
+    let mut in_use_context = MockInUseContext::new();
+    in_use_context
+        .expect_count_active_locks()
+        .returning(move |_| Ok(count));
+
+    ActiveLocksGauge::new(Arc::new(in_use_context), &LockConfig::default())
+}
+
+/// Builds the layer in front of a service answering every request it is passed with `418 I'm a teapot`.
+fn layered(
+    access: MetricsAccess,
+    metrics: Arc<ReveaalMetrics>,
+) -> impl Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible> {
+    layered_with_locks(access, metrics, active_locks(0))
+}
+
+fn layered_with_locks(
+    access: MetricsAccess,
+    metrics: Arc<ReveaalMetrics>,
+    active_locks: ActiveLocksGauge,
+) -> impl Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible> {
+    MetricsLayer::new(access, metrics, active_locks).layer(service_fn(
+        |_: http::Request<()>| async {
+            Ok::<_, Infallible>(
+                http::Response::builder()
+                    .status(StatusCode::IM_A_TEAPOT)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+        },
+    ))
+}
+
+async fn call<S>(service: &mut S, request: http::Request<()>) -> (StatusCode, String)
+where
+    S: Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible>,
+{
+    let response = service.ready().await.unwrap().call(request).await.unwrap();
+    let status = response.status();
+    let mut body = response.into_body();
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+
+    (status, String::from_utf8(bytes).unwrap())
+}
+
+#[tokio::test]
+async fn scrape_with_token_returns_the_counters() {
+    let metrics = Arc::new(ReveaalMetrics::default());
+    metrics.record(ReveaalOutcome::Success);
+    let mut service = layered(MetricsAccess::Token("secret".to_string()), metrics);
+
+    let (status, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "10.0.0.2", Some("Bearer secret")),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("ecdar_reveaal_queries_total{outcome=\"success\"} 1"));
+}
+
+#[tokio::test]
+async fn scrape_counts_queries_sent_through_the_reveaal_service() {
+    let reveaal_service =
+        ReveaalService::with_concurrency_limit("http://127.0.0.1:1", 1, Duration::from_secs(5));
+    let mut service = layered(MetricsAccess::LocalhostOnly, reveaal_service.metrics());
+
+    let res = reveaal_service
+        .send_query(Request::new(Default::default()))
+        .await;
+    let (status, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("ecdar_reveaal_queries_total{outcome=\"transport_error\"} 1"));
+}
+
+#[tokio::test]
+async fn scrape_returns_the_active_locks() {
+    let mut service = layered_with_locks(
+        MetricsAccess::LocalhostOnly,
+        Arc::new(ReveaalMetrics::default()),
+        active_locks(2),
+    );
+
+    let (status, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("# TYPE ecdar_active_locks gauge"));
+    assert!(body.contains("\necdar_active_locks 2\n"));
+}
+
+#[tokio::test]
+async fn scrape_counts_locks_held_within_duration_and_grace_period() {
+    let locks = LockConfig {
+        duration: chrono::Duration::minutes(10),
+        grace_period: chrono::Duration::minutes(2),
+        ..Default::default()
+    };
+    let mut in_use_context = MockInUseContext::new();
+    in_use_context
+        .expect_count_active_locks()
+        .withf(|timeout| *timeout == chrono::Duration::minutes(12))
+        .times(1)
+        .returning(|_| Ok(1));
+    let mut service = layered_with_locks(
+        MetricsAccess::LocalhostOnly,
+        Arc::new(ReveaalMetrics::default()),
+        ActiveLocksGauge::new(Arc::new(in_use_context), &locks),
+    );
+
+    let (_, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert!(body.contains("\necdar_active_locks 1\n"));
+}
+
+#[tokio::test]
+async fn scrape_failed_lock_count_still_returns_the_counters() {
+    let mut in_use_context = MockInUseContext::new();
+    in_use_context
+        .expect_count_active_locks()
+        .returning(|_| Err(DbErr::Custom("connection lost".to_string())));
+    let mut service = layered_with_locks(
+        MetricsAccess::LocalhostOnly,
+        Arc::new(ReveaalMetrics::default()),
+        ActiveLocksGauge::new(Arc::new(in_use_context), &LockConfig::default()),
+    );
+
+    let (status, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("# TYPE ecdar_reveaal_queries_total counter"));
+    assert!(!body.contains("ecdar_active_locks"));
+}
+
+#[tokio::test]
+async fn scrape_without_token_is_unauthorized() {
+    let mut service = layered(
+        MetricsAccess::Token("secret".to_string()),
+        Arc::new(ReveaalMetrics::default()),
+    );
+
+    let (status, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body.is_empty());
+}
+
+#[tokio::test]
+async fn scrape_localhost_only_from_elsewhere_is_forbidden() {
+    let mut service = layered(
+        MetricsAccess::LocalhostOnly,
+        Arc::new(ReveaalMetrics::default()),
+    );
+
+    let (status, _) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "10.0.0.2", None),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn scrape_localhost_only_from_loopback_returns_the_counters() {
+    let mut service = layered(
+        MetricsAccess::LocalhostOnly,
+        Arc::new(ReveaalMetrics::default()),
+    );
+
+    let (status, body) = call(
+        &mut service,
+        request(Method::GET, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("# TYPE ecdar_reveaal_queries_total counter"));
+}
+
+#[tokio::test]
+async fn other_requests_are_passed_on() {
+    let mut service = layered(
+        MetricsAccess::LocalhostOnly,
+        Arc::new(ReveaalMetrics::default()),
+    );
+
+    let (grpc_status, _) = call(
+        &mut service,
+        request(
+            Method::POST,
+            "/ecdar_proto_buf.EcdarApiAuth/GetAuthToken",
+            "10.0.0.2",
+            None,
+        ),
+    )
+    .await;
+    let (post_status, _) = call(
+        &mut service,
+        request(Method::POST, METRICS_PATH, "127.0.0.1", None),
+    )
+    .await;
+
+    assert_eq!(grpc_status, StatusCode::IM_A_TEAPOT);
+    assert_eq!(post_status, StatusCode::IM_A_TEAPOT);
+}
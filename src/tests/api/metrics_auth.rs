@@ -0,0 +1,82 @@
+use crate::api::metrics_auth::{authorize_metrics_request, parse_metrics_token, MetricsAccess};
+use http::{Request, StatusCode};
+use std::net::SocketAddr;
+
+fn scrape(authorization: Option<&str>) -> Request<()> {
+    let mut request = Request::builder().uri("/metrics");
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization);
+    }
+    request.body(()).unwrap()
+}
+
+fn remote(addr: &str) -> Option<SocketAddr> {
+    Some(addr.parse().unwrap())
+}
+
+#[tokio::test]
+async fn token_unauthenticated_scrape_is_rejected() {
+    let access = MetricsAccess::Token("secret".to_string());
+
+    let res = authorize_metrics_request(&access, &scrape(None), remote("10.0.0.2:4000"));
+
+    assert_eq!(res, Err(StatusCode::UNAUTHORIZED));
+}
+
+#[tokio::test]
+async fn token_wrong_token_is_rejected() {
+    let access = MetricsAccess::Token("secret".to_string());
+
+    let res = authorize_metrics_request(
+        &access,
+        &scrape(Some("Bearer secreT")),
+        remote("127.0.0.1:4000"),
+    );
+
+    assert_eq!(res, Err(StatusCode::UNAUTHORIZED));
+}
+
+#[tokio::test]
+async fn token_authenticated_scrape_succeeds() {
+    let access = MetricsAccess::Token("secret".to_string());
+
+    let res = authorize_metrics_request(
+        &access,
+        &scrape(Some("Bearer secret")),
+        remote("10.0.0.2:4000"),
+    );
+
+    assert_eq!(res, Ok(()));
+}
+
+#[tokio::test]
+async fn localhost_only_local_scrape_succeeds() {
+    let res = authorize_metrics_request(
+        &MetricsAccess::LocalhostOnly,
+        &scrape(None),
+        remote("[::1]:4000"),
+    );
+
+    assert_eq!(res, Ok(()));
+}
+
+#[tokio::test]
+async fn localhost_only_remote_scrape_is_rejected() {
+    let res = authorize_metrics_request(
+        &MetricsAccess::LocalhostOnly,
+        &scrape(Some("Bearer secret")),
+        remote("10.0.0.2:4000"),
+    );
+
+    assert_eq!(res, Err(StatusCode::FORBIDDEN));
+}
+
+#[test]
+fn parse_metrics_token_accepts_visible_ascii() {
+    assert_eq!(
+        parse_metrics_token("s3cr3t-token"),
+        Ok(MetricsAccess::Token("s3cr3t-token".to_string()))
+    );
+    assert!(parse_metrics_token("two words").is_err());
+    assert!(parse_metrics_token("tøken").is_err());
+}
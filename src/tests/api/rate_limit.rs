@@ -0,0 +1,114 @@
+use crate::api::rate_limit::{client_ip, RateLimit, RateLimitLayer};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use tonic::body::{empty_body, BoxBody};
+use tonic::transport::server::TcpConnectInfo;
+use tonic::{Code, Status};
+use tower::{service_fn, Layer, Service, ServiceExt};
+
+fn rate_limit(trusted_proxies: Vec<IpAddr>) -> RateLimit {
+    RateLimit {
+        requests_per_second: 1,
+        burst: 2,
+        trusted_proxies,
+    }
+}
+
+fn request_from(peer: &str, forwarded_for: Option<&str>) -> http::Request<()> {
+    let mut request = http::Request::builder().uri("/ecdar_proto_buf.EcdarApiAuth/GetAuthToken");
+    if let Some(forwarded_for) = forwarded_for {
+        request = request.header("x-forwarded-for", forwarded_for);
+    }
+    let mut request = request.body(()).unwrap();
+
+    request.extensions_mut().insert(TcpConnectInfo {
+        local_addr: None,
+        remote_addr: Some(SocketAddr::new(peer.parse().unwrap(), 40000)),
+    });
+    request
+}
+
+async fn call<S>(service: &mut S, request: http::Request<()>) -> Option<Status>
+where
+    S: Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible>,
+{
+    let response = service.ready().await.unwrap().call(request).await.unwrap();
+
+    Status::from_header_map(response.headers())
+}
+
+fn layered(
+    rate_limit: Option<RateLimit>,
+) -> impl Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible> {
+    RateLimitLayer::new(rate_limit).layer(service_fn(|_: http::Request<()>| async {
+        Ok::<_, Infallible>(http::Response::new(empty_body()))
+    }))
+}
+
+#[tokio::test]
+async fn rapid_requests_from_one_ip_are_throttled_while_another_ip_is_served() {
+    let mut service = layered(Some(rate_limit(vec![])));
+
+    assert!(call(&mut service, request_from("10.0.0.2", None))
+        .await
+        .is_none());
+    assert!(call(&mut service, request_from("10.0.0.2", None))
+        .await
+        .is_none());
+    let throttled = call(&mut service, request_from("10.0.0.2", None)).await;
+    let other = call(&mut service, request_from("10.0.0.3", None)).await;
+
+    assert_eq!(throttled.unwrap().code(), Code::ResourceExhausted);
+    assert!(other.is_none());
+}
+
+#[tokio::test]
+async fn disabled_rate_limit_serves_every_request() {
+    let mut service = layered(None);
+
+    for _ in 0..5 {
+        assert!(call(&mut service, request_from("10.0.0.2", None))
+            .await
+            .is_none());
+    }
+}
+
+#[tokio::test]
+async fn clients_behind_trusted_proxy_are_limited_separately() {
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    let mut service = layered(Some(rate_limit(vec![proxy])));
+
+    for _ in 0..2 {
+        call(&mut service, request_from("10.0.0.1", Some("192.0.2.1"))).await;
+    }
+    let throttled = call(&mut service, request_from("10.0.0.1", Some("192.0.2.1"))).await;
+    let other = call(&mut service, request_from("10.0.0.1", Some("192.0.2.2"))).await;
+
+    assert_eq!(throttled.unwrap().code(), Code::ResourceExhausted);
+    assert!(other.is_none());
+}
+
+#[test]
+fn client_ip_ignores_forwarded_header_from_untrusted_peer() {
+    let request = request_from("10.0.0.2", Some("192.0.2.1"));
+
+    assert_eq!(client_ip(&request, &[]), Some("10.0.0.2".parse().unwrap()));
+}
+
+#[test]
+fn client_ip_skips_trusted_proxies_in_forwarded_header() {
+    let proxies: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap(), "10.0.0.5".parse().unwrap()];
+    let request = request_from("10.0.0.1", Some("203.0.113.9, 192.0.2.1, 10.0.0.5"));
+
+    assert_eq!(
+        client_ip(&request, &proxies),
+        Some("192.0.2.1".parse().unwrap())
+    );
+}
+
+#[test]
+fn client_ip_without_connection_info_is_unknown() {
+    let request = http::Request::builder().body(()).unwrap();
+
+    assert_eq!(client_ip(&request, &[]), None);
+}
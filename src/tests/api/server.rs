@@ -0,0 +1,106 @@
+use crate::api::ecdar_api::ConcreteEcdarApi;
+use crate::api::server::parse_compression;
+use crate::api::server::protobuf::ecdar_api_auth_server::EcdarApiAuthServer;
+use crate::controllers::controller_collection::ControllerCollection;
+use crate::controllers::controller_impls::*;
+use crate::tests::controllers::helpers::{
+    disguise_context_mocks, disguise_service_mocks, get_mock_contexts, get_mock_services,
+    get_test_config,
+};
+use sea_orm::DbBackend;
+use std::sync::Arc;
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::Body;
+use tower::ServiceExt;
+
+fn api() -> ConcreteEcdarApi {
+    let contexts = disguise_context_mocks(get_mock_contexts());
+    let services = disguise_service_mocks(get_mock_services());
+    let config = get_test_config();
+
+    ConcreteEcdarApi::new(ControllerCollection {
+        access_controller: Arc::new(AccessController::new(contexts.clone(), config.clone())),
+        project_controller: Arc::new(ProjectController::new(contexts.clone(), config.clone())),
+        query_controller: Arc::new(QueryController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        session_controller: Arc::new(SessionController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        user_controller: Arc::new(UserController::new(
+            contexts.clone(),
+            services.clone(),
+            config.clone(),
+        )),
+        reveaal_controller: Arc::new(ReveaalController::new(contexts, services, config.clone())),
+        server_info_controller: Arc::new(ServerInfoController::new(config, DbBackend::Sqlite)),
+    })
+}
+
+/// Calls the endpoints endpoint, whose response lists every endpoint, as a client accepting `accept_encoding`.
+async fn call_endpoints(
+    compression: Option<CompressionEncoding>,
+    accept_encoding: Option<&str>,
+) -> http::Response<BoxBody> {
+    let server = with_compression!(EcdarApiAuthServer::new(api()), compression);
+
+    let mut request = http::Request::builder()
+        .method("POST")
+        .uri("/ecdar_proto_buf.EcdarApiAuth/Endpoints")
+        .header("content-type", "application/grpc");
+    if let Some(accept_encoding) = accept_encoding {
+        request = request.header("grpc-accept-encoding", accept_encoding);
+    }
+
+    // An uncompressed, empty message
+    let body = tonic::transport::Body::from(vec![0u8; 5]);
+
+    server.oneshot(request.body(body).unwrap()).await.unwrap()
+}
+
+/// Returns whether the first message of a response body is flagged as compressed.
+async fn first_message_is_compressed(response: http::Response<BoxBody>) -> bool {
+    let mut body = response.into_body();
+    let frame = body.data().await.unwrap().unwrap();
+
+    frame[0] == 1
+}
+
+#[tokio::test]
+async fn gzip_enabled_client_accepts_gzip_response_is_compressed() {
+    let response = call_endpoints(Some(CompressionEncoding::Gzip), Some("gzip")).await;
+
+    assert_eq!(response.headers().get("grpc-encoding").unwrap(), "gzip");
+    assert!(first_message_is_compressed(response).await);
+}
+
+#[tokio::test]
+async fn gzip_enabled_client_without_gzip_response_is_not_compressed() {
+    let response = call_endpoints(Some(CompressionEncoding::Gzip), None).await;
+
+    assert!(response.headers().get("grpc-encoding").is_none());
+    assert!(!first_message_is_compressed(response).await);
+}
+
+#[tokio::test]
+async fn compression_disabled_response_is_not_compressed() {
+    let response = call_endpoints(None, Some("gzip")).await;
+
+    assert!(response.headers().get("grpc-encoding").is_none());
+    assert!(!first_message_is_compressed(response).await);
+}
+
+#[test]
+fn parse_compression_accepts_gzip_and_none() {
+    assert_eq!(
+        parse_compression("gzip"),
+        Ok(Some(CompressionEncoding::Gzip))
+    );
+    assert_eq!(parse_compression("none"), Ok(None));
+    assert!(parse_compression("zstd").is_err());
+}
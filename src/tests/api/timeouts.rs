@@ -0,0 +1,103 @@
+use crate::api::timeouts::{parse_endpoint_timeouts, EndpointTimeouts, TimeoutLayer};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::time::Duration;
+use tonic::body::empty_body;
+use tonic::{Code, Status};
+use tower::{service_fn, Layer, ServiceExt};
+
+/// Calls an endpoint that takes 50 ms to answer, behind a [`TimeoutLayer`] with the given timeouts.
+async fn call_slow_endpoint(timeouts: EndpointTimeouts, path: &str) -> Option<Status> {
+    let service = TimeoutLayer::new(timeouts).layer(service_fn(|_: http::Request<()>| async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok::<_, Infallible>(http::Response::new(empty_body()))
+    }));
+
+    let request = http::Request::builder().uri(path).body(()).unwrap();
+    let response = service.oneshot(request).await.unwrap();
+
+    Status::from_header_map(response.headers())
+}
+
+fn get_project_timeout(timeout: Duration) -> EndpointTimeouts {
+    EndpointTimeouts {
+        default: None,
+        endpoints: HashMap::from([("EcdarApi/GetProject".to_string(), timeout)]),
+    }
+}
+
+#[tokio::test]
+async fn endpoint_exceeding_its_timeout_returns_deadline_exceeded() {
+    let status = call_slow_endpoint(
+        get_project_timeout(Duration::from_millis(1)),
+        "/ecdar_proto_buf.EcdarApi/GetProject",
+    )
+    .await;
+
+    assert_eq!(status.unwrap().code(), Code::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn endpoint_without_timeout_is_served() {
+    let status = call_slow_endpoint(
+        get_project_timeout(Duration::from_millis(1)),
+        "/ecdar_proto_buf.EcdarApi/SendQuery",
+    )
+    .await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn endpoint_within_its_timeout_is_served() {
+    let status = call_slow_endpoint(
+        get_project_timeout(Duration::from_secs(10)),
+        "/ecdar_proto_buf.EcdarApi/GetProject",
+    )
+    .await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn default_timeout_applies_to_endpoints_without_own_timeout() {
+    let timeouts = EndpointTimeouts {
+        default: Some(Duration::from_millis(1)),
+        ..get_project_timeout(Duration::from_secs(10))
+    };
+
+    assert_eq!(
+        call_slow_endpoint(timeouts.clone(), "/ecdar_proto_buf.EcdarApi/SendQuery")
+            .await
+            .unwrap()
+            .code(),
+        Code::DeadlineExceeded
+    );
+    assert!(
+        call_slow_endpoint(timeouts, "/ecdar_proto_buf.EcdarApi/GetProject")
+            .await
+            .is_none()
+    );
+}
+
+#[test]
+fn parse_endpoint_timeouts_reads_entries() {
+    let timeouts =
+        parse_endpoint_timeouts(" EcdarApi/GetProject=2, EcdarApi/SendQuery=0.25 ,").unwrap();
+
+    assert_eq!(
+        timeouts,
+        HashMap::from([
+            ("EcdarApi/GetProject".to_string(), Duration::from_secs(2)),
+            ("EcdarApi/SendQuery".to_string(), Duration::from_millis(250)),
+        ])
+    );
+}
+
+#[test]
+fn parse_endpoint_timeouts_rejects_invalid_entries() {
+    assert!(parse_endpoint_timeouts("EcdarApi/GetProject").is_err());
+    assert!(parse_endpoint_timeouts("EcdarApi/Unknown=2").is_err());
+    assert!(parse_endpoint_timeouts("EcdarApi/GetProject=0").is_err());
+    assert!(parse_endpoint_timeouts("EcdarApi/GetProject=soon").is_err());
+}
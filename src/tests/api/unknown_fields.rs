@@ -0,0 +1,113 @@
+use crate::api::server::protobuf::GetProjectRequest;
+use crate::api::unknown_fields::{body_has_unknown_fields, has_unknown_fields, StrictFieldsLayer};
+use prost::Message;
+use std::convert::Infallible;
+use tonic::body::empty_body;
+use tonic::transport::Body;
+use tonic::{Code, Status};
+use tower::{service_fn, Layer, ServiceExt};
+
+/// A field number that no request message declares.
+const UNKNOWN_FIELD_NUMBER: u32 = 1000;
+
+fn encoded_request(unknown_field: bool) -> Vec<u8> {
+    let mut message = GetProjectRequest {
+        id: 1,
+        known_version: None,
+        include_query_results: None,
+    }
+    .encode_to_vec();
+
+    if unknown_field {
+        prost::encoding::uint32::encode(UNKNOWN_FIELD_NUMBER, &7, &mut message);
+    }
+    message
+}
+
+/// Wraps a message in the gRPC message prefix.
+fn grpc_body(message: &[u8], compressed: bool) -> Vec<u8> {
+    let mut body = vec![u8::from(compressed)];
+    body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    body.extend_from_slice(message);
+    body
+}
+
+async fn call_endpoint(enabled: bool, path: &str, body: Vec<u8>) -> Option<Status> {
+    let service =
+        StrictFieldsLayer::new(enabled).layer(service_fn(|_: http::Request<Body>| async {
+            Ok::<_, Infallible>(http::Response::new(empty_body()))
+        }));
+
+    let request = http::Request::builder()
+        .uri(path)
+        .body(Body::from(body))
+        .unwrap();
+    let response = service.oneshot(request).await.unwrap();
+
+    Status::from_header_map(response.headers())
+}
+
+#[test]
+fn has_unknown_fields_known_fields_returns_false() {
+    assert!(!has_unknown_fields::<GetProjectRequest>(&encoded_request(
+        false
+    )));
+}
+
+#[test]
+fn has_unknown_fields_unknown_field_returns_true() {
+    assert!(has_unknown_fields::<GetProjectRequest>(&encoded_request(
+        true
+    )));
+}
+
+#[test]
+fn has_unknown_fields_invalid_message_returns_false() {
+    assert!(!has_unknown_fields::<GetProjectRequest>(&[0xff]));
+}
+
+#[test]
+fn body_has_unknown_fields_compressed_message_returns_false() {
+    let body = grpc_body(&encoded_request(true), true);
+
+    assert!(!body_has_unknown_fields(
+        &body,
+        has_unknown_fields::<GetProjectRequest>
+    ));
+}
+
+#[tokio::test]
+async fn strict_unknown_field_returns_invalid_argument() {
+    let body = grpc_body(&encoded_request(true), false);
+
+    let status = call_endpoint(true, "/ecdar_proto_buf.EcdarApi/GetProject", body).await;
+
+    assert_eq!(status.unwrap().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn strict_known_fields_is_served() {
+    let body = grpc_body(&encoded_request(false), false);
+
+    let status = call_endpoint(true, "/ecdar_proto_buf.EcdarApi/GetProject", body).await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn lenient_unknown_field_is_served() {
+    let body = grpc_body(&encoded_request(true), false);
+
+    let status = call_endpoint(false, "/ecdar_proto_buf.EcdarApi/GetProject", body).await;
+
+    assert!(status.is_none());
+}
+
+#[tokio::test]
+async fn strict_unknown_endpoint_is_served() {
+    let body = grpc_body(&encoded_request(true), false);
+
+    let status = call_endpoint(true, "/ecdar_proto_buf.EcdarApi/NotAnEndpoint", body).await;
+
+    assert!(status.is_none());
+}
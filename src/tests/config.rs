@@ -0,0 +1,444 @@
+use crate::api::metrics_auth::MetricsAccess;
+use crate::config::Config;
+use crate::contexts::components_storage::ComponentsStorage;
+use crate::contexts::context_impls::query_result_context::DEFAULT_QUERY_RESULT_HISTORY_DEPTH;
+use crate::contexts::pagination::DEFAULT_MAX_PAGE_SIZE;
+use crate::contexts::readiness::DEFAULT_READY_TIMEOUT;
+use crate::controllers::controller_impls::access_controller::DuplicateAccessPolicy;
+use std::collections::HashMap;
+use std::time::Duration;
+use tonic::codec::CompressionEncoding;
+
+fn valid_vars() -> HashMap<&'static str, &'static str> {
+    HashMap::from([
+        ("DATABASE_URL", "sqlite::memory:"),
+        ("REVEAAL_ADDRESS", "http://127.0.0.1:4000"),
+        ("API_ADDRESS", "127.0.0.1:5000"),
+        ("ACCESS_TOKEN_HS512_SECRET", "access_secret"),
+        ("REFRESH_TOKEN_HS512_SECRET", "refresh_secret"),
+    ])
+}
+
+fn load(vars: HashMap<&'static str, &'static str>) -> Result<Config, Vec<String>> {
+    Config::from_lookup(|name| vars.get(name).map(|value| value.to_string()))
+        .map_err(|err| err.problems)
+}
+
+#[test]
+fn from_lookup_valid_vars_returns_config() {
+    let config = load(valid_vars()).unwrap();
+
+    assert_eq!(
+        config,
+        Config {
+            database_url: "sqlite::memory:".to_string(),
+            reveaal_address: "http://127.0.0.1:4000".to_string(),
+            api_address: "127.0.0.1:5000".parse().unwrap(),
+            read_only: false,
+            require_authentication: false,
+            compression: None,
+            rate_limit: None,
+            strict_unknown_fields: false,
+            endpoint_timeouts: Default::default(),
+            metrics_access: MetricsAccess::LocalhostOnly,
+            database_ready_timeout: DEFAULT_READY_TIMEOUT,
+            log_statements: false,
+            components_storage: ComponentsStorage::Json,
+            query_result_history_depth: DEFAULT_QUERY_RESULT_HISTORY_DEPTH,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            legacy_not_found_on_empty_list: false,
+            allowed_origins: vec![],
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+            duplicate_access_policy: DuplicateAccessPolicy::Reject,
+            reveaal_proxy_methods: vec![],
+            response_size_warning: Default::default(),
+            reveaal: Default::default(),
+            locks: Default::default(),
+            projects: Default::default(),
+            queries: Default::default(),
+            sessions: Default::default(),
+            users: Default::default(),
+        }
+    );
+}
+
+#[test]
+fn from_lookup_reads_optional_vars() {
+    let mut vars = valid_vars();
+    vars.insert("READ_ONLY", "true");
+    vars.insert("REQUIRE_AUTHENTICATION", "true");
+    vars.insert("STRICT_UNKNOWN_FIELDS", "true");
+    vars.insert("BCRYPT_COST", "10");
+    vars.insert("PROJECT_NAME_MAX_LENGTH", "64");
+
+    let config = load(vars).unwrap();
+
+    assert!(config.read_only);
+    assert!(config.require_authentication);
+    assert!(config.strict_unknown_fields);
+    assert_eq!(config.bcrypt_cost, 10);
+    assert_eq!(config.projects.name_max_length, 64);
+}
+
+#[test]
+fn from_lookup_reads_runtime_settings() {
+    let mut vars = valid_vars();
+    vars.insert("IN_USE_DURATION_MINUTES", "20");
+    vars.insert("IN_USE_GRACE_MINUTES", "2");
+    vars.insert("SESSION_MAX_LIFETIME_HOURS", "24");
+    vars.insert("REVEAAL_TIMEOUT_SECS", "5");
+    vars.insert("MAX_PAGE_SIZE", "50");
+    vars.insert("ALLOWED_EMAIL_DOMAINS", "Example.com, ecdar.dk");
+    vars.insert("ALLOWED_ORIGINS", "https://ecdar.example.com/");
+    vars.insert("PROJECT_NAME_DISALLOWED_CHARACTERS", "/\\");
+
+    let config = load(vars).unwrap();
+
+    assert_eq!(config.locks.duration, chrono::Duration::minutes(20));
+    assert_eq!(config.locks.grace_period, chrono::Duration::minutes(2));
+    assert_eq!(
+        config.sessions.max_lifetime,
+        Some(chrono::Duration::hours(24))
+    );
+    assert_eq!(config.queries.reveaal_timeout, Duration::from_secs(5));
+    assert_eq!(config.max_page_size, 50);
+    assert_eq!(
+        config.users.allowed_email_domains,
+        vec!["example.com".to_string(), "ecdar.dk".to_string()]
+    );
+    assert_eq!(
+        config.allowed_origins,
+        vec!["https://ecdar.example.com".to_string()]
+    );
+    assert_eq!(config.projects.name_disallowed_characters, "/\\");
+}
+
+#[test]
+fn from_lookup_zero_session_max_lifetime_is_unlimited() {
+    let mut vars = valid_vars();
+    vars.insert("SESSION_MAX_LIFETIME_HOURS", "0");
+
+    let config = load(vars).unwrap();
+
+    assert_eq!(config.sessions.max_lifetime, None);
+}
+
+#[test]
+fn from_lookup_negative_session_max_lifetime_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("SESSION_MAX_LIFETIME_HOURS", "-1");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("SESSION_MAX_LIFETIME_HOURS"));
+}
+
+#[test]
+fn from_lookup_out_of_range_duration_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("SESSION_MAX_LIFETIME_HOURS", "9223372036854775807");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("SESSION_MAX_LIFETIME_HOURS is out of range"));
+}
+
+#[test]
+fn from_lookup_invalid_allowed_email_domain_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("ALLOWED_EMAIL_DOMAINS", "example.com,user@example.com");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("ALLOWED_EMAIL_DOMAINS"));
+}
+
+#[test]
+fn from_lookup_invalid_allowed_origin_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("ALLOWED_ORIGINS", "ecdar.example.com");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("ALLOWED_ORIGINS"));
+}
+
+#[test]
+fn from_lookup_whitespace_in_disallowed_characters_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("PROJECT_NAME_DISALLOWED_CHARACTERS", "/ \\");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("PROJECT_NAME_DISALLOWED_CHARACTERS"));
+}
+
+#[test]
+fn from_lookup_empty_optional_var_is_unset() {
+    let mut vars = valid_vars();
+    vars.insert("DEFAULT_COMPONENTS_INFO", "");
+    vars.insert("MAX_QUERIES_PER_PROJECT", "");
+
+    assert!(load(vars).is_ok());
+}
+
+#[test]
+fn from_lookup_missing_vars_lists_all() {
+    let mut vars = valid_vars();
+    vars.remove("DATABASE_URL");
+    vars.remove("ACCESS_TOKEN_HS512_SECRET");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(
+        problems,
+        vec![
+            "DATABASE_URL is not set".to_string(),
+            "ACCESS_TOKEN_HS512_SECRET is not set".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn from_lookup_empty_required_var_is_missing() {
+    let mut vars = valid_vars();
+    vars.insert("REVEAAL_ADDRESS", "");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems, vec!["REVEAAL_ADDRESS is not set".to_string()]);
+}
+
+#[test]
+fn from_lookup_invalid_values_lists_all() {
+    let mut vars = valid_vars();
+    vars.insert("API_ADDRESS", "not an address");
+    vars.insert("REVEAAL_TIMEOUT_SECS", "-1");
+    vars.insert("READ_ONLY", "yes");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 3);
+    assert!(problems[0].starts_with("API_ADDRESS"));
+    assert!(problems[1].starts_with("READ_ONLY"));
+    assert!(problems[2].starts_with("REVEAAL_TIMEOUT_SECS"));
+}
+
+#[test]
+fn from_lookup_invalid_session_limit_policy_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("SESSION_LIMIT_POLICY", "evict_newest");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("SESSION_LIMIT_POLICY"));
+}
+
+#[test]
+fn from_lookup_username_max_length_above_column_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("USERNAME_MAX_LENGTH", "33");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("USERNAME_MAX_LENGTH"));
+}
+
+#[test]
+fn from_lookup_gzip_compression_returns_config() {
+    let mut vars = valid_vars();
+    vars.insert("GRPC_COMPRESSION", "gzip");
+
+    let config = load(vars).unwrap();
+
+    assert_eq!(config.compression, Some(CompressionEncoding::Gzip));
+}
+
+#[test]
+fn from_lookup_unsupported_compression_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("GRPC_COMPRESSION", "brotli");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("GRPC_COMPRESSION"));
+}
+
+#[test]
+fn from_lookup_min_length_above_max_length_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("PROJECT_NAME_MIN_LENGTH", "10");
+    vars.insert("PROJECT_NAME_MAX_LENGTH", "5");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("PROJECT_NAME_MIN_LENGTH"));
+}
+
+#[test]
+fn from_lookup_in_use_warn_not_below_duration_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("IN_USE_DURATION_MINUTES", "10");
+    vars.insert("IN_USE_WARN_MINUTES", "10");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("IN_USE_WARN_MINUTES"));
+}
+
+#[test]
+fn from_lookup_bcrypt_cost_out_of_range_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("BCRYPT_COST", "40");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("BCRYPT_COST"));
+}
+
+#[test]
+fn from_lookup_invalid_default_components_info_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("DEFAULT_COMPONENTS_INFO", "{not json");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("DEFAULT_COMPONENTS_INFO"));
+}
+
+#[test]
+fn config_error_display_lists_problems() {
+    let mut vars = valid_vars();
+    vars.remove("DATABASE_URL");
+    vars.remove("REVEAAL_ADDRESS");
+
+    let err =
+        Config::from_lookup(|name| vars.get(name).map(|value| value.to_string())).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "invalid configuration:\n  - DATABASE_URL is not set\n  - REVEAAL_ADDRESS is not set"
+    );
+}
+
+#[test]
+fn from_lookup_rate_limit_returns_config() {
+    let mut vars = valid_vars();
+    vars.insert("RATE_LIMIT_REQUESTS_PER_SECOND", "5");
+    vars.insert("RATE_LIMIT_TRUSTED_PROXIES", "10.0.0.1, ::1");
+
+    let rate_limit = load(vars).unwrap().rate_limit.unwrap();
+
+    assert_eq!(rate_limit.requests_per_second, 5);
+    assert_eq!(rate_limit.burst, 5);
+    assert_eq!(
+        rate_limit.trusted_proxies,
+        vec![
+            "10.0.0.1".parse::<std::net::IpAddr>().unwrap(),
+            "::1".parse().unwrap()
+        ]
+    );
+}
+
+#[test]
+fn from_lookup_invalid_trusted_proxy_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("RATE_LIMIT_TRUSTED_PROXIES", "10.0.0.1,proxy.local");
+
+    let problems = load(vars).unwrap_err();
+
+    assert!(problems[0].contains("RATE_LIMIT_TRUSTED_PROXIES"));
+}
+
+#[test]
+fn from_lookup_unknown_reveaal_setting_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert(
+        "REVEAAL_ALLOWED_SETTINGS",
+        "disable_clock_reduction,max_search_depth",
+    );
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("REVEAAL_ALLOWED_SETTINGS"));
+}
+
+#[test]
+fn from_lookup_invalid_reveaal_proxy_method_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("REVEAAL_PROXY_METHODS", "SendQuery,../Admin");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("REVEAAL_PROXY_METHODS"));
+}
+
+#[test]
+fn from_lookup_reads_endpoint_timeouts() {
+    let mut vars = valid_vars();
+    vars.insert("ENDPOINT_TIMEOUTS", "EcdarApi/GetProject=0.5");
+    vars.insert("ENDPOINT_TIMEOUT_DEFAULT_SECS", "60");
+
+    let config = load(vars).unwrap();
+
+    assert_eq!(
+        config
+            .endpoint_timeouts
+            .endpoints
+            .get("EcdarApi/GetProject"),
+        Some(&Duration::from_millis(500))
+    );
+    assert_eq!(
+        config.endpoint_timeouts.default,
+        Some(Duration::from_secs(60))
+    );
+}
+
+#[test]
+fn from_lookup_unknown_endpoint_timeout_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("ENDPOINT_TIMEOUTS", "EcdarApi/Unknown=5");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("ENDPOINT_TIMEOUTS"));
+}
+
+#[test]
+fn from_lookup_metrics_token_requires_it_for_scrapes() {
+    let mut vars = valid_vars();
+    vars.insert("METRICS_TOKEN", "secret");
+
+    let config = load(vars).unwrap();
+
+    assert_eq!(
+        config.metrics_access,
+        MetricsAccess::Token("secret".to_string())
+    );
+}
+
+#[test]
+fn from_lookup_metrics_token_with_whitespace_returns_err() {
+    let mut vars = valid_vars();
+    vars.insert("METRICS_TOKEN", "not a token");
+
+    let problems = load(vars).unwrap_err();
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("METRICS_TOKEN"));
+}
@@ -1,7 +1,8 @@
 use crate::api::server::protobuf::AccessInfo;
 use crate::contexts::context_traits::{AccessContextTrait, EntityContextTrait};
+use crate::contexts::pagination::DEFAULT_MAX_PAGE_SIZE;
 use crate::tests::contexts::helpers::{
-    create_accesses, create_projects, create_users, get_reset_database_context,
+    create_accesses, create_entities, create_projects, create_users, get_reset_database_context,
 };
 use crate::{
     contexts::context_impls::AccessContext,
@@ -31,6 +32,15 @@ async fn seed_db() -> (AccessContext, access::Model, user::Model, project::Model
     (access_context, access, user, project)
 }
 
+/// Clears the audit columns, which are set by the context and therefore differ from the input.
+fn without_timestamps(access: access::Model) -> access::Model {
+    access::Model {
+        created_at: None,
+        updated_at: None,
+        ..access
+    }
+}
+
 // Test the functionality of the 'create' function, which creates a access in the contexts
 #[tokio::test]
 async fn create_test() {
@@ -45,10 +55,20 @@ async fn create_test() {
         .unwrap();
 
     // Assert if the fetched access is the same as the created access
-    assert_eq!(access, created_access);
+    assert_eq!(access, without_timestamps(created_access.clone()));
     assert_eq!(fetched_access, created_access);
 }
 
+#[tokio::test]
+async fn create_sets_timestamps_test() {
+    let (access_context, access, _, _) = seed_db().await;
+
+    let created_access = access_context.create(access).await.unwrap();
+
+    assert!(created_access.created_at.is_some());
+    assert_eq!(created_access.created_at, created_access.updated_at);
+}
+
 #[tokio::test]
 async fn create_check_unique_pair_project_id_user_id_test() {
     let (access_context, access, _, _) = seed_db().await;
@@ -94,6 +114,8 @@ async fn create_auto_increment_test() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let access_2 = access::Model {
@@ -101,6 +123,8 @@ async fn create_auto_increment_test() {
         role: "Editor".to_string(),
         project_id: 2,
         user_id: user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let created_access1 = access_context.create(access_1.clone()).await.unwrap();
@@ -201,10 +225,29 @@ async fn update_test() {
         .unwrap()
         .unwrap();
 
-    assert_eq!(new_access, updated_access);
+    assert_eq!(new_access, without_timestamps(updated_access.clone()));
     assert_eq!(updated_access, fetched_access);
 }
 
+#[tokio::test]
+async fn update_bumps_updated_at_test() {
+    let (access_context, access, _, _) = seed_db().await;
+
+    let created_access = access_context.create(access).await.unwrap();
+
+    let updated_access = access_context
+        .update(access::Model {
+            role: "Reader".into(),
+            ..created_access.clone()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(updated_access.created_at, created_access.created_at);
+    assert!(updated_access.updated_at >= created_access.updated_at);
+    assert!(updated_access.updated_at.is_some());
+}
+
 #[tokio::test]
 async fn update_modifies_role_test() {
     let (access_context, access, _, _) = seed_db().await;
@@ -262,7 +305,7 @@ async fn update_does_not_modify_project_id_test() {
     };
     let res = access_context.update(updated_access.clone()).await.unwrap();
 
-    assert_eq!(access, res);
+    assert_eq!(access, without_timestamps(res));
 }
 
 #[tokio::test]
@@ -280,7 +323,7 @@ async fn update_does_not_modify_user_id_test() {
     };
     let res = access_context.update(updated_access.clone()).await.unwrap();
 
-    assert_eq!(access, res);
+    assert_eq!(access, without_timestamps(res));
 }
 
 #[tokio::test]
@@ -364,13 +407,14 @@ async fn get_by_uid_and_project_id_test() {
 
 #[tokio::test]
 async fn get_access_by_project_id_test_returns_ok() {
-    let (access_context, expected_access, _, model) = seed_db().await;
+    let (access_context, expected_access, user, model) = seed_db().await;
 
     let expected_access_access_info_vector = vec![AccessInfo {
         id: expected_access.id,
         project_id: expected_access.project_id,
         user_id: expected_access.user_id,
         role: expected_access.role.clone(),
+        display_name: user.username,
     }];
 
     access::Entity::insert(expected_access.clone().into_active_model())
@@ -378,16 +422,416 @@ async fn get_access_by_project_id_test_returns_ok() {
         .await
         .unwrap();
 
-    let access = access_context.get_access_by_project_id(model.id).await;
+    let access = access_context
+        .get_access_by_project_id(model.id, 0, 10)
+        .await;
 
-    assert!(access.unwrap() == expected_access_access_info_vector);
+    assert!(access.unwrap().items == expected_access_access_info_vector);
 }
 
 #[tokio::test]
 async fn get_access_by_project_id_test_returns_empty() {
     let (access_context, _, _, model) = seed_db().await;
 
-    let access = access_context.get_access_by_project_id(model.id).await;
+    let access = access_context
+        .get_access_by_project_id(model.id, 0, 10)
+        .await;
+
+    assert!(access.unwrap().items.is_empty());
+}
+
+#[tokio::test]
+async fn get_access_by_project_id_has_next_test() {
+    let (access_context, _, user, project) = seed_db().await;
+
+    let users = create_users(3);
+
+    user::Entity::insert_many(to_active_models!(users[1..].to_vec()))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let accesses = create_entities(3, |i| access::Model {
+        id: i + 1,
+        role: "Reader".into(),
+        project_id: project.id,
+        user_id: user.id + i,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    });
+
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let first_page = access_context
+        .get_access_by_project_id(project.id, 0, 2)
+        .await
+        .unwrap();
+    let last_page = access_context
+        .get_access_by_project_id(project.id, 1, 2)
+        .await
+        .unwrap();
+
+    assert_eq!(first_page.items.len(), 2);
+    assert_eq!(first_page.page_info.total, 3);
+    assert!(first_page.page_info.has_next);
+    assert_eq!(last_page.items.len(), 1);
+    assert!(!last_page.page_info.has_next);
+}
+
+#[tokio::test]
+async fn get_access_by_project_id_clamps_page_size_test() {
+    let (access_context, access, _, project) = seed_db().await;
+
+    access::Entity::insert(access.into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let page = access_context
+        .get_access_by_project_id(project.id, 0, DEFAULT_MAX_PAGE_SIZE + 1)
+        .await
+        .unwrap();
+
+    assert_eq!(page.page_info.page_size, DEFAULT_MAX_PAGE_SIZE);
+    assert_eq!(page.items.len(), 1);
+}
+
+#[tokio::test]
+async fn get_access_by_project_id_clamps_to_configured_max_page_size_test() {
+    let (access_context, access, _, project) = seed_db().await;
+
+    access::Entity::insert(access.into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+    let access_context = AccessContext::with_max_page_size(access_context.db_context, 1);
+
+    let page = access_context
+        .get_access_by_project_id(project.id, 0, 10)
+        .await
+        .unwrap();
+
+    assert_eq!(page.page_info.page_size, 1);
+}
+
+#[tokio::test]
+async fn get_access_by_project_id_same_role_across_pages_test() {
+    let (access_context, _, user, project) = seed_db().await;
+
+    let users = create_users(5);
+
+    user::Entity::insert_many(to_active_models!(users[1..].to_vec()))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let accesses = create_entities(5, |i| access::Model {
+        id: i + 1,
+        role: "Reader".into(),
+        project_id: project.id,
+        user_id: user.id + i,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    });
+
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let mut ids = vec![];
+    for page in 0..3 {
+        let page = access_context
+            .get_access_by_project_id(project.id, page, 2)
+            .await
+            .unwrap();
+        ids.extend(page.items.into_iter().map(|access| access.id));
+    }
+
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn get_accessible_project_ids_test() {
+    let (access_context, _, user, _) = seed_db().await;
+
+    let other_user = create_users(2)[1].clone();
+    let projects = create_projects(3, user.id);
+
+    user::Entity::insert(other_user.clone().into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert_many(to_active_models!(projects[1..].to_vec()))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let accesses = vec![
+        access::Model {
+            id: 1,
+            role: "Editor".into(),
+            project_id: projects[2].id,
+            user_id: user.id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        },
+        access::Model {
+            id: 2,
+            role: "Reader".into(),
+            project_id: projects[0].id,
+            user_id: user.id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        },
+        access::Model {
+            id: 3,
+            role: "Reader".into(),
+            project_id: projects[1].id,
+            user_id: other_user.id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        },
+    ];
+
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let project_ids = access_context
+        .get_accessible_project_ids(user.id)
+        .await
+        .unwrap();
+
+    assert_eq!(project_ids, vec![projects[0].id, projects[2].id]);
+}
+
+#[tokio::test]
+async fn get_accessible_project_ids_returns_empty_test() {
+    let (access_context, _, user, _) = seed_db().await;
+
+    let project_ids = access_context
+        .get_accessible_project_ids(user.id)
+        .await
+        .unwrap();
+
+    assert!(project_ids.is_empty());
+}
+
+#[tokio::test]
+async fn update_roles_test() {
+    let (access_context, _, user, project) = seed_db().await;
+
+    let users = create_users(2)[1..].to_vec();
+    let accesses = vec![
+        access::Model {
+            id: 1,
+            role: "Editor".into(),
+            project_id: project.id,
+            user_id: user.id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        },
+        access::Model {
+            id: 2,
+            role: "Editor".into(),
+            project_id: project.id,
+            user_id: users[0].id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        },
+    ];
+
+    user::Entity::insert_many(to_active_models!(users))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let new_accesses: Vec<access::Model> = accesses
+        .into_iter()
+        .map(|access| access::Model {
+            role: "Reader".into(),
+            ..access
+        })
+        .collect();
+
+    access_context
+        .update_roles(new_accesses.clone())
+        .await
+        .unwrap();
+
+    let fetched_accesses = access::Entity::find()
+        .all(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert!(fetched_accesses
+        .iter()
+        .all(|access| access.role == "Reader" && access.updated_at.is_some()));
+}
+
+#[tokio::test]
+async fn update_roles_non_existing_id_rolls_back_test() {
+    let (access_context, access, _, _) = seed_db().await;
+
+    access::Entity::insert(access.clone().into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let res = access_context
+        .update_roles(vec![
+            access::Model {
+                role: "Editor".into(),
+                ..access.clone()
+            },
+            access::Model {
+                id: access.id + 1,
+                role: "Editor".into(),
+                ..access.clone()
+            },
+        ])
+        .await;
+
+    let fetched_access = access::Entity::find_by_id(access.id)
+        .one(&access_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(matches!(res.unwrap_err(), DbErr::RecordNotUpdated));
+    assert_eq!(fetched_access, access);
+}
+
+#[tokio::test]
+async fn get_access_by_ids_test() {
+    let (access_context, _, user, project) = seed_db().await;
+
+    let accesses = create_accesses(1, user.id, project.id);
+    let other_user = create_users(2)[1].clone();
+    let other_project = create_projects(2, user.id)[1].clone();
+
+    user::Entity::insert(other_user.clone().into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert(other_project.clone().into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let accesses = vec![
+        accesses[0].clone(),
+        access::Model {
+            id: 2,
+            project_id: other_project.id,
+            ..accesses[0].clone()
+        },
+        access::Model {
+            id: 3,
+            user_id: other_user.id,
+            ..accesses[0].clone()
+        },
+    ];
+
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let ids: Vec<i32> = access_context
+        .get_access_by_ids(vec![3, 1, 4])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|access| access.id)
+        .collect();
+
+    assert_eq!(ids, vec![1, 3]);
+}
+
+#[tokio::test]
+async fn get_access_by_uid_and_project_ids_test() {
+    let (access_context, _, user, project) = seed_db().await;
+
+    let accesses = create_accesses(1, user.id, project.id);
+    let other_user = create_users(2)[1].clone();
+    let projects = create_projects(3, user.id);
+
+    user::Entity::insert(other_user.clone().into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert_many(to_active_models!(projects[1..].to_vec()))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let accesses = vec![
+        accesses[0].clone(),
+        access::Model {
+            id: 2,
+            project_id: projects[1].id,
+            ..accesses[0].clone()
+        },
+        access::Model {
+            id: 3,
+            project_id: projects[2].id,
+            ..accesses[0].clone()
+        },
+        access::Model {
+            id: 4,
+            user_id: other_user.id,
+            ..accesses[0].clone()
+        },
+    ];
+
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let mut ids: Vec<i32> = access_context
+        .get_access_by_uid_and_project_ids(user.id, vec![project.id, projects[1].id])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|access| access.id)
+        .collect();
+    ids.sort();
+
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn get_access_by_project_id_uses_display_name() {
+    let (access_context, access, user, project) = seed_db().await;
+
+    user::Entity::update(user::ActiveModel {
+        id: sea_orm::Unchanged(user.id),
+        display_name: sea_orm::Set(Some("Anders And".to_string())),
+        ..Default::default()
+    })
+    .exec(&access_context.db_context.get_connection())
+    .await
+    .unwrap();
+    access::Entity::insert(access.into_active_model())
+        .exec(&access_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let page = access_context
+        .get_access_by_project_id(project.id, 0, 10)
+        .await
+        .unwrap();
 
-    assert!(access.unwrap().is_empty());
+    assert_eq!(page.items[0].display_name, "Anders And");
 }
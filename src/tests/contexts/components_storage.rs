@@ -0,0 +1,76 @@
+use crate::contexts::components_storage::{
+    assemble_components, split_components, stored_component_name, ComponentsStorage,
+};
+use serde_json::json;
+
+#[test]
+fn parse_components_storage() {
+    assert_eq!("json".parse(), Ok(ComponentsStorage::Json));
+    assert_eq!("rows".parse(), Ok(ComponentsStorage::Rows));
+    assert!("columns".parse::<ComponentsStorage>().is_err());
+}
+
+#[test]
+fn split_components_keeps_hash_in_components_info() {
+    let components_info = json!({
+        "components": [{ "rep": { "Json": "{\"name\":\"A\"}" } }],
+        "components_hash": 42
+    });
+
+    let (components_info, components) = split_components(components_info);
+
+    assert_eq!(
+        components_info,
+        json!({ "components": [], "components_hash": 42 })
+    );
+    assert_eq!(
+        components,
+        vec![json!({ "rep": { "Json": "{\"name\":\"A\"}" } })]
+    );
+}
+
+#[test]
+fn assemble_components_reverses_split() {
+    let components_info = json!({
+        "components": [{ "rep": { "Json": "{\"name\":\"A\"}" } }, { "rep": { "Json": "{\"name\":\"B\"}" } }],
+        "components_hash": 42
+    });
+
+    let (stripped, components) = split_components(components_info.clone());
+
+    assert_eq!(assemble_components(stripped, components), components_info);
+}
+
+#[test]
+fn assemble_components_without_rows_keeps_components_info() {
+    let components_info = json!({
+        "components": [{ "rep": { "Json": "{\"name\":\"A\"}" } }],
+        "components_hash": 42
+    });
+
+    assert_eq!(
+        assemble_components(components_info.clone(), vec![]),
+        components_info
+    );
+}
+
+#[test]
+fn json_storage_does_not_split() {
+    let components_info = json!({
+        "components": [{ "rep": { "Json": "{\"name\":\"A\"}" } }],
+        "components_hash": 42
+    });
+
+    let (stored, components) = ComponentsStorage::Json.split(components_info.clone());
+
+    assert_eq!(stored, components_info);
+    assert!(components.is_empty());
+}
+
+#[test]
+fn stored_component_name_reads_name() {
+    let component = json!({ "rep": { "Json": "{\"name\":\"A\"}" } });
+
+    assert_eq!(stored_component_name(&component), Some("A".to_string()));
+    assert_eq!(stored_component_name(&json!({})), None);
+}
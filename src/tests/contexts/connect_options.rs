@@ -0,0 +1,59 @@
+use crate::contexts::connect_options::connect_options;
+use log::{LevelFilter, Log, Metadata, Record};
+use sea_orm::{ConnectionTrait, Database};
+use std::sync::{Mutex, PoisonError};
+
+/// Collects the messages sqlx logs, so tests can check which statements were logged.
+struct StatementLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+impl Log for StatementLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("sqlx")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.messages
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StatementLogger = StatementLogger {
+    messages: Mutex::new(vec![]),
+};
+
+/// Runs a query containing `marker` and returns whether a logged message mentions it.
+async fn run_query_and_check_log(log_statements: bool, marker: &str) -> bool {
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(LevelFilter::Debug);
+
+    let db = Database::connect(connect_options("sqlite::memory:", log_statements))
+        .await
+        .unwrap();
+    db.execute_unprepared(&format!("SELECT 1 AS {}", marker))
+        .await
+        .unwrap();
+
+    let messages = LOGGER
+        .messages
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    messages.iter().any(|message| message.contains(marker))
+}
+
+#[tokio::test]
+async fn connect_options_log_statements_logs_query() {
+    assert!(run_query_and_check_log(true, "logged_marker").await);
+}
+
+#[tokio::test]
+async fn connect_options_without_log_statements_logs_nothing() {
+    assert!(!run_query_and_check_log(false, "unlogged_marker").await);
+}
@@ -0,0 +1,247 @@
+//! Runs the same uniqueness and foreign key scenarios against every configured database backend,
+//! as SQLite and PostgreSQL report constraint violations differently.
+//!
+//! SQLite always runs in memory. PostgreSQL runs when `TEST_POSTGRES_DATABASE_URL` is set,
+//! or when `TEST_DATABASE_URL` points at a PostgreSQL database.
+use crate::contexts::context_impls::{
+    AccessContext, PostgresDatabaseContext, ProjectContext, SQLiteDatabaseContext, UserContext,
+};
+use crate::contexts::context_traits::{DatabaseContextTrait, EntityContextTrait};
+use crate::entities::{access, project, user};
+use crate::tests::contexts::helpers::{create_projects, create_users};
+use dotenv::dotenv;
+use sea_orm::{DbErr, SqlErr};
+use std::env;
+use std::sync::Arc;
+
+pub(super) async fn get_reset_database_contexts(
+) -> Vec<(&'static str, Arc<dyn DatabaseContextTrait>)> {
+    dotenv().ok();
+
+    let sqlite: Arc<dyn DatabaseContextTrait> = Arc::new(
+        SQLiteDatabaseContext::new("sqlite::memory:", false)
+            .await
+            .unwrap(),
+    );
+    let mut db_contexts = vec![("sqlite", sqlite.reset().await.unwrap())];
+
+    let postgres_url = env::var("TEST_POSTGRES_DATABASE_URL").ok().or_else(|| {
+        env::var("TEST_DATABASE_URL")
+            .ok()
+            .filter(|url| url.starts_with("postgres"))
+    });
+    if let Some(url) = postgres_url {
+        let postgres: Arc<dyn DatabaseContextTrait> =
+            Arc::new(PostgresDatabaseContext::new(&url, false).await.unwrap());
+        db_contexts.push(("postgres", postgres.reset().await.unwrap()));
+    }
+
+    db_contexts
+}
+
+fn assert_unique_violation(backend: &str, result: Result<impl std::fmt::Debug, DbErr>) -> String {
+    match result.unwrap_err().sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(message)) => message,
+        other => panic!(
+            "{}: expected a unique constraint violation, got {:?}",
+            backend, other
+        ),
+    }
+}
+
+fn assert_foreign_key_violation(backend: &str, result: Result<impl std::fmt::Debug, DbErr>) {
+    match result.unwrap_err().sql_err() {
+        Some(SqlErr::ForeignKeyConstraintViolation(_)) => {}
+        other => panic!(
+            "{}: expected a foreign key constraint violation, got {:?}",
+            backend, other
+        ),
+    }
+}
+
+#[tokio::test]
+async fn create_project_duplicate_name_is_unique_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context.clone());
+        let project_context = ProjectContext::new(db_context);
+
+        let user = user_context
+            .create(create_users(1)[0].clone())
+            .await
+            .unwrap();
+        let project = create_projects(1, user.id)[0].clone();
+
+        project_context.create(project.clone()).await.unwrap();
+        let message = assert_unique_violation(backend, project_context.create(project).await);
+
+        // The project controller relies on the message naming the column to report the duplicate name
+        assert!(
+            message.contains("name"),
+            "{}: message '{}' does not mention the name",
+            backend,
+            message
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_project_same_name_different_owner_is_ok_on_all_backends() {
+    for (_, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context.clone());
+        let project_context = ProjectContext::new(db_context);
+
+        let mut users = vec![];
+        for user in create_users(2) {
+            users.push(user_context.create(user).await.unwrap());
+        }
+
+        project_context
+            .create(create_projects(1, users[0].id)[0].clone())
+            .await
+            .unwrap();
+        let res = project_context
+            .create(create_projects(1, users[1].id)[0].clone())
+            .await;
+
+        assert!(res.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn create_project_unknown_owner_is_foreign_key_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let project_context = ProjectContext::new(db_context);
+
+        let res = project_context
+            .create(create_projects(1, 1)[0].clone())
+            .await;
+
+        assert_foreign_key_violation(backend, res);
+    }
+}
+
+#[tokio::test]
+async fn create_user_duplicate_email_is_unique_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context);
+
+        let user = create_users(1)[0].clone();
+        user_context.create(user.clone()).await.unwrap();
+
+        let res = user_context
+            .create(user::Model {
+                username: "another_username".into(),
+                ..user
+            })
+            .await;
+
+        assert_unique_violation(backend, res);
+    }
+}
+
+#[tokio::test]
+async fn create_user_duplicate_username_is_unique_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context);
+
+        let user = create_users(1)[0].clone();
+        user_context.create(user.clone()).await.unwrap();
+
+        let res = user_context
+            .create(user::Model {
+                email: "another@mail.dk".into(),
+                ..user
+            })
+            .await;
+
+        assert_unique_violation(backend, res);
+    }
+}
+
+#[tokio::test]
+async fn create_access_duplicate_is_unique_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context.clone());
+        let project_context = ProjectContext::new(db_context.clone());
+        let access_context = AccessContext::new(db_context);
+
+        let user = user_context
+            .create(create_users(1)[0].clone())
+            .await
+            .unwrap();
+        let project = project_context
+            .create(create_projects(1, user.id)[0].clone())
+            .await
+            .unwrap();
+        let access = access::Model {
+            id: Default::default(),
+            role: "Editor".into(),
+            project_id: project.id,
+            user_id: user.id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        };
+
+        access_context.create(access.clone()).await.unwrap();
+        let res = access_context.create(access).await;
+
+        assert_unique_violation(backend, res);
+    }
+}
+
+#[tokio::test]
+async fn create_access_unknown_project_is_foreign_key_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context.clone());
+        let access_context = AccessContext::new(db_context);
+
+        let user = user_context
+            .create(create_users(1)[0].clone())
+            .await
+            .unwrap();
+
+        let res = access_context
+            .create(access::Model {
+                id: Default::default(),
+                role: "Editor".into(),
+                project_id: 1,
+                user_id: user.id,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            })
+            .await;
+
+        assert_foreign_key_violation(backend, res);
+    }
+}
+
+#[tokio::test]
+async fn create_access_unknown_role_is_foreign_key_violation_on_all_backends() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        let user_context = UserContext::new(db_context.clone());
+        let project_context = ProjectContext::new(db_context.clone());
+        let access_context = AccessContext::new(db_context);
+
+        let user = user_context
+            .create(create_users(1)[0].clone())
+            .await
+            .unwrap();
+        let project: project::Model = project_context
+            .create(create_projects(1, user.id)[0].clone())
+            .await
+            .unwrap();
+
+        let res = access_context
+            .create(access::Model {
+                id: Default::default(),
+                role: "Owner".into(),
+                project_id: project.id,
+                user_id: user.id,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            })
+            .await;
+
+        assert_foreign_key_violation(backend, res);
+    }
+}
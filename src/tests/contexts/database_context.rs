@@ -0,0 +1,14 @@
+use crate::controllers::controller_impls::server_info_controller::database_backend_name;
+use crate::tests::contexts::constraints::get_reset_database_contexts;
+use sea_orm::ConnectionTrait;
+
+#[tokio::test]
+async fn backend_kind_reports_backend_of_every_context() {
+    for (backend, db_context) in get_reset_database_contexts().await {
+        assert_eq!(database_backend_name(db_context.backend_kind()), backend);
+        assert_eq!(
+            db_context.backend_kind(),
+            db_context.get_connection().get_database_backend()
+        );
+    }
+}
@@ -1,10 +1,14 @@
 #![cfg(test)]
 
-use crate::contexts::context_impls::{PostgresDatabaseContext, SQLiteDatabaseContext};
+use crate::contexts::context_collection::ContextCollection;
+use crate::contexts::context_impls::{
+    AccessContext, InUseContext, PostgresDatabaseContext, ProjectContext, ProjectEventContext,
+    QueryContext, QueryResultContext, SQLiteDatabaseContext, SessionContext, UserContext,
+};
 use crate::contexts::context_traits::DatabaseContextTrait;
-use crate::entities::{access, in_use, project, query, session, user};
+use crate::entities::{access, in_use, project, project_event, query, query_result, session, user};
 use dotenv::dotenv;
-use sea_orm::{ConnectionTrait, Database, DbBackend};
+use sea_orm::{ConnectionTrait, Database, DbBackend, EntityTrait, IntoActiveModel};
 use std::env;
 use std::sync::Arc;
 
@@ -14,8 +18,8 @@ pub async fn get_reset_database_context() -> Arc<dyn DatabaseContextTrait> {
     let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set to run tests.");
     let db = Database::connect(&url).await.unwrap();
     let db_context: Arc<dyn DatabaseContextTrait> = match db.get_database_backend() {
-        DbBackend::Sqlite => Arc::new(SQLiteDatabaseContext::new(&url).await.unwrap()),
-        DbBackend::Postgres => Arc::new(PostgresDatabaseContext::new(&url).await.unwrap()),
+        DbBackend::Sqlite => Arc::new(SQLiteDatabaseContext::new(&url, false).await.unwrap()),
+        DbBackend::Postgres => Arc::new(PostgresDatabaseContext::new(&url, false).await.unwrap()),
         _ => panic!("Database protocol not supported"),
     };
 
@@ -59,6 +63,7 @@ pub fn create_users(amount: i32) -> Vec<user::Model> {
         email: format!("mail{}@mail.dk", &i),
         username: format!("username{}", &i),
         password: format!("qwerty{}", &i),
+        display_name: None,
     })
 }
 
@@ -77,6 +82,8 @@ pub fn create_accesses(amount: i32, user_id: i32, project_id: i32) -> Vec<access
         role: "Reader".into(),
         project_id: project_id + i,
         user_id: user_id + i,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     })
 }
 
@@ -86,6 +93,7 @@ pub fn create_sessions(amount: i32, user_id: i32) -> Vec<session::Model> {
         refresh_token: "test_refresh_token".to_string() + format!("{}", i).as_str(),
         access_token: "test_access_token".to_string() + format!("{}", i).as_str(),
         user_id,
+        created_at: Default::default(),
         updated_at: Default::default(),
     })
 }
@@ -105,9 +113,135 @@ pub fn create_queries(amount: i32, project_id: i32) -> Vec<query::Model> {
         result: None,
         outdated: true,
         project_id,
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     })
 }
 
+pub fn create_query_results(amount: i32, query_id: i32) -> Vec<query_result::Model> {
+    create_entities(amount, |i| query_result::Model {
+        id: i + 1,
+        query_id,
+        result: Some(serde_json::json!({ "Success": {} })),
+        last_run_ms: Some(i64::from(i)),
+        created_at: Default::default(),
+    })
+}
+
+pub fn create_project_events(
+    amount: i32,
+    project_id: i32,
+    user_id: i32,
+) -> Vec<project_event::Model> {
+    create_entities(amount, |i| project_event::Model {
+        id: i + 1,
+        project_id,
+        user_id,
+        event_type: "NameChanged".to_string(),
+        description: format!("description {}", i),
+        created_at: Default::default(),
+        components_snapshot: None,
+    })
+}
+
+/// The ids of the entities inserted by [`seed_full_project`].
+pub struct SeededProject {
+    pub owner_id: i32,
+    pub project_id: i32,
+    pub session_id: i32,
+    pub access_token: String,
+    pub query_ids: Vec<i32>,
+}
+
+/// Inserts a coherent project graph into the database: the owner with id `owner_id`,
+/// a project they own with an Editor access, a session of theirs holding the unlocked
+/// in use of the project, and two queries on the project.
+pub async fn seed_full_project(
+    db_context: &Arc<dyn DatabaseContextTrait>,
+    owner_id: i32,
+) -> SeededProject {
+    let db = db_context.get_connection();
+
+    let owner = user::Model {
+        id: owner_id,
+        ..create_users(1)[0].clone()
+    };
+    let project = project::Model {
+        components_info: serde_json::json!({ "components": [], "components_hash": 0 }),
+        ..create_projects(1, owner.id)[0].clone()
+    };
+    let access = access::Model {
+        role: "Editor".into(),
+        ..create_accesses(1, owner.id, project.id)[0].clone()
+    };
+    let session = create_sessions(1, owner.id)[0].clone();
+    let in_use = create_in_uses(1, project.id, session.id)[0].clone();
+    let queries: Vec<query::Model> = create_queries(2, project.id)
+        .into_iter()
+        .zip(["A <= B", "consistency: A"])
+        .map(|(query, string)| query::Model {
+            string: string.to_string(),
+            ..query
+        })
+        .collect();
+
+    user::Entity::insert(owner.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    project::Entity::insert(project.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    access::Entity::insert(access.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    session::Entity::insert(session.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    in_use::Entity::insert(in_use.into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    query::Entity::insert_many(
+        queries
+            .iter()
+            .cloned()
+            .map(IntoActiveModel::into_active_model),
+    )
+    .exec(&db)
+    .await
+    .unwrap();
+
+    SeededProject {
+        owner_id: owner.id,
+        project_id: project.id,
+        session_id: session.id,
+        access_token: session.access_token,
+        query_ids: queries.iter().map(|query| query.id).collect(),
+    }
+}
+
+/// Returns a collection of contexts backed by the given database, as the server uses them.
+pub fn get_context_collection(db_context: Arc<dyn DatabaseContextTrait>) -> ContextCollection {
+    ContextCollection {
+        access_context: Arc::new(AccessContext::new(db_context.clone())),
+        in_use_context: Arc::new(InUseContext::new(db_context.clone())),
+        project_context: Arc::new(ProjectContext::new(db_context.clone())),
+        project_event_context: Arc::new(ProjectEventContext::new(db_context.clone())),
+        query_context: Arc::new(QueryContext::new(db_context.clone())),
+        query_result_context: Arc::new(QueryResultContext::new(db_context.clone())),
+        session_context: Arc::new(SessionContext::new(db_context.clone())),
+        user_context: Arc::new(UserContext::new(db_context)),
+    }
+}
+
 #[macro_export]
 macro_rules! to_active_models {
     ($vec:expr) => {{
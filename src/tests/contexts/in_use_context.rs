@@ -1,7 +1,7 @@
 use crate::tests::contexts::helpers::*;
 use crate::{
     contexts::context_impls::InUseContext,
-    contexts::context_traits::EntityContextTrait,
+    contexts::context_traits::{EntityContextTrait, InUseContextTrait},
     entities::{in_use, project, session, user},
     to_active_models,
 };
@@ -269,3 +269,102 @@ async fn delete_non_existing_id_test() {
         DbErr::RecordNotFound(_)
     ))
 }
+
+#[tokio::test]
+async fn count_active_locks_test() {
+    let (in_use_context, _, session, _, user) = seed_db().await;
+
+    let projects = create_projects(2, user.id);
+
+    project::Entity::insert_many(to_active_models!(projects[1..].to_vec()))
+        .exec(&in_use_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fresh_in_use = in_use::Model {
+        project_id: projects[0].id,
+        session_id: session.id,
+        latest_activity: Utc::now().naive_utc(),
+    };
+
+    let stale_in_use = in_use::Model {
+        project_id: projects[1].id,
+        session_id: session.id,
+        latest_activity: Utc::now().naive_utc() - Duration::minutes(20),
+    };
+
+    in_use::Entity::insert_many(to_active_models!(vec![fresh_in_use, stale_in_use]))
+        .exec(&in_use_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let active_locks = in_use_context
+        .count_active_locks(Duration::minutes(10))
+        .await
+        .unwrap();
+
+    assert_eq!(active_locks, 1);
+}
+
+#[tokio::test]
+async fn get_active_locks_by_uid_test() {
+    let (in_use_context, _, session, _, user) = seed_db().await;
+
+    let other_user = create_users(2)[1].clone();
+    let other_session = session::Model {
+        id: session.id + 1,
+        refresh_token: "other_refresh_token".to_string(),
+        access_token: "other_access_token".to_string(),
+        user_id: other_user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+    let projects = create_projects(3, user.id);
+
+    user::Entity::insert(other_user.into_active_model())
+        .exec(&in_use_context.db_context.get_connection())
+        .await
+        .unwrap();
+    session::Entity::insert(other_session.clone().into_active_model())
+        .exec(&in_use_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert_many(to_active_models!(projects[1..].to_vec()))
+        .exec(&in_use_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let held_in_use = in_use::Model {
+        project_id: projects[0].id,
+        session_id: session.id,
+        latest_activity: Utc::now().naive_utc(),
+    };
+    let stale_in_use = in_use::Model {
+        project_id: projects[1].id,
+        session_id: session.id,
+        latest_activity: Utc::now().naive_utc() - Duration::minutes(20),
+    };
+    let other_in_use = in_use::Model {
+        project_id: projects[2].id,
+        session_id: other_session.id,
+        latest_activity: Utc::now().naive_utc(),
+    };
+
+    in_use::Entity::insert_many(to_active_models!(vec![
+        held_in_use.clone(),
+        stale_in_use,
+        other_in_use
+    ]))
+    .exec(&in_use_context.db_context.get_connection())
+    .await
+    .unwrap();
+
+    let locks = in_use_context
+        .get_active_locks_by_uid(user.id, Duration::minutes(10))
+        .await
+        .unwrap();
+
+    assert_eq!(locks.len(), 1);
+    assert_eq!(locks[0].project_id, held_in_use.project_id);
+    assert_eq!(locks[0].session_id, session.id);
+}
@@ -1 +1,3 @@
+mod constraints;
+mod database_context;
 pub mod helpers;
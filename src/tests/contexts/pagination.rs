@@ -0,0 +1,21 @@
+use crate::contexts::pagination::{effective_page_size, DEFAULT_PAGE_SIZE};
+
+#[test]
+fn effective_page_size_clamps_oversized_request() {
+    assert_eq!(effective_page_size(1000, 100), 100);
+}
+
+#[test]
+fn effective_page_size_keeps_size_within_max() {
+    assert_eq!(effective_page_size(50, 100), 50);
+}
+
+#[test]
+fn effective_page_size_uses_default_for_zero() {
+    assert_eq!(effective_page_size(0, 100), DEFAULT_PAGE_SIZE);
+}
+
+#[test]
+fn effective_page_size_clamps_default_to_max() {
+    assert_eq!(effective_page_size(0, 5), 5);
+}
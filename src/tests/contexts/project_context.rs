@@ -1,12 +1,15 @@
 use crate::tests::contexts::helpers::*;
 use crate::{
+    contexts::components_storage::ComponentsStorage,
+    contexts::context_impls::project_event_context::PROJECT_EVENT_RETENTION,
     contexts::context_impls::ProjectContext,
-    contexts::context_traits::EntityContextTrait,
-    entities::{access, in_use, project, query, session, user},
+    contexts::context_traits::{EntityContextTrait, ProjectContextTrait},
+    contexts::pagination::DEFAULT_MAX_PAGE_SIZE,
+    entities::{access, component, in_use, project, project_event, query, session, user},
     to_active_models,
 };
 use sea_orm::error::DbErr;
-use sea_orm::{entity::prelude::*, IntoActiveModel};
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder};
 use std::matches;
 
 async fn seed_db() -> (ProjectContext, project::Model, user::Model) {
@@ -41,6 +44,28 @@ async fn create_test() {
     assert_eq!(fetched_project, created_project);
 }
 
+#[tokio::test]
+async fn create_default_id_returns_database_id_test() {
+    let (project_context, _, user) = seed_db().await;
+
+    let project = project::Model {
+        name: "project".into(),
+        owner_id: user.id,
+        ..Default::default()
+    };
+
+    let created_project = project_context.create(project).await.unwrap();
+
+    let fetched_project = project::Entity::find_by_id(created_project.id)
+        .one(&project_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_ne!(created_project.id, project::Model::default().id);
+    assert_eq!(fetched_project, created_project);
+}
+
 #[tokio::test]
 async fn create_auto_increment_test() {
     let (project_context, project, _) = seed_db().await;
@@ -219,30 +244,48 @@ async fn update_does_not_modify_id_test() {
 }
 
 #[tokio::test]
-async fn update_does_not_modify_owner_id_test() {
+async fn update_modifies_owner_id_test() {
     let (project_context, project, _) = seed_db().await;
 
+    let new_owner = create_users(2)[1].clone();
+
+    user::Entity::insert(new_owner.clone().into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
     project::Entity::insert(project.clone().into_active_model())
         .exec(&project_context.db_context.get_connection())
         .await
         .unwrap();
 
     let new_project = project::Model {
-        owner_id: &project.owner_id + 1,
+        owner_id: new_owner.id,
         ..project.clone()
     };
 
-    let res = project_context.update(new_project.clone()).await.unwrap();
+    let updated_project = project_context.update(new_project.clone()).await.unwrap();
+
+    let fetched_project = project::Entity::find_by_id(project.id)
+        .one(&project_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
 
-    assert_eq!(project, res);
+    assert_eq!(updated_project, new_project);
+    assert_eq!(fetched_project.owner_id, new_owner.id);
 }
 
 #[tokio::test]
-async fn update_check_query_outdated_test() {
+async fn update_components_does_not_mark_queries_outdated_test() {
     let (project_context, project, _) = seed_db().await;
 
+    let project = project::Model {
+        components_info: components_info(&["A"]),
+        ..project
+    };
     let mut query = create_queries(1, project.id)[0].clone();
-
+    query.string = "consistency: A".into();
     query.outdated = false;
 
     project::Entity::insert(project.clone().into_active_model())
@@ -255,17 +298,21 @@ async fn update_check_query_outdated_test() {
         .await
         .unwrap();
 
-    let new_project = project::Model { ..project };
+    // Only the queries referencing a changed component are marked, and that is left to the controller
+    let new_project = project::Model {
+        components_info: components_info(&["A", "B"]),
+        ..project
+    };
 
-    let updated_project = project_context.update(new_project.clone()).await.unwrap();
+    project_context.update(new_project).await.unwrap();
 
-    let fetched_query = query::Entity::find_by_id(updated_project.id)
+    let fetched_query = query::Entity::find_by_id(query.id)
         .one(&project_context.db_context.get_connection())
         .await
         .unwrap()
         .unwrap();
 
-    assert!(fetched_query.outdated);
+    assert!(!fetched_query.outdated);
 }
 
 #[tokio::test]
@@ -407,3 +454,513 @@ async fn delete_non_existing_id_test() {
         DbErr::RecordNotFound(_)
     ));
 }
+
+#[tokio::test]
+async fn get_project_info_by_uid_has_next_test() {
+    let (project_context, _, user) = seed_db().await;
+
+    let projects = create_projects(3, user.id);
+    let accesses = create_entities(3, |i| access::Model {
+        id: i + 1,
+        role: "Editor".into(),
+        project_id: projects[i as usize].id,
+        user_id: user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    });
+
+    project::Entity::insert_many(to_active_models!(projects))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let first_page = project_context
+        .get_project_info_by_uid(user.id, 0, 2)
+        .await
+        .unwrap();
+    let last_page = project_context
+        .get_project_info_by_uid(user.id, 1, 2)
+        .await
+        .unwrap();
+
+    assert_eq!(first_page.items.len(), 2);
+    assert_eq!(first_page.page_info.total, 3);
+    assert!(first_page.page_info.has_next);
+    assert_eq!(last_page.items.len(), 1);
+    assert!(!last_page.page_info.has_next);
+}
+
+#[tokio::test]
+async fn get_project_info_by_uid_includes_owner_username_test() {
+    let (project_context, _, user) = seed_db().await;
+
+    let owner = create_users(2)[1].clone();
+    let project = create_projects(1, owner.id)[0].clone();
+    let access = create_accesses(1, user.id, project.id)[0].clone();
+
+    user::Entity::insert(owner.clone().into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert(project.into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    access::Entity::insert(access.into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let page = project_context
+        .get_project_info_by_uid(user.id, 0, 10)
+        .await
+        .unwrap();
+
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].project_owner_id, owner.id);
+    assert_eq!(page.items[0].project_owner_username, Some(owner.username));
+}
+
+#[tokio::test]
+async fn get_projects_by_uid_same_name_across_pages_test() {
+    let (project_context, _, user) = seed_db().await;
+
+    let users = create_users(5);
+    let projects = create_entities(5, |i| project::Model {
+        id: i + 1,
+        name: "shared".into(),
+        components_info: "{}".to_owned().parse().unwrap(),
+        owner_id: users[i as usize].id,
+    });
+    let accesses = create_entities(5, |i| access::Model {
+        id: i + 1,
+        role: "Reader".into(),
+        project_id: projects[i as usize].id,
+        user_id: user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    });
+
+    user::Entity::insert_many(to_active_models!(users[1..].to_vec()))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert_many(to_active_models!(projects))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let mut ids = vec![];
+    for page in 0..3 {
+        let page = project_context
+            .get_projects_by_uid(user.id, page, 2)
+            .await
+            .unwrap();
+        ids.extend(page.items.into_iter().map(|project| project.id));
+    }
+
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn update_with_events_test() {
+    let (project_context, project, user) = seed_db().await;
+
+    project::Entity::insert(project.clone().into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let new_project = project::Model {
+        name: "new name".into(),
+        ..project.clone()
+    };
+    let events = create_project_events(2, project.id, user.id);
+
+    let updated_project = project_context
+        .update_with_events(new_project.clone(), events)
+        .await
+        .unwrap();
+
+    let fetched_events = project_event::Entity::find()
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(updated_project, new_project);
+    assert_eq!(fetched_events.len(), 2);
+}
+
+#[tokio::test]
+async fn update_with_events_non_existing_id_test() {
+    let (project_context, project, user) = seed_db().await;
+
+    let events = create_project_events(1, project.id, user.id);
+
+    let res = project_context.update_with_events(project, events).await;
+
+    let fetched_events = project_event::Entity::find()
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert!(matches!(res.unwrap_err(), DbErr::RecordNotUpdated));
+    assert!(fetched_events.is_empty());
+}
+
+#[tokio::test]
+async fn update_with_events_prunes_old_events_test() {
+    let (project_context, project, user) = seed_db().await;
+
+    project::Entity::insert(project.clone().into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    // Ids start at 2 so the id sequence does not collide with the event logged by the update
+    let events = create_entities(PROJECT_EVENT_RETENTION as i32, |i| project_event::Model {
+        id: i + 2,
+        ..create_project_events(1, project.id, user.id)[0].clone()
+    });
+
+    project_event::Entity::insert_many(to_active_models!(events))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    project_context
+        .update_with_events(
+            project.clone(),
+            create_project_events(1, project.id, user.id),
+        )
+        .await
+        .unwrap();
+
+    let fetched_events = project_event::Entity::find()
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(fetched_events.len() as u64, PROJECT_EVENT_RETENTION);
+    assert!(!fetched_events.iter().any(|event| event.id == 2));
+}
+
+#[tokio::test]
+async fn get_projects_by_uid_only_returns_accessible_projects_test() {
+    let (project_context, _, user) = seed_db().await;
+
+    let projects = create_projects(3, user.id);
+    // The user only has access to the first two projects
+    let accesses = create_entities(2, |i| access::Model {
+        id: i + 1,
+        role: "Reader".into(),
+        project_id: projects[i as usize].id,
+        user_id: user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    });
+
+    project::Entity::insert_many(to_active_models!(projects.clone()))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let page = project_context
+        .get_projects_by_uid(user.id, 0, 10)
+        .await
+        .unwrap();
+
+    assert_eq!(page.items, projects[..2].to_vec());
+    assert_eq!(page.page_info.total, 2);
+}
+
+#[tokio::test]
+async fn get_projects_by_uid_paginates_test() {
+    let (project_context, _, user) = seed_db().await;
+
+    let projects = create_projects(3, user.id);
+    let accesses = create_entities(3, |i| access::Model {
+        id: i + 1,
+        role: "Reader".into(),
+        project_id: projects[i as usize].id,
+        user_id: user.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    });
+
+    project::Entity::insert_many(to_active_models!(projects.clone()))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    access::Entity::insert_many(to_active_models!(accesses))
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let first_page = project_context
+        .get_projects_by_uid(user.id, 0, 2)
+        .await
+        .unwrap();
+    let last_page = project_context
+        .get_projects_by_uid(user.id, 1, 2)
+        .await
+        .unwrap();
+
+    assert_eq!(first_page.items, projects[..2].to_vec());
+    assert!(first_page.page_info.has_next);
+    assert_eq!(last_page.items, projects[2..].to_vec());
+    assert!(!last_page.page_info.has_next);
+}
+
+fn create_with_queries_models(
+    user_id: i32,
+    session_id: i32,
+) -> (access::Model, in_use::Model, Vec<query::Model>) {
+    let access = access::Model {
+        id: Default::default(),
+        role: "Editor".into(),
+        project_id: Default::default(),
+        user_id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+    let in_use = in_use::Model {
+        project_id: Default::default(),
+        session_id,
+        latest_activity: Default::default(),
+    };
+    let queries = create_entities(2, |i| query::Model {
+        id: Default::default(),
+        string: format!("query {}", i),
+        result: None,
+        outdated: true,
+        project_id: Default::default(),
+        last_run_ms: None,
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
+    });
+
+    (access, in_use, queries)
+}
+
+#[tokio::test]
+async fn create_with_queries_test() {
+    let (project_context, project, user) = seed_db().await;
+
+    let session = create_sessions(1, user.id)[0].clone();
+    session::Entity::insert(session.clone().into_active_model())
+        .exec(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let (access, in_use, queries) = create_with_queries_models(user.id, session.id);
+
+    let (created_project, created_queries) = project_context
+        .create_with_queries(project, access, Some(in_use), queries)
+        .await
+        .unwrap();
+
+    let accesses = access::Entity::find()
+        .filter(access::Column::ProjectId.eq(created_project.id))
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    let in_use = in_use::Entity::find_by_id(created_project.id)
+        .one(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    let queries = query::Entity::find()
+        .filter(query::Column::ProjectId.eq(created_project.id))
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(accesses.len(), 1);
+    assert_eq!(in_use.unwrap().session_id, session.id);
+    assert_eq!(queries, created_queries);
+    assert_eq!(queries.len(), 2);
+}
+
+#[tokio::test]
+async fn create_with_queries_failure_rolls_back_test() {
+    let (project_context, project, user) = seed_db().await;
+
+    // The session does not exist, so the in_use insert violates its foreign key
+    let (access, in_use, queries) = create_with_queries_models(user.id, 1);
+
+    let res = project_context
+        .create_with_queries(project, access, Some(in_use), queries)
+        .await;
+
+    let projects = project::Entity::find()
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    let accesses = access::Entity::find()
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+    let queries = query::Entity::find()
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert!(res.is_err());
+    assert!(projects.is_empty());
+    assert!(accesses.is_empty());
+    assert!(queries.is_empty());
+}
+
+fn components_info(names: &[&str]) -> Json {
+    serde_json::json!({
+        "components": names
+            .iter()
+            .map(|name| serde_json::json!({ "rep": { "Json": format!("{{\"name\":\"{}\"}}", name) } }))
+            .collect::<Vec<_>>(),
+        "components_hash": 0
+    })
+}
+
+async fn seed_rows_db() -> (ProjectContext, project::Model) {
+    let (project_context, project, _) = seed_db().await;
+    let project_context = ProjectContext::with_settings(
+        project_context.db_context,
+        ComponentsStorage::Rows,
+        DEFAULT_MAX_PAGE_SIZE,
+    );
+
+    let project = project::Model {
+        components_info: components_info(&["A", "B"]),
+        ..project
+    };
+
+    (project_context, project)
+}
+
+#[tokio::test]
+async fn rows_storage_round_trips_components_test() {
+    let (project_context, project) = seed_rows_db().await;
+
+    let created_project = project_context.create(project.clone()).await.unwrap();
+    let fetched_project = project_context
+        .get_by_id(created_project.id)
+        .await
+        .unwrap()
+        .unwrap();
+    let stored_project = project::Entity::find_by_id(created_project.id)
+        .one(&project_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+    let rows = component::Entity::find()
+        .filter(component::Column::ProjectId.eq(created_project.id))
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(created_project, project);
+    assert_eq!(fetched_project, project);
+    assert_eq!(stored_project.components_info, components_info(&[]));
+    assert_eq!(
+        rows.iter()
+            .map(|row| row.name.clone().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["A", "B"]
+    );
+}
+
+#[tokio::test]
+async fn rows_storage_update_rewrites_changed_component_only_test() {
+    let (project_context, project) = seed_rows_db().await;
+    let project = project_context.create(project).await.unwrap();
+    let rows_before = component::Entity::find()
+        .filter(component::Column::ProjectId.eq(project.id))
+        .order_by_asc(component::Column::Position)
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let new_project = project::Model {
+        components_info: components_info(&["A", "C"]),
+        ..project
+    };
+    let updated_project = project_context.update(new_project.clone()).await.unwrap();
+    let fetched_project = project_context
+        .get_by_id(project.id)
+        .await
+        .unwrap()
+        .unwrap();
+    let rows_after = component::Entity::find()
+        .filter(component::Column::ProjectId.eq(project.id))
+        .order_by_asc(component::Column::Position)
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(updated_project, new_project);
+    assert_eq!(fetched_project, new_project);
+    assert_eq!(rows_after[0], rows_before[0]);
+    assert_eq!(rows_after[1].id, rows_before[1].id);
+    assert_eq!(rows_after[1].name, Some("C".to_string()));
+}
+
+#[tokio::test]
+async fn rows_storage_update_removes_dropped_components_test() {
+    let (project_context, project) = seed_rows_db().await;
+    let project = project_context.create(project).await.unwrap();
+
+    let new_project = project::Model {
+        components_info: components_info(&["A"]),
+        ..project
+    };
+    project_context.update(new_project.clone()).await.unwrap();
+    let rows = component::Entity::find()
+        .filter(component::Column::ProjectId.eq(project.id))
+        .all(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        project_context
+            .get_by_id(project.id)
+            .await
+            .unwrap()
+            .unwrap(),
+        new_project
+    );
+}
+
+#[tokio::test]
+async fn create_with_queries_without_in_use_test() {
+    let (project_context, project, user) = seed_db().await;
+
+    let (access, _, queries) = create_with_queries_models(user.id, 1);
+
+    let (created_project, _) = project_context
+        .create_with_queries(project, access, None, queries)
+        .await
+        .unwrap();
+
+    let in_use = in_use::Entity::find_by_id(created_project.id)
+        .one(&project_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert!(in_use.is_none());
+}
@@ -0,0 +1,225 @@
+use crate::tests::contexts::helpers::*;
+use crate::{
+    contexts::context_impls::project_event_context::PROJECT_EVENT_RETENTION,
+    contexts::context_impls::ProjectEventContext,
+    contexts::context_traits::{EntityContextTrait, ProjectEventContextTrait},
+    entities::{project, project_event, user},
+    to_active_models,
+};
+use chrono::{Duration, Utc};
+use sea_orm::error::DbErr;
+use sea_orm::{entity::prelude::*, IntoActiveModel};
+use std::matches;
+
+async fn seed_db() -> (ProjectEventContext, project_event::Model, project::Model) {
+    let db_context = get_reset_database_context().await;
+
+    let project_event_context = ProjectEventContext::new(db_context);
+
+    let user = create_users(1)[0].clone();
+    let project = create_projects(1, user.id)[0].clone();
+    let event = create_project_events(1, project.id, user.id)[0].clone();
+
+    user::Entity::insert(user.into_active_model())
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert(project.clone().into_active_model())
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    (project_event_context, event, project)
+}
+
+#[tokio::test]
+async fn create_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    let created_event = project_event_context.create(event.clone()).await.unwrap();
+
+    let fetched_event = project_event::Entity::find_by_id(created_event.id)
+        .one(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(created_event.description, event.description);
+    assert_eq!(fetched_event, created_event);
+}
+
+#[tokio::test]
+async fn create_sets_created_at_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    let before = Utc::now().naive_utc() - Duration::seconds(1);
+
+    let created_event = project_event_context.create(event).await.unwrap();
+
+    assert!(created_event.created_at > before);
+}
+
+#[tokio::test]
+async fn create_keeps_components_snapshot_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    let snapshot = serde_json::json!({ "components": [], "components_hash": 0 });
+    let event = project_event::Model {
+        components_snapshot: Some(snapshot.clone()),
+        ..event
+    };
+
+    let created_event = project_event_context.create(event).await.unwrap();
+
+    let fetched_event = project_event_context
+        .get_by_id(created_event.id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(fetched_event.components_snapshot, Some(snapshot));
+}
+
+#[tokio::test]
+async fn get_by_id_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    project_event::Entity::insert(event.clone().into_active_model())
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched_event = project_event_context
+        .get_by_id(event.id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(fetched_event, event);
+}
+
+#[tokio::test]
+async fn get_all_test() {
+    let (project_event_context, event, project) = seed_db().await;
+
+    let events = create_project_events(3, project.id, event.user_id);
+
+    project_event::Entity::insert_many(to_active_models!(events.clone()))
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    assert_eq!(project_event_context.get_all().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn get_by_project_id_returns_newest_first_test() {
+    let (project_event_context, event, project) = seed_db().await;
+
+    let events = create_entities(3, |i| project_event::Model {
+        id: i + 1,
+        created_at: Utc::now().naive_utc() - Duration::minutes(i as i64),
+        ..event.clone()
+    });
+
+    project_event::Entity::insert_many(to_active_models!(events.clone()))
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched_events = project_event_context
+        .get_by_project_id(project.id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        fetched_events.iter().map(|e| e.id).collect::<Vec<i32>>(),
+        vec![1, 2, 3]
+    );
+}
+
+#[tokio::test]
+async fn get_by_project_id_other_project_test() {
+    let (project_event_context, event, project) = seed_db().await;
+
+    project_event::Entity::insert(event.into_active_model())
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched_events = project_event_context
+        .get_by_project_id(project.id + 1)
+        .await
+        .unwrap();
+
+    assert!(fetched_events.is_empty());
+}
+
+#[tokio::test]
+async fn create_prunes_old_events_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    // Ids start at 2 so the id sequence does not collide with the created event
+    let events = create_entities(PROJECT_EVENT_RETENTION as i32, |i| project_event::Model {
+        id: i + 2,
+        ..event.clone()
+    });
+
+    project_event::Entity::insert_many(to_active_models!(events))
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    project_event_context.create(event).await.unwrap();
+
+    let fetched_events = project_event_context.get_all().await.unwrap();
+
+    assert_eq!(fetched_events.len() as u64, PROJECT_EVENT_RETENTION);
+    assert!(!fetched_events.iter().any(|e| e.id == 2));
+}
+
+#[tokio::test]
+async fn update_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    project_event::Entity::insert(event.clone().into_active_model())
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let new_event = project_event::Model {
+        description: "new description".into(),
+        ..event
+    };
+
+    let updated_event = project_event_context
+        .update(new_event.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(updated_event, new_event);
+}
+
+#[tokio::test]
+async fn delete_test() {
+    let (project_event_context, event, _) = seed_db().await;
+
+    project_event::Entity::insert(event.clone().into_active_model())
+        .exec(&project_event_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let deleted_event = project_event_context.delete(event.id).await.unwrap();
+
+    assert_eq!(deleted_event, event);
+    assert!(project_event_context.get_all().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn delete_non_existing_id_test() {
+    let (project_event_context, _, _) = seed_db().await;
+
+    let res = project_event_context.delete(1).await;
+
+    assert!(matches!(res.unwrap_err(), DbErr::RecordNotFound(_)));
+}
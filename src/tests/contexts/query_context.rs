@@ -3,7 +3,7 @@ use crate::tests::contexts::helpers::{
 };
 use crate::{
     contexts::context_impls::QueryContext,
-    contexts::context_traits::EntityContextTrait,
+    contexts::context_traits::{EntityContextTrait, QueryContextTrait},
     entities::{project, query, user},
     to_active_models,
 };
@@ -30,6 +30,15 @@ async fn seed_db() -> (QueryContext, query::Model, project::Model) {
     (query_context, query, project)
 }
 
+/// Clears the audit columns, which are set by the context and therefore differ from the input.
+fn without_timestamps(query: query::Model) -> query::Model {
+    query::Model {
+        created_at: None,
+        updated_at: None,
+        ..query
+    }
+}
+
 #[tokio::test]
 async fn create_test() {
     let (query_context, query, _) = seed_db().await;
@@ -43,10 +52,44 @@ async fn create_test() {
         .unwrap();
 
     // Assert if the fetched access is the same as the created access
-    assert_eq!(query, created_query);
+    assert_eq!(query, without_timestamps(created_query.clone()));
     assert_eq!(fetched_query, created_query);
 }
 
+#[tokio::test]
+async fn create_default_id_returns_database_id_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let query = query::Model {
+        string: "refinement: A <= B".into(),
+        project_id: project.id,
+        ..Default::default()
+    };
+
+    let created_query1 = query_context.create(query.clone()).await.unwrap();
+    let created_query2 = query_context.create(query).await.unwrap();
+
+    let fetched_query2 = query::Entity::find_by_id(created_query2.id)
+        .one(&query_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_ne!(created_query1.id, query::Model::default().id);
+    assert_ne!(created_query1.id, created_query2.id);
+    assert_eq!(fetched_query2, created_query2);
+}
+
+#[tokio::test]
+async fn create_sets_timestamps_test() {
+    let (query_context, query, _) = seed_db().await;
+
+    let created_query = query_context.create(query).await.unwrap();
+
+    assert!(created_query.created_at.is_some());
+    assert_eq!(created_query.created_at, created_query.updated_at);
+}
+
 #[tokio::test]
 async fn create_default_outdated_test() {
     let (query_context, query, _) = seed_db().await;
@@ -135,6 +178,73 @@ async fn get_all_test() {
     }
 }
 
+#[tokio::test]
+async fn get_all_by_project_id_orders_by_id_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let queries = create_queries(5, project.id);
+    let shuffled: Vec<query::Model> = [2, 0, 4, 1, 3]
+        .into_iter()
+        .map(|i| queries[i].clone())
+        .collect();
+
+    for query in shuffled {
+        query::Entity::insert(query.into_active_model())
+            .exec(&query_context.db_context.get_connection())
+            .await
+            .unwrap();
+    }
+
+    let fetched_ids: Vec<i32> = query_context
+        .get_all_by_project_id(project.id, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|query| query.id)
+        .collect();
+
+    assert_eq!(fetched_ids, vec![1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn get_all_by_project_id_limit_returns_oldest_queries_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let queries = create_queries(5, project.id);
+
+    query::Entity::insert_many(to_active_models!(queries.clone()))
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched_ids: Vec<i32> = query_context
+        .get_all_by_project_id(project.id, Some(2))
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|query| query.id)
+        .collect();
+
+    assert_eq!(fetched_ids, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn get_all_by_project_id_other_project_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    query::Entity::insert_many(to_active_models!(create_queries(2, project.id)))
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched = query_context
+        .get_all_by_project_id(project.id + 1, None)
+        .await
+        .unwrap();
+
+    assert!(fetched.is_empty());
+}
+
 #[tokio::test]
 async fn get_all_empty_test() {
     let (query_context, _, _) = seed_db().await;
@@ -163,10 +273,54 @@ async fn update_test() {
         .unwrap()
         .unwrap();
 
-    assert_eq!(new_query, updated_query);
+    assert_eq!(new_query, without_timestamps(updated_query.clone()));
     assert_eq!(updated_query, fetched_query);
 }
 
+#[tokio::test]
+async fn update_bumps_updated_at_test() {
+    let (query_context, query, _) = seed_db().await;
+
+    let created_query = query_context.create(query).await.unwrap();
+
+    let updated_query = query_context
+        .update(query::Model {
+            outdated: false,
+            ..created_query.clone()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(updated_query.created_at, created_query.created_at);
+    assert!(updated_query.updated_at >= created_query.updated_at);
+    assert!(updated_query.updated_at.is_some());
+}
+
+#[tokio::test]
+async fn update_persists_settings_test() {
+    let (query_context, query, _) = seed_db().await;
+
+    let created_query = query_context.create(query).await.unwrap();
+    assert_eq!(created_query.settings, None);
+
+    let settings = serde_json::json!({ "disable_clock_reduction": true });
+    query_context
+        .update(query::Model {
+            settings: Some(settings.clone()),
+            ..created_query.clone()
+        })
+        .await
+        .unwrap();
+
+    let fetched_query = query_context
+        .get_by_id(created_query.id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(fetched_query.settings, Some(settings));
+}
+
 #[tokio::test]
 async fn update_modifies_string_test() {
     let (query_context, query, _) = seed_db().await;
@@ -207,6 +361,51 @@ async fn update_modifies_outdated_test() {
     assert_ne!(query, new_query);
 }
 
+#[tokio::test]
+async fn update_modifies_last_run_ms_test() {
+    let (query_context, query, _) = seed_db().await;
+
+    query::Entity::insert(query.clone().into_active_model())
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let new_query = query::Model {
+        last_run_ms: Some(42),
+        ..query.clone()
+    };
+
+    let updated_query = query_context.update(new_query.clone()).await.unwrap();
+
+    assert_eq!(query.last_run_ms, None);
+    assert_eq!(without_timestamps(updated_query), new_query);
+}
+
+#[tokio::test]
+async fn update_modifies_result_computed_at_test() {
+    let (query_context, query, _) = seed_db().await;
+
+    query::Entity::insert(query.clone().into_active_model())
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let new_query = query::Model {
+        result_computed_at: Some(
+            chrono::NaiveDate::from_ymd_opt(2026, 10, 15)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        ),
+        ..query.clone()
+    };
+
+    let updated_query = query_context.update(new_query.clone()).await.unwrap();
+
+    assert_eq!(query.result_computed_at, None);
+    assert_eq!(without_timestamps(updated_query), new_query);
+}
+
 #[tokio::test]
 async fn update_modifies_result_test() {
     let (query_context, mut query, _) = seed_db().await;
@@ -267,7 +466,7 @@ async fn update_does_not_modify_project_id_test() {
 
     let updated_query = query_context.update(new_query.clone()).await.unwrap();
 
-    assert_eq!(query, updated_query);
+    assert_eq!(query, without_timestamps(updated_query));
 }
 
 #[tokio::test]
@@ -313,3 +512,112 @@ async fn delete_non_existing_id_test() {
         DbErr::RecordNotFound(_)
     ))
 }
+
+#[tokio::test]
+async fn count_outdated_queries_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let other_project = project::Model {
+        id: project.id + 1,
+        name: "other project".into(),
+        ..project.clone()
+    };
+    project::Entity::insert(other_project.clone().into_active_model())
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let mut queries = create_queries(6, project.id);
+    queries[1].outdated = false;
+    queries[3].outdated = false;
+    queries[5].project_id = other_project.id;
+
+    query::Entity::insert_many(to_active_models!(queries))
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let count = query_context
+        .count_outdated_queries(project.id)
+        .await
+        .unwrap();
+
+    assert_eq!(count, 3);
+}
+
+#[tokio::test]
+async fn mark_outdated_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let mut queries = create_queries(3, project.id);
+    for query in queries.iter_mut() {
+        query.outdated = false;
+    }
+
+    query::Entity::insert_many(to_active_models!(queries.clone()))
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let marked = query_context
+        .mark_outdated(vec![queries[0].id, queries[2].id])
+        .await
+        .unwrap();
+
+    let outdated: Vec<bool> = query_context
+        .get_all_by_project_id(project.id, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|query| query.outdated)
+        .collect();
+
+    assert_eq!(marked, 2);
+    assert_eq!(outdated, vec![true, false, true]);
+}
+
+#[tokio::test]
+async fn count_outdated_queries_no_queries_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let count = query_context
+        .count_outdated_queries(project.id)
+        .await
+        .unwrap();
+
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn count_by_project_id_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let queries = create_queries(3, project.id);
+
+    query::Entity::insert_many(to_active_models!(queries))
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let count = query_context.count_by_project_id(project.id).await.unwrap();
+
+    assert_eq!(count, 3);
+}
+
+#[tokio::test]
+async fn count_by_project_id_after_delete_test() {
+    let (query_context, _, project) = seed_db().await;
+
+    let queries = create_queries(3, project.id);
+
+    query::Entity::insert_many(to_active_models!(queries.clone()))
+        .exec(&query_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    query_context.delete(queries[0].id).await.unwrap();
+
+    let count = query_context.count_by_project_id(project.id).await.unwrap();
+
+    assert_eq!(count, 2);
+}
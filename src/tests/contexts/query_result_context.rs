@@ -0,0 +1,182 @@
+use crate::tests::contexts::helpers::{
+    create_projects, create_queries, create_query_results, create_users, get_reset_database_context,
+};
+use crate::{
+    contexts::context_impls::QueryResultContext,
+    contexts::context_traits::{EntityContextTrait, QueryResultContextTrait},
+    entities::{project, query, query_result, user},
+};
+use sea_orm::{entity::prelude::*, IntoActiveModel};
+
+async fn seed_db(history_depth: u64) -> (QueryResultContext, query::Model) {
+    let db_context = get_reset_database_context().await;
+
+    let query_result_context = QueryResultContext::with_history_depth(db_context, history_depth);
+
+    let user = create_users(1)[0].clone();
+    let project = create_projects(1, user.id)[0].clone();
+    let query = create_queries(1, project.id)[0].clone();
+
+    user::Entity::insert(user.clone().into_active_model())
+        .exec(&query_result_context.db_context.get_connection())
+        .await
+        .unwrap();
+    project::Entity::insert(project.clone().into_active_model())
+        .exec(&query_result_context.db_context.get_connection())
+        .await
+        .unwrap();
+    query::Entity::insert(query.clone().into_active_model())
+        .exec(&query_result_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    (query_result_context, query)
+}
+
+#[tokio::test]
+async fn create_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    let result = create_query_results(1, query.id)[0].clone();
+
+    let created_result = query_result_context.create(result.clone()).await.unwrap();
+
+    let fetched_result = query_result::Entity::find_by_id(created_result.id)
+        .one(&query_result_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(created_result.query_id, result.query_id);
+    assert_eq!(created_result.result, result.result);
+    assert_eq!(created_result.last_run_ms, result.last_run_ms);
+    assert_eq!(fetched_result, created_result);
+}
+
+#[tokio::test]
+async fn create_grows_history_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    for result in create_query_results(3, query.id) {
+        query_result_context.create(result).await.unwrap();
+    }
+
+    let history = query_result_context
+        .get_by_query_id(query.id)
+        .await
+        .unwrap();
+
+    assert_eq!(history.len(), 3);
+}
+
+#[tokio::test]
+async fn create_caps_history_test() {
+    let (query_result_context, query) = seed_db(2).await;
+
+    let mut created_ids = vec![];
+    for result in create_query_results(3, query.id) {
+        created_ids.push(query_result_context.create(result).await.unwrap().id);
+    }
+
+    let history = query_result_context
+        .get_by_query_id(query.id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        history.iter().map(|run| run.id).collect::<Vec<i32>>(),
+        vec![created_ids[2], created_ids[1]]
+    );
+}
+
+#[tokio::test]
+async fn get_by_query_id_newest_first_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    let mut created_ids = vec![];
+    for result in create_query_results(2, query.id) {
+        created_ids.push(query_result_context.create(result).await.unwrap().id);
+    }
+
+    let history = query_result_context
+        .get_by_query_id(query.id)
+        .await
+        .unwrap();
+
+    assert_eq!(history[0].id, created_ids[1]);
+    assert_eq!(history[1].id, created_ids[0]);
+}
+
+#[tokio::test]
+async fn get_by_query_id_returns_empty_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    let history = query_result_context
+        .get_by_query_id(query.id)
+        .await
+        .unwrap();
+
+    assert!(history.is_empty());
+}
+
+#[tokio::test]
+async fn get_by_id_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    let result = create_query_results(1, query.id)[0].clone();
+    let created_result = query_result_context.create(result).await.unwrap();
+
+    let fetched_result = query_result_context
+        .get_by_id(created_result.id)
+        .await
+        .unwrap();
+
+    assert_eq!(fetched_result, Some(created_result));
+}
+
+#[tokio::test]
+async fn delete_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    let result = create_query_results(1, query.id)[0].clone();
+    let created_result = query_result_context.create(result).await.unwrap();
+
+    let deleted_result = query_result_context
+        .delete(created_result.id)
+        .await
+        .unwrap();
+
+    let all_results = query_result_context.get_all().await.unwrap();
+
+    assert_eq!(deleted_result, created_result);
+    assert!(all_results.is_empty());
+}
+
+#[tokio::test]
+async fn delete_non_existing_id_test() {
+    let (query_result_context, _) = seed_db(20).await;
+
+    let deleted_result = query_result_context.delete(1).await;
+
+    assert!(matches!(
+        deleted_result.unwrap_err(),
+        DbErr::RecordNotFound(_)
+    ));
+}
+
+#[tokio::test]
+async fn delete_query_cascades_test() {
+    let (query_result_context, query) = seed_db(20).await;
+
+    let result = create_query_results(1, query.id)[0].clone();
+    query_result_context.create(result).await.unwrap();
+
+    query::Entity::delete_by_id(query.id)
+        .exec(&query_result_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let all_results = query_result_context.get_all().await.unwrap();
+
+    assert!(all_results.is_empty());
+}
@@ -0,0 +1,101 @@
+use crate::contexts::context_traits::DatabaseContextTrait;
+use crate::contexts::readiness::connect_with_retry;
+use async_trait::async_trait;
+use sea_orm::{DatabaseConnection, DbBackend, DbErr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A database context that fails the first `failures` pings, as a database that is still starting would.
+#[derive(Debug)]
+struct StartingDatabaseContext {
+    pings: Arc<AtomicUsize>,
+    failures: usize,
+}
+
+#[async_trait]
+impl DatabaseContextTrait for StartingDatabaseContext {
+    async fn reset(&self) -> Result<Arc<dyn DatabaseContextTrait>, DbErr> {
+        unimplemented!()
+    }
+
+    async fn ping(&self) -> Result<(), DbErr> {
+        if self.pings.fetch_add(1, Ordering::SeqCst) < self.failures {
+            Err(DbErr::Conn(sea_orm::RuntimeErr::Internal(
+                "database is starting up".to_string(),
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_connection(&self) -> DatabaseConnection {
+        DatabaseConnection::Disconnected
+    }
+
+    fn backend_kind(&self) -> DbBackend {
+        DbBackend::Postgres
+    }
+}
+
+fn starting_database(
+    pings: Arc<AtomicUsize>,
+    failures: usize,
+) -> impl FnMut() -> std::future::Ready<Result<Arc<dyn DatabaseContextTrait>, DbErr>> {
+    move || {
+        std::future::ready(Ok(Arc::new(StartingDatabaseContext {
+            pings: pings.clone(),
+            failures,
+        }) as Arc<dyn DatabaseContextTrait>))
+    }
+}
+
+#[tokio::test]
+async fn connect_with_retry_database_becomes_ready_returns_ok() {
+    let pings = Arc::new(AtomicUsize::new(0));
+
+    let res =
+        connect_with_retry(starting_database(pings.clone(), 2), Duration::from_secs(10)).await;
+
+    assert!(res.is_ok());
+    assert_eq!(pings.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn connect_with_retry_database_never_ready_returns_err() {
+    let pings = Arc::new(AtomicUsize::new(0));
+
+    let res = connect_with_retry(
+        starting_database(pings.clone(), usize::MAX),
+        Duration::from_millis(600),
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert!(pings.load(Ordering::SeqCst) > 1);
+}
+
+#[tokio::test]
+async fn connect_with_retry_connection_refused_retries() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let pings = Arc::new(AtomicUsize::new(0));
+
+    let mut connect = starting_database(pings.clone(), 0);
+    let res = connect_with_retry(
+        || {
+            if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                std::future::ready(Err(DbErr::Conn(sea_orm::RuntimeErr::Internal(
+                    "connection refused".to_string(),
+                ))))
+            } else {
+                connect()
+            }
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    assert!(res.is_ok());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    assert_eq!(pings.load(Ordering::SeqCst), 1);
+}
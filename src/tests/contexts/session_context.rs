@@ -9,7 +9,7 @@ use crate::{
     to_active_models,
 };
 
-use chrono::{Duration, Utc};
+use chrono::{Duration, Local, Utc};
 
 async fn seed_db() -> (SessionContext, session::Model, user::Model, project::Model) {
     let db_context = get_reset_database_context().await;
@@ -39,6 +39,7 @@ async fn create_test() {
 
     let created_session = session_context.create(session.clone()).await.unwrap();
 
+    session.created_at = created_session.created_at;
     session.updated_at = created_session.updated_at;
 
     let fetched_session = session::Entity::find_by_id(created_session.id)
@@ -71,6 +72,43 @@ async fn create_default_created_at_test() {
     assert!(t_min <= t_actual && t_actual <= t_max)
 }
 
+#[tokio::test]
+async fn create_sets_created_at_test() {
+    let t_min = Local::now().naive_local();
+
+    let (session_context, session, _, _) = seed_db().await;
+
+    let created_session = session_context.create(session.clone()).await.unwrap();
+
+    let fetched_session = session::Entity::find_by_id(created_session.id)
+        .one(&session_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let t_max = Local::now().naive_local();
+    let created_at = fetched_session.created_at.unwrap();
+
+    assert!(t_min <= created_at && created_at <= t_max);
+}
+
+#[tokio::test]
+async fn update_does_not_modify_created_at_test() {
+    let (session_context, session, _, _) = seed_db().await;
+
+    let created_session = session_context.create(session).await.unwrap();
+
+    let updated_session = session_context
+        .update(session::Model {
+            created_at: None,
+            ..created_session.clone()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(updated_session.created_at, created_session.created_at);
+}
+
 #[tokio::test]
 async fn create_auto_increment_test() {
     // Setting up contexts and session context
@@ -420,3 +458,47 @@ async fn delete_by_token_access_test() {
 
     assert!(fetched_session.is_none());
 }
+
+#[tokio::test]
+async fn get_by_user_id_least_recently_updated_first_test() {
+    let (session_context, _, user, _) = seed_db().await;
+
+    let now = Utc::now().naive_utc();
+    let sessions: Vec<session::Model> = create_sessions(3, user.id)
+        .into_iter()
+        .zip([2, 0, 1])
+        .map(|(session, age)| session::Model {
+            updated_at: now - Duration::minutes(age),
+            ..session
+        })
+        .collect();
+
+    session::Entity::insert_many(to_active_models!(sessions.clone()))
+        .exec(&session_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched_sessions = session_context.get_by_user_id(user.id).await.unwrap();
+
+    assert_eq!(
+        fetched_sessions
+            .iter()
+            .map(|session| session.id)
+            .collect::<Vec<i32>>(),
+        vec![sessions[0].id, sessions[2].id, sessions[1].id]
+    );
+}
+
+#[tokio::test]
+async fn get_by_user_id_other_user_returns_empty_test() {
+    let (session_context, session, user, _) = seed_db().await;
+
+    session::Entity::insert(session.clone().into_active_model())
+        .exec(&session_context.db_context.get_connection())
+        .await
+        .unwrap();
+
+    let fetched_sessions = session_context.get_by_user_id(user.id + 1).await.unwrap();
+
+    assert!(fetched_sessions.is_empty());
+}
@@ -0,0 +1,75 @@
+use crate::contexts::sqlite_lock::{is_database_locked, retry_if_locked, MAX_LOCKED_ATTEMPTS};
+use sea_orm::{DbBackend, DbErr, RuntimeErr};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+fn locked() -> DbErr {
+    DbErr::Exec(RuntimeErr::Internal(
+        "error returned from database: (code: 5) database is locked".to_string(),
+    ))
+}
+
+/// Runs an operation that fails with a lock error the first `failures` times.
+async fn run_locked(backend: DbBackend, failures: u32) -> (Result<i32, DbErr>, u32) {
+    let attempts = AtomicU32::new(0);
+
+    let res = retry_if_locked(backend, || async {
+        if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+            Err(locked())
+        } else {
+            Ok(1)
+        }
+    })
+    .await;
+
+    (res, attempts.load(Ordering::SeqCst))
+}
+
+#[test]
+fn is_database_locked_lock_error_returns_true() {
+    assert!(is_database_locked(&locked()));
+}
+
+#[test]
+fn is_database_locked_other_error_returns_false() {
+    assert!(!is_database_locked(&DbErr::RecordNotFound(
+        "project".to_string()
+    )));
+}
+
+#[tokio::test]
+async fn retry_if_locked_transient_lock_succeeds_on_retry() {
+    let (res, attempts) = run_locked(DbBackend::Sqlite, 2).await;
+
+    assert_eq!(res.unwrap(), 1);
+    assert_eq!(attempts, 3);
+}
+
+#[tokio::test]
+async fn retry_if_locked_persistent_lock_returns_err() {
+    let (res, attempts) = run_locked(DbBackend::Sqlite, u32::MAX).await;
+
+    assert!(is_database_locked(&res.unwrap_err()));
+    assert_eq!(attempts, MAX_LOCKED_ATTEMPTS);
+}
+
+#[tokio::test]
+async fn retry_if_locked_postgres_does_not_retry() {
+    let (res, attempts) = run_locked(DbBackend::Postgres, 1).await;
+
+    assert!(res.is_err());
+    assert_eq!(attempts, 1);
+}
+
+#[tokio::test]
+async fn retry_if_locked_other_error_does_not_retry() {
+    let attempts = AtomicU32::new(0);
+
+    let res: Result<(), DbErr> = retry_if_locked(DbBackend::Sqlite, || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err(DbErr::RecordNotFound("project".to_string()))
+    })
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
@@ -18,6 +18,27 @@ async fn seed_db() -> (UserContext, user::Model) {
     (user_context, user)
 }
 
+#[tokio::test]
+async fn create_default_id_returns_database_id_test() {
+    let (user_context, user) = seed_db().await;
+
+    let user = user::Model {
+        id: Default::default(),
+        ..user
+    };
+
+    let created_user = user_context.create(user).await.unwrap();
+
+    let fetched_user = user::Entity::find_by_id(created_user.id)
+        .one(&user_context.db_context.get_connection())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_ne!(created_user.id, user::Model::default().id);
+    assert_eq!(fetched_user, created_user);
+}
+
 // Test the functionality of the 'create' function, which creates a user in the contexts
 #[tokio::test]
 async fn create_test() {
@@ -1,16 +1,25 @@
+use crate::api::auth::VerifiedUid;
 use crate::api::server::protobuf::create_access_request::User;
+use crate::api::server::protobuf::remove_collaborator_request;
 use crate::api::server::protobuf::{
-    AccessInfo, CreateAccessRequest, DeleteAccessRequest, ListAccessInfoRequest,
-    UpdateAccessRequest,
+    AccessInfo, AccessRecord, AccessUpdate, CreateAccessRequest, DeleteAccessRequest,
+    GetAccessesRequest, ListAccessInfoRequest, ListAccessInfoResponse, PageInfo,
+    RemoveCollaboratorRequest, UpdateAccessRequest, UpdateAccessesRequest,
 };
+use crate::config::Config;
+use crate::contexts::pagination::Page;
+use crate::controllers::controller_impls::access_controller::DuplicateAccessPolicy;
 use crate::controllers::controller_impls::AccessController;
 use crate::controllers::controller_traits::AccessControllerTrait;
 use crate::entities::{access, project, user};
-use crate::tests::controllers::helpers::{disguise_context_mocks, get_mock_contexts};
+use crate::services::access_events::AccessEvent;
+use crate::tests::controllers::helpers::{
+    disguise_context_mocks, get_mock_contexts, get_test_config, MockContexts,
+};
 use mockall::predicate;
 use sea_orm::DbErr;
 use std::str::FromStr;
-use tonic::{metadata, Code, Request};
+use tonic::{Code, Request, Response, Status};
 
 #[tokio::test]
 async fn create_invalid_access_returns_err() {
@@ -20,7 +29,9 @@ async fn create_invalid_access_returns_err() {
         id: Default::default(),
         role: "Editor".to_string(),
         project_id: 1,
-        user_id: 1,
+        user_id: 2,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -39,35 +50,41 @@ async fn create_invalid_access_returns_err() {
                 role: "Editor".to_owned(),
                 user_id: 1,
                 project_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(2), predicate::eq(1))
+        .returning(move |_, _| Ok(None));
+
     mock_contexts
         .user_context_mock
         .expect_get_by_id()
-        .with(predicate::eq(1))
+        .with(predicate::eq(2))
         .returning(move |_| {
             Ok(Some(user::Model {
-                id: 1,
+                id: 2,
                 email: Default::default(),
                 username: "test".to_string(),
                 password: "test".to_string(),
+                display_name: None,
             }))
         });
 
     let mut request = Request::new(CreateAccessRequest {
         role: "Editor".to_string(),
         project_id: 1,
-        user: Some(User::UserId(1)),
+        user: Some(User::UserId(2)),
     });
 
-    request.metadata_mut().insert(
-        "uid",
-        tonic::metadata::MetadataValue::from_str("1").unwrap(),
-    );
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.create_access(request).await.unwrap_err();
 
@@ -82,7 +99,9 @@ async fn create_access_returns_ok() {
         id: Default::default(),
         role: "Editor".to_string(),
         project_id: 1,
-        user_id: 1,
+        user_id: 2,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -95,6 +114,8 @@ async fn create_access_returns_ok() {
                 role: "Editor".to_string(),
                 user_id: 1,
                 project_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -104,32 +125,36 @@ async fn create_access_returns_ok() {
         .with(predicate::eq(access.clone()))
         .returning(move |_| Ok(access.clone()));
 
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(2), predicate::eq(1))
+        .returning(move |_, _| Ok(None));
+
     mock_contexts
         .user_context_mock
         .expect_get_by_id()
-        .with(predicate::eq(1))
+        .with(predicate::eq(2))
         .returning(move |_| {
             Ok(Some(user::Model {
-                id: 1,
+                id: 2,
                 email: Default::default(),
                 username: "test".to_string(),
                 password: "test".to_string(),
+                display_name: None,
             }))
         });
 
     let mut request = Request::new(CreateAccessRequest {
         role: "Editor".to_string(),
         project_id: 1,
-        user: Some(User::UserId(1)),
+        user: Some(User::UserId(2)),
     });
 
-    request.metadata_mut().insert(
-        "uid",
-        tonic::metadata::MetadataValue::from_str("1").unwrap(),
-    );
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.create_access(request).await;
 
@@ -145,6 +170,8 @@ async fn update_invalid_access_returns_err() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -163,6 +190,8 @@ async fn update_invalid_access_returns_err() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -176,6 +205,8 @@ async fn update_invalid_access_returns_err() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -197,13 +228,10 @@ async fn update_invalid_access_returns_err() {
         role: "Editor".to_string(),
     });
 
-    request.metadata_mut().insert(
-        "uid",
-        tonic::metadata::MetadataValue::from_str("1").unwrap(),
-    );
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.update_access(request).await.unwrap_err();
 
@@ -219,6 +247,8 @@ async fn update_access_returns_ok() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -237,6 +267,8 @@ async fn update_access_returns_ok() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -250,6 +282,8 @@ async fn update_access_returns_ok() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -271,13 +305,10 @@ async fn update_access_returns_ok() {
         role: "Editor".to_string(),
     });
 
-    request.metadata_mut().insert(
-        "uid",
-        tonic::metadata::MetadataValue::from_str("1").unwrap(),
-    );
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.update_access(request).await;
 
@@ -286,6 +317,82 @@ async fn update_access_returns_ok() {
     assert!(res.is_ok());
 }
 
+#[tokio::test]
+async fn update_access_publishes_role_changed_event() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_update()
+        .returning(move |access| Ok(access));
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(2))
+        .returning(move |_| {
+            Ok(Some(access::Model {
+                id: 2,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "test".to_string(),
+                owner_id: 1,
+                components_info: Default::default(),
+            }))
+        });
+
+    let mut request = Request::new(UpdateAccessRequest {
+        id: 2,
+        role: "Editor".to_string(),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+    let mut events = access_logic.events().subscribe();
+
+    access_logic.update_access(request).await.unwrap();
+
+    assert_eq!(
+        events.try_recv().unwrap(),
+        AccessEvent::RoleChanged {
+            project_id: 1,
+            user_id: 2,
+            role: "Editor".to_string(),
+            changed_by: 1,
+        }
+    );
+}
+
 #[tokio::test]
 async fn delete_invalid_access_returns_err() {
     let mut mock_contexts = get_mock_contexts();
@@ -306,6 +413,8 @@ async fn delete_invalid_access_returns_err() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -319,6 +428,8 @@ async fn delete_invalid_access_returns_err() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -337,13 +448,10 @@ async fn delete_invalid_access_returns_err() {
 
     let mut request = Request::new(DeleteAccessRequest { id: 2 });
 
-    request.metadata_mut().insert(
-        "uid",
-        tonic::metadata::MetadataValue::from_str("1").unwrap(),
-    );
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.delete_access(request).await.unwrap_err();
 
@@ -359,6 +467,8 @@ async fn delete_access_returns_ok() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -377,6 +487,8 @@ async fn delete_access_returns_ok() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -390,6 +502,8 @@ async fn delete_access_returns_ok() {
                 role: "Editor".to_string(),
                 project_id: 1,
                 user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -408,13 +522,10 @@ async fn delete_access_returns_ok() {
 
     let mut request = Request::new(DeleteAccessRequest { id: 2 });
 
-    request.metadata_mut().insert(
-        "uid",
-        tonic::metadata::MetadataValue::from_str("1").unwrap(),
-    );
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.delete_access(request).await;
 
@@ -425,18 +536,20 @@ async fn delete_access_returns_ok() {
 async fn list_access_info_returns_ok() {
     let mut mock_contexts = get_mock_contexts();
 
-    let mut request: Request<ListAccessInfoRequest> =
-        Request::new(ListAccessInfoRequest { project_id: 1 });
+    let mut request: Request<ListAccessInfoRequest> = Request::new(ListAccessInfoRequest {
+        project_id: 1,
+        page: 0,
+        page_size: 0,
+    });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let access = AccessInfo {
         id: 1,
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        display_name: "test".to_string(),
     };
 
     mock_contexts
@@ -448,43 +561,70 @@ async fn list_access_info_returns_ok() {
                 role: "Editor".to_string(),
                 project_id: Default::default(),
                 user_id: Default::default(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
     mock_contexts
         .access_context_mock
         .expect_get_access_by_project_id()
-        .returning(move |_| Ok(vec![access.clone()]));
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![access.clone()],
+                page_info: PageInfo {
+                    total: 1,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.list_access_info(request).await;
 
     assert!(res.is_ok());
 }
 
-#[tokio::test]
-async fn list_access_info_returns_not_found() {
+async fn list_access_info_with_no_accesses(
+    config: Config,
+) -> Result<Response<ListAccessInfoResponse>, Status> {
     let mut mock_contexts = get_mock_contexts();
 
-    let mut request = Request::new(ListAccessInfoRequest { project_id: 1 });
+    let mut request = Request::new(ListAccessInfoRequest {
+        project_id: 1,
+        page: 0,
+        page_size: 0,
+    });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let access = access::Model {
         id: 1,
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
         .access_context_mock
         .expect_get_access_by_project_id()
-        .returning(move |_| Ok(vec![]));
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![],
+                page_info: PageInfo {
+                    total: 0,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
 
     mock_contexts
         .access_context_mock
@@ -492,20 +632,40 @@ async fn list_access_info_returns_not_found() {
         .returning(move |_, _| Ok(Some(access.clone())));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, config);
 
-    let res = access_logic.list_access_info(request).await.unwrap_err();
+    access_logic.list_access_info(request).await
+}
 
-    assert_eq!(res.code(), Code::NotFound);
+#[tokio::test]
+async fn list_access_info_no_accesses_returns_empty_list() {
+    let res = list_access_info_with_no_accesses(get_test_config())
+        .await
+        .unwrap();
+
+    assert!(res.get_ref().access_info_list.is_empty());
+}
+
+#[tokio::test]
+async fn list_access_info_no_accesses_in_legacy_mode_returns_not_found() {
+    let res = list_access_info_with_no_accesses(Config {
+        legacy_not_found_on_empty_list: true,
+        ..get_test_config()
+    })
+    .await;
+
+    assert_eq!(res.unwrap_err().code(), Code::NotFound);
 }
 
 #[tokio::test]
 async fn list_access_info_returns_no_permission() {
-    let mut request = Request::new(ListAccessInfoRequest { project_id: 1 });
+    let mut request = Request::new(ListAccessInfoRequest {
+        project_id: 1,
+        page: 0,
+        page_size: 0,
+    });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let mut mock_contexts = get_mock_contexts();
 
@@ -515,9 +675,718 @@ async fn list_access_info_returns_no_permission() {
         .returning(move |_, _| Ok(None));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let access_logic = AccessController::new(contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
 
     let res = access_logic.list_access_info(request).await.unwrap_err();
 
     assert_eq!(res.code(), Code::PermissionDenied);
 }
+
+#[tokio::test]
+async fn update_accesses_reports_each_entry() {
+    let mut mock_contexts = get_mock_contexts();
+
+    // Access 1 belongs to the owner, access 2 to a collaborator and access 3 does not exist
+    mock_contexts
+        .access_context_mock
+        .expect_get_by_id()
+        .returning(move |id| match id {
+            1 | 2 => Ok(Some(access::Model {
+                id,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: id,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            })),
+            _ => Ok(None),
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "test".to_string(),
+                owner_id: 1,
+                components_info: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_update_roles()
+        .withf(|accesses| {
+            accesses.len() == 1 && accesses[0].id == 2 && accesses[0].role == "Reader"
+        })
+        .times(1)
+        .returning(move |accesses| Ok(accesses));
+
+    let mut request = Request::new(UpdateAccessesRequest {
+        updates: vec![
+            AccessUpdate {
+                access_id: 1,
+                new_role: "Reader".to_string(),
+            },
+            AccessUpdate {
+                access_id: 2,
+                new_role: "Reader".to_string(),
+            },
+            AccessUpdate {
+                access_id: 3,
+                new_role: "Reader".to_string(),
+            },
+        ],
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic.update_accesses(request).await.unwrap();
+
+    let results = &res.get_ref().results;
+
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| (result.access_id, result.success))
+            .collect::<Vec<_>>(),
+        vec![(1, false), (2, true), (3, false)]
+    );
+}
+
+#[tokio::test]
+async fn update_accesses_other_project_is_reported_as_not_found() {
+    let mut mock_contexts = get_mock_contexts();
+
+    // Access 3 is on project 3, the others on project 1, where user 2 is the owner
+    mock_contexts
+        .access_context_mock
+        .expect_get_by_id()
+        .returning(move |id| {
+            Ok(Some(access::Model {
+                id,
+                role: "Editor".to_string(),
+                project_id: if id == 3 { 3 } else { 1 },
+                user_id: id + 10,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "test".to_string(),
+                owner_id: 2,
+                components_info: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_update_roles()
+        .withf(|accesses| accesses.iter().map(|access| access.id).eq([2, 1]))
+        .times(1)
+        .returning(move |accesses| Ok(accesses));
+
+    let mut request = Request::new(UpdateAccessesRequest {
+        updates: [2, 3, 1]
+            .into_iter()
+            .map(|access_id| AccessUpdate {
+                access_id,
+                new_role: "Reader".to_string(),
+            })
+            .collect(),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic.update_accesses(request).await.unwrap();
+
+    let results = &res.get_ref().results;
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| (result.access_id, result.success))
+            .collect::<Vec<_>>(),
+        vec![(2, true), (3, false), (1, true)]
+    );
+    assert_eq!(results[1].error, "No access entity found with given id");
+}
+
+#[tokio::test]
+async fn update_accesses_not_editor_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_by_id()
+        .returning(move |id| {
+            Ok(Some(access::Model {
+                id,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| Ok(None));
+
+    mock_contexts
+        .access_context_mock
+        .expect_update_roles()
+        .never();
+
+    let mut request = Request::new(UpdateAccessesRequest {
+        updates: vec![AccessUpdate {
+            access_id: 2,
+            new_role: "Reader".to_string(),
+        }],
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic.update_accesses(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+/// Mocks a project owned by user 1, on which the requester (user 1) is an Editor,
+/// and a user named "collaborator" with id 2 whose access to the project is `collaborator_access`.
+fn remove_collaborator_mock_contexts(collaborator_access: Option<access::Model>) -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(2), predicate::eq(1))
+        .returning(move |_, _| Ok(collaborator_access.clone()));
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_username()
+        .returning(move |username| {
+            Ok((username == "collaborator").then(|| user::Model {
+                id: 2,
+                email: "collaborator@example.com".to_string(),
+                username: "collaborator".to_string(),
+                password: "password".to_string(),
+                display_name: None,
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "test".to_string(),
+                owner_id: 1,
+                components_info: Default::default(),
+            }))
+        });
+
+    mock_contexts
+}
+
+fn remove_collaborator_request(username: &str) -> Request<RemoveCollaboratorRequest> {
+    let mut request = Request::new(RemoveCollaboratorRequest {
+        project_id: 1,
+        user: Some(remove_collaborator_request::User::Username(
+            username.to_string(),
+        )),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn remove_collaborator_returns_ok() {
+    let collaborator_access = access::Model {
+        id: 2,
+        role: "Reader".to_string(),
+        project_id: 1,
+        user_id: 2,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    let mut mock_contexts = remove_collaborator_mock_contexts(Some(collaborator_access.clone()));
+
+    mock_contexts
+        .access_context_mock
+        .expect_delete()
+        .with(predicate::eq(2))
+        .times(1)
+        .returning(move |_| Ok(collaborator_access.clone()));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .remove_collaborator(remove_collaborator_request("collaborator"))
+        .await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn remove_collaborator_owner_returns_err() {
+    // The owner's own access, found by the owner's id
+    let mut mock_contexts = remove_collaborator_mock_contexts(None);
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(user::Model {
+                id: 1,
+                email: "owner@example.com".to_string(),
+                username: "owner".to_string(),
+                password: "password".to_string(),
+                display_name: None,
+            }))
+        });
+
+    mock_contexts.access_context_mock.expect_delete().never();
+
+    let mut request = Request::new(RemoveCollaboratorRequest {
+        project_id: 1,
+        user: Some(remove_collaborator_request::User::UserId(1)),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic.remove_collaborator(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn remove_collaborator_not_collaborator_returns_err() {
+    let mut mock_contexts = remove_collaborator_mock_contexts(None);
+
+    mock_contexts.access_context_mock.expect_delete().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .remove_collaborator(remove_collaborator_request("collaborator"))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn remove_collaborator_unknown_user_returns_err() {
+    let mut mock_contexts = remove_collaborator_mock_contexts(None);
+
+    mock_contexts.access_context_mock.expect_delete().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .remove_collaborator(remove_collaborator_request("unknown"))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn remove_collaborator_requester_not_editor_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Commenter".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts.access_context_mock.expect_delete().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .remove_collaborator(remove_collaborator_request("collaborator"))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+fn get_accesses_request(ids: Vec<i32>) -> Request<GetAccessesRequest> {
+    let mut request = Request::new(GetAccessesRequest { ids });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn get_accesses_skips_inaccessible_projects() {
+    let mut mock_contexts = get_mock_contexts();
+
+    // Access 1 and 2 are on project 1, where the requester is an Editor,
+    // access 3 is on project 2, where the requester is a Reader,
+    // and access 4 is on project 3, which the requester has no access to
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_ids()
+        .with(predicate::eq(vec![1, 2, 3, 4, 5]))
+        .times(1)
+        .returning(move |_| {
+            Ok([(1, 1, 1), (2, 1, 2), (3, 2, 3), (4, 3, 3)]
+                .into_iter()
+                .map(|(id, project_id, user_id)| access::Model {
+                    id,
+                    role: "Editor".to_string(),
+                    project_id,
+                    user_id,
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                })
+                .collect())
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_ids()
+        .withf(|uid, project_ids| {
+            let mut project_ids = project_ids.clone();
+            project_ids.sort();
+            *uid == 1 && project_ids == vec![1, 2, 3]
+        })
+        .times(1)
+        .returning(move |_, _| {
+            Ok(vec![
+                access::Model {
+                    id: 1,
+                    role: "Editor".to_string(),
+                    project_id: 1,
+                    user_id: 1,
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                },
+                access::Model {
+                    id: 5,
+                    role: "Reader".to_string(),
+                    project_id: 2,
+                    user_id: 1,
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                },
+            ])
+        });
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_ids()
+        .withf(|ids| {
+            let mut ids = ids.clone();
+            ids.sort();
+            ids == vec![1, 2]
+        })
+        .times(1)
+        .returning(move |ids| {
+            Ok(ids
+                .into_iter()
+                .map(|id| user::Model {
+                    id,
+                    email: Default::default(),
+                    username: format!("user{}", id),
+                    password: Default::default(),
+                    display_name: (id == 2).then(|| "User Two".to_string()),
+                })
+                .collect())
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .get_accesses(get_accesses_request(vec![1, 2, 3, 4, 5]))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(
+        res.accesses,
+        vec![
+            AccessRecord {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                username: "user1".to_string(),
+                display_name: "user1".to_string(),
+            },
+            AccessRecord {
+                id: 2,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 2,
+                username: "user2".to_string(),
+                display_name: "User Two".to_string(),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_accesses_none_accessible_returns_empty() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_ids()
+        .returning(move |_| {
+            Ok(vec![access::Model {
+                id: 3,
+                role: "Editor".to_string(),
+                project_id: 2,
+                user_id: 3,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }])
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_ids()
+        .returning(move |_, _| Ok(vec![]));
+
+    mock_contexts.user_context_mock.expect_get_by_ids().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .get_accesses(get_accesses_request(vec![3]))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.accesses.is_empty());
+}
+
+#[tokio::test]
+async fn get_accesses_too_many_ids_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_ids()
+        .never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(contexts, get_test_config());
+
+    let res = access_logic
+        .get_accesses(get_accesses_request((1..=101).collect()))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+}
+
+/// Invites user 2 as an Editor on project 1, where they already are a Reader,
+/// expecting the existing access to be updated `update_times` times.
+async fn reinvite_collaborator(
+    duplicate_access_policy: DuplicateAccessPolicy,
+    update_times: usize,
+) -> Result<Response<()>, Status> {
+    let mut mock_contexts = get_mock_contexts();
+
+    let existing = access::Model {
+        id: 5,
+        role: "Reader".to_string(),
+        project_id: 1,
+        user_id: 2,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(2), predicate::eq(1))
+        .returning(move |_, _| Ok(Some(existing.clone())));
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(2))
+        .returning(move |_| {
+            Ok(Some(user::Model {
+                id: 2,
+                email: Default::default(),
+                username: "collaborator".to_string(),
+                password: Default::default(),
+                display_name: None,
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                owner_id: 1,
+                components_info: Default::default(),
+            }))
+        });
+
+    mock_contexts.access_context_mock.expect_create().never();
+
+    mock_contexts
+        .access_context_mock
+        .expect_update()
+        .withf(|access| access.id == 5 && access.user_id == 2 && access.role == "Editor")
+        .times(update_times)
+        .returning(move |access| Ok(access));
+
+    let mut request = Request::new(CreateAccessRequest {
+        role: "Editor".to_string(),
+        project_id: 1,
+        user: Some(User::UserId(2)),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let access_logic = AccessController::new(
+        contexts,
+        Config {
+            duplicate_access_policy,
+            ..get_test_config()
+        },
+    );
+
+    access_logic.create_access(request).await
+}
+
+#[tokio::test]
+async fn create_access_existing_collaborator_follows_duplicate_access_policy() {
+    let res = reinvite_collaborator(DuplicateAccessPolicy::Reject, 0)
+        .await
+        .unwrap_err();
+    assert_eq!(res.code(), Code::AlreadyExists);
+
+    let res = reinvite_collaborator(DuplicateAccessPolicy::UpdateRole, 1).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn duplicate_access_policy_from_str() {
+    assert_eq!(
+        DuplicateAccessPolicy::from_str("update_role"),
+        Ok(DuplicateAccessPolicy::UpdateRole)
+    );
+    assert!(DuplicateAccessPolicy::from_str("upsert").is_err());
+}
@@ -0,0 +1,60 @@
+use crate::api::server::protobuf::ErrorDetail;
+use crate::controllers::error_details::{database_busy_error, status_with_reason, ReasonCode};
+use prost::Message;
+use sea_orm::{DbErr, RuntimeErr};
+use tonic::Code;
+
+#[tokio::test]
+async fn status_with_reason_details_decode() {
+    let status = status_with_reason(
+        Code::AlreadyExists,
+        "A user with that email already exists",
+        ReasonCode::DuplicateEmail,
+        Some("email"),
+    );
+
+    let detail = ErrorDetail::decode(status.details()).unwrap();
+
+    assert_eq!(status.code(), Code::AlreadyExists);
+    assert_eq!(status.message(), "A user with that email already exists");
+    assert_eq!(
+        detail,
+        ErrorDetail {
+            reason_code: "DUPLICATE_EMAIL".to_string(),
+            field: "email".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn status_with_reason_without_field_leaves_field_empty() {
+    let status = status_with_reason(
+        Code::AlreadyExists,
+        "User already exists",
+        ReasonCode::DuplicateUser,
+        None,
+    );
+
+    let detail = ErrorDetail::decode(status.details()).unwrap();
+
+    assert_eq!(detail.reason_code, "DUPLICATE_USER");
+    assert!(detail.field.is_empty());
+}
+
+#[tokio::test]
+async fn database_busy_error_locked_returns_unavailable() {
+    let error = DbErr::Exec(RuntimeErr::Internal("database is locked".to_string()));
+
+    let status = database_busy_error(&error).unwrap();
+    let detail = ErrorDetail::decode(status.details()).unwrap();
+
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(detail.reason_code, "DATABASE_BUSY");
+}
+
+#[tokio::test]
+async fn database_busy_error_other_error_returns_none() {
+    let error = DbErr::RecordNotFound("query".to_string());
+
+    assert!(database_busy_error(&error).is_none());
+}
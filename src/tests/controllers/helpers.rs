@@ -7,12 +7,15 @@ use crate::api::server::protobuf::{
     QueryRequest, QueryResponse, SimulationStartRequest, SimulationStepRequest,
     SimulationStepResponse, UserTokenResponse,
 };
+use crate::config::Config;
 use crate::contexts::context_collection::ContextCollection;
 use crate::contexts::context_traits::*;
-use crate::entities::{access, in_use, project, query, session, user};
+use crate::contexts::pagination::Page;
+use crate::entities::{access, in_use, project, project_event, query, query_result, session, user};
 use crate::services::service_collection::ServiceCollection;
 use crate::services::service_traits::*;
 use async_trait::async_trait;
+use chrono::Duration;
 use mockall::mock;
 use sea_orm::DbErr;
 use std::sync::Arc;
@@ -23,12 +26,30 @@ pub fn get_mock_contexts() -> MockContexts {
         access_context_mock: MockAccessContext::new(),
         in_use_context_mock: MockInUseContext::new(),
         project_context_mock: MockProjectContext::new(),
+        project_event_context_mock: MockProjectEventContext::new(),
         query_context_mock: MockQueryContext::new(),
+        query_result_context_mock: MockQueryResultContext::new(),
         session_context_mock: MockSessionContext::new(),
         user_context_mock: MockUserContext::new(),
     }
 }
 
+/// Returns a valid configuration with every optional setting left at its default.
+pub fn get_test_config() -> Config {
+    Config::from_lookup(|name| {
+        match name {
+            "DATABASE_URL" => Some("sqlite::memory:"),
+            "REVEAAL_ADDRESS" => Some("http://127.0.0.1:4000"),
+            "API_ADDRESS" => Some("127.0.0.1:5000"),
+            "ACCESS_TOKEN_HS512_SECRET" => Some("access_secret"),
+            "REFRESH_TOKEN_HS512_SECRET" => Some("refresh_secret"),
+            _ => None,
+        }
+        .map(str::to_string)
+    })
+    .unwrap()
+}
+
 pub fn get_mock_services() -> MockServices {
     MockServices {
         hashing_service_mock: MockHashingService::new(),
@@ -41,7 +62,9 @@ pub fn disguise_context_mocks(mock_services: MockContexts) -> ContextCollection
         access_context: Arc::new(mock_services.access_context_mock),
         in_use_context: Arc::new(mock_services.in_use_context_mock),
         project_context: Arc::new(mock_services.project_context_mock),
+        project_event_context: Arc::new(mock_services.project_event_context_mock),
         query_context: Arc::new(mock_services.query_context_mock),
+        query_result_context: Arc::new(mock_services.query_result_context_mock),
         session_context: Arc::new(mock_services.session_context_mock),
         user_context: Arc::new(mock_services.user_context_mock),
     }
@@ -58,7 +81,9 @@ pub struct MockContexts {
     pub(crate) access_context_mock: MockAccessContext,
     pub(crate) in_use_context_mock: MockInUseContext,
     pub(crate) project_context_mock: MockProjectContext,
+    pub(crate) project_event_context_mock: MockProjectEventContext,
     pub(crate) query_context_mock: MockQueryContext,
+    pub(crate) query_result_context_mock: MockQueryResultContext,
     pub(crate) session_context_mock: MockSessionContext,
     pub(crate) user_context_mock: MockUserContext,
 }
@@ -89,7 +114,21 @@ mock! {
         async fn get_access_by_project_id(
             &self,
             project_id: i32,
-        ) -> Result<Vec<AccessInfo>, DbErr>;
+            page: u64,
+            page_size: u64,
+        ) -> Result<Page<AccessInfo>, DbErr>;
+
+        async fn update_roles(&self, entities: Vec<access::Model>) -> Result<Vec<access::Model>, DbErr>;
+
+        async fn get_accessible_project_ids(&self, uid: i32) -> Result<Vec<i32>, DbErr>;
+
+        async fn get_access_by_ids(&self, ids: Vec<i32>) -> Result<Vec<access::Model>, DbErr>;
+
+        async fn get_access_by_uid_and_project_ids(
+            &self,
+            uid: i32,
+            project_ids: Vec<i32>,
+        ) -> Result<Vec<access::Model>, DbErr>;
     }
 }
 
@@ -104,7 +143,10 @@ mock! {
         async fn delete(&self, entity_id: i32) -> Result<in_use::Model, DbErr>;
     }
     #[async_trait]
-    impl InUseContextTrait for InUseContext {}
+    impl InUseContextTrait for InUseContext {
+        async fn count_active_locks(&self, timeout: Duration) -> Result<u64, DbErr>;
+        async fn get_active_locks_by_uid(&self, uid: i32, timeout: Duration) -> Result<Vec<in_use::Model>, DbErr>;
+    }
 }
 
 mock! {
@@ -119,7 +161,42 @@ mock! {
     }
     #[async_trait]
     impl ProjectContextTrait for ProjectContext {
-        async fn get_project_info_by_uid(&self, uid: i32) -> Result<Vec<ProjectInfo>, DbErr>;
+        async fn get_project_info_by_uid(&self, uid: i32, page: u64, page_size: u64) -> Result<Page<ProjectInfo>, DbErr>;
+        async fn get_projects_by_uid(&self, uid: i32, page: u64, page_size: u64) -> Result<Page<project::Model>, DbErr>;
+        async fn update_with_events(&self, entity: project::Model, events: Vec<project_event::Model>) -> Result<project::Model, DbErr>;
+        async fn create_with_queries(&self, entity: project::Model, access: access::Model, in_use: Option<in_use::Model>, queries: Vec<query::Model>) -> Result<(project::Model, Vec<query::Model>), DbErr>;
+    }
+}
+
+mock! {
+    pub ProjectEventContext {}
+    #[async_trait]
+    impl EntityContextTrait<project_event::Model> for ProjectEventContext {
+        async fn create(&self, entity: project_event::Model) -> Result<project_event::Model, DbErr>;
+        async fn get_by_id(&self, entity_id: i32) -> Result<Option<project_event::Model>, DbErr>;
+        async fn get_all(&self) -> Result<Vec<project_event::Model>, DbErr>;
+        async fn update(&self, entity: project_event::Model) -> Result<project_event::Model, DbErr>;
+        async fn delete(&self, entity_id: i32) -> Result<project_event::Model, DbErr>;
+    }
+    #[async_trait]
+    impl ProjectEventContextTrait for ProjectEventContext {
+        async fn get_by_project_id(&self, project_id: i32) -> Result<Vec<project_event::Model>, DbErr>;
+    }
+}
+
+mock! {
+    pub QueryResultContext {}
+    #[async_trait]
+    impl EntityContextTrait<query_result::Model> for QueryResultContext {
+        async fn create(&self, entity: query_result::Model) -> Result<query_result::Model, DbErr>;
+        async fn get_by_id(&self, entity_id: i32) -> Result<Option<query_result::Model>, DbErr>;
+        async fn get_all(&self) -> Result<Vec<query_result::Model>, DbErr>;
+        async fn update(&self, entity: query_result::Model) -> Result<query_result::Model, DbErr>;
+        async fn delete(&self, entity_id: i32) -> Result<query_result::Model, DbErr>;
+    }
+    #[async_trait]
+    impl QueryResultContextTrait for QueryResultContext {
+        async fn get_by_query_id(&self, query_id: i32) -> Result<Vec<query_result::Model>, DbErr>;
     }
 }
 
@@ -135,7 +212,10 @@ mock! {
     }
     #[async_trait]
     impl QueryContextTrait for QueryContext {
-        async fn get_all_by_project_id(&self, project_id: i32) -> Result<Vec<query::Model>, DbErr>;
+        async fn get_all_by_project_id(&self, project_id: i32, limit: Option<u64>) -> Result<Vec<query::Model>, DbErr>;
+        async fn count_outdated_queries(&self, project_id: i32) -> Result<u64, DbErr>;
+        async fn count_by_project_id(&self, project_id: i32) -> Result<u64, DbErr>;
+        async fn mark_outdated(&self, query_ids: Vec<i32>) -> Result<u64, DbErr>;
     }
 }
 
@@ -153,6 +233,7 @@ mock! {
     impl SessionContextTrait for SessionContext {
         async fn get_by_token(&self, token_type: TokenType, token: String) -> Result<Option<session::Model>, DbErr>;
         async fn delete_by_token(&self, token_type: TokenType, token: String) -> Result<session::Model, DbErr>;
+        async fn get_by_user_id(&self, user_id: i32) -> Result<Vec<session::Model>, DbErr>;
     }
 }
 
@@ -182,6 +263,7 @@ mock! {
         async fn send_query(&self,request: Request<QueryRequest>) -> Result<Response<QueryResponse>, Status>;
         async fn start_simulation(&self, request: Request<SimulationStartRequest>) -> Result<Response<SimulationStepResponse>, Status>;
         async fn take_simulation_step(&self, request: Request<SimulationStepRequest>) -> Result<Response<SimulationStepResponse>, Status>;
+        async fn call_raw(&self, method: &str, payload: Vec<u8>) -> Result<Vec<u8>, Status>;
     }
 }
 
@@ -0,0 +1,144 @@
+use crate::controllers::project_access::load_project_with_role;
+use crate::entities::role::Role;
+use crate::entities::{access, project};
+use crate::tests::controllers::helpers::{disguise_context_mocks, get_mock_contexts, MockContexts};
+use mockall::predicate;
+use sea_orm::DbErr;
+use tonic::Code;
+
+fn project() -> project::Model {
+    project::Model {
+        id: 1,
+        name: "project".to_string(),
+        components_info: Default::default(),
+        owner_id: 1,
+    }
+}
+
+fn access(role: Role) -> access::Model {
+    access::Model {
+        id: 1,
+        role: role.to_string(),
+        project_id: 1,
+        user_id: 2,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    }
+}
+
+fn mock_contexts(project: Option<project::Model>, access: Option<access::Model>) -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(project.clone()));
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(2), predicate::eq(1))
+        .returning(move |_, _| Ok(access.clone()));
+
+    mock_contexts
+}
+
+#[tokio::test]
+async fn load_project_with_role_returns_project_and_access() {
+    let contexts =
+        disguise_context_mocks(mock_contexts(Some(project()), Some(access(Role::Editor))));
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Commenter)
+        .await
+        .unwrap();
+
+    assert_eq!(res, (project(), access(Role::Editor)));
+}
+
+#[tokio::test]
+async fn load_project_with_role_exact_role_returns_ok() {
+    let contexts =
+        disguise_context_mocks(mock_contexts(Some(project()), Some(access(Role::Reader))));
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Reader).await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn load_project_with_role_no_project_returns_err() {
+    let contexts = disguise_context_mocks(mock_contexts(None, Some(access(Role::Editor))));
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Reader)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn load_project_with_role_no_access_returns_err() {
+    let contexts = disguise_context_mocks(mock_contexts(Some(project()), None));
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Reader)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn load_project_with_role_insufficient_role_returns_err() {
+    let contexts = disguise_context_mocks(mock_contexts(
+        Some(project()),
+        Some(access(Role::Commenter)),
+    ));
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Editor)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn load_project_with_role_project_context_fails_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| Err(DbErr::RecordNotFound("project".to_string())));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Reader)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+}
+
+#[tokio::test]
+async fn load_project_with_role_access_context_fails_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| Ok(Some(project())));
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| Err(DbErr::RecordNotFound("access".to_string())));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+
+    let res = load_project_with_role(&contexts, 2, 1, Role::Reader)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+}
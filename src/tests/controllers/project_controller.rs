@@ -1,22 +1,41 @@
+use crate::config::Config;
+use crate::contexts::context_traits::EntityContextTrait;
+use crate::contexts::pagination::Page;
+use crate::controllers::controller_impls::project_controller::{
+    check_delete_confirmation, compute_components_hash, diff_components, json_depth_exceeds,
+    normalize_components_info, project_version, validate_components_info, validate_project_name,
+    LockConfig, LockState, ProjectConfig,
+};
+use crate::controllers::controller_impls::query_controller::{query_result_to_json, QueryConfig};
 use crate::controllers::controller_impls::ProjectController;
 use crate::controllers::controller_traits::ProjectControllerTrait;
-use crate::tests::controllers::helpers::disguise_context_mocks;
+use crate::tests::contexts::helpers::{
+    create_accesses, create_sessions, create_users, get_context_collection,
+    get_reset_database_context, seed_full_project,
+};
+use crate::tests::controllers::helpers::{disguise_context_mocks, get_test_config, MockContexts};
 use crate::{
     api::{
-        auth::TokenType,
+        auth::{DeleteConfirmationClaims, TokenType, VerifiedUid},
         server::protobuf::{
-            component::Rep, Component, ComponentsInfo, CreateProjectRequest, DeleteProjectRequest,
-            GetProjectRequest, ProjectInfo, UpdateProjectRequest,
+            component::Rep, query_response, Component, ComponentsInfo, CreateProjectRequest,
+            CreateProjectWithQueriesRequest, DeleteProjectRequest, DiffProjectRequest, ErrorDetail,
+            GetLockStatusRequest, GetProjectHistoryRequest, GetProjectRequest, HeartbeatRequest,
+            ListMyRolesRequest, ListProjectsInfoRequest, ListProjectsInfoResponse,
+            ListProjectsRequest, PageInfo, ProjectInfo, ProjectLock, ProjectRole, Query,
+            RequestDeleteRequest, UpdateProjectRequest,
         },
     },
-    entities::{access, in_use, project, query, session},
+    entities::{access, in_use, project, project_event, query, session, user},
     tests::controllers::helpers::get_mock_contexts,
 };
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use mockall::predicate;
-use sea_orm::DbErr;
+use prost::Message;
+use sea_orm::{DbErr, EntityTrait, IntoActiveModel};
+use std::env;
 use std::str::FromStr;
-use tonic::{metadata, Code, Request};
+use tonic::{metadata, Code, Request, Response, Status};
 
 #[tokio::test]
 async fn create_project_returns_ok() {
@@ -31,8 +50,12 @@ async fn create_project_returns_ok() {
 
     let project = project::Model {
         id: Default::default(),
-        name: Default::default(),
-        components_info: serde_json::to_value(components_info.clone()).unwrap(),
+        name: "project".to_string(),
+        components_info: serde_json::to_value(ComponentsInfo {
+            components: vec![],
+            components_hash: compute_components_hash(&[]),
+        })
+        .unwrap(),
         owner_id: uid,
     };
 
@@ -41,12 +64,15 @@ async fn create_project_returns_ok() {
         role: "Editor".to_string(),
         user_id: uid,
         project_id: project.id,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let session = session::Model {
         id: Default::default(),
         refresh_token: "refresh_token".to_string(),
         access_token: "access_token".to_string(),
+        created_at: Default::default(),
         updated_at: Default::default(),
         user_id: uid,
     };
@@ -85,13 +111,11 @@ async fn create_project_returns_ok() {
         .returning(move |_| Ok(in_use.clone()));
 
     let mut request = Request::new(CreateProjectRequest {
-        name: Default::default(),
+        name: "project".to_string(),
         components_info: Option::from(components_info),
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", uid.to_string().parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(uid));
 
     request.metadata_mut().insert(
         "authorization",
@@ -99,11 +123,55 @@ async fn create_project_returns_ok() {
     );
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
-    let res = project_logic.create_project(request).await;
+    let res = project_logic.create_project(request).await.unwrap();
 
-    assert!(res.is_ok());
+    assert_eq!(res.get_ref().components_hash, compute_components_hash(&[]));
+}
+
+#[tokio::test]
+async fn create_project_without_session_skips_in_use() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .returning(move |_, _| Ok(None));
+
+    mock_contexts
+        .project_context_mock
+        .expect_create()
+        .times(1)
+        .returning(move |project| Ok(project::Model { id: 1, ..project }));
+
+    mock_contexts
+        .access_context_mock
+        .expect_create()
+        .withf(|access| access.project_id == 1 && access.user_id == 0)
+        .times(1)
+        .returning(move |access| Ok(access));
+
+    mock_contexts.in_use_context_mock.expect_create().never();
+
+    let mut request = Request::new(CreateProjectRequest {
+        name: "project".to_string(),
+        components_info: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
+    );
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.create_project(request).await.unwrap();
+
+    assert_eq!(res.get_ref().id, 1);
 }
 
 #[tokio::test]
@@ -115,33 +183,64 @@ async fn create_project_existing_name_returns_err() {
     let project = project::Model {
         id: Default::default(),
         name: "project".to_string(),
-        components_info: Default::default(),
+        components_info: serde_json::to_value(ProjectConfig::default().default_components_info)
+            .unwrap(),
         owner_id: uid,
     };
 
+    // The real constraint errors of each backend are covered in tests/contexts/constraints.rs
     mock_contexts
         .project_context_mock
         .expect_create()
         .with(predicate::eq(project.clone()))
-        .returning(move |_| Err(DbErr::RecordNotInserted)); //todo!("Needs to be a SqlError with UniqueConstraintViolation with 'name' in message)
+        .returning(move |_| Err(DbErr::RecordNotInserted));
 
     let mut request = Request::new(CreateProjectRequest {
         name: "project".to_string(),
         components_info: Default::default(),
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", uid.to_string().parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(uid));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.create_project(request).await;
 
     assert_eq!(res.unwrap_err().code(), Code::InvalidArgument); //todo!("Needs to be code AlreadyExists when mocked Error is corrected)
 }
 
+#[tokio::test]
+async fn create_project_error_quoting_name_is_sanitized() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_create()
+        .returning(move |project| {
+            Err(DbErr::Custom(format!(
+                "could not insert \"{}\"\nERROR forged log line",
+                project.name
+            )))
+        });
+
+    let mut request = Request::new(CreateProjectRequest {
+        name: "project".to_string(),
+        components_info: Default::default(),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.create_project(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+    assert!(!res.message().contains('\n'));
+    assert!(res.message().contains("ERROR forged log line"));
+}
+
 #[tokio::test]
 async fn get_project_user_has_access_returns_ok() {
     let mut mock_contexts = get_mock_contexts();
@@ -158,6 +257,8 @@ async fn get_project_user_has_access_returns_ok() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let in_use = in_use::Model {
@@ -189,15 +290,19 @@ async fn get_project_user_has_access_returns_ok() {
     mock_contexts
         .query_context_mock
         .expect_get_all_by_project_id()
-        .with(predicate::eq(0))
-        .returning(move |_| Ok(queries.clone()));
+        .with(predicate::eq(0), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
 
-    let mut request = Request::new(GetProjectRequest { id: 0 });
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
 
-    request.metadata_mut().insert("uid", "0".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.get_project(request).await;
 
@@ -221,14 +326,15 @@ async fn delete_not_owner_returns_err() {
             }))
         });
 
-    let mut request = Request::new(DeleteProjectRequest { id: 1 });
+    let mut request = Request::new(DeleteProjectRequest {
+        id: 1,
+        confirmation_token: None,
+    });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.delete_project(request).await.unwrap_err();
 
@@ -245,14 +351,15 @@ async fn delete_invalid_project_returns_err() {
         .with(predicate::eq(2))
         .returning(move |_| Ok(None));
 
-    let mut request = Request::new(DeleteProjectRequest { id: 2 });
+    let mut request = Request::new(DeleteProjectRequest {
+        id: 2,
+        confirmation_token: None,
+    });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.delete_project(request).await.unwrap_err();
 
@@ -289,20 +396,109 @@ async fn delete_project_returns_ok() {
             })
         });
 
-    let mut request = Request::new(DeleteProjectRequest { id: 1 });
+    let mut request = Request::new(DeleteProjectRequest {
+        id: 1,
+        confirmation_token: None,
+    });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.delete_project(request).await;
 
     assert!(res.is_ok());
 }
 
+#[tokio::test]
+async fn request_delete_token_confirms_deletion() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: Default::default(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    let mut request = Request::new(RequestDeleteRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.request_delete(request).await.unwrap();
+    let token = res.get_ref().confirmation_token.as_str();
+
+    assert!(res.get_ref().expires_at > Utc::now().timestamp());
+    assert!(check_delete_confirmation(Some(token), 1, 1).is_ok());
+}
+
+#[tokio::test]
+async fn request_delete_non_owner_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: Default::default(),
+                components_info: Default::default(),
+                owner_id: 2,
+            }))
+        });
+
+    let mut request = Request::new(RequestDeleteRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.request_delete(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn check_delete_confirmation_expired_token_returns_err() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+    let (token, _) = DeleteConfirmationClaims::new_token(1, 1, Duration::minutes(-5)).unwrap();
+
+    let res = check_delete_confirmation(Some(&token), 1, 1).unwrap_err();
+
+    assert_eq!(res.code(), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn check_delete_confirmation_other_project_returns_err() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+    let (token, _) = DeleteConfirmationClaims::new_token(1, 2, Duration::minutes(1)).unwrap();
+
+    let res = check_delete_confirmation(Some(&token), 1, 1).unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn check_delete_confirmation_missing_token_returns_err() {
+    let res = check_delete_confirmation(None, 1, 1).unwrap_err();
+
+    assert_eq!(res.code(), Code::FailedPrecondition);
+}
+
 #[tokio::test]
 async fn get_project_user_has_no_access_returns_err() {
     let mut mock_contexts = get_mock_contexts();
@@ -343,15 +539,19 @@ async fn get_project_user_has_no_access_returns_err() {
     mock_contexts
         .query_context_mock
         .expect_get_all_by_project_id()
-        .with(predicate::eq(0))
-        .returning(move |_| Ok(queries.clone()));
+        .with(predicate::eq(0), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
 
-    let mut request = Request::new(GetProjectRequest { id: 0 });
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
 
-    request.metadata_mut().insert("uid", "0".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.get_project(request).await.unwrap_err();
 
@@ -374,6 +574,8 @@ async fn get_project_is_in_use_is_true() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let in_use = in_use::Model {
@@ -405,15 +607,19 @@ async fn get_project_is_in_use_is_true() {
     mock_contexts
         .query_context_mock
         .expect_get_all_by_project_id()
-        .with(predicate::eq(0))
-        .returning(move |_| Ok(queries.clone()));
+        .with(predicate::eq(0), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
 
-    let mut request = Request::new(GetProjectRequest { id: 0 });
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
 
-    request.metadata_mut().insert("uid", "0".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.get_project(request).await;
 
@@ -436,6 +642,8 @@ async fn get_project_is_in_use_is_false() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let in_use = in_use::Model {
@@ -454,6 +662,7 @@ async fn get_project_is_in_use_is_false() {
         id: 0,
         refresh_token: "refresh_token".to_owned(),
         access_token: "access_token".to_owned(),
+        created_at: Default::default(),
         updated_at: Default::default(),
         user_id: Default::default(),
     };
@@ -490,29 +699,122 @@ async fn get_project_is_in_use_is_false() {
     mock_contexts
         .query_context_mock
         .expect_get_all_by_project_id()
-        .with(predicate::eq(0))
-        .returning(move |_| Ok(queries.clone()));
+        .with(predicate::eq(0), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
 
     mock_contexts
         .in_use_context_mock
         .expect_update()
         .returning(move |_| Ok(updated_in_use.clone()));
 
-    let mut request = Request::new(GetProjectRequest { id: 0 });
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
 
     request
         .metadata_mut()
         .insert("authorization", "Bearer access_token".parse().unwrap());
-    request.metadata_mut().insert("uid", "0".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.get_project(request).await;
 
     assert!(!res.unwrap().get_ref().in_use);
 }
 
+#[tokio::test]
+async fn get_project_missing_in_use_recreates_unlocked_in_use() {
+    let mut mock_contexts = get_mock_contexts();
+
+    let project = project::Model {
+        name: "project".to_string(),
+        components_info: serde_json::to_value(ComponentsInfo::default()).unwrap(),
+        ..Default::default()
+    };
+
+    let access = access::Model {
+        role: "Reader".to_string(),
+        ..Default::default()
+    };
+
+    let session = session::Model {
+        id: 2,
+        access_token: "access_token".to_owned(),
+        ..Default::default()
+    };
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(project.clone())));
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .returning(|_| Ok(None));
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .with(
+            predicate::eq(TokenType::AccessToken),
+            predicate::eq("access_token".to_owned()),
+        )
+        .returning(move |_, _| Ok(Some(session.clone())));
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_create()
+        .withf(|in_use| in_use.project_id == 0 && in_use.session_id == 2)
+        .times(1)
+        .returning(|in_use| {
+            Ok(in_use::Model {
+                latest_activity: Utc::now().naive_utc(),
+                ..in_use
+            })
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_update()
+        .withf(|in_use| in_use.latest_activity == Default::default())
+        .times(1)
+        .returning(Ok);
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .returning(|_, _| Ok(vec![]));
+
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
+
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer access_token".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.get_project(request).await.unwrap();
+
+    assert!(!res.get_ref().in_use);
+    assert_eq!(res.get_ref().project.as_ref().unwrap().name, "project");
+}
+
 #[tokio::test]
 async fn get_project_project_has_no_queries_queries_are_empty() {
     let mut mock_contexts = get_mock_contexts();
@@ -529,6 +831,8 @@ async fn get_project_project_has_no_queries_queries_are_empty() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let in_use = in_use::Model {
@@ -560,15 +864,19 @@ async fn get_project_project_has_no_queries_queries_are_empty() {
     mock_contexts
         .query_context_mock
         .expect_get_all_by_project_id()
-        .with(predicate::eq(0))
-        .returning(move |_| Ok(queries.clone()));
+        .with(predicate::eq(0), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
 
-    let mut request = Request::new(GetProjectRequest { id: 0 });
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
 
-    request.metadata_mut().insert("uid", "0".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.get_project(request).await;
 
@@ -591,6 +899,8 @@ async fn get_project_query_has_no_result_query_is_empty() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let in_use = in_use::Model {
@@ -605,6 +915,12 @@ async fn get_project_query_has_no_result_query_is_empty() {
         string: "query".to_owned(),
         result: None,
         outdated: false,
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let queries: Vec<query::Model> = vec![query];
@@ -630,15 +946,19 @@ async fn get_project_query_has_no_result_query_is_empty() {
     mock_contexts
         .query_context_mock
         .expect_get_all_by_project_id()
-        .with(predicate::eq(0))
-        .returning(move |_| Ok(queries.clone()));
+        .with(predicate::eq(0), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
 
-    let mut request = Request::new(GetProjectRequest { id: 0 });
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
 
-    request.metadata_mut().insert("uid", "0".parse().unwrap());
+    request.extensions_mut().insert(VerifiedUid(0));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.get_project(request).await;
 
@@ -646,30 +966,198 @@ async fn get_project_query_has_no_result_query_is_empty() {
 }
 
 #[tokio::test]
-async fn list_projects_info_returns_ok() {
+async fn get_project_stored_query_results_survive_round_trip() {
     let mut mock_contexts = get_mock_contexts();
 
-    let project_info = ProjectInfo {
-        project_id: 1,
-        project_name: "project::Model name".to_owned(),
-        project_owner_id: 1,
-        user_role_on_project: "Editor".to_owned(),
+    let project = project::Model {
+        id: Default::default(),
+        name: "project".to_string(),
+        components_info: Default::default(),
+        owner_id: 0,
     };
 
-    mock_contexts
-        .project_context_mock
-        .expect_get_project_info_by_uid()
-        .with(predicate::eq(1))
-        .returning(move |_| Ok(vec![project_info.clone()]));
+    let access = access::Model {
+        id: Default::default(),
+        role: "Reader".to_string(),
+        project_id: 1,
+        user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
 
-    let mut list_projects_info_request = Request::new(());
+    let in_use = in_use::Model {
+        project_id: Default::default(),
+        session_id: 0,
+        latest_activity: Utc::now().naive_utc(),
+    };
+
+    // Stored the way send_query stores a result
+    let stored_result = query_result_to_json(Some(query_response::Result::Success(
+        query_response::Success {},
+    )))
+    .unwrap();
+
+    let queries = vec![
+        query::Model {
+            id: 1,
+            project_id: 0,
+            string: "A <= B".to_owned(),
+            result: Some(stored_result.clone()),
+            outdated: false,
+            last_run_ms: Default::default(),
+            result_computed_at: Default::default(),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            title: Default::default(),
+            settings: Default::default(),
+        },
+        query::Model {
+            id: 2,
+            project_id: 0,
+            string: "legacy query".to_owned(),
+            result: Some("result".into()),
+            outdated: false,
+            last_run_ms: Default::default(),
+            result_computed_at: Default::default(),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            title: Default::default(),
+            settings: Default::default(),
+        },
+    ];
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(project.clone())));
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(in_use.clone())));
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .returning(move |_, _| Ok(queries.clone()));
+
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.get_project(request).await.unwrap();
+
+    let queries = &res.get_ref().queries;
+    assert_eq!(queries.len(), 2);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&queries[0].result).unwrap(),
+        stored_result
+    );
+    assert_eq!(queries[1].result, "result");
+}
+
+#[tokio::test]
+async fn get_project_corrupt_components_info_returns_data_loss() {
+    let mut mock_contexts = get_mock_contexts();
+
+    // Stored in a shape that does not match ComponentsInfo, as legacy data might be
+    let project = project::Model {
+        id: Default::default(),
+        name: "project".to_string(),
+        components_info: serde_json::json!({ "components": "not a list" }),
+        owner_id: 0,
+    };
+
+    let access = access::Model {
+        id: Default::default(),
+        role: "Reader".to_string(),
+        project_id: 0,
+        user_id: 0,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(0), predicate::eq(0))
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(0))
+        .returning(move |_| Ok(Some(project.clone())));
+
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version: None,
+        include_query_results: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.get_project(request).await.unwrap_err();
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::DataLoss);
+    assert_eq!(detail.reason_code, "CORRUPT_COMPONENTS_INFO");
+}
+
+#[tokio::test]
+async fn list_projects_info_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+
+    let project_info = ProjectInfo {
+        project_id: 1,
+        project_name: "project::Model name".to_owned(),
+        project_owner_id: 1,
+        project_owner_username: Some("owner".to_owned()),
+        user_role_on_project: "Editor".to_owned(),
+    };
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_project_info_by_uid()
+        .with(predicate::eq(1), predicate::eq(0), predicate::eq(0))
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![project_info.clone()],
+                page_info: PageInfo {
+                    total: 1,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
+
+    let mut list_projects_info_request = Request::new(ListProjectsInfoRequest {
+        page: 0,
+        page_size: 0,
+    });
 
     list_projects_info_request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+        .extensions_mut()
+        .insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic
         .list_projects_info(list_projects_info_request)
@@ -679,29 +1167,265 @@ async fn list_projects_info_returns_ok() {
 }
 
 #[tokio::test]
-async fn list_projects_info_returns_err() {
+async fn list_my_roles_returns_owned_and_shared_projects() {
     let mut mock_contexts = get_mock_contexts();
 
+    let project_infos = vec![
+        ProjectInfo {
+            project_id: 1,
+            project_name: "mine".to_owned(),
+            project_owner_id: 1,
+            project_owner_username: Some("me".to_owned()),
+            user_role_on_project: "Editor".to_owned(),
+        },
+        ProjectInfo {
+            project_id: 2,
+            project_name: "shared".to_owned(),
+            project_owner_id: 2,
+            project_owner_username: Some("other".to_owned()),
+            user_role_on_project: "Reader".to_owned(),
+        },
+    ];
+
     mock_contexts
         .project_context_mock
         .expect_get_project_info_by_uid()
+        .with(predicate::eq(1), predicate::eq(0), predicate::eq(0))
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: project_infos.clone(),
+                page_info: PageInfo {
+                    total: 2,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
+
+    let mut request = Request::new(ListMyRolesRequest {
+        page: 0,
+        page_size: 0,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.list_my_roles(request).await.unwrap();
+
+    assert_eq!(
+        res.get_ref().roles,
+        vec![
+            ProjectRole {
+                project_id: 1,
+                project_name: "mine".to_owned(),
+                role: "Editor".to_owned(),
+                is_owner: true,
+            },
+            ProjectRole {
+                project_id: 2,
+                project_name: "shared".to_owned(),
+                role: "Reader".to_owned(),
+                is_owner: false,
+            },
+        ]
+    );
+    assert_eq!(res.get_ref().page_info.as_ref().unwrap().total, 2);
+}
+
+#[tokio::test]
+async fn list_my_locks_returns_locks_with_expiry() {
+    let mut mock_contexts = get_mock_contexts();
+
+    let latest_activity = Utc::now().naive_utc();
+    let in_use = in_use::Model {
+        project_id: 3,
+        session_id: 1,
+        latest_activity,
+    };
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_active_locks_by_uid()
+        .with(
+            predicate::eq(1),
+            predicate::eq(LockConfig::default().duration),
+        )
+        .returning(move |_, _| Ok(vec![in_use.clone()]));
+
+    let mut request = Request::new(());
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.list_my_locks(request).await.unwrap();
+
+    assert_eq!(
+        res.get_ref().locks,
+        vec![ProjectLock {
+            project_id: 3,
+            expires_at: LockConfig::default()
+                .expires_at(latest_activity)
+                .timestamp(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn get_dashboard_returns_user_projects_and_locks() {
+    let mut mock_contexts = get_mock_contexts();
+
+    let latest_activity = Utc::now().naive_utc();
+    let in_use = in_use::Model {
+        project_id: 1,
+        session_id: 1,
+        latest_activity,
+    };
+    let project_info = ProjectInfo {
+        project_id: 1,
+        project_name: "project::Model name".to_owned(),
+        project_owner_id: 1,
+        project_owner_username: Some("owner".to_owned()),
+        user_role_on_project: "Editor".to_owned(),
+    };
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
         .with(predicate::eq(1))
-        .returning(move |_| Ok(vec![]));
+        .returning(move |id| {
+            Ok(Some(user::Model {
+                id,
+                email: "owner@example.com".to_string(),
+                username: "owner".to_string(),
+                password: "".to_string(),
+                display_name: Some("Owner".to_string()),
+            }))
+        });
+
+    let info = project_info.clone();
+    mock_contexts
+        .project_context_mock
+        .expect_get_project_info_by_uid()
+        .with(
+            predicate::eq(1),
+            predicate::eq(0),
+            predicate::eq(ProjectConfig::default().dashboard_page_size),
+        )
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![info.clone()],
+                page_info: PageInfo {
+                    total: 1,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
 
-    let mut list_projects_info_request = Request::new(());
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_active_locks_by_uid()
+        .with(
+            predicate::eq(1),
+            predicate::eq(LockConfig::default().duration),
+        )
+        .returning(move |_, _| Ok(vec![in_use.clone()]));
 
-    list_projects_info_request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    let mut request = Request::new(());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic
+        .get_dashboard(request)
+        .await
+        .unwrap()
+        .into_inner();
+
+    let user = res.user.unwrap();
+    let projects = res.projects.unwrap();
+    let locks = res.locks.unwrap();
+
+    assert_eq!(user.id, 1);
+    assert_eq!(user.display_name, "Owner");
+    assert_eq!(projects.project_info_list, vec![project_info]);
+    assert_eq!(
+        projects.page_info.unwrap().page_size,
+        ProjectConfig::default().dashboard_page_size
+    );
+    assert_eq!(
+        locks.locks,
+        vec![ProjectLock {
+            project_id: 1,
+            expires_at: LockConfig::default()
+                .expires_at(latest_activity)
+                .timestamp(),
+        }]
+    );
+}
+
+async fn list_projects_info_with_no_projects(
+    config: Config,
+) -> Result<Response<ListProjectsInfoResponse>, Status> {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_project_info_by_uid()
+        .with(predicate::eq(1), predicate::eq(0), predicate::eq(0))
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![],
+                page_info: PageInfo {
+                    total: 0,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
+
+    let mut list_projects_info_request = Request::new(ListProjectsInfoRequest {
+        page: 0,
+        page_size: 0,
+    });
+
+    list_projects_info_request
+        .extensions_mut()
+        .insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, config);
+
+    project_logic
         .list_projects_info(list_projects_info_request)
-        .await;
+        .await
+}
+
+#[tokio::test]
+async fn list_projects_info_no_projects_returns_empty_list() {
+    let res = list_projects_info_with_no_projects(get_test_config())
+        .await
+        .unwrap();
+
+    assert!(res.get_ref().project_info_list.is_empty());
+}
 
-    assert!(res.is_err());
+#[tokio::test]
+async fn list_projects_info_no_projects_in_legacy_mode_returns_not_found() {
+    let res = list_projects_info_with_no_projects(Config {
+        legacy_not_found_on_empty_list: true,
+        ..get_test_config()
+    })
+    .await;
+
+    assert_eq!(res.unwrap_err().code(), Code::NotFound);
 }
 
 #[tokio::test]
@@ -724,10 +1448,9 @@ async fn update_name_returns_ok() {
         metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
     );
 
-    update_project_request.metadata_mut().insert(
-        "uid",
-        metadata::MetadataValue::from_str(user_id.to_string().as_str()).unwrap(),
-    );
+    update_project_request
+        .extensions_mut()
+        .insert(VerifiedUid(user_id));
 
     mock_contexts
         .project_context_mock
@@ -752,6 +1475,8 @@ async fn update_name_returns_ok() {
                 user_id,
                 project_id,
                 role: "Editor".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -767,6 +1492,7 @@ async fn update_name_returns_ok() {
                 id: 1,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id,
             }))
@@ -774,8 +1500,9 @@ async fn update_name_returns_ok() {
 
     mock_contexts
         .project_context_mock
-        .expect_update()
-        .returning(move |_| {
+        .expect_update_with_events()
+        .withf(|_, events| events.len() == 1 && events[0].event_type == "NameChanged")
+        .returning(move |_, _| {
             Ok(project::Model {
                 id: project_id,
                 name: new_project_name.clone(),
@@ -807,7 +1534,7 @@ async fn update_name_returns_ok() {
         });
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(update_project_request).await;
 
@@ -818,6 +1545,12 @@ async fn update_name_returns_ok() {
 async fn update_components_info_returns_ok() {
     let mut mock_contexts = get_mock_contexts();
 
+    // The components cannot be told apart by name, so every query is checked
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .returning(|_, _| Ok(vec![]));
+
     let user_id = 1;
     let project_id = 1;
     let components_info_non_json = ComponentsInfo {
@@ -840,10 +1573,9 @@ async fn update_components_info_returns_ok() {
         metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
     );
 
-    update_project_request.metadata_mut().insert(
-        "uid",
-        metadata::MetadataValue::from_str(user_id.to_string().as_str()).unwrap(),
-    );
+    update_project_request
+        .extensions_mut()
+        .insert(VerifiedUid(user_id));
 
     mock_contexts
         .project_context_mock
@@ -868,6 +1600,8 @@ async fn update_components_info_returns_ok() {
                 user_id,
                 project_id,
                 role: "Editor".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -883,6 +1617,7 @@ async fn update_components_info_returns_ok() {
                 id: 1,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id,
             }))
@@ -890,8 +1625,13 @@ async fn update_components_info_returns_ok() {
 
     mock_contexts
         .project_context_mock
-        .expect_update()
-        .returning(move |_| {
+        .expect_update_with_events()
+        .withf(|_, events| {
+            events.len() == 1
+                && events[0].event_type == "ComponentsChanged"
+                && events[0].components_snapshot.is_some()
+        })
+        .returning(move |_, _| {
             Ok(project::Model {
                 id: project_id,
                 name: Default::default(),
@@ -923,13 +1663,41 @@ async fn update_components_info_returns_ok() {
         });
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(update_project_request).await;
 
     assert!(res.is_ok());
 }
 
+/// Mocks `new_owner_id` as an existing user with access to the project, so the owner may be changed to them.
+fn expect_valid_new_owner(mock_contexts: &mut MockContexts, new_owner_id: i32, project_id: i32) {
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(new_owner_id))
+        .returning(move |id| {
+            Ok(Some(user::Model {
+                id,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(new_owner_id), predicate::eq(project_id))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 2,
+                user_id: new_owner_id,
+                project_id,
+                role: "Reader".to_string(),
+                ..Default::default()
+            }))
+        });
+}
+
 #[tokio::test]
 async fn update_owner_id_returns_ok() {
     let mut mock_contexts = get_mock_contexts();
@@ -950,10 +1718,9 @@ async fn update_owner_id_returns_ok() {
         metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
     );
 
-    update_project_request.metadata_mut().insert(
-        "uid",
-        metadata::MetadataValue::from_str(user_id.to_string().as_str()).unwrap(),
-    );
+    update_project_request
+        .extensions_mut()
+        .insert(VerifiedUid(user_id));
 
     mock_contexts
         .project_context_mock
@@ -978,9 +1745,13 @@ async fn update_owner_id_returns_ok() {
                 user_id,
                 project_id,
                 role: "Editor".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
+    expect_valid_new_owner(&mut mock_contexts, new_owner_id, project_id);
+
     mock_contexts
         .session_context_mock
         .expect_get_by_token()
@@ -993,6 +1764,7 @@ async fn update_owner_id_returns_ok() {
                 id: 1,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id,
             }))
@@ -1000,8 +1772,14 @@ async fn update_owner_id_returns_ok() {
 
     mock_contexts
         .project_context_mock
-        .expect_update()
-        .returning(move |_| {
+        .expect_update_with_events()
+        .withf(move |project, events| {
+            project.owner_id == new_owner_id
+                && events.len() == 1
+                && events[0].event_type == "OwnerChanged"
+                && events[0].description == "Transferred ownership from user 1 to user 2"
+        })
+        .returning(move |_, _| {
             Ok(project::Model {
                 id: project_id,
                 name: Default::default(),
@@ -1033,7 +1811,7 @@ async fn update_owner_id_returns_ok() {
         });
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(update_project_request).await;
 
@@ -1044,6 +1822,12 @@ async fn update_owner_id_returns_ok() {
 async fn update_returns_ok() {
     let mut mock_contexts = get_mock_contexts();
 
+    // The components cannot be told apart by name, so every query is checked
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .returning(|_, _| Ok(vec![]));
+
     let user_id = 1;
     let project_id = 1;
     let new_project_name = "new_name".to_string();
@@ -1068,10 +1852,9 @@ async fn update_returns_ok() {
         metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
     );
 
-    update_project_request.metadata_mut().insert(
-        "uid",
-        metadata::MetadataValue::from_str(user_id.to_string().as_str()).unwrap(),
-    );
+    update_project_request
+        .extensions_mut()
+        .insert(VerifiedUid(user_id));
 
     mock_contexts
         .project_context_mock
@@ -1096,9 +1879,13 @@ async fn update_returns_ok() {
                 user_id,
                 project_id,
                 role: "Editor".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
+    expect_valid_new_owner(&mut mock_contexts, new_owner_id, project_id);
+
     mock_contexts
         .session_context_mock
         .expect_get_by_token()
@@ -1111,6 +1898,7 @@ async fn update_returns_ok() {
                 id: 1,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id,
             }))
@@ -1118,8 +1906,8 @@ async fn update_returns_ok() {
 
     mock_contexts
         .project_context_mock
-        .expect_update()
-        .returning(move |_| {
+        .expect_update_with_events()
+        .returning(move |_, _| {
             Ok(project::Model {
                 id: project_id,
                 name: new_project_name.clone(),
@@ -1151,7 +1939,7 @@ async fn update_returns_ok() {
         });
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(update_project_request).await;
 
@@ -1185,6 +1973,8 @@ async fn update_owner_not_owner_returns_err() {
                 user_id: 1,
                 project_id: 1,
                 role: "Editor".to_owned(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -1200,6 +1990,7 @@ async fn update_owner_not_owner_returns_err() {
                 id: 1,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: 1,
             }))
@@ -1235,9 +2026,7 @@ async fn update_owner_not_owner_returns_err() {
         owner_id: Some(1),
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     request.metadata_mut().insert(
         "authorization",
@@ -1245,27 +2034,28 @@ async fn update_owner_not_owner_returns_err() {
     );
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(request).await.unwrap_err();
 
     assert_eq!(res.code(), Code::PermissionDenied);
 }
 
-#[tokio::test]
-async fn update_no_in_use_returns_err() {
-    let mut mock_contexts = get_mock_contexts();
-
+/// Returns a request from the owner of project 1 to make the user with `new_owner_id` its owner,
+/// with the project and the owner's access mocked.
+fn owner_change_request(
+    mock_contexts: &mut MockContexts,
+    new_owner_id: i32,
+) -> Request<UpdateProjectRequest> {
     mock_contexts
         .project_context_mock
         .expect_get_by_id()
         .with(predicate::eq(1))
-        .returning(move |_| {
+        .returning(|_| {
             Ok(Some(project::Model {
                 id: 1,
-                name: Default::default(),
-                components_info: Default::default(),
                 owner_id: 1,
+                ..Default::default()
             }))
         });
 
@@ -1273,30 +2063,132 @@ async fn update_no_in_use_returns_err() {
         .access_context_mock
         .expect_get_access_by_uid_and_project_id()
         .with(predicate::eq(1), predicate::eq(1))
-        .returning(move |_, _| {
+        .returning(|_, _| {
             Ok(Some(access::Model {
                 id: 1,
                 user_id: 1,
                 project_id: 1,
-                role: "Editor".to_owned(),
+                role: "Editor".to_string(),
+                ..Default::default()
             }))
         });
 
     mock_contexts
-        .session_context_mock
-        .expect_get_by_token()
-        .with(
-            predicate::eq(TokenType::AccessToken),
-            predicate::eq("access_token".to_string()),
-        )
-        .returning(move |_, _| {
-            Ok(Some(session::Model {
-                id: 1,
-                refresh_token: "refresh_token".to_string(),
-                access_token: "access_token".to_string(),
-                updated_at: Default::default(),
-                user_id: 1,
-            }))
+        .project_context_mock
+        .expect_update_with_events()
+        .never();
+
+    let mut request = Request::new(UpdateProjectRequest {
+        id: 1,
+        name: None,
+        components_info: None,
+        owner_id: Some(new_owner_id),
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+    request
+}
+
+#[tokio::test]
+async fn update_owner_nonexistent_user_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let request = owner_change_request(&mut mock_contexts, 3);
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(3))
+        .returning(|_| Ok(None));
+
+    let project_logic =
+        ProjectController::new(disguise_context_mocks(mock_contexts), get_test_config());
+
+    let res = project_logic.update_project(request).await.unwrap_err();
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(detail.reason_code, "UNKNOWN_OWNER");
+}
+
+#[tokio::test]
+async fn update_owner_user_without_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let request = owner_change_request(&mut mock_contexts, 3);
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(3))
+        .returning(|id| {
+            Ok(Some(user::Model {
+                id,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(3), predicate::eq(1))
+        .returning(|_, _| Ok(None));
+
+    let project_logic =
+        ProjectController::new(disguise_context_mocks(mock_contexts), get_test_config());
+
+    let res = project_logic.update_project(request).await.unwrap_err();
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(detail.reason_code, "OWNER_WITHOUT_ACCESS");
+}
+
+#[tokio::test]
+async fn update_no_in_use_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: Default::default(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                user_id: 1,
+                project_id: 1,
+                role: "Editor".to_owned(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .with(
+            predicate::eq(TokenType::AccessToken),
+            predicate::eq("access_token".to_string()),
+        )
+        .returning(move |_, _| {
+            Ok(Some(session::Model {
+                id: 1,
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 1,
+            }))
         });
 
     mock_contexts
@@ -1311,6 +2203,21 @@ async fn update_no_in_use_returns_err() {
             }))
         });
 
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(2))
+        .returning(move |_| {
+            Ok(Some(session::Model {
+                id: 2,
+                refresh_token: "other_refresh_token".to_string(),
+                access_token: "other_access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 2,
+            }))
+        });
+
     let mut request = Request::new(UpdateProjectRequest {
         id: 1,
         name: None,
@@ -1318,9 +2225,7 @@ async fn update_no_in_use_returns_err() {
         owner_id: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     request.metadata_mut().insert(
         "authorization",
@@ -1328,13 +2233,124 @@ async fn update_no_in_use_returns_err() {
     );
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(request).await.unwrap_err();
 
     assert_eq!(res.code(), Code::FailedPrecondition);
 }
 
+#[tokio::test]
+async fn update_takes_over_lock_from_own_session_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: Default::default(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                user_id: 1,
+                project_id: 1,
+                role: "Editor".to_owned(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .with(
+            predicate::eq(TokenType::AccessToken),
+            predicate::eq("access_token".to_string()),
+        )
+        .returning(move |_, _| {
+            Ok(Some(session::Model {
+                id: 1,
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(2))
+        .returning(move |_| {
+            Ok(Some(session::Model {
+                id: 2,
+                refresh_token: "other_refresh_token".to_string(),
+                access_token: "other_access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(in_use::Model {
+                session_id: 2,
+                latest_activity: Utc::now().naive_utc(),
+                project_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_update()
+        .withf(|in_use| in_use.session_id == 1)
+        .times(1)
+        .returning(move |in_use| Ok(in_use));
+
+    mock_contexts
+        .project_context_mock
+        .expect_update_with_events()
+        .returning(move |project, _| Ok(project));
+
+    let mut request = Request::new(UpdateProjectRequest {
+        id: 1,
+        name: None,
+        components_info: None,
+        owner_id: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("access_token").unwrap(),
+    );
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.update_project(request).await;
+
+    assert!(res.is_ok());
+}
+
 #[tokio::test]
 async fn update_no_access_returns_err() {
     let mut mock_contexts = get_mock_contexts();
@@ -1365,12 +2381,10 @@ async fn update_no_access_returns_err() {
         owner_id: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(request).await.unwrap_err();
 
@@ -1404,6 +2418,8 @@ async fn update_incorrect_role_returns_err() {
                 user_id: 1,
                 project_id: 1,
                 role: "Viewer".to_owned(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -1414,12 +2430,10 @@ async fn update_incorrect_role_returns_err() {
         owner_id: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(request).await.unwrap_err();
 
@@ -1453,6 +2467,8 @@ async fn update_no_session_returns_err() {
                 user_id: 1,
                 project_id: 1,
                 role: "Editor".to_owned(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
             }))
         });
 
@@ -1472,9 +2488,7 @@ async fn update_no_session_returns_err() {
         owner_id: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     request.metadata_mut().insert(
         "authorization",
@@ -1482,7 +2496,7 @@ async fn update_no_session_returns_err() {
     );
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(request).await.unwrap_err();
 
@@ -1506,14 +2520,1785 @@ async fn update_no_project_returns_err() {
         owner_id: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
-    let project_logic = ProjectController::new(contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
 
     let res = project_logic.update_project(request).await.unwrap_err();
 
     assert_eq!(res.code(), Code::NotFound);
 }
+
+#[tokio::test]
+async fn create_project_invalid_name_returns_err() {
+    let mock_contexts = get_mock_contexts();
+
+    let mut request = Request::new(CreateProjectRequest {
+        name: "   ".to_string(),
+        components_info: Some(ComponentsInfo {
+            components: vec![],
+            components_hash: 0,
+        }),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.create_project(request).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+fn nested_json(depth: usize) -> String {
+    format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+fn components_info_with_json(json: String) -> ComponentsInfo {
+    ComponentsInfo {
+        components: vec![Component {
+            rep: Some(Rep::Json(json)),
+        }],
+        components_hash: 0,
+    }
+}
+
+#[tokio::test]
+async fn json_depth_exceeds_counts_nesting() {
+    assert!(!json_depth_exceeds(r#"{"a": [1, {"b": 2}]}"#, 3));
+    assert!(json_depth_exceeds(r#"{"a": [1, {"b": 2}]}"#, 2));
+}
+
+#[tokio::test]
+async fn json_depth_exceeds_ignores_brackets_in_strings() {
+    assert!(!json_depth_exceeds(r#"{"a": "[[[{{{\"]]]"}"#, 1));
+}
+
+#[tokio::test]
+async fn validate_components_info_pathologically_nested_returns_err() {
+    let components_info = components_info_with_json(nested_json(100_000));
+
+    let res = validate_components_info(
+        &components_info,
+        ProjectConfig::default().components_json_max_depth,
+    )
+    .unwrap_err();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn validate_components_info_at_max_depth_returns_ok() {
+    let max_depth = ProjectConfig::default().components_json_max_depth;
+    let components_info = components_info_with_json(nested_json(max_depth));
+
+    assert!(validate_components_info(&components_info, max_depth).is_ok());
+}
+
+#[tokio::test]
+async fn create_project_too_deep_components_returns_err() {
+    // No context expectations are set, so the project must be rejected before anything is written
+    let mock_contexts = get_mock_contexts();
+
+    let mut request = Request::new(CreateProjectRequest {
+        name: "project".to_string(),
+        components_info: Some(components_info_with_json(nested_json(
+            ProjectConfig::default().components_json_max_depth + 1,
+        ))),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(0));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.create_project(request).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn update_invalid_name_returns_err() {
+    let mock_contexts = get_mock_contexts();
+
+    let mut request = Request::new(UpdateProjectRequest {
+        id: 1,
+        name: Some("new\nname".to_string()),
+        components_info: None,
+        owner_id: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.update_project(request).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn validate_project_name_empty_returns_err() {
+    let res = validate_project_name("", &ProjectConfig::default());
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn validate_project_name_too_long_returns_err() {
+    let res = validate_project_name(&"a".repeat(256), &ProjectConfig::default());
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn validate_project_name_control_character_returns_err() {
+    let res = validate_project_name("project\u{7}name", &ProjectConfig::default());
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn validate_project_name_valid_name_is_trimmed() {
+    let res = validate_project_name("  project name  ", &ProjectConfig::default());
+
+    assert_eq!(res.unwrap(), "project name");
+}
+
+#[tokio::test]
+async fn get_project_history_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+
+    let project_id = 1;
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(project_id))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_event_context_mock
+        .expect_get_by_project_id()
+        .with(predicate::eq(project_id))
+        .returning(move |_| {
+            Ok(vec![project_event::Model {
+                id: 1,
+                project_id,
+                user_id: 2,
+                event_type: "NameChanged".to_string(),
+                description: "Renamed project from \"a\" to \"b\"".to_string(),
+                created_at: Default::default(),
+                components_snapshot: None,
+            }])
+        });
+
+    let mut request = Request::new(GetProjectHistoryRequest { project_id });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.get_project_history(request).await.unwrap();
+
+    assert_eq!(res.get_ref().events.len(), 1);
+    assert_eq!(res.get_ref().events[0].event_type, "NameChanged");
+}
+
+#[tokio::test]
+async fn get_project_history_no_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| Ok(None));
+
+    let mut request = Request::new(GetProjectHistoryRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.get_project_history(request).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn create_project_without_components_info_uses_default() {
+    let mut mock_contexts = get_mock_contexts();
+
+    let uid = 0;
+
+    let project = project::Model {
+        id: Default::default(),
+        name: "project".to_string(),
+        components_info: serde_json::to_value(ProjectConfig::default().default_components_info)
+            .unwrap(),
+        owner_id: uid,
+    };
+
+    mock_contexts
+        .project_context_mock
+        .expect_create()
+        .with(predicate::eq(project.clone()))
+        .returning(move |_| Ok(project.clone()));
+
+    mock_contexts
+        .access_context_mock
+        .expect_create()
+        .returning(move |access| Ok(access));
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .returning(move |_, _| {
+            Ok(Some(session::Model {
+                id: Default::default(),
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: uid,
+            }))
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_create()
+        .returning(move |in_use| Ok(in_use));
+
+    let mut request = Request::new(CreateProjectRequest {
+        name: "project".to_string(),
+        components_info: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(uid));
+
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
+    );
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.create_project(request).await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn create_project_malformed_components_info_returns_err() {
+    let mock_contexts = get_mock_contexts();
+
+    let mut request = Request::new(CreateProjectRequest {
+        name: "project".to_string(),
+        components_info: Some(ComponentsInfo {
+            components: vec![Component { rep: None }],
+            components_hash: 0,
+        }),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.create_project(request).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn default_components_info_has_no_components() {
+    let components_info = ProjectConfig::default().default_components_info;
+
+    assert!(components_info.components.is_empty());
+    assert_eq!(
+        components_info.components_hash,
+        compute_components_hash(&[])
+    );
+}
+
+fn heartbeat_mock_contexts(role: &str, in_use_session_id: i32) -> MockContexts {
+    heartbeat_mock_contexts_with_in_use(
+        role,
+        Some(in_use::Model {
+            project_id: 1,
+            session_id: in_use_session_id,
+            latest_activity: Utc::now().naive_utc(),
+        }),
+    )
+}
+
+fn heartbeat_mock_contexts_with_in_use(role: &str, in_use: Option<in_use::Model>) -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+    let role = role.to_string();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: role.clone(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .with(
+            predicate::eq(TokenType::AccessToken),
+            predicate::eq("access_token".to_string()),
+        )
+        .returning(move |_, _| {
+            Ok(Some(session::Model {
+                id: 1,
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(in_use.clone()));
+
+    mock_contexts
+}
+
+fn heartbeat_request() -> Request<HeartbeatRequest> {
+    let mut request = Request::new(HeartbeatRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
+    );
+
+    request
+}
+
+#[tokio::test]
+async fn heartbeat_lock_holder_returns_ok() {
+    let mut mock_contexts = heartbeat_mock_contexts("Editor", 1);
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_update()
+        .times(1)
+        .returning(move |in_use| Ok(in_use));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.heartbeat(heartbeat_request()).await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn heartbeat_not_lock_holder_returns_err() {
+    let mut mock_contexts = heartbeat_mock_contexts("Editor", 2);
+
+    mock_contexts.in_use_context_mock.expect_update().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.heartbeat(heartbeat_request()).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn heartbeat_without_in_use_returns_err() {
+    let mut mock_contexts = heartbeat_mock_contexts_with_in_use("Editor", None);
+
+    mock_contexts.in_use_context_mock.expect_update().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .heartbeat(heartbeat_request())
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::FailedPrecondition);
+    assert_eq!(res.message(), "You do not hold the lock on this project");
+}
+
+#[tokio::test]
+async fn heartbeat_not_editor_returns_err() {
+    let mock_contexts = heartbeat_mock_contexts("Reader", 1);
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.heartbeat(heartbeat_request()).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::PermissionDenied);
+}
+
+/// Mocks a project on which user 1 is a Reader, whose lock was last renewed at `latest_activity`
+/// by session 2 belonging to `holder_user_id`.
+fn lock_status_mock_contexts(
+    latest_activity: chrono::NaiveDateTime,
+    holder_user_id: i32,
+) -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(in_use::Model {
+                project_id: 1,
+                session_id: 2,
+                latest_activity,
+            }))
+        });
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(2))
+        .returning(move |_| {
+            Ok(Some(session::Model {
+                id: 2,
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: holder_user_id,
+            }))
+        });
+
+    // Claiming the lock is never part of reading its status
+    mock_contexts.in_use_context_mock.expect_update().never();
+
+    mock_contexts
+}
+
+fn lock_status_request() -> Request<GetLockStatusRequest> {
+    let mut request = Request::new(GetLockStatusRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn get_lock_status_free_lock_returns_unlocked() {
+    let expired =
+        Utc::now().naive_utc() - LockConfig::default().duration - chrono::Duration::minutes(1);
+    let contexts = disguise_context_mocks(lock_status_mock_contexts(expired, 2));
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .get_lock_status(lock_status_request())
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(!res.locked);
+    assert_eq!(res.holder_user_id, None);
+    assert_eq!(res.expires_at, None);
+}
+
+#[tokio::test]
+async fn get_lock_status_lock_in_configured_grace_period_returns_locked() {
+    let locks = LockConfig {
+        grace_period: chrono::Duration::minutes(5),
+        ..Default::default()
+    };
+    let latest_activity = Utc::now().naive_utc() - locks.duration - chrono::Duration::minutes(1);
+    let contexts = disguise_context_mocks(lock_status_mock_contexts(latest_activity, 2));
+    let project_logic = ProjectController::new(
+        contexts,
+        Config {
+            locks: locks.clone(),
+            ..get_test_config()
+        },
+    );
+
+    let res = project_logic
+        .get_lock_status(lock_status_request())
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.locked);
+    assert_eq!(res.holder_user_id, Some(2));
+    assert_eq!(
+        res.expires_at,
+        Some(locks.expires_at(latest_activity).timestamp())
+    );
+}
+
+#[tokio::test]
+async fn get_lock_status_self_held_lock_returns_requester() {
+    let latest_activity = Utc::now().naive_utc();
+    let contexts = disguise_context_mocks(lock_status_mock_contexts(latest_activity, 1));
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .get_lock_status(lock_status_request())
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.locked);
+    assert_eq!(res.holder_user_id, Some(1));
+    assert_eq!(
+        res.expires_at,
+        Some(
+            LockConfig::default()
+                .expires_at(latest_activity)
+                .timestamp()
+        )
+    );
+    assert!(!res.expiring);
+}
+
+#[tokio::test]
+async fn get_lock_status_other_held_lock_returns_holder() {
+    let contexts = disguise_context_mocks(lock_status_mock_contexts(Utc::now().naive_utc(), 3));
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .get_lock_status(lock_status_request())
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.locked);
+    assert_eq!(res.holder_user_id, Some(3));
+}
+
+/// The lock state after `minutes` of inactivity with a 10 minute lock, a warning after 8 minutes
+/// and a grace period of 5 minutes
+fn lock_state_after(minutes: i64, seconds: i64) -> LockState {
+    LockState::after_inactivity(
+        chrono::Duration::minutes(minutes) + chrono::Duration::seconds(seconds),
+        chrono::Duration::minutes(10),
+        Some(chrono::Duration::minutes(8)),
+        chrono::Duration::minutes(5),
+    )
+}
+
+#[test]
+fn lock_state_at_warn_boundary_is_expiring() {
+    assert_eq!(lock_state_after(7, 59), LockState::Held);
+    assert_eq!(lock_state_after(8, 0), LockState::Expiring);
+    assert_eq!(lock_state_after(9, 59), LockState::Expiring);
+}
+
+#[test]
+fn lock_state_within_grace_is_still_held() {
+    assert_eq!(lock_state_after(10, 0), LockState::InGrace);
+    assert_eq!(lock_state_after(14, 59), LockState::InGrace);
+    assert!(lock_state_after(14, 59).is_held());
+}
+
+#[test]
+fn lock_state_past_grace_is_free() {
+    assert_eq!(lock_state_after(15, 0), LockState::Free);
+    assert!(!lock_state_after(15, 0).is_held());
+}
+
+#[test]
+fn lock_free_at_includes_grace_period() {
+    let latest_activity = Utc::now().naive_utc();
+    let free_at = LockState::free_at(
+        latest_activity,
+        chrono::Duration::minutes(10),
+        chrono::Duration::minutes(5),
+    );
+
+    assert_eq!(free_at, latest_activity + chrono::Duration::minutes(15));
+    assert_eq!(
+        lock_state_after(0, (free_at - latest_activity).num_seconds() - 1),
+        LockState::InGrace
+    );
+    assert_eq!(
+        lock_state_after(0, (free_at - latest_activity).num_seconds()),
+        LockState::Free
+    );
+}
+
+#[test]
+fn lock_state_without_warning_or_grace_expires_at_duration() {
+    let state = |minutes| {
+        LockState::after_inactivity(
+            chrono::Duration::minutes(minutes),
+            chrono::Duration::minutes(10),
+            None,
+            chrono::Duration::zero(),
+        )
+    };
+
+    assert_eq!(state(9), LockState::Held);
+    assert_eq!(state(10), LockState::Free);
+}
+
+#[tokio::test]
+async fn get_lock_status_without_in_use_returns_unlocked() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(None));
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_id()
+        .never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .get_lock_status(lock_status_request())
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(!res.locked);
+    assert_eq!(res.holder_user_id, None);
+    assert_eq!(res.expires_at, None);
+    assert!(!res.expiring);
+}
+
+#[tokio::test]
+async fn get_lock_status_no_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| Ok(None));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.get_lock_status(lock_status_request()).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn list_projects_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_projects_by_uid()
+        .with(predicate::eq(1), predicate::eq(1), predicate::eq(1))
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![project::Model {
+                    id: 2,
+                    name: "project".to_string(),
+                    components_info: serde_json::to_value(ComponentsInfo::default()).unwrap(),
+                    owner_id: 1,
+                }],
+                page_info: PageInfo {
+                    total: 2,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .with(predicate::eq(2), predicate::eq(None))
+        .returning(move |_, _| {
+            Ok(vec![query::Model {
+                id: 1,
+                string: "query".to_string(),
+                result: None,
+                outdated: false,
+                project_id: 2,
+                last_run_ms: Default::default(),
+                result_computed_at: Default::default(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                title: Default::default(),
+                settings: Default::default(),
+            }])
+        });
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(2))
+        .returning(move |_| {
+            Ok(Some(in_use::Model {
+                project_id: 2,
+                session_id: 1,
+                latest_activity: Utc::now().naive_utc(),
+            }))
+        });
+
+    // Listing projects must not claim their locks
+    mock_contexts.in_use_context_mock.expect_update().never();
+
+    let mut request = Request::new(ListProjectsRequest {
+        page: 1,
+        page_size: 1,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.list_projects(request).await.unwrap();
+
+    let projects = &res.get_ref().projects;
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].project.as_ref().unwrap().id, 2);
+    assert_eq!(projects[0].queries.len(), 1);
+    assert!(projects[0].in_use);
+    assert_eq!(res.get_ref().page_info.as_ref().unwrap().page, 1);
+}
+
+#[tokio::test]
+async fn list_projects_no_projects_returns_empty() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_projects_by_uid()
+        .returning(move |_, page, page_size| {
+            Ok(Page {
+                items: vec![],
+                page_info: PageInfo {
+                    total: 0,
+                    page,
+                    page_size,
+                    has_next: false,
+                },
+            })
+        });
+
+    let mut request = Request::new(ListProjectsRequest {
+        page: 0,
+        page_size: 0,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic.list_projects(request).await.unwrap();
+
+    assert!(res.get_ref().projects.is_empty());
+}
+
+fn components_info_from_json(json: &[&str], components_hash: u32) -> ComponentsInfo {
+    ComponentsInfo {
+        components: json
+            .iter()
+            .map(|json| Component {
+                rep: Some(Rep::Json(json.to_string())),
+            })
+            .collect(),
+        components_hash,
+    }
+}
+
+#[tokio::test]
+async fn normalize_components_info_differently_ordered_json_has_same_hash() {
+    let a = normalize_components_info(components_info_from_json(
+        &[
+            r#"{"name": "A", "location": {"x": 1, "y": 2}, "edges": [{"source": "L0", "target": "L1"}]}"#,
+        ],
+        1,
+    ));
+    let b = normalize_components_info(components_info_from_json(
+        &[r#"{"edges":[{"target":"L1","source":"L0"}],"location":{"y":2,"x":1},"name":"A"}"#],
+        2,
+    ));
+
+    assert_eq!(a, b);
+}
+
+#[tokio::test]
+async fn normalize_components_info_different_json_has_different_hash() {
+    let a = normalize_components_info(components_info_from_json(&[r#"{"name": "A"}"#], 0));
+    let b = normalize_components_info(components_info_from_json(&[r#"{"name": "B"}"#], 0));
+
+    assert_ne!(a.components_hash, b.components_hash);
+}
+
+#[tokio::test]
+async fn normalize_components_info_replaces_client_hash() {
+    let components_info =
+        normalize_components_info(components_info_from_json(&[r#"{"name": "A"}"#], 1234));
+
+    assert_eq!(
+        components_info.components_hash,
+        compute_components_hash(&components_info.components)
+    );
+}
+
+#[tokio::test]
+async fn compute_components_hash_is_stable() {
+    // The 64-bit FNV-1a digest of `[]`, truncated to 32 bits
+    assert_eq!(compute_components_hash(&[]), 3_052_189_093);
+}
+
+#[tokio::test]
+async fn normalize_components_info_invalid_json_is_kept() {
+    let components_info = normalize_components_info(components_info_from_json(&["a"], 0));
+
+    assert_eq!(
+        components_info.components[0].rep,
+        Some(Rep::Json("a".to_owned()))
+    );
+}
+
+fn create_project_with_queries_request(
+    queries: Vec<&str>,
+) -> Request<CreateProjectWithQueriesRequest> {
+    let mut request = Request::new(CreateProjectWithQueriesRequest {
+        name: "project".to_string(),
+        components_info: None,
+        queries: queries.into_iter().map(String::from).collect(),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
+    );
+
+    request
+}
+
+#[tokio::test]
+async fn create_project_with_queries_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .with(
+            predicate::eq(TokenType::AccessToken),
+            predicate::eq("access_token".to_string()),
+        )
+        .returning(move |_, _| {
+            Ok(Some(session::Model {
+                id: 2,
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_create_with_queries()
+        .withf(|project, access, in_use, queries| {
+            project.name == "project"
+                && project.owner_id == 1
+                && access.user_id == 1
+                && access.role == "Editor"
+                && in_use.as_ref().is_some_and(|in_use| in_use.session_id == 2)
+                && queries
+                    .iter()
+                    .map(|query| query.string.as_str())
+                    .eq(["A <= B", "consistency: A"])
+        })
+        .returning(move |project, _, _, queries| {
+            Ok((
+                project::Model { id: 5, ..project },
+                queries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, query)| query::Model {
+                        id: i as i32 + 10,
+                        project_id: 5,
+                        ..query
+                    })
+                    .collect(),
+            ))
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .create_project_with_queries(create_project_with_queries_request(vec![
+            "A <= B",
+            " consistency: A ",
+        ]))
+        .await
+        .unwrap();
+
+    assert_eq!(res.get_ref().id, 5);
+    assert_eq!(res.get_ref().query_ids, vec![10, 11]);
+}
+
+#[tokio::test]
+async fn create_project_with_queries_invalid_query_returns_err() {
+    // No context expectations are set, so nothing may be written when a query is invalid
+    let mock_contexts = get_mock_contexts();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .create_project_with_queries(create_project_with_queries_request(vec!["A <= B", "  "]))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn create_project_with_queries_over_limit_returns_err() {
+    let mock_contexts = get_mock_contexts();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let queries = vec!["A <= B"; QueryConfig::default().max_queries_per_project as usize + 1];
+
+    let res = project_logic
+        .create_project_with_queries(create_project_with_queries_request(queries))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::ResourceExhausted);
+}
+
+#[tokio::test]
+async fn create_project_with_queries_transaction_fails_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .returning(move |_, _| {
+            Ok(Some(session::Model {
+                id: 2,
+                refresh_token: "refresh_token".to_string(),
+                access_token: "access_token".to_string(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                user_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_create_with_queries()
+        .returning(move |_, _, _, _| Err(DbErr::RecordNotInserted));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let project_logic = ProjectController::new(contexts, get_test_config());
+
+    let res = project_logic
+        .create_project_with_queries(create_project_with_queries_request(vec!["A <= B"]))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+}
+
+fn get_project_controller(name: &str) -> ProjectController {
+    get_project_controller_with_queries(name, vec![])
+}
+
+fn get_project_controller_with_queries(
+    name: &str,
+    queries: Vec<query::Model>,
+) -> ProjectController {
+    let mut mock_contexts = get_mock_contexts();
+
+    let project = project::Model {
+        id: Default::default(),
+        name: name.to_string(),
+        components_info: serde_json::to_value(ComponentsInfo::default()).unwrap(),
+        owner_id: 0,
+    };
+
+    let access = access::Model {
+        id: Default::default(),
+        role: "Reader".to_string(),
+        project_id: 0,
+        user_id: 0,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    let in_use = in_use::Model {
+        project_id: Default::default(),
+        session_id: 1,
+        latest_activity: Utc::now().naive_utc(),
+    };
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(project.clone())));
+
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(in_use.clone())));
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .returning(move |_, _| Ok(queries.clone()));
+
+    ProjectController::new(disguise_context_mocks(mock_contexts), get_test_config())
+}
+
+fn get_project_request(known_version: Option<u64>) -> Request<GetProjectRequest> {
+    get_project_request_with_results(known_version, None)
+}
+
+fn get_project_request_with_results(
+    known_version: Option<u64>,
+    include_query_results: Option<bool>,
+) -> Request<GetProjectRequest> {
+    let mut request = Request::new(GetProjectRequest {
+        id: 0,
+        known_version,
+        include_query_results,
+    });
+    request.extensions_mut().insert(VerifiedUid(0));
+    request
+}
+
+#[tokio::test]
+async fn get_project_known_version_matches_returns_not_modified() {
+    let project_logic = get_project_controller("project");
+
+    let version = project_logic
+        .get_project(get_project_request(None))
+        .await
+        .unwrap()
+        .into_inner()
+        .version;
+
+    let res = project_logic
+        .get_project(get_project_request(Some(version)))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.not_modified);
+    assert_eq!(res.version, version);
+    assert!(res.project.is_none());
+    assert!(res.queries.is_empty());
+}
+
+#[tokio::test]
+async fn get_project_known_version_differs_returns_project() {
+    let stale_version = get_project_controller("old name")
+        .get_project(get_project_request(None))
+        .await
+        .unwrap()
+        .into_inner()
+        .version;
+
+    let res = get_project_controller("new name")
+        .get_project(get_project_request(Some(stale_version)))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(!res.not_modified);
+    assert_ne!(res.version, stale_version);
+    assert_eq!(res.project.unwrap().name, "new name");
+}
+
+fn query_with_result() -> query::Model {
+    query::Model {
+        id: 1,
+        string: "refinement: A <= B".to_string(),
+        result: Some("result".into()),
+        project_id: 0,
+        outdated: false,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn get_project_include_query_results_false_omits_results() {
+    let res = get_project_controller_with_queries("project", vec![query_with_result()])
+        .get_project(get_project_request_with_results(None, Some(false)))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.queries.len(), 1);
+    assert_eq!(res.queries[0].id, 1);
+    assert_eq!(res.queries[0].query, "refinement: A <= B");
+    assert!(!res.queries[0].outdated);
+    assert!(res.queries[0].result.is_empty());
+}
+
+#[tokio::test]
+async fn get_project_includes_query_results_by_default() {
+    let res = get_project_controller_with_queries("project", vec![query_with_result()])
+        .get_project(get_project_request(None))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.queries[0].result, "result");
+}
+
+#[tokio::test]
+async fn get_project_version_does_not_depend_on_include_query_results() {
+    let project_logic = get_project_controller_with_queries("project", vec![query_with_result()]);
+
+    let with_results = project_logic
+        .get_project(get_project_request_with_results(None, Some(true)))
+        .await
+        .unwrap()
+        .into_inner();
+    let without_results = project_logic
+        .get_project(get_project_request_with_results(None, Some(false)))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(with_results.version, without_results.version);
+}
+
+#[tokio::test]
+async fn project_version_is_stable() {
+    // The 64-bit FNV-1a digest of the length prefix of the empty encoded project
+    assert_eq!(
+        project_version(&Default::default(), &[]),
+        12_161_962_213_042_174_405
+    );
+}
+
+#[tokio::test]
+async fn project_version_changes_with_queries() {
+    let project = Default::default();
+    let query = Query {
+        id: 1,
+        ..Default::default()
+    };
+
+    assert_ne!(
+        project_version(&project, &[]),
+        project_version(&project, &[query])
+    );
+}
+
+fn json_component(rep: &str) -> Component {
+    Component {
+        rep: Some(Rep::Json(rep.to_string())),
+    }
+}
+
+fn components(reps: &[&str]) -> ComponentsInfo {
+    ComponentsInfo {
+        components: reps.iter().map(|rep| json_component(rep)).collect(),
+        components_hash: 0,
+    }
+}
+
+/// Returns a controller for project 1, readable by user 1, with two recorded versions:
+/// version 10 and version 11, where B was modified, C removed and D added.
+fn diff_project_controller() -> ProjectController {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    let version =
+        |id: i32, project_id: i32, snapshot: Option<ComponentsInfo>| project_event::Model {
+            id,
+            project_id,
+            user_id: 1,
+            event_type: "ComponentsChanged".to_string(),
+            description: "Updated the components of the project".to_string(),
+            created_at: Default::default(),
+            components_snapshot: snapshot.map(|snapshot| serde_json::to_value(snapshot).unwrap()),
+        };
+    let events = vec![
+        version(
+            10,
+            1,
+            Some(components(&[
+                r#"{"name":"A","x":1}"#,
+                r#"{"name":"B","x":1}"#,
+                r#"{"name":"C","x":1}"#,
+            ])),
+        ),
+        version(
+            11,
+            1,
+            Some(components(&[
+                r#"{"name":"A","x":1}"#,
+                r#"{"name":"B","x":2}"#,
+                r#"{"name":"D","x":1}"#,
+            ])),
+        ),
+        // A name change, which has no snapshot
+        version(12, 1, None),
+        // A version of another project
+        version(13, 2, Some(components(&[]))),
+    ];
+
+    mock_contexts
+        .project_event_context_mock
+        .expect_get_by_id()
+        .returning(move |id| Ok(events.iter().find(|event| event.id == id).cloned()));
+
+    ProjectController::new(disguise_context_mocks(mock_contexts), get_test_config())
+}
+
+fn diff_project_request(from_version: i32, to_version: i32) -> Request<DiffProjectRequest> {
+    let mut request = Request::new(DiffProjectRequest {
+        project_id: 1,
+        from_version,
+        to_version,
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+    request
+}
+
+#[tokio::test]
+async fn diff_project_returns_changed_components() {
+    let res = diff_project_controller()
+        .diff_project(diff_project_request(10, 11))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.added, vec!["D".to_string()]);
+    assert_eq!(res.removed, vec!["C".to_string()]);
+    assert_eq!(res.modified, vec!["B".to_string()]);
+}
+
+#[tokio::test]
+async fn diff_project_reversed_versions_swaps_added_and_removed() {
+    let res = diff_project_controller()
+        .diff_project(diff_project_request(11, 10))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.added, vec!["C".to_string()]);
+    assert_eq!(res.removed, vec!["D".to_string()]);
+    assert_eq!(res.modified, vec!["B".to_string()]);
+}
+
+#[tokio::test]
+async fn diff_project_same_version_is_empty() {
+    let res = diff_project_controller()
+        .diff_project(diff_project_request(10, 10))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.added.is_empty() && res.removed.is_empty() && res.modified.is_empty());
+}
+
+#[tokio::test]
+async fn diff_project_unknown_version_returns_err() {
+    let res = diff_project_controller()
+        .diff_project(diff_project_request(10, 99))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn diff_project_event_without_snapshot_returns_err() {
+    let res = diff_project_controller()
+        .diff_project(diff_project_request(10, 12))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn diff_project_version_of_other_project_returns_err() {
+    let res = diff_project_controller()
+        .diff_project(diff_project_request(10, 13))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn diff_project_no_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 2,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| Ok(None));
+
+    mock_contexts
+        .project_event_context_mock
+        .expect_get_by_id()
+        .never();
+
+    let project_logic =
+        ProjectController::new(disguise_context_mocks(mock_contexts), get_test_config());
+
+    let res = project_logic
+        .diff_project(diff_project_request(10, 11))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[test]
+fn diff_components_unnamed_components_are_identified_by_position() {
+    let res = diff_components(&components(&["not json"]), &components(&["not json", "{}"]));
+
+    assert_eq!(res.added, vec!["#1".to_string()]);
+    assert!(res.removed.is_empty() && res.modified.is_empty());
+}
+
+#[tokio::test]
+async fn get_project_seeded_project_end_to_end() {
+    let db_context = get_reset_database_context().await;
+    let seeded = seed_full_project(&db_context, 1).await;
+
+    let contexts = get_context_collection(db_context);
+    let project_logic = ProjectController::new(contexts.clone(), get_test_config());
+
+    let mut request = Request::new(GetProjectRequest {
+        id: seeded.project_id,
+        known_version: None,
+        include_query_results: None,
+    });
+
+    request
+        .extensions_mut()
+        .insert(VerifiedUid(seeded.owner_id));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str(&format!("Bearer {}", seeded.access_token)).unwrap(),
+    );
+
+    let res = project_logic
+        .get_project(request)
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.project.unwrap().id, seeded.project_id);
+    assert_eq!(
+        res.queries.iter().map(|query| query.id).collect::<Vec<_>>(),
+        seeded.query_ids
+    );
+    // The project was unlocked, so loading it as its owner claims the lock
+    assert!(!res.in_use);
+
+    let in_use = contexts
+        .in_use_context
+        .get_by_id(seeded.project_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(in_use.session_id, seeded.session_id);
+    assert!(in_use.latest_activity > Utc::now().naive_utc() - LockConfig::default().duration);
+}
+
+#[tokio::test]
+async fn update_project_missing_in_use_creates_lock_and_updates() {
+    let db_context = get_reset_database_context().await;
+    let seeded = seed_full_project(&db_context, 1).await;
+
+    let contexts = get_context_collection(db_context);
+    contexts
+        .in_use_context
+        .delete(seeded.project_id)
+        .await
+        .unwrap();
+
+    let project_logic = ProjectController::new(contexts.clone(), get_test_config());
+
+    let mut request = Request::new(UpdateProjectRequest {
+        id: seeded.project_id,
+        name: Some("renamed".to_string()),
+        components_info: None,
+        owner_id: None,
+    });
+    request
+        .extensions_mut()
+        .insert(VerifiedUid(seeded.owner_id));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str(&format!("Bearer {}", seeded.access_token)).unwrap(),
+    );
+
+    let res = project_logic.update_project(request).await;
+
+    let project = contexts
+        .project_context
+        .get_by_id(seeded.project_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let in_use = contexts
+        .in_use_context
+        .get_by_id(seeded.project_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(res.is_ok());
+    assert_eq!(project.name, "renamed");
+    assert_eq!(in_use.session_id, seeded.session_id);
+    assert!(in_use.latest_activity > Utc::now().naive_utc() - LockConfig::default().duration);
+}
+
+#[tokio::test]
+async fn create_project_without_session_then_update_creates_in_use() {
+    let db_context = get_reset_database_context().await;
+    let user = create_users(1)[0].clone();
+    let session = create_sessions(1, user.id)[0].clone();
+
+    user::Entity::insert(user.clone().into_active_model())
+        .exec(&db_context.get_connection())
+        .await
+        .unwrap();
+
+    let contexts = get_context_collection(db_context.clone());
+    let project_logic = ProjectController::new(contexts.clone(), get_test_config());
+
+    fn authorize<T>(request: &mut Request<T>, uid: i32, access_token: &str) {
+        request.extensions_mut().insert(VerifiedUid(uid));
+        request.metadata_mut().insert(
+            "authorization",
+            metadata::MetadataValue::from_str(&format!("Bearer {}", access_token)).unwrap(),
+        );
+    }
+
+    // The session of the access token is not stored yet
+    let mut create_request = Request::new(CreateProjectRequest {
+        name: "project".to_string(),
+        components_info: None,
+    });
+    authorize(&mut create_request, user.id, &session.access_token);
+    let project_id = project_logic
+        .create_project(create_request)
+        .await
+        .unwrap()
+        .into_inner()
+        .id;
+
+    let mut get_request = Request::new(GetProjectRequest {
+        id: project_id,
+        known_version: None,
+        include_query_results: None,
+    });
+    authorize(&mut get_request, user.id, &session.access_token);
+    let get_res = project_logic
+        .get_project(get_request)
+        .await
+        .unwrap()
+        .into_inner();
+    let in_use_before = contexts.in_use_context.get_by_id(project_id).await.unwrap();
+
+    session::Entity::insert(session.clone().into_active_model())
+        .exec(&db_context.get_connection())
+        .await
+        .unwrap();
+
+    let mut update_request = Request::new(UpdateProjectRequest {
+        id: project_id,
+        name: Some("renamed".to_string()),
+        components_info: None,
+        owner_id: None,
+    });
+    authorize(&mut update_request, user.id, &session.access_token);
+    let update_res = project_logic.update_project(update_request).await;
+
+    let in_use = contexts
+        .in_use_context
+        .get_by_id(project_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(!get_res.in_use);
+    assert_eq!(in_use_before, None);
+    assert!(update_res.is_ok());
+    assert_eq!(in_use.session_id, session.id);
+    assert!(in_use.latest_activity > Utc::now().naive_utc() - LockConfig::default().duration);
+}
+
+#[tokio::test]
+async fn update_project_components_only_marks_referencing_queries_outdated() {
+    let db_context = get_reset_database_context().await;
+    let seeded = seed_full_project(&db_context, 1).await;
+
+    let contexts = get_context_collection(db_context);
+    for query_id in seeded.query_ids.iter() {
+        let query = contexts
+            .query_context
+            .get_by_id(*query_id)
+            .await
+            .unwrap()
+            .unwrap();
+        contexts
+            .query_context
+            .update(query::Model {
+                outdated: false,
+                ..query
+            })
+            .await
+            .unwrap();
+    }
+
+    let project_logic = ProjectController::new(contexts.clone(), get_test_config());
+
+    // Adds component B, which is referenced by "A <= B" but not by "consistency: A"
+    let mut request = Request::new(UpdateProjectRequest {
+        id: seeded.project_id,
+        name: None,
+        components_info: Some(ComponentsInfo {
+            components: vec![Component {
+                rep: Some(Rep::Json("{\"name\":\"B\"}".to_string())),
+            }],
+            components_hash: 0,
+        }),
+        owner_id: None,
+    });
+    request
+        .extensions_mut()
+        .insert(VerifiedUid(seeded.owner_id));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str(&format!("Bearer {}", seeded.access_token)).unwrap(),
+    );
+
+    project_logic.update_project(request).await.unwrap();
+
+    let outdated: Vec<bool> = contexts
+        .query_context
+        .get_all_by_project_id(seeded.project_id, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|query| query.outdated)
+        .collect();
+
+    assert_eq!(outdated, vec![true, false]);
+}
+
+#[tokio::test]
+async fn update_project_owner_id_transfers_ownership() {
+    let db_context = get_reset_database_context().await;
+    let seeded = seed_full_project(&db_context, 1).await;
+
+    let new_owner = create_users(2)[1].clone();
+    let new_owner_access = access::Model {
+        project_id: seeded.project_id,
+        ..create_accesses(2, seeded.owner_id, seeded.project_id)[1].clone()
+    };
+
+    let db = db_context.get_connection();
+    user::Entity::insert(new_owner.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    access::Entity::insert(new_owner_access.into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+
+    let contexts = get_context_collection(db_context);
+    let project_logic = ProjectController::new(contexts.clone(), get_test_config());
+
+    let mut request = Request::new(UpdateProjectRequest {
+        id: seeded.project_id,
+        name: None,
+        components_info: None,
+        owner_id: Some(new_owner.id),
+    });
+    request
+        .extensions_mut()
+        .insert(VerifiedUid(seeded.owner_id));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str(&format!("Bearer {}", seeded.access_token)).unwrap(),
+    );
+
+    project_logic.update_project(request).await.unwrap();
+
+    let project = contexts
+        .project_context
+        .get_by_id(seeded.project_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let events = contexts
+        .project_event_context
+        .get_by_project_id(seeded.project_id)
+        .await
+        .unwrap();
+
+    assert_eq!(project.owner_id, new_owner.id);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, "OwnerChanged");
+}
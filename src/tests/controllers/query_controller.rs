@@ -1,17 +1,37 @@
+use crate::api::auth::{TokenType, VerifiedUid};
+use crate::api::server::protobuf::query_request::Settings;
 use crate::api::server::protobuf::query_response::{self, Result};
 use crate::api::server::protobuf::{
-    CreateQueryRequest, DeleteQueryRequest, QueryResponse, SendQueryRequest, UpdateQueryRequest,
+    component::Rep, Component, ComponentsInfo, CreateQueryRequest, DeleteQueryRequest,
+    GetOutdatedQueryCountRequest, GetQueryHistoryRequest, ParsingError, QueryRequest,
+    QueryResponse, SendOutdatedQueriesRequest, SendQueryRequest, SimulationStartRequest,
+    SimulationStepRequest, SimulationStepResponse, UpdateQueryRequest, UserTokenResponse,
+};
+use crate::config::Config;
+use crate::controllers::controller_impls::query_controller::{
+    parse_allowed_settings, parse_query_title, query_result_to_json, response_result_to_json,
+    result_is_stale, result_timed_out, validate_settings, ComponentsCache, EmptyResultPolicy,
+    QueryConfig, QueryMutationRole, QuerySettings, CORRELATION_ID_KEY, RESULT_NOT_SAVED_WARNING,
+    REVEAAL_UNAVAILABLE_MESSAGE,
 };
 use crate::controllers::controller_impls::QueryController;
 use crate::controllers::controller_traits::QueryControllerTrait;
-use crate::entities::{access, project, query};
+use crate::entities::role::Role;
+use crate::entities::{access, in_use, project, query, query_result, session};
+use crate::services::service_collection::ServiceCollection;
+use crate::services::service_traits::ReveaalServiceTrait;
 use crate::tests::controllers::helpers::{
     disguise_context_mocks, disguise_service_mocks, get_mock_contexts, get_mock_services,
+    get_test_config, MockContexts,
 };
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
 use mockall::predicate;
 use sea_orm::DbErr;
 use std::str::FromStr;
-use tonic::{metadata, Code, Request, Response};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tonic::{metadata, Code, Request, Response, Status};
 
 #[tokio::test]
 async fn create_invalid_query_returns_err() {
@@ -24,6 +44,12 @@ async fn create_invalid_query_returns_err() {
         result: Default::default(),
         project_id: 1,
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let access = access::Model {
@@ -31,14 +57,35 @@ async fn create_invalid_query_returns_err() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
     mock_contexts
         .access_context_mock
         .expect_get_access_by_uid_and_project_id()
         .with(predicate::eq(1), predicate::eq(1))
         .returning(move |_, _| Ok(Some(access.clone())));
 
+    mock_contexts
+        .query_context_mock
+        .expect_count_by_project_id()
+        .with(predicate::eq(1))
+        .returning(|_| Ok(0));
+
     mock_contexts
         .query_context_mock
         .expect_create()
@@ -48,15 +95,14 @@ async fn create_invalid_query_returns_err() {
     let mut request = Request::new(CreateQueryRequest {
         string: "".to_string(),
         project_id: 1,
+        title: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.create_query(request).await.unwrap_err();
 
@@ -74,6 +120,12 @@ async fn create_query_returns_ok() {
         result: Default::default(),
         project_id: 1,
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let access = access::Model {
@@ -81,14 +133,35 @@ async fn create_query_returns_ok() {
         role: "Editor".to_string(),
         project_id: 1,
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
     mock_contexts
         .access_context_mock
         .expect_get_access_by_uid_and_project_id()
         .with(predicate::eq(1), predicate::eq(1))
         .returning(move |_, _| Ok(Some(access.clone())));
 
+    mock_contexts
+        .query_context_mock
+        .expect_count_by_project_id()
+        .with(predicate::eq(1))
+        .returning(|_| Ok(0));
+
     mock_contexts
         .query_context_mock
         .expect_create()
@@ -98,15 +171,14 @@ async fn create_query_returns_ok() {
     let mut request = Request::new(CreateQueryRequest {
         string: "".to_string(),
         project_id: 1,
+        title: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.create_query(request).await;
 
@@ -124,6 +196,12 @@ async fn update_invalid_query_returns_err() {
         result: None,
         project_id: Default::default(),
         outdated: true,
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let query = query::Model {
@@ -136,6 +214,8 @@ async fn update_invalid_query_returns_err() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -159,15 +239,14 @@ async fn update_invalid_query_returns_err() {
     let mut request = Request::new(UpdateQueryRequest {
         id: 1,
         string: "updated".to_string(),
+        title: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.update_query(request).await.unwrap_err();
 
@@ -185,6 +264,12 @@ async fn update_query_returns_ok() {
         result: None,
         project_id: Default::default(),
         outdated: true,
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let query = query::Model {
@@ -197,6 +282,8 @@ async fn update_query_returns_ok() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -220,21 +307,198 @@ async fn update_query_returns_ok() {
     let mut request = Request::new(UpdateQueryRequest {
         id: 1,
         string: "updated".to_string(),
+        title: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.update_query(request).await;
 
     assert!(res.is_ok());
 }
 
+/// Updates the string and title of the query `A <= B` that holds an up to date result,
+/// returning the query as it was stored.
+async fn update_up_to_date_query(
+    old_title: Option<&str>,
+    string: &str,
+    title: Option<&str>,
+) -> query::Model {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let old_query = query::Model {
+        id: 1,
+        string: "A <= B".to_string(),
+        title: old_title.map(str::to_string),
+        result: Some(serde_json::to_value(Result::Success(query_response::Success {})).unwrap()),
+        outdated: false,
+        last_run_ms: Some(5),
+        ..Default::default()
+    };
+
+    let access = access::Model {
+        role: "Editor".to_string(),
+        user_id: 1,
+        ..Default::default()
+    };
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(0))
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(Some(old_query.clone())));
+
+    let stored = Arc::new(Mutex::new(None));
+    let updated = stored.clone();
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .returning(move |query| {
+            *updated.lock().unwrap() = Some(query.clone());
+            Ok(query)
+        });
+
+    let mut request = Request::new(UpdateQueryRequest {
+        id: 1,
+        string: string.to_string(),
+        title: title.map(str::to_string),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    query_logic.update_query(request).await.unwrap();
+
+    let stored = stored.lock().unwrap().clone();
+    stored.unwrap()
+}
+
+#[tokio::test]
+async fn update_query_title_keeps_result_up_to_date() {
+    let query = update_up_to_date_query(None, "A <= B", Some("  Deadlock check ")).await;
+
+    assert_eq!(query.title, Some("Deadlock check".to_string()));
+    assert!(!query.outdated);
+    assert!(query.result.is_some());
+    assert_eq!(query.last_run_ms, Some(5));
+}
+
+#[tokio::test]
+async fn update_query_empty_title_clears_title() {
+    let query = update_up_to_date_query(Some("Deadlock check"), "A <= B", Some("")).await;
+
+    assert_eq!(query.title, None);
+    assert!(!query.outdated);
+}
+
+#[tokio::test]
+async fn update_query_unchanged_string_keeps_result_up_to_date() {
+    let query = update_up_to_date_query(Some("Deadlock check"), "A <= B", None).await;
+
+    assert_eq!(query.title, Some("Deadlock check".to_string()));
+    assert!(!query.outdated);
+    assert!(query.result.is_some());
+}
+
+#[tokio::test]
+async fn update_query_changed_string_marks_result_outdated() {
+    let query = update_up_to_date_query(Some("Deadlock check"), "A <= C", None).await;
+
+    assert_eq!(query.string, "A <= C");
+    assert_eq!(query.title, Some("Deadlock check".to_string()));
+    assert!(query.outdated);
+    assert!(query.result.is_some());
+}
+
+#[tokio::test]
+async fn create_query_sets_title() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let access = access::Model {
+        role: "Editor".to_string(),
+        project_id: 1,
+        user_id: 1,
+        ..Default::default()
+    };
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .query_context_mock
+        .expect_count_by_project_id()
+        .returning(|_| Ok(0));
+
+    mock_contexts
+        .query_context_mock
+        .expect_create()
+        .withf(|query| query.title == Some("Deadlock check".to_string()))
+        .returning(Ok);
+
+    let mut request = Request::new(CreateQueryRequest {
+        string: "A <= B".to_string(),
+        project_id: 1,
+        title: Some("Deadlock check".to_string()),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic.create_query(request).await;
+
+    assert!(res.is_ok());
+}
+
+#[test]
+fn parse_query_title_rejects_invalid_titles() {
+    assert_eq!(
+        parse_query_title(&"a".repeat(129)).unwrap_err().code(),
+        Code::InvalidArgument
+    );
+    assert_eq!(
+        parse_query_title("Deadlock\ncheck").unwrap_err().code(),
+        Code::InvalidArgument
+    );
+    assert_eq!(
+        parse_query_title(&"a".repeat(128)).unwrap(),
+        Some("a".repeat(128))
+    );
+}
+
 #[tokio::test]
 async fn delete_invalid_query_returns_err() {
     let mut mock_contexts = get_mock_contexts();
@@ -245,6 +509,8 @@ async fn delete_invalid_query_returns_err() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let query = query::Model {
@@ -253,6 +519,12 @@ async fn delete_invalid_query_returns_err() {
         result: Default::default(),
         project_id: Default::default(),
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     mock_contexts
@@ -275,13 +547,11 @@ async fn delete_invalid_query_returns_err() {
 
     let mut request = Request::new(DeleteQueryRequest { id: 1 });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.delete_query(request).await.unwrap_err();
 
@@ -299,6 +569,12 @@ async fn delete_query_returns_ok() {
         result: Default::default(),
         project_id: Default::default(),
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let query_clone = query.clone();
@@ -308,6 +584,8 @@ async fn delete_query_returns_ok() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -330,13 +608,11 @@ async fn delete_query_returns_ok() {
 
     let mut request = Request::new(DeleteQueryRequest { id: 1 });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.delete_query(request).await;
 
@@ -354,6 +630,12 @@ async fn create_query_invalid_role_returns_err() {
         result: Default::default(),
         project_id: Default::default(),
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let access = access::Model {
@@ -361,8 +643,23 @@ async fn create_query_invalid_role_returns_err() {
         role: "Viewer".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
     mock_contexts
         .access_context_mock
         .expect_get_access_by_uid_and_project_id()
@@ -378,15 +675,14 @@ async fn create_query_invalid_role_returns_err() {
     let mut request = Request::new(CreateQueryRequest {
         string: "".to_string(),
         project_id: 1,
+        title: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.create_query(request).await.unwrap_err();
 
@@ -404,6 +700,12 @@ async fn delete_query_invalid_role_returns_err() {
         result: Default::default(),
         project_id: Default::default(),
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let query_clone = query.clone();
@@ -413,6 +715,8 @@ async fn delete_query_invalid_role_returns_err() {
         role: "Viewer".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -435,13 +739,11 @@ async fn delete_query_invalid_role_returns_err() {
 
     let mut request = Request::new(DeleteQueryRequest { id: 1 });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.delete_query(request).await.unwrap_err();
 
@@ -459,6 +761,12 @@ async fn update_query_invalid_role_returns_err() {
         result: None,
         project_id: Default::default(),
         outdated: true,
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let query = query::Model {
@@ -471,6 +779,8 @@ async fn update_query_invalid_role_returns_err() {
         role: "Viewer".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     mock_contexts
@@ -494,15 +804,14 @@ async fn update_query_invalid_role_returns_err() {
     let mut request = Request::new(UpdateQueryRequest {
         id: 1,
         string: "updated".to_string(),
+        title: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.update_query(request).await.unwrap_err();
 
@@ -520,6 +829,12 @@ async fn send_query_returns_ok() {
         result: Default::default(),
         project_id: Default::default(),
         outdated: Default::default(),
+        last_run_ms: Default::default(),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
     };
 
     let access = access::Model {
@@ -527,6 +842,8 @@ async fn send_query_returns_ok() {
         role: "Editor".to_string(),
         project_id: Default::default(),
         user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
     };
 
     let project = project::Model {
@@ -546,6 +863,7 @@ async fn send_query_returns_ok() {
         result: Some(serde_json::to_value(query_response.clone().result).unwrap()),
         ..query.clone()
     };
+    let expected_result = updated_query.result.clone();
 
     mock_contexts
         .project_context_mock
@@ -570,26 +888,1885 @@ async fn send_query_returns_ok() {
         .expect_send_query()
         .returning(move |_| Ok(Response::new(query_response.clone())));
 
+    // The run duration and time vary, so they are only required to be populated
+    let expected_query = updated_query.clone();
     mock_contexts
         .query_context_mock
         .expect_update()
-        .with(predicate::eq(updated_query.clone()))
+        .withf(move |query| {
+            query.last_run_ms.is_some()
+                && query.result_computed_at.is_some()
+                && query::Model {
+                    last_run_ms: None,
+                    result_computed_at: Default::default(),
+                    ..query.clone()
+                } == expected_query
+        })
         .returning(move |_| Ok(updated_query.clone()));
 
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .withf(move |run| {
+            run.query_id == 0 && run.result == expected_result && run.last_run_ms.is_some()
+        })
+        .times(1)
+        .returning(Ok);
+
     let mut request = Request::new(SendQueryRequest {
         id: Default::default(),
         project_id: Default::default(),
+        settings: None,
     });
 
-    request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    request.extensions_mut().insert(VerifiedUid(1));
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let query_logic = QueryController::new(contexts, services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
 
     let res = query_logic.send_query(request).await;
 
     assert!(res.is_ok());
 }
+
+#[tokio::test]
+async fn send_query_of_other_project_returns_not_found() {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                owner_id: 1,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+
+    // The query belongs to project 2, which the requester has no access to
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(5))
+        .returning(|_| {
+            Ok(Some(query::Model {
+                id: 5,
+                project_id: 2,
+                string: "A <= B".to_string(),
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts.query_context_mock.expect_update().never();
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .never();
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 5,
+        project_id: 1,
+        settings: None,
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let query_logic = QueryController::new(
+        disguise_context_mocks(mock_contexts),
+        disguise_service_mocks(mock_services),
+        get_test_config(),
+    );
+
+    let res = query_logic.send_query(request).await;
+
+    assert_eq!(res.unwrap_err().code(), Code::NotFound);
+}
+
+/// Sends a query as session 1 while the lock on the project, last active a minute ago, is held by `lock_session_id`.
+/// Returns the `latest_activity` the lock was refreshed to, if it was refreshed.
+async fn send_query_with_lock_held_by(lock_session_id: i32) -> Option<NaiveDateTime> {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    let query = query::Model {
+        id: 1,
+        project_id: 1,
+        ..Default::default()
+    };
+    let in_use = in_use::Model {
+        project_id: 1,
+        session_id: lock_session_id,
+        latest_activity: chrono::Utc::now().naive_utc() - chrono::Duration::minutes(1),
+    };
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(query.clone())));
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .returning(Ok);
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .returning(Ok);
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .returning(|_| {
+            Ok(Response::new(QueryResponse {
+                query_id: 1,
+                info: Default::default(),
+                result: Some(Result::Success(query_response::Success {})),
+            }))
+        });
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .with(
+            predicate::eq(TokenType::AccessToken),
+            predicate::eq("access_token".to_string()),
+        )
+        .returning(|_, _| {
+            Ok(Some(session::Model {
+                id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .in_use_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(Some(in_use.clone())));
+
+    let refreshed_to = Arc::new(Mutex::new(None));
+    let refreshed = refreshed_to.clone();
+    mock_contexts
+        .in_use_context_mock
+        .expect_update()
+        .returning(move |in_use| {
+            *refreshed.lock().unwrap() = Some(in_use.latest_activity);
+            Ok(in_use)
+        });
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("Bearer access_token").unwrap(),
+    );
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    query_logic.send_query(request).await.unwrap();
+
+    let refreshed_to = *refreshed_to.lock().unwrap();
+    refreshed_to
+}
+
+/// Sends a query whose result fails to be saved the first `failures` times with `error`.
+/// Returns the response and how many times saving the result was attempted.
+async fn send_query_with_failing_save(
+    failures: u32,
+    error: fn() -> DbErr,
+) -> (crate::api::server::protobuf::SendQueryResponse, u32) {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    let query = query::Model {
+        id: 1,
+        project_id: 1,
+        ..Default::default()
+    };
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(query.clone())));
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .returning(Ok);
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .returning(|_| {
+            Ok(Response::new(QueryResponse {
+                query_id: 1,
+                info: Default::default(),
+                result: Some(Result::Success(query_response::Success {})),
+            }))
+        });
+
+    let attempts = Arc::new(Mutex::new(0));
+    let counted = attempts.clone();
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .returning(move |query| {
+            let mut attempts = counted.lock().unwrap();
+            *attempts += 1;
+            if *attempts <= failures {
+                Err(error())
+            } else {
+                Ok(query)
+            }
+        });
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic.send_query(request).await.unwrap().into_inner();
+
+    let attempts = *attempts.lock().unwrap();
+    (res, attempts)
+}
+
+fn database_locked() -> DbErr {
+    DbErr::Exec(sea_orm::RuntimeErr::Internal(
+        "database is locked".to_string(),
+    ))
+}
+
+#[tokio::test]
+async fn send_query_retries_transient_save_failure() {
+    let (res, attempts) = send_query_with_failing_save(1, database_locked).await;
+
+    assert_eq!(attempts, 2);
+    assert_eq!(res.warning, None);
+    assert!(res.response.is_some());
+}
+
+#[tokio::test]
+async fn send_query_save_keeps_failing_returns_result_with_warning() {
+    let (res, attempts) = send_query_with_failing_save(u32::MAX, database_locked).await;
+
+    assert_eq!(attempts, QueryConfig::default().result_save_attempts);
+    assert_eq!(res.warning.as_deref(), Some(RESULT_NOT_SAVED_WARNING));
+    assert_eq!(
+        res.response.unwrap().result,
+        Some(Result::Success(query_response::Success {}))
+    );
+}
+
+#[tokio::test]
+async fn send_query_does_not_retry_permanent_save_failure() {
+    let (res, attempts) = send_query_with_failing_save(u32::MAX, || DbErr::RecordNotUpdated).await;
+
+    assert_eq!(attempts, 1);
+    assert_eq!(res.warning.as_deref(), Some(RESULT_NOT_SAVED_WARNING));
+}
+
+#[tokio::test]
+async fn send_query_refreshes_held_lock() {
+    let before = chrono::Utc::now().naive_utc();
+
+    let refreshed_to = send_query_with_lock_held_by(1).await;
+
+    assert!(refreshed_to.is_some_and(|latest_activity| latest_activity >= before));
+}
+
+#[tokio::test]
+async fn send_query_does_not_refresh_lock_of_other_session() {
+    assert_eq!(send_query_with_lock_held_by(2).await, None);
+}
+
+#[tokio::test]
+async fn send_query_propagates_correlation_id() {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(query::Model {
+                id: 1,
+                string: "".to_string(),
+                result: None,
+                project_id: 1,
+                outdated: true,
+                last_run_ms: None,
+                result_computed_at: Default::default(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                title: Default::default(),
+                settings: Default::default(),
+            }))
+        });
+
+    // The correlation id Reveaal receives
+    let sent_correlation_id = Arc::new(std::sync::Mutex::new(None));
+    let sent = sent_correlation_id.clone();
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .times(1)
+        .returning(move |request| {
+            *sent.lock().unwrap() = request
+                .metadata()
+                .get(CORRELATION_ID_KEY)
+                .map(|id| id.to_str().unwrap().to_string());
+            Ok(Response::new(QueryResponse {
+                query_id: 1,
+                info: Default::default(),
+                result: Some(Result::Success(query_response::Success {})),
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .returning(Ok);
+
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .returning(Ok);
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic.send_query(request).await.unwrap().into_inner();
+
+    assert!(!res.correlation_id.is_empty());
+    assert_eq!(
+        *sent_correlation_id.lock().unwrap(),
+        Some(res.correlation_id)
+    );
+}
+
+/// A Reveaal service that never responds
+struct UnresponsiveReveaalService;
+
+#[async_trait]
+impl ReveaalServiceTrait for UnresponsiveReveaalService {
+    async fn get_user_token(
+        &self,
+        _request: Request<()>,
+    ) -> std::result::Result<Response<UserTokenResponse>, Status> {
+        std::future::pending().await
+    }
+
+    async fn send_query(
+        &self,
+        _request: Request<QueryRequest>,
+    ) -> std::result::Result<Response<QueryResponse>, Status> {
+        std::future::pending().await
+    }
+
+    async fn start_simulation(
+        &self,
+        _request: Request<SimulationStartRequest>,
+    ) -> std::result::Result<Response<SimulationStepResponse>, Status> {
+        std::future::pending().await
+    }
+
+    async fn take_simulation_step(
+        &self,
+        _request: Request<SimulationStepRequest>,
+    ) -> std::result::Result<Response<SimulationStepResponse>, Status> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn send_query_reveaal_timeout_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: Default::default(),
+                role: "Editor".to_string(),
+                project_id: Default::default(),
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: Default::default(),
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 0,
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(query::Model {
+                id: Default::default(),
+                string: "".to_string(),
+                result: Default::default(),
+                project_id: Default::default(),
+                outdated: Default::default(),
+                last_run_ms: Default::default(),
+                result_computed_at: Default::default(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                title: Default::default(),
+                settings: Default::default(),
+            }))
+        });
+
+    mock_contexts.query_context_mock.expect_update().never();
+
+    let mut request = Request::new(SendQueryRequest {
+        id: Default::default(),
+        project_id: Default::default(),
+        settings: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = ServiceCollection {
+        reveaal_service: Arc::new(UnresponsiveReveaalService),
+        ..disguise_service_mocks(get_mock_services())
+    };
+    let config = Config {
+        queries: QueryConfig {
+            reveaal_timeout: std::time::Duration::from_secs(1),
+            ..Default::default()
+        },
+        ..get_test_config()
+    };
+    let query_logic = QueryController::new(contexts, services, config);
+
+    let res = query_logic.send_query(request).await;
+
+    let status = res.unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert!(status.message().contains("Reveaal"));
+}
+
+/// A Reveaal service that answers after a short delay with the given result, counting the queries it receives
+struct CountingReveaalService {
+    calls: AtomicUsize,
+    result: std::result::Result<QueryResponse, Status>,
+}
+
+#[async_trait]
+impl ReveaalServiceTrait for CountingReveaalService {
+    async fn get_user_token(
+        &self,
+        _request: Request<()>,
+    ) -> std::result::Result<Response<UserTokenResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn send_query(
+        &self,
+        _request: Request<QueryRequest>,
+    ) -> std::result::Result<Response<QueryResponse>, Status> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        self.result.clone().map(Response::new)
+    }
+
+    async fn start_simulation(
+        &self,
+        _request: Request<SimulationStartRequest>,
+    ) -> std::result::Result<Response<SimulationStepResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn take_simulation_step(
+        &self,
+        _request: Request<SimulationStepRequest>,
+    ) -> std::result::Result<Response<SimulationStepResponse>, Status> {
+        unimplemented!()
+    }
+}
+
+fn get_query_controller_with_reveaal(reveaal: Arc<CountingReveaalService>) -> QueryController {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(query::Model {
+                id: 1,
+                project_id: 1,
+                outdated: true,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .returning(Ok);
+
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .returning(Ok);
+
+    let services = ServiceCollection {
+        reveaal_service: reveaal,
+        ..disguise_service_mocks(get_mock_services())
+    };
+
+    QueryController::new(
+        disguise_context_mocks(mock_contexts),
+        services,
+        get_test_config(),
+    )
+}
+
+fn send_query_request() -> Request<SendQueryRequest> {
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn concurrent_send_query_runs_query_once() {
+    let reveaal = Arc::new(CountingReveaalService {
+        calls: AtomicUsize::new(0),
+        result: Ok(QueryResponse {
+            query_id: 1,
+            info: Default::default(),
+            result: Some(Result::Success(query_response::Success {})),
+        }),
+    });
+    let query_logic = get_query_controller_with_reveaal(reveaal.clone());
+
+    let (first, second) = tokio::join!(
+        query_logic.send_query(send_query_request()),
+        query_logic.send_query(send_query_request())
+    );
+
+    let (first, second) = (first.unwrap().into_inner(), second.unwrap().into_inner());
+    assert_eq!(reveaal.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first.response, second.response);
+    assert_eq!(first.correlation_id, second.correlation_id);
+}
+
+#[tokio::test]
+async fn concurrent_send_query_shares_failure_and_retries_afterwards() {
+    let reveaal = Arc::new(CountingReveaalService {
+        calls: AtomicUsize::new(0),
+        result: Err(Status::invalid_argument("could not parse query")),
+    });
+    let query_logic = get_query_controller_with_reveaal(reveaal.clone());
+
+    let (first, second) = tokio::join!(
+        query_logic.send_query(send_query_request()),
+        query_logic.send_query(send_query_request())
+    );
+
+    assert_eq!(reveaal.calls.load(Ordering::SeqCst), 1);
+    for status in [first.unwrap_err(), second.unwrap_err()] {
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "could not parse query");
+    }
+
+    // The failed run is no longer in flight, so the next call runs the query again
+    let _ = query_logic.send_query(send_query_request()).await;
+
+    assert_eq!(reveaal.calls.load(Ordering::SeqCst), 2);
+}
+
+fn get_outdated_query_count_request() -> Request<GetOutdatedQueryCountRequest> {
+    let mut request = Request::new(GetOutdatedQueryCountRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn get_outdated_query_count_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_count_outdated_queries()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(3));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic
+        .get_outdated_query_count(get_outdated_query_count_request())
+        .await
+        .unwrap();
+
+    assert_eq!(res.get_ref().count, 3);
+}
+
+#[tokio::test]
+async fn get_outdated_query_count_no_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| Ok(None));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic
+        .get_outdated_query_count(get_outdated_query_count_request())
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn send_query_malformed_result_keeps_stored_result() {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    let query = query::Model {
+        id: 1,
+        string: "A <= B".to_string(),
+        result: Some(serde_json::to_value(Result::Success(query_response::Success {})).unwrap()),
+        project_id: 1,
+        outdated: false,
+        last_run_ms: Some(5),
+        result_computed_at: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
+    };
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(query.clone())));
+
+    // Reveaal answers with a parsing error that does not say what could not be parsed
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .returning(move |_| {
+            Ok(Response::new(QueryResponse {
+                query_id: 1,
+                info: Default::default(),
+                result: Some(Result::ParsingError(ParsingError::default())),
+            }))
+        });
+
+    mock_contexts.query_context_mock.expect_update().never();
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic.send_query(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+    assert!(res.message().contains("malformed ParsingError result"));
+}
+
+#[tokio::test]
+async fn query_result_to_json_round_trips() {
+    let result = Result::Success(query_response::Success {});
+
+    let value = query_result_to_json(Some(result.clone())).unwrap();
+
+    assert_eq!(serde_json::from_value::<Result>(value).unwrap(), result);
+}
+
+#[tokio::test]
+async fn query_result_to_json_failure_without_details_returns_err() {
+    let res =
+        query_result_to_json(Some(Result::ParsingError(ParsingError::default()))).unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+}
+
+#[test]
+fn response_result_to_json_missing_result_is_error_by_default() {
+    let res = response_result_to_json(None, EmptyResultPolicy::Error).unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+    assert!(res.message().contains("without a result"));
+}
+
+#[test]
+fn response_result_to_json_missing_result_is_timeout_with_timeout_policy() {
+    let res = response_result_to_json(None, EmptyResultPolicy::Timeout).unwrap();
+
+    assert_eq!(res, None);
+}
+
+#[test]
+fn response_result_to_json_keeps_result_with_timeout_policy() {
+    let result = Result::Success(query_response::Success {});
+
+    let res = response_result_to_json(Some(result.clone()), EmptyResultPolicy::Timeout).unwrap();
+
+    assert_eq!(res, Some(serde_json::to_value(result).unwrap()));
+}
+
+#[test]
+fn result_timed_out_requires_run_without_result() {
+    let query = query::Model {
+        result_computed_at: Some(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+    let never_run = query::Model::default();
+    let with_result = query::Model {
+        result: Some(serde_json::json!("result")),
+        ..query.clone()
+    };
+
+    assert!(result_timed_out(&query));
+    assert!(!result_timed_out(&never_run));
+    assert!(!result_timed_out(&with_result));
+}
+
+#[test]
+fn empty_result_policy_from_str_parses_policies() {
+    assert_eq!(
+        EmptyResultPolicy::from_str("error"),
+        Ok(EmptyResultPolicy::Error)
+    );
+    assert_eq!(
+        EmptyResultPolicy::from_str("timeout"),
+        Ok(EmptyResultPolicy::Timeout)
+    );
+    assert!(EmptyResultPolicy::from_str("ignore").is_err());
+}
+
+#[test]
+fn query_mutation_role_from_str_parses_roles_and_owner() {
+    assert_eq!(
+        QueryMutationRole::from_str("Commenter"),
+        Ok(QueryMutationRole::Role(Role::Commenter))
+    );
+    assert_eq!(
+        QueryMutationRole::from_str("Owner"),
+        Ok(QueryMutationRole::Owner)
+    );
+    assert_eq!(QueryMutationRole::Owner.min_role(), Role::Editor);
+    assert!(QueryMutationRole::from_str("owner").is_err());
+}
+
+/// Creates a query in a project owned by user 1 as `uid`, who has `Editor` access to it
+async fn create_query_as_editor(
+    mutation_role: QueryMutationRole,
+    uid: i32,
+) -> std::result::Result<Response<()>, Status> {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |uid, _| {
+            Ok(Some(access::Model {
+                id: uid,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: uid,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_count_by_project_id()
+        .returning(|_| Ok(0));
+
+    mock_contexts
+        .query_context_mock
+        .expect_create()
+        .returning(Ok);
+
+    let mut request = Request::new(CreateQueryRequest {
+        string: "".to_string(),
+        project_id: 1,
+        title: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(uid));
+
+    let config = Config {
+        queries: QueryConfig {
+            mutation_role,
+            ..Default::default()
+        },
+        ..get_test_config()
+    };
+    let query_logic = QueryController::new(
+        disguise_context_mocks(mock_contexts),
+        disguise_service_mocks(get_mock_services()),
+        config,
+    );
+
+    query_logic.create_query(request).await
+}
+
+#[tokio::test]
+async fn create_query_owner_only_denies_editor_who_is_not_owner() {
+    let default_editor = create_query_as_editor(QueryConfig::default().mutation_role, 2).await;
+    let editor = create_query_as_editor(QueryMutationRole::Owner, 2).await;
+    let owner = create_query_as_editor(QueryMutationRole::Owner, 1).await;
+
+    assert!(default_editor.is_ok());
+    assert_eq!(editor.unwrap_err().code(), Code::PermissionDenied);
+    assert!(owner.is_ok());
+}
+
+async fn create_query_with_existing_count(count: u64) -> std::result::Result<Response<()>, Status> {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let access = access::Model {
+        id: Default::default(),
+        role: "Editor".to_string(),
+        project_id: 1,
+        user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| Ok(Some(access.clone())));
+
+    mock_contexts
+        .query_context_mock
+        .expect_count_by_project_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(count));
+
+    mock_contexts
+        .query_context_mock
+        .expect_create()
+        .returning(Ok);
+
+    let mut request = Request::new(CreateQueryRequest {
+        string: "A <= B".to_string(),
+        project_id: 1,
+        title: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    query_logic.create_query(request).await
+}
+
+#[tokio::test]
+async fn create_query_below_limit_returns_ok() {
+    let res =
+        create_query_with_existing_count(QueryConfig::default().max_queries_per_project - 1).await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn create_query_at_limit_returns_err() {
+    let res = create_query_with_existing_count(QueryConfig::default().max_queries_per_project)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::ResourceExhausted);
+}
+
+fn components_info_json(rep: &str, components_hash: u32) -> serde_json::Value {
+    serde_json::to_value(ComponentsInfo {
+        components: vec![Component {
+            rep: Some(Rep::Json(rep.to_string())),
+        }],
+        components_hash,
+    })
+    .unwrap()
+}
+
+#[test]
+fn in_flight_queries_same_query_id_in_other_project_is_not_shared() {
+    let in_flight = super::InFlightQueries::default();
+    let settings = QuerySettings::default();
+
+    let leader = in_flight.join((1, 5), settings, true);
+    let follower = in_flight.join((1, 5), settings, true);
+    let other_project = in_flight.join((2, 5), settings, true);
+
+    assert!(matches!(leader, super::InFlightRun::Leader(_)));
+    assert!(matches!(follower, super::InFlightRun::Follower(_)));
+    assert!(matches!(other_project, super::InFlightRun::Leader(_)));
+}
+
+const MAX_DEPTH: usize = 64;
+
+#[tokio::test]
+async fn components_cache_same_hash_reuses_parsed_components() {
+    let cache = ComponentsCache::default();
+
+    let first = cache
+        .get_or_parse(1, components_info_json("first", 1), MAX_DEPTH)
+        .unwrap();
+
+    // The hash is unchanged, so the cached components are returned instead of these
+    let second = cache
+        .get_or_parse(1, components_info_json("second", 1), MAX_DEPTH)
+        .unwrap();
+
+    assert_eq!(second, first);
+}
+
+#[tokio::test]
+async fn components_cache_changed_hash_parses_again() {
+    let cache = ComponentsCache::default();
+
+    cache
+        .get_or_parse(1, components_info_json("first", 1), MAX_DEPTH)
+        .unwrap();
+
+    let second = cache
+        .get_or_parse(1, components_info_json("second", 2), MAX_DEPTH)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        second.components[0].rep,
+        Some(Rep::Json("second".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn components_cache_is_per_project() {
+    let cache = ComponentsCache::default();
+
+    cache
+        .get_or_parse(1, components_info_json("first", 1), MAX_DEPTH)
+        .unwrap();
+
+    let other = cache
+        .get_or_parse(2, components_info_json("other", 1), MAX_DEPTH)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        other.components[0].rep,
+        Some(Rep::Json("other".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn components_cache_invalid_components_returns_err() {
+    let cache = ComponentsCache::default();
+
+    let res = cache
+        .get_or_parse(
+            1,
+            serde_json::json!({ "components": 1, "components_hash": 1 }),
+            MAX_DEPTH,
+        )
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+}
+
+#[tokio::test]
+async fn send_outdated_queries_failed_query_does_not_abort_others() {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    let queries: Vec<query::Model> = (1..=3)
+        .map(|id| query::Model {
+            id,
+            string: format!("query {}", id),
+            result: None,
+            project_id: 1,
+            // Query 3 is up to date and must not be run
+            outdated: id != 3,
+            last_run_ms: None,
+            result_computed_at: Default::default(),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            title: Default::default(),
+            settings: Default::default(),
+        })
+        .collect();
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .with(predicate::eq(1), predicate::eq(None))
+        .returning(move |_, _| Ok(queries.clone()));
+
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .times(2)
+        .returning(move |request| match request.get_ref().query_id {
+            1 => Err(Status::unavailable("Reveaal is unavailable")),
+            query_id => Ok(Response::new(QueryResponse {
+                query_id,
+                info: Default::default(),
+                result: Some(Result::Success(query_response::Success {})),
+            })),
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .withf(|query| query.id == 2 && !query.outdated && query.result.is_some())
+        .times(1)
+        .returning(Ok);
+
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .withf(|run| run.query_id == 2)
+        .times(1)
+        .returning(Ok);
+
+    let mut request = Request::new(SendOutdatedQueriesRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic
+        .send_outdated_queries(request)
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.results.len(), 2);
+    assert_eq!(res.results[0].query_id, 1);
+    assert!(!res.results[0].success);
+    assert_eq!(res.results[0].error, REVEAAL_UNAVAILABLE_MESSAGE);
+    assert_eq!(res.results[1].query_id, 2);
+    assert!(res.results[1].success);
+}
+
+#[tokio::test]
+async fn send_outdated_queries_no_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| Ok(None));
+
+    let mut request = Request::new(SendOutdatedQueriesRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic
+        .send_outdated_queries(request)
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+fn query_computed_at(result_computed_at: Option<chrono::NaiveDateTime>) -> query::Model {
+    query::Model {
+        id: 1,
+        string: "A <= B".to_string(),
+        result: Some(serde_json::to_value(Result::Success(query_response::Success {})).unwrap()),
+        outdated: false,
+        project_id: 1,
+        last_run_ms: Some(5),
+        result_computed_at,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+        title: Default::default(),
+        settings: Default::default(),
+    }
+}
+
+#[test]
+fn result_is_stale_fresh_within_max_age_returns_false() {
+    let now = chrono::Utc::now().naive_utc();
+    let query = query_computed_at(Some(now - chrono::Duration::minutes(30)));
+
+    assert!(!result_is_stale(
+        &query,
+        Some(chrono::Duration::minutes(60)),
+        now
+    ));
+}
+
+#[test]
+fn result_is_stale_past_max_age_returns_true() {
+    let now = chrono::Utc::now().naive_utc();
+    let query = query_computed_at(Some(now - chrono::Duration::minutes(90)));
+
+    assert!(result_is_stale(
+        &query,
+        Some(chrono::Duration::minutes(60)),
+        now
+    ));
+}
+
+#[test]
+fn result_is_stale_no_max_age_returns_false() {
+    let now = chrono::Utc::now().naive_utc();
+    let query = query_computed_at(Some(now - chrono::Duration::days(365)));
+
+    assert!(!result_is_stale(&query, None, now));
+}
+
+#[test]
+fn result_is_stale_unknown_computation_time_returns_true() {
+    let now = chrono::Utc::now().naive_utc();
+
+    assert!(result_is_stale(
+        &query_computed_at(None),
+        Some(chrono::Duration::minutes(60)),
+        now
+    ));
+}
+
+#[test]
+fn result_is_stale_no_result_returns_false() {
+    let now = chrono::Utc::now().naive_utc();
+    let query = query::Model {
+        result: None,
+        ..query_computed_at(None)
+    };
+
+    assert!(!result_is_stale(
+        &query,
+        Some(chrono::Duration::minutes(60)),
+        now
+    ));
+}
+
+async fn send_query_with_reveaal_error(error: Status) -> Status {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Editor".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| Ok(Some(query_computed_at(None))));
+
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .returning(move |_| Err(error.clone()));
+
+    mock_contexts.query_context_mock.expect_update().never();
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    query_logic.send_query(request).await.unwrap_err()
+}
+
+#[tokio::test]
+async fn send_query_reveaal_unreachable_returns_unavailable() {
+    let res = send_query_with_reveaal_error(Status::unavailable(
+        "error trying to connect: tcp connect error: Connection refused (os error 111)",
+    ))
+    .await;
+
+    assert_eq!(res.code(), Code::Unavailable);
+    assert_eq!(res.message(), REVEAAL_UNAVAILABLE_MESSAGE);
+}
+
+#[tokio::test]
+async fn send_query_reveaal_rejects_query_returns_error_as_is() {
+    let res =
+        send_query_with_reveaal_error(Status::invalid_argument("Could not parse query")).await;
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(res.message(), "Could not parse query");
+}
+
+fn get_query_history_mock_contexts(query_project_id: i32) -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: Default::default(),
+                owner_id: 1,
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(Some(query::Model {
+                id: 1,
+                string: "".to_string(),
+                result: None,
+                project_id: query_project_id,
+                outdated: false,
+                last_run_ms: None,
+                result_computed_at: Default::default(),
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                title: Default::default(),
+                settings: Default::default(),
+            }))
+        });
+
+    mock_contexts
+}
+
+fn get_query_history_request() -> Request<GetQueryHistoryRequest> {
+    let mut request = Request::new(GetQueryHistoryRequest {
+        project_id: 1,
+        query_id: 1,
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn get_query_history_returns_ok() {
+    let mut mock_contexts = get_query_history_mock_contexts(1);
+    let mock_services = get_mock_services();
+
+    let runs: Vec<query_result::Model> = (1..=2)
+        .rev()
+        .map(|id| query_result::Model {
+            id,
+            query_id: 1,
+            result: Some(format!("result {}", id).into()),
+            last_run_ms: Some(i64::from(id)),
+            created_at: chrono::DateTime::from_timestamp(i64::from(id), 0)
+                .unwrap()
+                .naive_utc(),
+        })
+        .collect();
+
+    mock_contexts
+        .query_result_context_mock
+        .expect_get_by_query_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(runs.clone()));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic
+        .get_query_history(get_query_history_request())
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(
+        res.runs
+            .iter()
+            .map(|run| (run.id, run.result.as_str(), run.last_run_ms, run.created_at))
+            .collect::<Vec<_>>(),
+        vec![(2, "result 2", Some(2), 2), (1, "result 1", Some(1), 1)]
+    );
+}
+
+#[tokio::test]
+async fn get_query_history_query_in_other_project_returns_err() {
+    let mut mock_contexts = get_query_history_mock_contexts(2);
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .query_result_context_mock
+        .expect_get_by_query_id()
+        .never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic
+        .get_query_history(get_query_history_request())
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), Code::NotFound);
+}
+
+#[test]
+fn validate_settings_default_settings_returns_ok() {
+    assert!(validate_settings(&Settings::default(), &[]).is_ok());
+}
+
+#[test]
+fn validate_settings_allowed_setting_returns_ok() {
+    let settings = Settings {
+        disable_clock_reduction: true,
+    };
+
+    assert!(validate_settings(&settings, &["disable_clock_reduction".to_string()]).is_ok());
+}
+
+#[test]
+fn validate_settings_disallowed_setting_returns_invalid_argument() {
+    let settings = Settings {
+        disable_clock_reduction: true,
+    };
+
+    let status = validate_settings(&settings, &[]).unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(status.message().contains("disable_clock_reduction"));
+}
+
+#[test]
+fn parse_allowed_settings_unknown_setting_returns_err() {
+    assert_eq!(
+        parse_allowed_settings(" disable_clock_reduction ,"),
+        Ok(vec!["disable_clock_reduction".to_string()])
+    );
+    assert!(parse_allowed_settings("max_search_depth").is_err());
+}
+
+#[tokio::test]
+async fn send_query_disallowed_settings_returns_err_without_running_query() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: Some(Settings {
+            disable_clock_reduction: true,
+        }),
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    // Reveaal is not expected to be called, so the mock panics if the query is run
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(get_mock_services());
+    let query_logic = QueryController::new(contexts, services, get_test_config());
+
+    let res = query_logic.send_query(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+}
+
+#[test]
+fn query_settings_of_query_without_settings_returns_defaults() {
+    let query = query::Model::default();
+
+    let settings = QuerySettings::of_query(&query).unwrap();
+
+    assert_eq!(settings, QuerySettings::default());
+    assert!(!settings.disable_clock_reduction);
+}
+
+#[test]
+fn query_settings_of_query_fills_in_missing_settings() {
+    let query = query::Model {
+        settings: Some(serde_json::json!({})),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        QuerySettings::of_query(&query).unwrap(),
+        QuerySettings::default()
+    );
+}
+
+#[test]
+fn query_settings_round_trip_through_json() {
+    let settings = QuerySettings {
+        disable_clock_reduction: true,
+    };
+    let query = query::Model {
+        settings: Some(settings.to_json()),
+        ..Default::default()
+    };
+
+    assert_eq!(QuerySettings::of_query(&query).unwrap(), settings);
+}
+
+#[test]
+fn query_settings_of_query_unreadable_settings_returns_err() {
+    let query = query::Model {
+        settings: Some(serde_json::json!({ "disable_clock_reduction": "yes" })),
+        ..Default::default()
+    };
+
+    let res = QuerySettings::of_query(&query).unwrap_err();
+
+    assert_eq!(res.code(), Code::Internal);
+}
+
+/// Sends a query stored with `stored_settings` without settings in the request,
+/// returning the settings Reveaal received and the settings stored with the result.
+async fn send_query_with_stored_settings(
+    stored_settings: Option<serde_json::Value>,
+) -> (Option<Settings>, Option<serde_json::Value>) {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+    mock_contexts
+        .query_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(query::Model {
+                id: 1,
+                project_id: 1,
+                settings: stored_settings.clone(),
+                ..Default::default()
+            }))
+        });
+
+    let sent_settings = Arc::new(Mutex::new(None));
+    let sent = sent_settings.clone();
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .times(1)
+        .returning(move |request| {
+            *sent.lock().unwrap() = request.get_ref().settings.clone();
+            Ok(Response::new(QueryResponse {
+                query_id: 1,
+                info: Default::default(),
+                result: Some(Result::Success(query_response::Success {})),
+            }))
+        });
+
+    let saved_settings = Arc::new(Mutex::new(None));
+    let saved = saved_settings.clone();
+    mock_contexts
+        .query_context_mock
+        .expect_update()
+        .returning(move |query| {
+            *saved.lock().unwrap() = query.settings.clone();
+            Ok(query)
+        });
+    mock_contexts
+        .query_result_context_mock
+        .expect_create()
+        .returning(Ok);
+
+    let mut request = Request::new(SendQueryRequest {
+        id: 1,
+        project_id: 1,
+        settings: None,
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    let query_logic = QueryController::new(
+        disguise_context_mocks(mock_contexts),
+        disguise_service_mocks(mock_services),
+        get_test_config(),
+    );
+
+    query_logic.send_query(request).await.unwrap();
+
+    let sent_settings = sent_settings.lock().unwrap().clone();
+    let saved_settings = saved_settings.lock().unwrap().clone();
+    (sent_settings, saved_settings)
+}
+
+#[tokio::test]
+async fn send_query_without_stored_settings_uses_defaults() {
+    let (sent, saved) = send_query_with_stored_settings(None).await;
+
+    assert_eq!(
+        sent,
+        Some(Settings {
+            disable_clock_reduction: false,
+        })
+    );
+    assert_eq!(saved, Some(QuerySettings::default().to_json()));
+}
+
+#[tokio::test]
+async fn send_query_without_settings_reuses_stored_settings() {
+    let stored = QuerySettings {
+        disable_clock_reduction: true,
+    };
+
+    let (sent, saved) = send_query_with_stored_settings(Some(stored.to_json())).await;
+
+    assert_eq!(sent, Some(stored.into()));
+    assert_eq!(saved, Some(stored.to_json()));
+}
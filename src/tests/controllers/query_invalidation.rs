@@ -0,0 +1,204 @@
+use crate::api::server::protobuf::{component::Rep, Component, ComponentsInfo};
+use crate::controllers::query_invalidation::{
+    changed_component_names, invalidate_affected_queries, query_identifiers, query_references_any,
+};
+use crate::entities::query;
+use crate::tests::controllers::helpers::{disguise_context_mocks, get_mock_contexts};
+use mockall::predicate;
+use std::collections::HashSet;
+
+fn component(name: &str, initial_location: &str) -> Component {
+    Component {
+        rep: Some(Rep::Json(
+            serde_json::json!({ "name": name, "initial": initial_location }).to_string(),
+        )),
+    }
+}
+
+fn components_info(components: Vec<Component>) -> ComponentsInfo {
+    ComponentsInfo {
+        components,
+        components_hash: 0,
+    }
+}
+
+fn names(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn query(id: i32, string: &str, outdated: bool) -> query::Model {
+    query::Model {
+        id,
+        string: string.to_string(),
+        project_id: 1,
+        outdated,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn changed_component_names_returns_changed_added_and_removed() {
+    let old = components_info(vec![
+        component("A", "L0"),
+        component("B", "L0"),
+        component("C", "L0"),
+    ]);
+    let new = components_info(vec![
+        component("A", "L0"),
+        component("B", "L1"),
+        component("D", "L0"),
+    ]);
+
+    assert_eq!(
+        changed_component_names(&old, &new),
+        Some(names(&["B", "C", "D"]))
+    );
+}
+
+#[test]
+fn changed_component_names_unchanged_returns_empty() {
+    let components = components_info(vec![component("A", "L0")]);
+
+    assert_eq!(
+        changed_component_names(&components, &components),
+        Some(HashSet::new())
+    );
+}
+
+#[test]
+fn changed_component_names_unnamed_component_returns_none() {
+    let old = components_info(vec![component("A", "L0")]);
+    let new = components_info(vec![
+        component("A", "L0"),
+        Component {
+            rep: Some(Rep::Json("{}".to_string())),
+        },
+    ]);
+
+    assert_eq!(changed_component_names(&old, &new), None);
+}
+
+#[test]
+fn changed_component_names_name_that_is_not_an_identifier_returns_none() {
+    let old = components_info(vec![component("A", "L0")]);
+    let new = components_info(vec![component("A", "L0"), component("My machine", "L0")]);
+
+    assert_eq!(changed_component_names(&old, &new), None);
+}
+
+#[test]
+fn query_identifiers_splits_on_operators() {
+    assert_eq!(
+        query_identifiers("refinement: A<=B || Machine_2"),
+        ["refinement", "A", "B", "Machine_2"].into_iter().collect()
+    );
+}
+
+#[test]
+fn query_references_any_matches_whole_identifiers_only() {
+    let changed = names(&["B"]);
+
+    assert!(query_references_any("refinement: A <= B", &changed));
+    assert!(!query_references_any("refinement: A <= BB", &changed));
+    assert!(!query_references_any("consistency: A", &changed));
+}
+
+#[test]
+fn query_references_any_without_identifiers_returns_true() {
+    assert!(query_references_any("", &names(&["B"])));
+}
+
+#[tokio::test]
+async fn invalidate_affected_queries_marks_only_queries_referencing_changed_components() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .with(predicate::eq(1), predicate::eq(None))
+        .returning(|_, _| {
+            Ok(vec![
+                query(1, "refinement: A <= A", false),
+                query(2, "consistency: B", false),
+                query(3, "refinement: A <= B", false),
+                query(4, "determinism: B", true),
+            ])
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_mark_outdated()
+        .with(predicate::eq(vec![2, 3]))
+        .times(1)
+        .returning(|ids| Ok(ids.len() as u64));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let old = components_info(vec![component("A", "L0"), component("B", "L0")]);
+    let new = components_info(vec![component("A", "L0"), component("B", "L1")]);
+
+    let affected =
+        invalidate_affected_queries(&contexts, 1, &serde_json::to_value(old).unwrap(), &new)
+            .await
+            .unwrap();
+
+    assert_eq!(affected, vec![2, 3]);
+}
+
+#[tokio::test]
+async fn invalidate_affected_queries_unchanged_components_marks_nothing() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .never();
+    mock_contexts
+        .query_context_mock
+        .expect_mark_outdated()
+        .never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let components = components_info(vec![component("A", "L0")]);
+
+    let affected = invalidate_affected_queries(
+        &contexts,
+        1,
+        &serde_json::to_value(components.clone()).unwrap(),
+        &components,
+    )
+    .await
+    .unwrap();
+
+    assert!(affected.is_empty());
+}
+
+#[tokio::test]
+async fn invalidate_affected_queries_unreadable_old_components_marks_every_query() {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .query_context_mock
+        .expect_get_all_by_project_id()
+        .returning(|_, _| {
+            Ok(vec![
+                query(1, "refinement: A <= A", false),
+                query(2, "consistency: B", false),
+            ])
+        });
+
+    mock_contexts
+        .query_context_mock
+        .expect_mark_outdated()
+        .with(predicate::eq(vec![1, 2]))
+        .times(1)
+        .returning(|ids| Ok(ids.len() as u64));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let new = components_info(vec![component("B", "L1")]);
+
+    let affected = invalidate_affected_queries(&contexts, 1, &serde_json::Value::Null, &new)
+        .await
+        .unwrap();
+
+    assert_eq!(affected, vec![1, 2]);
+}
@@ -0,0 +1,76 @@
+use crate::api::server::protobuf::{GetProjectResponse, Query};
+use crate::controllers::response_size::{
+    check_response_size, ResponseSizeWarning, LARGE_RESPONSE_HEADER,
+};
+use prost::Message;
+use tonic::Response;
+
+fn response_with_query(string: &str) -> Response<GetProjectResponse> {
+    Response::new(GetProjectResponse {
+        queries: vec![Query {
+            string: string.to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+}
+
+fn warning(threshold: usize, header: bool) -> ResponseSizeWarning {
+    ResponseSizeWarning { threshold, header }
+}
+
+#[test]
+fn response_size_warning_default_threshold_is_one_mebibyte() {
+    assert_eq!(ResponseSizeWarning::default().threshold, 1024 * 1024);
+}
+
+#[test]
+fn check_response_size_small_response_is_not_reported() {
+    let mut small = response_with_query("A <= B");
+
+    assert!(!check_response_size(
+        "get_project",
+        &mut small,
+        &warning(64, true)
+    ));
+    assert!(small.metadata().get(LARGE_RESPONSE_HEADER).is_none());
+}
+
+#[test]
+fn check_response_size_large_response_is_marked() {
+    let mut large = response_with_query(&"A <= B && ".repeat(20));
+    let size = large.get_ref().encoded_len();
+
+    assert!(check_response_size(
+        "get_project",
+        &mut large,
+        &warning(64, true)
+    ));
+    assert_eq!(
+        large.metadata().get(LARGE_RESPONSE_HEADER).unwrap(),
+        size.to_string().as_str()
+    );
+}
+
+#[test]
+fn check_response_size_without_header_leaves_response_unmarked() {
+    let mut unmarked = response_with_query(&"A <= B && ".repeat(20));
+
+    assert!(check_response_size(
+        "get_project",
+        &mut unmarked,
+        &warning(64, false)
+    ));
+    assert!(unmarked.metadata().get(LARGE_RESPONSE_HEADER).is_none());
+}
+
+#[test]
+fn check_response_size_zero_threshold_disables_warning() {
+    let mut disabled = response_with_query(&"A <= B && ".repeat(20));
+
+    assert!(!check_response_size(
+        "get_project",
+        &mut disabled,
+        &warning(0, true)
+    ));
+}
@@ -0,0 +1,420 @@
+use crate::api::auth::VerifiedUid;
+use crate::api::server::protobuf::{
+    component::Rep, query_response, CheckConsistencyRequest, Component, ComponentsInfo,
+    ErrorDetail, ProxyReveaalRequest, QueryRequest, QueryResponse, SimulationStartRequest,
+    SimulationStepRequest, SimulationStepResponse, UserTokenResponse,
+};
+use crate::config::Config;
+use crate::controllers::controller_impls::query_controller::{
+    QueryConfig, REVEAAL_UNAVAILABLE_MESSAGE,
+};
+use crate::controllers::controller_impls::reveaal_controller::parse_proxy_methods;
+use crate::controllers::controller_impls::ReveaalController;
+use crate::controllers::controller_traits::ReveaalControllerTrait;
+use crate::entities::role::Role;
+use crate::entities::{access, project};
+use crate::services::service_collection::ServiceCollection;
+use crate::services::service_traits::ReveaalServiceTrait;
+use crate::tests::controllers::helpers::{
+    disguise_context_mocks, disguise_service_mocks, get_mock_contexts, get_mock_services,
+    get_test_config, MockContexts,
+};
+use async_trait::async_trait;
+use mockall::predicate;
+use prost::Message;
+use std::sync::Arc;
+use tonic::{Code, Request, Response, Status};
+
+/// The test configuration with only the given methods allowed through the proxy.
+fn proxy_config(methods: &[&str]) -> Config {
+    Config {
+        reveaal_proxy_methods: methods.iter().map(|method| method.to_string()).collect(),
+        ..get_test_config()
+    }
+}
+
+fn proxy_request(method: &str, project_id: Option<i32>) -> Request<ProxyReveaalRequest> {
+    let mut request = Request::new(ProxyReveaalRequest {
+        method: method.to_string(),
+        payload: vec![1, 2, 3],
+        project_id,
+    });
+    request.extensions_mut().insert(VerifiedUid(1));
+    request
+}
+
+/// Mocks project 1, on which user 1 is a Reader.
+fn readable_project_contexts() -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                owner_id: 2,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(|_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: Role::Reader.to_string(),
+                project_id: 1,
+                user_id: 1,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+}
+
+#[tokio::test]
+async fn proxy_allowed_method_returns_reveaal_response() {
+    let mut mock_services = get_mock_services();
+
+    mock_services
+        .reveaal_service_mock
+        .expect_call_raw()
+        .with(predicate::eq("SendQuery"), predicate::eq(vec![1, 2, 3]))
+        .times(1)
+        .returning(|_, _| Ok(vec![4, 5]));
+
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(readable_project_contexts()),
+        disguise_service_mocks(mock_services),
+        proxy_config(&["SendQuery"]),
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("SendQuery", Some(1)))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.payload, vec![4, 5]);
+}
+
+#[tokio::test]
+async fn proxy_without_project_returns_err() {
+    let mut mock_services = get_mock_services();
+
+    mock_services.reveaal_service_mock.expect_call_raw().never();
+
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(get_mock_contexts()),
+        disguise_service_mocks(mock_services),
+        proxy_config(&["SendQuery"]),
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("SendQuery", None))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+}
+
+/// A Reveaal service that answers raw calls only after two seconds.
+struct SlowReveaalService;
+
+#[async_trait]
+impl ReveaalServiceTrait for SlowReveaalService {
+    async fn get_user_token(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<UserTokenResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn send_query(
+        &self,
+        _request: Request<QueryRequest>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn start_simulation(
+        &self,
+        _request: Request<SimulationStartRequest>,
+    ) -> Result<Response<SimulationStepResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn take_simulation_step(
+        &self,
+        _request: Request<SimulationStepRequest>,
+    ) -> Result<Response<SimulationStepResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn call_raw(&self, _method: &str, _payload: Vec<u8>) -> Result<Vec<u8>, Status> {
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        Ok(vec![])
+    }
+}
+
+#[tokio::test]
+async fn proxy_reveaal_timeout_returns_err() {
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(readable_project_contexts()),
+        ServiceCollection {
+            hashing_service: Arc::new(get_mock_services().hashing_service_mock),
+            reveaal_service: Arc::new(SlowReveaalService),
+        },
+        Config {
+            queries: QueryConfig {
+                reveaal_timeout: std::time::Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..proxy_config(&["SendQuery"])
+        },
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("SendQuery", Some(1)))
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), Code::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn proxy_disallowed_method_returns_err() {
+    let mut mock_services = get_mock_services();
+
+    mock_services.reveaal_service_mock.expect_call_raw().never();
+
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(get_mock_contexts()),
+        disguise_service_mocks(mock_services),
+        proxy_config(&["SendQuery"]),
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("StartSimulation", None))
+        .await
+        .unwrap_err();
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+    assert_eq!(detail.reason_code, "DISALLOWED_REVEAAL_METHOD");
+}
+
+#[tokio::test]
+async fn proxy_without_allowed_methods_returns_err() {
+    let mut mock_services = get_mock_services();
+
+    mock_services.reveaal_service_mock.expect_call_raw().never();
+
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(get_mock_contexts()),
+        disguise_service_mocks(mock_services),
+        proxy_config(&[]),
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("SendQuery", None))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn proxy_project_without_access_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(|_| {
+            Ok(Some(project::Model {
+                id: 1,
+                owner_id: 2,
+                ..Default::default()
+            }))
+        });
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(|_, _| Ok(None));
+
+    mock_services.reveaal_service_mock.expect_call_raw().never();
+
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(mock_contexts),
+        disguise_service_mocks(mock_services),
+        proxy_config(&["SendQuery"]),
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("SendQuery", Some(1)))
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn proxy_project_with_reader_access_returns_ok() {
+    let mut mock_services = get_mock_services();
+
+    mock_services
+        .reveaal_service_mock
+        .expect_call_raw()
+        .times(1)
+        .returning(|_, _| Ok(vec![]));
+
+    let reveaal_logic = ReveaalController::new(
+        disguise_context_mocks(readable_project_contexts()),
+        disguise_service_mocks(mock_services),
+        proxy_config(&["SendQuery"]),
+    );
+
+    let res = reveaal_logic
+        .proxy(proxy_request("SendQuery", Some(1)))
+        .await;
+
+    assert!(res.is_ok());
+}
+
+#[test]
+fn parse_proxy_methods_trims_and_skips_empty_entries() {
+    assert_eq!(
+        parse_proxy_methods(" SendQuery , StartSimulation,"),
+        Ok(vec!["SendQuery".to_string(), "StartSimulation".to_string()])
+    );
+}
+
+#[test]
+fn parse_proxy_methods_path_returns_err() {
+    assert!(parse_proxy_methods("EcdarBackend/SendQuery").is_err());
+    assert!(parse_proxy_methods("1SendQuery").is_err());
+}
+
+/// Mocks a project on which user 1 is a Reader, with a component named `A` and an unnamed component.
+fn check_consistency_mock_contexts() -> MockContexts {
+    let mut mock_contexts = get_mock_contexts();
+
+    mock_contexts
+        .access_context_mock
+        .expect_get_access_by_uid_and_project_id()
+        .returning(move |_, _| {
+            Ok(Some(access::Model {
+                id: 1,
+                role: "Reader".to_string(),
+                project_id: 1,
+                user_id: 1,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            }))
+        });
+
+    mock_contexts
+        .project_context_mock
+        .expect_get_by_id()
+        .returning(move |_| {
+            Ok(Some(project::Model {
+                id: 1,
+                name: "project".to_string(),
+                components_info: serde_json::to_value(ComponentsInfo {
+                    components: vec![
+                        Component {
+                            rep: Some(Rep::Json(r#"{"name":"A"}"#.to_string())),
+                        },
+                        Component {
+                            rep: Some(Rep::Json("{}".to_string())),
+                        },
+                    ],
+                    components_hash: 0,
+                })
+                .unwrap(),
+                owner_id: 1,
+            }))
+        });
+
+    // Checking consistency never stores a result
+    mock_contexts.query_context_mock.expect_update().never();
+
+    mock_contexts
+}
+
+fn check_consistency_request() -> Request<CheckConsistencyRequest> {
+    let mut request = Request::new(CheckConsistencyRequest { project_id: 1 });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    request
+}
+
+#[tokio::test]
+async fn check_consistency_returns_result_per_component() {
+    let mock_contexts = check_consistency_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .withf(|request| request.get_ref().query == "consistency: A")
+        .times(1)
+        .returning(move |_| {
+            Ok(Response::new(QueryResponse {
+                query_id: 0,
+                info: vec![],
+                result: Some(query_response::Result::Success(query_response::Success {})),
+            }))
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let reveaal_logic = ReveaalController::new(contexts, services, get_test_config());
+
+    let components = reveaal_logic
+        .check_consistency(check_consistency_request())
+        .await
+        .unwrap()
+        .into_inner()
+        .components;
+
+    assert_eq!(components.len(), 2);
+    assert_eq!(components[0].component_name, "A");
+    assert!(components[0].consistent);
+    assert!(components[0].response.is_some());
+    assert_eq!(components[1].component_name, "#1");
+    assert!(!components[1].consistent);
+    assert!(components[1].response.is_none());
+}
+
+#[tokio::test]
+async fn check_consistency_reveaal_unreachable_returns_unavailable() {
+    let mock_contexts = check_consistency_mock_contexts();
+    let mut mock_services = get_mock_services();
+
+    mock_services
+        .reveaal_service_mock
+        .expect_send_query()
+        .returning(move |_| Err(Status::unavailable("Connection refused")));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let reveaal_logic = ReveaalController::new(contexts, services, get_test_config());
+
+    let res = reveaal_logic
+        .check_consistency(check_consistency_request())
+        .await
+        .unwrap_err();
+
+    assert_eq!(res.code(), Code::Unavailable);
+    assert_eq!(res.message(), REVEAAL_UNAVAILABLE_MESSAGE);
+}
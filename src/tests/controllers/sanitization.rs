@@ -0,0 +1,31 @@
+use crate::controllers::sanitization::{sanitize, MAX_SANITIZED_LENGTH};
+
+#[tokio::test]
+async fn sanitize_removes_control_characters() {
+    assert_eq!(
+        sanitize("project\nINFO forged log line\r\t\u{1b}[31m"),
+        "projectINFO forged log line[31m"
+    );
+}
+
+#[tokio::test]
+async fn sanitize_caps_length() {
+    let sanitized = sanitize(&"a".repeat(MAX_SANITIZED_LENGTH + 10));
+
+    assert_eq!(
+        sanitized,
+        format!("{}...", "a".repeat(MAX_SANITIZED_LENGTH))
+    );
+}
+
+#[tokio::test]
+async fn sanitize_keeps_value_at_max_length() {
+    let value = "æ".repeat(MAX_SANITIZED_LENGTH);
+
+    assert_eq!(sanitize(&value), value);
+}
+
+#[tokio::test]
+async fn sanitize_keeps_plain_value() {
+    assert_eq!(sanitize("my project"), "my project");
+}
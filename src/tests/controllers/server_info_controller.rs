@@ -0,0 +1,68 @@
+use crate::config::Config;
+use crate::controllers::controller_impls::server_info_controller::{
+    database_backend_name, enabled_features,
+};
+use crate::controllers::controller_impls::ServerInfoController;
+use crate::controllers::controller_traits::ServerInfoControllerTrait;
+use crate::tests::controllers::helpers::get_test_config;
+use sea_orm::DbBackend;
+use tonic::Request;
+
+#[tokio::test]
+async fn get_server_info_returns_cargo_version() {
+    let server_info_logic = ServerInfoController::new(get_test_config(), DbBackend::Sqlite);
+
+    let res = server_info_logic
+        .get_server_info(Request::new(()))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(res.database_backend, "sqlite");
+}
+
+#[tokio::test]
+async fn get_server_info_reflects_enabled_features() {
+    let config = Config {
+        require_authentication: true,
+        strict_unknown_fields: true,
+        legacy_not_found_on_empty_list: true,
+        reveaal_proxy_methods: vec!["SendQuery".to_string()],
+        ..get_test_config()
+    };
+    let server_info_logic = ServerInfoController::new(config, DbBackend::Postgres);
+
+    let res = server_info_logic
+        .get_server_info(Request::new(()))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(res.features.contains(&"require_authentication".to_string()));
+    assert!(res.features.contains(&"strict_unknown_fields".to_string()));
+    assert!(res
+        .features
+        .contains(&"legacy_not_found_on_empty_list".to_string()));
+    assert!(res.features.contains(&"reveaal_proxy".to_string()));
+    assert!(!res.features.contains(&"rate_limit".to_string()));
+    assert_eq!(res.database_backend, "postgres");
+}
+
+#[test]
+fn enabled_features_default_config_has_no_configured_features() {
+    let features = enabled_features(&get_test_config());
+
+    assert!(!features.contains(&"require_authentication".to_string()));
+    assert!(!features.contains(&"strict_unknown_fields".to_string()));
+    assert!(!features.contains(&"reveaal_proxy".to_string()));
+    assert!(!features
+        .iter()
+        .any(|feature| feature.starts_with("compression")));
+}
+
+#[test]
+fn database_backend_name_names_backends() {
+    assert_eq!(database_backend_name(DbBackend::Postgres), "postgres");
+    assert_eq!(database_backend_name(DbBackend::Sqlite), "sqlite");
+}
@@ -1,18 +1,30 @@
 use mockall::predicate;
 use std::env;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-use crate::entities::{session, user};
+use crate::entities::{access, session, user};
+use crate::tests::contexts::helpers::{
+    create_accesses, create_sessions, create_users, get_context_collection,
+    get_reset_database_context, seed_full_project,
+};
 use crate::tests::controllers::helpers::{
     disguise_context_mocks, disguise_service_mocks, get_mock_contexts, get_mock_services,
+    get_test_config,
 };
 
-use crate::api::auth::{Token, TokenType};
+use crate::api::auth::{Token, TokenType, VerifiedUid};
 use crate::api::server::protobuf::get_auth_token_request::{user_credentials, UserCredentials};
-use crate::api::server::protobuf::GetAuthTokenRequest;
-use crate::controllers::controller_impls::SessionController;
-use crate::controllers::controller_traits::SessionControllerTrait;
-use sea_orm::DbErr;
+use crate::api::server::protobuf::{GetAuthTokenRequest, GetProjectRequest};
+use crate::config::Config;
+use crate::contexts::context_traits::EntityContextTrait;
+use crate::controllers::controller_impls::session_controller::{
+    session_outlived, SessionConfig, SessionLimitPolicy,
+};
+use crate::controllers::controller_impls::{ProjectController, SessionController};
+use crate::controllers::controller_traits::{ProjectControllerTrait, SessionControllerTrait};
+use chrono::{Duration, Local};
+use sea_orm::{DbErr, EntityTrait, IntoActiveModel};
 use tonic::{metadata, Code, Request};
 
 #[tokio::test]
@@ -32,7 +44,7 @@ async fn update_session_no_session_exists_creates_session_returns_err() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let res = session_logic
         .update_session("old_refresh_token".to_string())
@@ -57,6 +69,7 @@ async fn update_session_returns_new_tokens_when_session_exists() {
                 id: 0,
                 access_token: "old_access_token".to_string(),
                 refresh_token: "old_refresh_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: 1,
             }))
@@ -71,6 +84,7 @@ async fn update_session_returns_new_tokens_when_session_exists() {
                 id: 0,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: 1,
             })
@@ -78,7 +92,7 @@ async fn update_session_returns_new_tokens_when_session_exists() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let result = session_logic.update_session(refresh_token).await;
 
@@ -103,7 +117,7 @@ async fn update_session_returns_error_when_no_session_found() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let result = session_logic.update_session(refresh_token).await;
 
@@ -126,7 +140,7 @@ async fn update_session_returns_error_when_database_error_occurs() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let result = session_logic.update_session(refresh_token).await;
 
@@ -155,6 +169,7 @@ async fn get_auth_token_from_credentials_returns_ok() {
                 email: "".to_string(),
                 username: "Example".to_string(),
                 password: "".to_string(),
+                display_name: None,
             }))
         });
 
@@ -163,6 +178,14 @@ async fn get_auth_token_from_credentials_returns_ok() {
         .expect_verify_password()
         .returning(move |_, _| Ok(true));
 
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_user_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(vec![]));
+
+    mock_contexts.session_context_mock.expect_delete().never();
+
     mock_contexts
         .session_context_mock
         .expect_create()
@@ -171,6 +194,7 @@ async fn get_auth_token_from_credentials_returns_ok() {
                 id: 0,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: 1,
             })
@@ -178,7 +202,7 @@ async fn get_auth_token_from_credentials_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let response = session_logic
         .get_auth_token(Request::new(request))
@@ -215,6 +239,7 @@ async fn get_auth_token_from_token_returns_ok() {
                 id: 0,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: 1,
             }))
@@ -228,6 +253,7 @@ async fn get_auth_token_from_token_returns_ok() {
                 id: 0,
                 refresh_token: "refresh_token".to_string(),
                 access_token: "access_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: 1,
             })
@@ -235,7 +261,7 @@ async fn get_auth_token_from_token_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let response = session_logic.get_auth_token(request).await.unwrap();
 
@@ -259,7 +285,7 @@ async fn get_auth_token_from_invalid_token_returns_err() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let response = session_logic.get_auth_token(request).await;
 
@@ -283,6 +309,7 @@ async fn delete_session_returns_ok() {
                 id: 1,
                 refresh_token: Default::default(),
                 access_token: "test_token".to_string(),
+                created_at: Default::default(),
                 updated_at: Default::default(),
                 user_id: Default::default(),
             })
@@ -290,7 +317,7 @@ async fn delete_session_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let mut request = Request::new(());
     request.metadata_mut().insert(
@@ -323,7 +350,7 @@ async fn delete_session_no_session_returns_err() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let session_logic = SessionController::new(contexts, services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
 
     let mut request = Request::new(());
     request.metadata_mut().insert(
@@ -335,3 +362,433 @@ async fn delete_session_no_session_returns_err() {
 
     assert_eq!(res.unwrap_err().code(), Code::Internal);
 }
+
+#[tokio::test]
+async fn get_auth_token_at_session_limit_evicts_oldest_session() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+    env::set_var("REFRESH_TOKEN_HS512_SECRET", "refresh_secret");
+
+    let mut mock_contexts = get_mock_contexts();
+    let mut mock_services = get_mock_services();
+    let config = Config {
+        sessions: SessionConfig {
+            max_sessions_per_user: 3,
+            ..Default::default()
+        },
+        ..get_test_config()
+    };
+
+    // A different subject keeps the token apart from the one issued by the new login
+    let oldest_refresh_token = Token::refresh("oldest").unwrap().to_string();
+
+    // The sessions of the user, least recently updated first, shared by the mocked session context
+    let sessions: Vec<session::Model> = (1..=config.sessions.max_sessions_per_user as i32)
+        .map(|id| session::Model {
+            id,
+            refresh_token: match id {
+                1 => oldest_refresh_token.clone(),
+                _ => format!("refresh_token{}", id),
+            },
+            access_token: format!("access_token{}", id),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            user_id: 1,
+        })
+        .collect();
+    let sessions = Arc::new(Mutex::new(sessions));
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_username()
+        .returning(move |_| {
+            Ok(Some(user::Model {
+                id: 1,
+                email: "".to_string(),
+                username: "Example".to_string(),
+                password: "".to_string(),
+                display_name: None,
+            }))
+        });
+
+    mock_services
+        .hashing_service_mock
+        .expect_verify_password()
+        .returning(move |_, _| Ok(true));
+
+    let stored = sessions.clone();
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_user_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(stored.lock().unwrap().clone()));
+
+    let stored = sessions.clone();
+    mock_contexts
+        .session_context_mock
+        .expect_delete()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(move |id| {
+            let mut stored = stored.lock().unwrap();
+            let index = stored.iter().position(|session| session.id == id).unwrap();
+            Ok(stored.remove(index))
+        });
+
+    let stored = sessions.clone();
+    mock_contexts
+        .session_context_mock
+        .expect_create()
+        .times(1)
+        .returning(move |session| {
+            stored.lock().unwrap().push(session.clone());
+            Ok(session)
+        });
+
+    let stored = sessions.clone();
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_token()
+        .returning(move |_, token| {
+            Ok(stored
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|session| session.refresh_token == token)
+                .cloned())
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let session_logic = SessionController::new(contexts, services, config.clone());
+
+    let login = session_logic
+        .get_auth_token(Request::new(GetAuthTokenRequest {
+            user_credentials: Some(UserCredentials {
+                password: "Password123".to_string(),
+                user: Some(user_credentials::User::Username("Example".to_string())),
+            }),
+        }))
+        .await;
+
+    let mut refresh = Request::new(GetAuthTokenRequest {
+        user_credentials: None,
+    });
+    refresh.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str(format!("Bearer {}", oldest_refresh_token).as_str())
+            .unwrap(),
+    );
+    let evicted_refresh = session_logic.get_auth_token(refresh).await;
+
+    assert!(login.is_ok());
+    assert_eq!(
+        sessions.lock().unwrap().len() as u64,
+        config.sessions.max_sessions_per_user
+    );
+    assert_eq!(evicted_refresh.unwrap_err().code(), Code::Unauthenticated);
+}
+
+#[test]
+fn session_limit_policy_from_str_parses_policies() {
+    assert_eq!(
+        SessionLimitPolicy::from_str("evict_oldest"),
+        Ok(SessionLimitPolicy::EvictOldest)
+    );
+    assert_eq!(
+        SessionLimitPolicy::from_str("reject"),
+        Ok(SessionLimitPolicy::Reject)
+    );
+    assert!(SessionLimitPolicy::from_str("newest").is_err());
+}
+
+#[tokio::test]
+async fn delete_session_releases_locks_for_collaborators() {
+    let db_context = get_reset_database_context().await;
+    let seeded = seed_full_project(&db_context, 1).await;
+
+    let collaborator = create_users(2)[1].clone();
+    let collaborator_access = access::Model {
+        role: "Editor".into(),
+        project_id: seeded.project_id,
+        ..create_accesses(2, seeded.owner_id, seeded.project_id)[1].clone()
+    };
+    let collaborator_session = session::Model {
+        id: seeded.session_id + 1,
+        ..create_sessions(2, collaborator.id)[1].clone()
+    };
+
+    let db = db_context.get_connection();
+    user::Entity::insert(collaborator.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    access::Entity::insert(collaborator_access.into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+    session::Entity::insert(collaborator_session.clone().into_active_model())
+        .exec(&db)
+        .await
+        .unwrap();
+
+    let contexts = get_context_collection(db_context);
+    let project_logic = ProjectController::new(contexts.clone(), get_test_config());
+    let session_logic = SessionController::new(
+        contexts.clone(),
+        disguise_service_mocks(get_mock_services()),
+        get_test_config(),
+    );
+
+    let get_project_request = |uid: i32, access_token: &str| {
+        let mut request = Request::new(GetProjectRequest {
+            id: seeded.project_id,
+            known_version: None,
+            include_query_results: None,
+        });
+        request.extensions_mut().insert(VerifiedUid(uid));
+        request.metadata_mut().insert(
+            "authorization",
+            metadata::MetadataValue::from_str(&format!("Bearer {}", access_token)).unwrap(),
+        );
+        request
+    };
+
+    // The owner opens the project and takes the lock
+    let owner_res = project_logic
+        .get_project(get_project_request(seeded.owner_id, &seeded.access_token))
+        .await
+        .unwrap()
+        .into_inner();
+    let locked_res = project_logic
+        .get_project(get_project_request(
+            collaborator.id,
+            &collaborator_session.access_token,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let mut logout = Request::new(());
+    logout.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str(&format!("Bearer {}", seeded.access_token)).unwrap(),
+    );
+    session_logic.delete_session(logout).await.unwrap();
+
+    let released_res = project_logic
+        .get_project(get_project_request(
+            collaborator.id,
+            &collaborator_session.access_token,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let in_use = contexts
+        .in_use_context
+        .get_by_id(seeded.project_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(!owner_res.in_use);
+    assert!(locked_res.in_use);
+    assert!(!released_res.in_use);
+    assert_eq!(in_use.session_id, collaborator_session.id);
+}
+
+#[tokio::test]
+async fn get_auth_token_evicting_session_releases_its_locks() {
+    env::set_var("ACCESS_TOKEN_HS512_SECRET", "access_secret");
+    env::set_var("REFRESH_TOKEN_HS512_SECRET", "refresh_secret");
+
+    let db_context = get_reset_database_context().await;
+    let seeded = seed_full_project(&db_context, 1).await;
+    let contexts = get_context_collection(db_context);
+
+    let mut mock_services = get_mock_services();
+    mock_services
+        .hashing_service_mock
+        .expect_verify_password()
+        .returning(move |_, _| Ok(true));
+
+    let config = Config {
+        sessions: SessionConfig {
+            max_sessions_per_user: 1,
+            ..Default::default()
+        },
+        ..get_test_config()
+    };
+    let session_logic = SessionController::new(
+        contexts.clone(),
+        disguise_service_mocks(mock_services),
+        config,
+    );
+
+    // Logging in again at the limit evicts the session holding the in use of the project
+    session_logic
+        .get_auth_token(Request::new(GetAuthTokenRequest {
+            user_credentials: Some(UserCredentials {
+                password: "qwerty0".to_string(),
+                user: Some(user_credentials::User::Username("username0".to_string())),
+            }),
+        }))
+        .await
+        .unwrap();
+
+    let evicted_session = contexts
+        .session_context
+        .get_by_id(seeded.session_id)
+        .await
+        .unwrap();
+    let in_use = contexts
+        .in_use_context
+        .get_by_id(seeded.project_id)
+        .await
+        .unwrap();
+
+    assert_eq!(evicted_session, None);
+    assert_eq!(in_use, None);
+}
+
+#[test]
+fn session_outlived_old_session_recently_refreshed_returns_true() {
+    let now = Local::now().naive_local();
+    let session = session::Model {
+        created_at: Some(now - Duration::hours(13)),
+        updated_at: now - Duration::minutes(1),
+        ..Default::default()
+    };
+
+    assert!(session_outlived(&session, Some(Duration::hours(12)), now));
+    assert!(!session_outlived(&session, Some(Duration::hours(14)), now));
+    assert!(!session_outlived(&session, None, now));
+}
+
+#[test]
+fn session_outlived_without_created_at_uses_updated_at() {
+    let now = Local::now().naive_local();
+    let session = session::Model {
+        created_at: None,
+        updated_at: now - Duration::hours(13),
+        ..Default::default()
+    };
+
+    assert!(session_outlived(&session, Some(Duration::hours(12)), now));
+}
+
+#[tokio::test]
+async fn end_outlived_session_recently_refreshed_old_session_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .session_context_mock
+        .expect_delete()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(Default::default()));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
+
+    let now = Local::now().naive_local();
+    let session = session::Model {
+        id: 1,
+        created_at: Some(now - Duration::days(2)),
+        updated_at: now,
+        user_id: 1,
+        ..Default::default()
+    };
+
+    let res = session_logic
+        .end_outlived_session(&session, Some(Duration::hours(24)))
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn end_outlived_session_young_session_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts.session_context_mock.expect_delete().never();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
+
+    let now = Local::now().naive_local();
+    let session = session::Model {
+        id: 1,
+        created_at: Some(now - Duration::hours(1)),
+        updated_at: now,
+        user_id: 1,
+        ..Default::default()
+    };
+
+    let res = session_logic
+        .end_outlived_session(&session, Some(Duration::hours(24)))
+        .await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn list_sessions_returns_created_at_and_marks_current_session() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let created_at = Local::now().naive_local() - Duration::hours(1);
+
+    mock_contexts
+        .session_context_mock
+        .expect_get_by_user_id()
+        .with(predicate::eq(1))
+        .returning(move |_| {
+            Ok(vec![
+                session::Model {
+                    id: 1,
+                    access_token: "other_token".to_string(),
+                    created_at: Some(created_at),
+                    updated_at: created_at,
+                    user_id: 1,
+                    ..Default::default()
+                },
+                session::Model {
+                    id: 2,
+                    access_token: "test_token".to_string(),
+                    created_at: None,
+                    updated_at: created_at,
+                    user_id: 1,
+                    ..Default::default()
+                },
+            ])
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let session_logic = SessionController::new(contexts, services, get_test_config());
+
+    let mut request = Request::new(());
+    request.extensions_mut().insert(VerifiedUid(1));
+    request.metadata_mut().insert(
+        "authorization",
+        metadata::MetadataValue::from_str("Bearer test_token").unwrap(),
+    );
+
+    let sessions = session_logic
+        .list_sessions(request)
+        .await
+        .unwrap()
+        .into_inner()
+        .sessions;
+
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].created_at, Some(created_at.timestamp()));
+    assert!(!sessions[0].current);
+    assert_eq!(sessions[1].created_at, None);
+    assert!(sessions[1].current);
+}
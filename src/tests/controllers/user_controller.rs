@@ -1,14 +1,28 @@
-use crate::api::server::protobuf::{CreateUserRequest, GetUsersRequest, UpdateUserRequest};
+use crate::api::auth::VerifiedUid;
+use crate::api::server::protobuf::resolve_user_request::Identifier;
+use crate::api::server::protobuf::{
+    CreateUserRequest, ErrorDetail, GetCurrentUserResponse, GetUsersRequest, ResolveUserRequest,
+    UpdateUserRequest,
+};
+use crate::contexts::context_impls::UserContext;
+use crate::contexts::context_traits::EntityContextTrait;
+use crate::controllers::controller_impls::user_controller::{
+    is_allowed_email_domain, parse_allowed_email_domains, parse_display_name,
+};
 use crate::controllers::controller_impls::UserController;
 use crate::controllers::controller_traits::UserControllerTrait;
 use crate::entities::user;
+use crate::tests::contexts::helpers::{create_users, get_reset_database_context};
 use crate::tests::controllers::helpers::{
     disguise_context_mocks, disguise_service_mocks, get_mock_contexts, get_mock_services,
+    get_test_config,
 };
+use migration::{DISPLAY_NAME_MAX_LENGTH, EMAIL_MAX_LENGTH, USERNAME_MAX_LENGTH};
 use mockall::predicate;
+use prost::Message;
 use sea_orm::DbErr;
-use std::str::FromStr;
-use tonic::{metadata, Code, Request};
+use std::sync::{Arc, Mutex};
+use tonic::{Code, Request};
 
 #[tokio::test]
 async fn delete_user_nonexistent_user_returns_err() {
@@ -23,14 +37,12 @@ async fn delete_user_nonexistent_user_returns_err() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let mut delete_request = Request::new(());
 
-    // Insert uid into request metadata
-    delete_request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    // Insert the verified uid into the request
+    delete_request.extensions_mut().insert(VerifiedUid(1));
 
     let delete_response = user_logic.delete_user(delete_request).await.unwrap_err();
     let expected_response_code = Code::Internal;
@@ -48,6 +60,7 @@ async fn delete_user_existing_user_returns_ok() {
         email: "".to_string(),
         username: "".to_string(),
         password: "".to_string(),
+        display_name: None,
     };
 
     mock_contexts
@@ -58,14 +71,12 @@ async fn delete_user_existing_user_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let mut delete_request = Request::new(());
 
-    // Insert uid into request metadata
-    delete_request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    // Insert the verified uid into the request
+    delete_request.extensions_mut().insert(VerifiedUid(1));
 
     let delete_response = user_logic.delete_user(delete_request).await;
 
@@ -84,6 +95,7 @@ async fn create_user_nonexistent_user_returns_ok() {
         email: "anders21@student.aau.dk".to_string(),
         username: "anders".to_string(),
         password: password.clone(),
+        display_name: None,
     };
 
     let create_user_request = Request::new(CreateUserRequest {
@@ -105,7 +117,7 @@ async fn create_user_nonexistent_user_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let create_user_response = user_logic.create_user(create_user_request).await;
     assert!(create_user_response.is_ok());
@@ -123,6 +135,7 @@ async fn create_user_duplicate_email_returns_error() {
         email: "anders21@student.aau.dk".to_string(),
         username: "anders".to_string(),
         password: password.clone(),
+        display_name: None,
     };
 
     let create_user_request = Request::new(CreateUserRequest {
@@ -144,7 +157,7 @@ async fn create_user_duplicate_email_returns_error() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let res = user_logic.create_user(create_user_request).await;
     assert_eq!(res.unwrap_err().code(), Code::Internal); //todo!("Needs to be code AlreadyExists when mocked Error is corrected)
@@ -157,7 +170,7 @@ async fn create_user_invalid_email_returns_error() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let create_user_request = Request::new(CreateUserRequest {
         email: "invalid-email".to_string(),
@@ -165,8 +178,15 @@ async fn create_user_invalid_email_returns_error() {
         password: "123".to_string(),
     });
 
-    let res = user_logic.create_user(create_user_request).await;
-    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+    let res = user_logic
+        .create_user(create_user_request)
+        .await
+        .unwrap_err();
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(detail.reason_code, "INVALID_EMAIL");
+    assert_eq!(detail.field, "email");
 }
 
 #[tokio::test]
@@ -181,6 +201,7 @@ async fn create_user_duplicate_username_returns_error() {
         email: "anders21@student.aau.dk".to_string(),
         username: "anders".to_string(),
         password: password.clone(),
+        display_name: None,
     };
 
     let create_user_request = Request::new(CreateUserRequest {
@@ -202,7 +223,7 @@ async fn create_user_duplicate_username_returns_error() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let res = user_logic.create_user(create_user_request).await;
     assert_eq!(res.unwrap_err().code(), Code::Internal); //todo!("Needs to be code AlreadyExists when mocked Error is corrected)
@@ -215,7 +236,7 @@ async fn create_user_invalid_username_returns_error() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let create_user_request = Request::new(CreateUserRequest {
         email: "valid@email.com".to_string(),
@@ -227,6 +248,60 @@ async fn create_user_invalid_username_returns_error() {
     assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
 }
 
+async fn create_user_with(username: String, email: String) -> tonic::Status {
+    let mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
+
+    let create_user_request = Request::new(CreateUserRequest {
+        email,
+        username,
+        password: "123".to_string(),
+    });
+
+    user_logic
+        .create_user(create_user_request)
+        .await
+        .unwrap_err()
+}
+
+#[tokio::test]
+async fn create_user_username_over_column_length_returns_error() {
+    let username = "a".repeat(USERNAME_MAX_LENGTH as usize + 1);
+
+    let res = create_user_with(username, "valid@email.com".to_string()).await;
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(
+        res.message(),
+        format!(
+            "Username must be at most {} characters long",
+            USERNAME_MAX_LENGTH
+        )
+    );
+    assert_eq!(detail.field, "username");
+}
+
+#[tokio::test]
+async fn create_user_email_over_column_length_returns_error() {
+    let domain = "@email.com";
+    let email = "a".repeat(EMAIL_MAX_LENGTH as usize + 1 - domain.len()) + domain;
+
+    let res = create_user_with("newuser".to_string(), email).await;
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(
+        res.message(),
+        format!("Email must be at most {} characters long", EMAIL_MAX_LENGTH)
+    );
+    assert_eq!(detail.field, "email");
+}
+
 #[tokio::test]
 async fn create_user_valid_request_returns_ok() {
     let mut mock_contexts = get_mock_contexts();
@@ -239,6 +314,7 @@ async fn create_user_valid_request_returns_ok() {
         email: "newuser@example.com".to_string(),
         username: "newuser".to_string(),
         password: password.clone(),
+        display_name: None,
     };
 
     let create_user_request = Request::new(CreateUserRequest {
@@ -260,7 +336,7 @@ async fn create_user_valid_request_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let create_user_response = user_logic.create_user(create_user_request).await;
     assert!(create_user_response.is_ok());
@@ -276,6 +352,7 @@ async fn update_user_returns_ok() {
         email: "olduser@example.com".to_string(),
         username: "old_username".to_string(),
         password: "StrongPassword123".to_string(),
+        display_name: None,
     };
 
     let new_user = user::Model {
@@ -283,6 +360,7 @@ async fn update_user_returns_ok() {
         email: "newuser@example.com".to_string(),
         username: "new_username".to_string(),
         password: "g76df2gd7hd837g8hjd8723hd8gd823d82d3".to_string(),
+        display_name: None,
     };
 
     mock_contexts
@@ -305,17 +383,16 @@ async fn update_user_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let mut update_user_request = Request::new(UpdateUserRequest {
         email: Some("newuser@example.com".to_string()),
         username: Some("new_username".to_string()),
         password: Some("StrongPassword123".to_string()),
+        display_name: None,
     });
 
-    update_user_request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    update_user_request.extensions_mut().insert(VerifiedUid(1));
 
     let update_user_response = user_logic.update_user(update_user_request).await;
 
@@ -335,17 +412,16 @@ async fn update_user_non_existant_user_returns_err() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let mut update_user_request = Request::new(UpdateUserRequest {
         email: Some("new_test@test".to_string()),
         username: Some("new_test_user".to_string()),
         password: Some("new_test_pass".to_string()),
+        display_name: None,
     });
 
-    update_user_request
-        .metadata_mut()
-        .insert("uid", metadata::MetadataValue::from_str("1").unwrap());
+    update_user_request.extensions_mut().insert(VerifiedUid(1));
 
     let res = user_logic.update_user(update_user_request).await;
 
@@ -363,12 +439,14 @@ async fn get_users_returns_ok() {
             email: "".to_string(),
             username: "".to_string(),
             password: "".to_string(),
+            display_name: None,
         },
         user::Model {
             id: 2,
             email: "".to_string(),
             username: "".to_string(),
             password: "".to_string(),
+            display_name: None,
         },
     ];
 
@@ -379,7 +457,7 @@ async fn get_users_returns_ok() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let get_users_request = Request::new(GetUsersRequest { ids: vec![1, 2] });
 
@@ -402,7 +480,7 @@ async fn get_users_returns_empty_array() {
 
     let contexts = disguise_context_mocks(mock_contexts);
     let services = disguise_service_mocks(mock_services);
-    let user_logic = UserController::new(contexts, services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
 
     let get_users_request = Request::new(GetUsersRequest { ids: vec![1, 2] });
 
@@ -410,3 +488,295 @@ async fn get_users_returns_empty_array() {
 
     assert_eq!(get_users_response.get_ref().users.len(), 0);
 }
+
+#[tokio::test]
+async fn resolve_user_by_email_returns_ok() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_email()
+        .with(predicate::eq("anders21@student.aau.dk".to_string()))
+        .returning(move |_| {
+            Ok(Some(user::Model {
+                id: 1,
+                email: "anders21@student.aau.dk".to_string(),
+                username: "anders".to_string(),
+                password: "".to_string(),
+                display_name: None,
+            }))
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
+
+    let request = Request::new(ResolveUserRequest {
+        identifier: Some(Identifier::Email("anders21@student.aau.dk".to_string())),
+    });
+
+    let res = user_logic.resolve_user(request).await.unwrap();
+
+    assert_eq!(res.get_ref().id, 1);
+    assert_eq!(res.get_ref().username, "anders");
+}
+
+#[tokio::test]
+async fn resolve_user_no_match_returns_err() {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_username()
+        .with(predicate::eq("anders".to_string()))
+        .returning(move |_| Ok(None));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
+
+    let request = Request::new(ResolveUserRequest {
+        identifier: Some(Identifier::Username("anders".to_string())),
+    });
+
+    let res = user_logic.resolve_user(request).await.unwrap_err();
+
+    assert_eq!(res.code(), Code::NotFound);
+}
+
+/// Creates a user, then a second user reusing the first user's username and/or email,
+/// returning the error of the second insert.
+async fn duplicate_user_db_error(same_username: bool, same_email: bool) -> DbErr {
+    let user_context = UserContext::new(get_reset_database_context().await);
+
+    let users = create_users(2);
+    user_context.create(users[0].clone()).await.unwrap();
+
+    let duplicate = user::Model {
+        username: if same_username {
+            users[0].username.clone()
+        } else {
+            users[1].username.clone()
+        },
+        email: if same_email {
+            users[0].email.clone()
+        } else {
+            users[1].email.clone()
+        },
+        ..users[1].clone()
+    };
+
+    user_context.create(duplicate).await.unwrap_err()
+}
+
+#[tokio::test]
+async fn duplicate_user_error_duplicate_email_has_reason() {
+    let error = duplicate_user_db_error(false, true).await;
+
+    let status = super::duplicate_user_error(&error).unwrap();
+    let detail = ErrorDetail::decode(status.details()).unwrap();
+
+    assert_eq!(status.code(), Code::AlreadyExists);
+    assert_eq!(detail.reason_code, "DUPLICATE_EMAIL");
+    assert_eq!(detail.field, "email");
+}
+
+#[tokio::test]
+async fn duplicate_user_error_duplicate_username_has_reason() {
+    let error = duplicate_user_db_error(true, false).await;
+
+    let status = super::duplicate_user_error(&error).unwrap();
+    let detail = ErrorDetail::decode(status.details()).unwrap();
+
+    assert_eq!(status.code(), Code::AlreadyExists);
+    assert_eq!(detail.reason_code, "DUPLICATE_USERNAME");
+    assert_eq!(detail.field, "username");
+}
+
+#[tokio::test]
+async fn duplicate_user_error_other_error_returns_none() {
+    assert!(super::duplicate_user_error(&DbErr::RecordNotInserted).is_none());
+}
+
+#[test]
+fn is_allowed_email_domain_allowed_domain_returns_true() {
+    let allowed = vec!["student.aau.dk".to_string(), "aau.dk".to_string()];
+
+    assert!(is_allowed_email_domain("anders21@Student.AAU.dk", &allowed));
+}
+
+#[test]
+fn is_allowed_email_domain_disallowed_domain_returns_false() {
+    let allowed = vec!["aau.dk".to_string()];
+
+    assert!(!is_allowed_email_domain("anders@gmail.com", &allowed));
+    assert!(!is_allowed_email_domain("anders@student.aau.dk", &allowed));
+}
+
+#[test]
+fn is_allowed_email_domain_empty_allowlist_returns_true() {
+    assert!(is_allowed_email_domain("anders@gmail.com", &[]));
+}
+
+#[test]
+fn parse_allowed_email_domains_trims_and_lowercases() {
+    assert_eq!(
+        parse_allowed_email_domains(" Student.AAU.dk , aau.dk,"),
+        Ok(vec!["student.aau.dk".to_string(), "aau.dk".to_string()])
+    );
+}
+
+#[test]
+fn parse_allowed_email_domains_email_address_returns_err() {
+    assert!(parse_allowed_email_domains("anders@aau.dk").is_err());
+    assert!(parse_allowed_email_domains("aau..dk").is_err());
+}
+
+/// Updates only the display name of user 1, whose current display name is `current`,
+/// returning the display name the user is updated with.
+async fn update_display_name(
+    current: Option<&str>,
+    requested: Option<&str>,
+) -> Result<Option<String>, tonic::Status> {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let user = user::Model {
+        id: 1,
+        email: "anders@example.com".to_string(),
+        username: "anders".to_string(),
+        password: "hash".to_string(),
+        display_name: current.map(str::to_string),
+    };
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(Some(user.clone())));
+
+    let updated = Arc::new(Mutex::new(None));
+    let updated_by_mock = Arc::clone(&updated);
+    mock_contexts
+        .user_context_mock
+        .expect_update()
+        .returning(move |user: user::Model| {
+            *updated_by_mock.lock().unwrap() = Some(user.display_name.clone());
+            Ok(user)
+        });
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
+
+    let mut request = Request::new(UpdateUserRequest {
+        email: None,
+        username: None,
+        password: None,
+        display_name: requested.map(str::to_string),
+    });
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    user_logic.update_user(request).await?;
+
+    let display_name = updated.lock().unwrap().clone();
+    Ok(display_name.unwrap())
+}
+
+#[tokio::test]
+async fn update_user_sets_display_name() {
+    let display_name = update_display_name(None, Some("  Anders And "))
+        .await
+        .unwrap();
+
+    assert_eq!(display_name, Some("Anders And".to_string()));
+}
+
+#[tokio::test]
+async fn update_user_empty_display_name_clears_it() {
+    let display_name = update_display_name(Some("Anders And"), Some(" "))
+        .await
+        .unwrap();
+
+    assert_eq!(display_name, None);
+}
+
+#[tokio::test]
+async fn update_user_without_display_name_keeps_it() {
+    let display_name = update_display_name(Some("Anders And"), None).await.unwrap();
+
+    assert_eq!(display_name, Some("Anders And".to_string()));
+}
+
+#[tokio::test]
+async fn update_user_display_name_too_long_returns_err() {
+    let display_name = "a".repeat(DISPLAY_NAME_MAX_LENGTH as usize + 1);
+
+    let res = update_display_name(None, Some(&display_name))
+        .await
+        .unwrap_err();
+
+    let detail = ErrorDetail::decode(res.details()).unwrap();
+    assert_eq!(res.code(), Code::InvalidArgument);
+    assert_eq!(detail.reason_code, "INVALID_DISPLAY_NAME");
+}
+
+#[tokio::test]
+async fn parse_display_name_rejects_control_characters() {
+    assert!(parse_display_name("Anders\nAnd").is_err());
+    assert_eq!(
+        parse_display_name("Ånders Ænd").unwrap(),
+        Some("Ånders Ænd".to_string())
+    );
+}
+
+async fn get_current_user(display_name: Option<&str>) -> GetCurrentUserResponse {
+    let mut mock_contexts = get_mock_contexts();
+    let mock_services = get_mock_services();
+
+    let user = user::Model {
+        id: 1,
+        email: "anders@example.com".to_string(),
+        username: "anders".to_string(),
+        password: "hash".to_string(),
+        display_name: display_name.map(str::to_string),
+    };
+
+    mock_contexts
+        .user_context_mock
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .returning(move |_| Ok(Some(user.clone())));
+
+    let contexts = disguise_context_mocks(mock_contexts);
+    let services = disguise_service_mocks(mock_services);
+    let user_logic = UserController::new(contexts, services, get_test_config());
+
+    let mut request = Request::new(());
+
+    request.extensions_mut().insert(VerifiedUid(1));
+
+    user_logic
+        .get_current_user(request)
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn get_current_user_returns_display_name() {
+    let res = get_current_user(Some("Anders And")).await;
+
+    assert_eq!(res.username, "anders");
+    assert_eq!(res.display_name, "Anders And");
+}
+
+#[tokio::test]
+async fn get_current_user_without_display_name_falls_back_to_username() {
+    let res = get_current_user(None).await;
+
+    assert_eq!(res.display_name, "anders");
+}
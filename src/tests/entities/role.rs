@@ -0,0 +1,53 @@
+use crate::entities::access;
+use crate::entities::role::Role;
+use std::str::FromStr;
+
+#[test]
+fn role_ordering_test() {
+    assert!(Role::Reader < Role::Commenter);
+    assert!(Role::Commenter < Role::Editor);
+    assert!(Role::Editor >= Role::Editor);
+}
+
+#[test]
+fn role_from_str_test() {
+    for role in [Role::Reader, Role::Commenter, Role::Editor] {
+        assert_eq!(Role::from_str(role.as_str()), Ok(role));
+    }
+}
+
+#[test]
+fn role_from_str_invalid_returns_err() {
+    assert!(Role::from_str("Owner").is_err());
+    assert!(Role::from_str("editor").is_err());
+}
+
+#[test]
+fn has_role_at_least_test() {
+    let access = access::Model {
+        id: 1,
+        role: "Commenter".to_string(),
+        project_id: 1,
+        user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    assert!(access.has_role_at_least(Role::Reader));
+    assert!(access.has_role_at_least(Role::Commenter));
+    assert!(!access.has_role_at_least(Role::Editor));
+}
+
+#[test]
+fn has_role_at_least_unknown_role_test() {
+    let access = access::Model {
+        id: 1,
+        role: "Unknown".to_string(),
+        project_id: 1,
+        user_id: 1,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    assert!(!access.has_role_at_least(Role::Reader));
+}
@@ -1,4 +1,5 @@
 pub mod api;
 pub mod contexts;
 pub mod controllers;
+pub mod entities;
 pub mod services;
@@ -0,0 +1,32 @@
+use crate::services::access_events::{AccessEvent, AccessEventBus};
+
+fn revoked(user_id: i32) -> AccessEvent {
+    AccessEvent::Revoked {
+        project_id: 1,
+        user_id,
+        changed_by: 1,
+    }
+}
+
+#[test]
+fn publish_without_subscribers_is_ignored() {
+    let bus = AccessEventBus::default();
+
+    bus.publish(revoked(2));
+}
+
+#[tokio::test]
+async fn every_subscriber_receives_events_published_after_subscribing() {
+    let bus = AccessEventBus::default();
+
+    bus.publish(revoked(2));
+
+    let mut first = bus.subscribe();
+    let mut second = bus.clone().subscribe();
+
+    bus.publish(revoked(3));
+
+    assert_eq!(first.recv().await.unwrap(), revoked(3));
+    assert_eq!(second.recv().await.unwrap(), revoked(3));
+    assert!(first.try_recv().is_err());
+}
@@ -0,0 +1,42 @@
+use crate::services::service_impls::HashingService;
+use crate::services::service_traits::HashingServiceTrait;
+use std::time::Duration;
+
+#[test]
+fn calibrate_time_increases_with_cost() {
+    let calibration = HashingService::calibrate(4..=8, Duration::from_millis(250)).unwrap();
+
+    let costs: Vec<u32> = calibration
+        .measurements
+        .iter()
+        .map(|(cost, _)| *cost)
+        .collect();
+    assert_eq!(costs, vec![4, 5, 6, 7, 8]);
+
+    // Each extra cost doubles the work, so hashing never gets faster as the cost rises
+    assert!(calibration
+        .measurements
+        .windows(2)
+        .all(|pair| pair[0].1 <= pair[1].1));
+}
+
+#[test]
+fn calibrate_suggests_lowest_cost_if_target_is_unreachable() {
+    let calibration = HashingService::calibrate(4..=5, Duration::ZERO).unwrap();
+
+    assert_eq!(calibration.suggested_cost, 4);
+}
+
+#[test]
+fn calibrate_invalid_cost_returns_err() {
+    assert!(HashingService::calibrate(1..=2, Duration::from_millis(250)).is_err());
+}
+
+#[test]
+fn hash_password_uses_configured_cost() {
+    let hash = HashingService::new(4)
+        .hash_password("password".to_string())
+        .unwrap();
+
+    assert!(hash.starts_with("$2b$04$"));
+}
@@ -1 +1,4 @@
+mod access_events;
+mod hashing_service;
+mod reveaal_metrics;
 mod reveaal_service;
@@ -0,0 +1,106 @@
+use crate::api::server::protobuf::{query_response, ParsingError, QueryResponse};
+use crate::services::reveaal_metrics::{ReveaalMetrics, ReveaalOutcome};
+use crate::services::service_impls::ReveaalService;
+use crate::services::service_traits::ReveaalServiceTrait;
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+
+fn response(result: Option<query_response::Result>) -> Result<Response<QueryResponse>, Status> {
+    Ok(Response::new(QueryResponse {
+        result,
+        ..Default::default()
+    }))
+}
+
+#[test]
+fn finish_counts_each_outcome() {
+    let metrics = ReveaalMetrics::default();
+
+    let results = [
+        (response(None), ReveaalOutcome::Success),
+        (
+            response(Some(query_response::Result::ParsingError(
+                ParsingError::default(),
+            ))),
+            ReveaalOutcome::InvalidQuery,
+        ),
+        (
+            Err(Status::invalid_argument("unknown component")),
+            ReveaalOutcome::InvalidQuery,
+        ),
+        (
+            Err(Status::deadline_exceeded("too slow")),
+            ReveaalOutcome::Timeout,
+        ),
+        (
+            Err(Status::unavailable("connection refused")),
+            ReveaalOutcome::TransportError,
+        ),
+        (Err(Status::internal("panicked")), ReveaalOutcome::Error),
+    ];
+
+    for (result, outcome) in results {
+        let before = metrics.count(outcome);
+        metrics.start_query().finish(result).ok();
+        assert_eq!(metrics.count(outcome), before + 1, "{:?}", outcome);
+    }
+
+    assert_eq!(metrics.count(ReveaalOutcome::InvalidQuery), 2);
+}
+
+#[test]
+fn abandoned_query_counts_as_timeout() {
+    let metrics = ReveaalMetrics::default();
+
+    drop(metrics.start_query());
+
+    assert_eq!(metrics.count(ReveaalOutcome::Timeout), 1);
+    assert_eq!(metrics.count(ReveaalOutcome::Success), 0);
+}
+
+#[test]
+fn render_lists_every_outcome() {
+    let metrics = ReveaalMetrics::default();
+    metrics.record(ReveaalOutcome::Success);
+    metrics.record(ReveaalOutcome::Success);
+
+    let rendered = metrics.render();
+
+    assert!(rendered.contains("# TYPE ecdar_reveaal_queries_total counter"));
+    assert!(rendered.contains("ecdar_reveaal_queries_total{outcome=\"success\"} 2"));
+    assert!(rendered.contains("ecdar_reveaal_queries_total{outcome=\"transport_error\"} 0"));
+}
+
+#[tokio::test]
+async fn send_query_unreachable_reveaal_counts_transport_error() {
+    let service =
+        ReveaalService::with_concurrency_limit("http://127.0.0.1:1", 1, Duration::from_secs(5));
+
+    let res = service.send_query(Request::new(Default::default())).await;
+
+    assert!(res.is_err());
+    assert_eq!(service.metrics().count(ReveaalOutcome::TransportError), 1);
+}
+
+#[tokio::test]
+async fn call_raw_unreachable_reveaal_counts_transport_error() {
+    let service =
+        ReveaalService::with_concurrency_limit("http://127.0.0.1:1", 1, Duration::from_secs(5));
+
+    let res = service.call_raw("SendQuery", vec![]).await;
+
+    assert!(res.is_err());
+    assert_eq!(service.metrics().count(ReveaalOutcome::TransportError), 1);
+}
+
+#[test]
+fn of_raw_call_classifies_by_status() {
+    assert_eq!(
+        ReveaalOutcome::of_raw_call(&Ok(vec![1])),
+        ReveaalOutcome::Success
+    );
+    assert_eq!(
+        ReveaalOutcome::of_raw_call(&Err(Status::unavailable("down"))),
+        ReveaalOutcome::TransportError
+    );
+}
@@ -27,3 +27,65 @@ async fn send_query_test_correct_query_returns_ok() {
     //...
     //https://crates.io/crates/wiremock-grpc
 }
+
+#[tokio::test]
+async fn send_query_connection_error_reconnects_on_next_call() {
+    use crate::api::server::protobuf::QueryRequest;
+    use crate::services::service_impls::{ReveaalConfig, ReveaalService};
+    use crate::services::service_traits::ReveaalServiceTrait;
+    use tonic::{Code, Request};
+
+    // Nothing listens on port 1, so every call fails at the connection level
+    let service = ReveaalService::new("http://127.0.0.1:1", &ReveaalConfig::default());
+
+    let first = service
+        .send_query(Request::new(QueryRequest::default()))
+        .await;
+    assert_eq!(first.unwrap_err().code(), Code::Unavailable);
+    assert_eq!(service.connection_count(), 1);
+
+    let second = service
+        .send_query(Request::new(QueryRequest::default()))
+        .await;
+    assert_eq!(second.unwrap_err().code(), Code::Unavailable);
+    assert_eq!(service.connection_count(), 2);
+}
+
+#[tokio::test]
+async fn acquire_permit_over_limit_waits_for_free_permit() {
+    use crate::services::service_impls::ReveaalService;
+    use std::time::Duration;
+
+    let service =
+        ReveaalService::with_concurrency_limit("http://127.0.0.1:1", 1, Duration::from_secs(5));
+
+    let first = service.acquire_permit().await.unwrap();
+
+    // The second call must wait while the first is outstanding
+    let mut second = Box::pin(service.acquire_permit());
+    assert!(tokio::time::timeout(Duration::from_millis(50), &mut second)
+        .await
+        .is_err());
+
+    drop(first);
+
+    let second = tokio::time::timeout(Duration::from_millis(50), second)
+        .await
+        .unwrap();
+    assert!(second.is_ok());
+}
+
+#[tokio::test]
+async fn acquire_permit_wait_exceeded_returns_resource_exhausted() {
+    use crate::services::service_impls::ReveaalService;
+    use std::time::Duration;
+    use tonic::Code;
+
+    let service =
+        ReveaalService::with_concurrency_limit("http://127.0.0.1:1", 1, Duration::from_millis(10));
+
+    let _first = service.acquire_permit().await.unwrap();
+
+    let second = service.acquire_permit().await;
+    assert_eq!(second.unwrap_err().code(), Code::ResourceExhausted);
+}